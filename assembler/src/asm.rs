@@ -1,5 +1,8 @@
 use crate::operands::OlaAsmOperand;
-use core::vm::opcodes::OlaOpcode;
+use core::program::binary_program::HostAssert;
+use core::vm::hardware::OlaRegister;
+use core::vm::opcodes::{OlaOpcode, SUB_U32_TOKEN};
+use core::vm::operands::ImmediateValue;
 use regex::Regex;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
@@ -11,6 +14,8 @@ pub(crate) struct OlaAsmInstruction {
     pub(crate) op0: Option<OlaAsmOperand>,
     pub(crate) op1: Option<OlaAsmOperand>,
     pub(crate) dst: Option<OlaAsmOperand>,
+    /// A `subu`, the `sub` with the u32 flag.
+    pub(crate) u32_mode: bool,
 }
 
 impl OlaAsmInstruction {
@@ -77,6 +82,7 @@ impl FromStr for OlaAsmInstruction {
         let asm = s.to_string();
         let (opcode, op0, op1, dst) = split_res.unwrap();
         Ok(OlaAsmInstruction {
+            u32_mode: is_sub_u32(&asm),
             asm,
             opcode,
             op0,
@@ -86,6 +92,38 @@ impl FromStr for OlaAsmInstruction {
     }
 }
 
+/// Pieces of the real instruction a pseudo-instruction stands for: `nop` is
+/// `add r0 r0 0`, `clr rX` is `mov rX 0`, `inc rX` is `add rX rX 1` and
+/// `lea rX label` is `mov rX label`, the address of a label or of the data
+/// section as an immediate. `spill rX offset` and `reload rX offset` are
+/// `mstore [r9,offset] rX` and `mload rX [r9,offset]`, a frame slot at
+/// `offset` from fp. Disassembly shows those two as the plain accesses they
+/// are. Other instructions are returned as they are.
+fn expand_pseudo_instruction(pieces: Vec<&str>) -> Result<Vec<String>, String> {
+    fn owned(pieces: &[&str]) -> Vec<String> {
+        pieces.iter().map(|piece| piece.to_string()).collect()
+    }
+    match pieces.as_slice() {
+        ["nop"] => Ok(owned(&["add", "r0", "r0", "0"])),
+        ["clr", reg] => Ok(owned(&["mov", reg, "0"])),
+        ["inc", reg] => Ok(owned(&["add", reg, reg, "1"])),
+        ["lea", reg, label] => Ok(owned(&["mov", reg, label])),
+        ["spill", reg, offset] => Ok(owned(&["mstore", &format!("[r9,{}]", offset), reg])),
+        ["reload", reg, offset] => Ok(owned(&["mload", reg, &format!("[r9,{}]", offset)])),
+        ["nop" | "clr" | "inc" | "lea" | "spill" | "reload", ..] => Err(format!(
+            "split asm error, invalid operands of pseudo-instruction: {}",
+            pieces.join(" ")
+        )),
+        _ => Ok(owned(&pieces)),
+    }
+}
+
+/// Whether `asm_line` is a `subu`, which is a `sub` with the u32 flag.
+fn is_sub_u32(asm_line: &str) -> bool {
+    let instruction_without_comment = asm_line.split(';').next().unwrap_or_default();
+    instruction_without_comment.split_whitespace().next() == Some(SUB_U32_TOKEN)
+}
+
 // disassemble into opcode, op0, op1, dst
 fn split_ola_asm_pieces(
     asm_line: String,
@@ -109,9 +147,14 @@ fn split_ola_asm_pieces(
             asm_line
         ));
     }
+    let str_pieces = expand_pseudo_instruction(str_pieces)?;
 
     let opcode_str = str_pieces.first().unwrap().to_string();
-    let opcode = OlaOpcode::from_str(opcode_str.as_str())?;
+    let opcode = if opcode_str == SUB_U32_TOKEN {
+        OlaOpcode::SUB
+    } else {
+        OlaOpcode::from_str(opcode_str.as_str())?
+    };
 
     let mut ops_pieces: Vec<String> = Vec::new();
     str_pieces
@@ -132,12 +175,14 @@ fn split_ola_asm_pieces(
     match opcode {
         OlaOpcode::ADD
         | OlaOpcode::MUL
+        | OlaOpcode::SUB
         | OlaOpcode::AND
         | OlaOpcode::OR
         | OlaOpcode::XOR
         | OlaOpcode::EQ
         | OlaOpcode::NEQ
         | OlaOpcode::GTE
+        | OlaOpcode::CAS
         | OlaOpcode::TLOAD
         | OlaOpcode::POSEIDON => {
             if ops.len() != 3 {
@@ -154,8 +199,25 @@ fn split_ola_asm_pieces(
             ))
         }
 
+        // loadp dst op1 op0, op1 is the register taking the second word.
+        OlaOpcode::LOADP => {
+            if ops.len() != 3 {
+                return Err(format!("invalid operand size: {}", asm_line));
+            }
+            let dst = ops.get(0).unwrap();
+            let op1 = ops.get(1).unwrap();
+            let op0 = ops.get(2).unwrap();
+            Ok((
+                opcode,
+                Some(op0.clone()),
+                Some(op1.clone()),
+                Some(dst.clone()),
+            ))
+        }
+
         OlaOpcode::MOV
         | OlaOpcode::NOT
+        | OlaOpcode::ISQRT
         | OlaOpcode::MLOAD
         | OlaOpcode::MSTORE
         | OlaOpcode::CJMP
@@ -163,12 +225,14 @@ fn split_ola_asm_pieces(
         | OlaOpcode::SCCALL
         | OlaOpcode::SLOAD
         | OlaOpcode::SSTORE
+        | OlaOpcode::SWAP
         | OlaOpcode::SIGCHECK => {
             if ops.len() != 2 {
                 return Err(format!("invalid operand size: {}", asm_line));
             }
             if opcode == OlaOpcode::MOV
                 || opcode == OlaOpcode::NOT
+                || opcode == OlaOpcode::ISQRT
                 || opcode == OlaOpcode::MLOAD
                 || opcode == OlaOpcode::SIGCHECK
             {
@@ -182,7 +246,11 @@ fn split_ola_asm_pieces(
             }
         }
 
-        OlaOpcode::JMP | OlaOpcode::CALL | OlaOpcode::RC | OlaOpcode::ASSERT => {
+        OlaOpcode::JMP
+        | OlaOpcode::CALL
+        | OlaOpcode::RC
+        | OlaOpcode::ASSERT
+        | OlaOpcode::REQUIRE => {
             if ops.len() != 1 {
                 return Err(format!("invalid operand size: {}", asm_line));
             }
@@ -205,6 +273,11 @@ pub(crate) enum AsmRow {
     LabelCall(String),
     LabelJmp(String),
     LabelProphet(String),
+    /// `assert_eq rX value`, checked by the executor and not encoded.
+    HostAssert(HostAssert),
+    /// `brk`, encoded as the nop `add r0 r0 0`. The executor flags it to its
+    /// step hook in debug mode.
+    Breakpoint,
 }
 
 impl Display for AsmRow {
@@ -222,6 +295,10 @@ impl Display for AsmRow {
             AsmRow::LabelProphet(value) => {
                 write!(f, "LabelProphet({})", value)
             }
+            AsmRow::HostAssert(assert) => {
+                write!(f, "HostAssert(r{} {})", assert.register, assert.expected)
+            }
+            AsmRow::Breakpoint => write!(f, "Breakpoint"),
         }
     }
 }
@@ -256,6 +333,19 @@ impl FromStr for AsmRow {
             return Ok(AsmRow::LabelProphet(label.to_string()));
         }
 
+        match s.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["assert_eq", reg, value] => {
+                let register = OlaRegister::from_str(reg)?.index() as usize;
+                let expected = ImmediateValue::from_str(value)?
+                    .to_u64()
+                    .map_err(|e| format!("AsmRow parse err: {}, {}", s, e))?;
+                return Ok(AsmRow::HostAssert(HostAssert { register, expected }));
+            }
+            ["assert_eq", ..] => return Err(format!("AsmRow parse err: {}", s)),
+            ["brk"] => return Ok(AsmRow::Breakpoint),
+            _ => {}
+        }
+
         let instruction_res = OlaAsmInstruction::from_str(s);
         return if instruction_res.is_ok() {
             Ok(AsmRow::Instruction(instruction_res.unwrap()))
@@ -270,6 +360,7 @@ mod tests {
     use crate::asm::{split_ola_asm_pieces, AsmRow, OlaAsmInstruction};
 
     use crate::operands::OlaAsmOperand;
+    use core::program::binary_program::HostAssert;
     use core::vm::hardware::OlaRegister;
     use core::vm::opcodes::OlaOpcode;
     use core::vm::operands::ImmediateValue;
@@ -320,7 +411,8 @@ mod tests {
                 }),
                 dst: Some(OlaAsmOperand::RegisterOperand {
                     register: OlaRegister::R0
-                })
+                }),
+                u32_mode: false,
             })
         );
 
@@ -339,6 +431,7 @@ mod tests {
                 dst: Some(OlaAsmOperand::RegisterOperand {
                     register: OlaRegister::R1
                 }),
+                u32_mode: false,
             })
         );
 
@@ -374,5 +467,17 @@ mod tests {
             row_label_prophet,
             AsmRow::LabelProphet(String::from(".PROPHET1_3"))
         );
+
+        let row_host_assert = AsmRow::from_str("assert_eq r3 -1").unwrap();
+        assert_eq!(
+            row_host_assert,
+            AsmRow::HostAssert(HostAssert {
+                register: 3,
+                expected: 0xFFFFFFFF00000000
+            })
+        );
+        assert!(AsmRow::from_str("assert_eq r3").is_err());
+
+        assert_eq!(AsmRow::from_str("brk").unwrap(), AsmRow::Breakpoint);
     }
 }
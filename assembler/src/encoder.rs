@@ -2,6 +2,8 @@ use crate::asm::OlaAsmInstruction;
 use crate::operands::OlaAsmOperand;
 use crate::relocate::{asm_relocate, AsmBundle, RelocatedAsmBundle};
 use core::program::binary_program::{BinaryInstruction, BinaryProgram, OlaProphet};
+use core::vm::hardware::OlaSpecialRegister;
+use core::vm::memory::{DATA_START_ADDR, DATA_START_LABEL};
 use core::vm::opcodes::OlaOpcode;
 use core::vm::operands::{ImmediateValue, OlaOperand};
 use log::debug;
@@ -10,8 +12,29 @@ use std::str::FromStr;
 
 pub fn encode_asm_from_json_file(path: String) -> Result<BinaryProgram, String> {
     let json_str = std::fs::read_to_string(path).unwrap();
-    let bundle: AsmBundle = serde_json::from_str(json_str.as_str()).unwrap();
-    let relocated = asm_relocate(bundle).unwrap();
+    encode_asm_from_json_str(json_str.as_str())
+}
+
+pub fn encode_asm_from_json_str(json_str: &str) -> Result<BinaryProgram, String> {
+    encode_asm_json(json_str, false)
+}
+
+/// Like `encode_asm_from_json_file`, also keeping the comment of each
+/// instruction line as its source line, for tooling.
+pub fn encode_annotated_asm_from_json_file(path: String) -> Result<BinaryProgram, String> {
+    let json_str = std::fs::read_to_string(path).unwrap();
+    encode_annotated_asm_from_json_str(json_str.as_str())
+}
+
+/// Like `encode_asm_from_json_str`, also keeping the comment of each
+/// instruction line as its source line, for tooling.
+pub fn encode_annotated_asm_from_json_str(json_str: &str) -> Result<BinaryProgram, String> {
+    encode_asm_json(json_str, true)
+}
+
+fn encode_asm_json(json_str: &str, annotate: bool) -> Result<BinaryProgram, String> {
+    let bundle: AsmBundle = serde_json::from_str(json_str).unwrap();
+    let relocated = asm_relocate(bundle, annotate).unwrap();
     let program = encode_to_binary(relocated).unwrap();
     Ok(program)
 }
@@ -21,6 +44,10 @@ pub(crate) fn encode_to_binary(bundle: RelocatedAsmBundle) -> Result<BinaryProgr
     let mapper_label_call = &bundle.mapper_label_call.clone();
     let mapper_label_jmp = &bundle.mapper_label_jmp.clone();
     let asm_prophets = &bundle.prophets;
+    let source_lines = bundle.source_lines;
+    let host_asserts = bundle.host_asserts;
+    let breakpoints = bundle.breakpoints;
+    let data = bundle.data;
 
     let mut binary_instructions: Vec<BinaryInstruction> = vec![];
     let mut iter = asm_instructions.iter();
@@ -101,6 +128,7 @@ pub(crate) fn encode_to_binary(bundle: RelocatedAsmBundle) -> Result<BinaryProgr
             op1,
             dst,
             prophet,
+            u32_mode: asm.u32_mode,
         };
         origin_asm.insert(binary_counter, asm.asm.clone());
         debug!(
@@ -110,7 +138,19 @@ pub(crate) fn encode_to_binary(bundle: RelocatedAsmBundle) -> Result<BinaryProgr
         binary_instructions.push(instruction);
         binary_counter += asm.binary_length() as usize;
     }
-    BinaryProgram::from_instructions(binary_instructions, Some(origin_asm), true)
+    let mut program =
+        BinaryProgram::from_instructions(binary_instructions, Some(origin_asm), true)?;
+    if !source_lines.is_empty() {
+        program.source_lines = Some(source_lines);
+    }
+    if !host_asserts.is_empty() {
+        program.host_asserts = Some(host_asserts);
+    }
+    if !breakpoints.is_empty() {
+        program.breakpoints = Some(breakpoints);
+    }
+    program.data = data;
+    Ok(program)
 }
 
 fn is_adjusted_operand(asm: &OlaAsmInstruction) -> bool {
@@ -223,6 +263,12 @@ fn operand_asm_to_binary(
             OlaAsmOperand::RegisterOperand { register } => {
                 Some(OlaOperand::RegisterOperand { register })
             }
+            // psp is an empty op1, clk is encoded from the dst register.
+            OlaAsmOperand::SpecialReg {
+                special_reg: OlaSpecialRegister::CLK,
+            } => Some(OlaOperand::SpecialReg {
+                special_reg: OlaSpecialRegister::CLK,
+            }),
             OlaAsmOperand::SpecialReg { .. } => None,
             OlaAsmOperand::Label { value } => {
                 if let Some(host) = mapper_label_jmp.get(value.as_str()) {
@@ -241,6 +287,11 @@ fn operand_asm_to_binary(
                     Some(OlaOperand::ImmediateOperand {
                         value: ImmediateValue::from_str(host.to_string().as_str()).unwrap(),
                     })
+                } else if value == DATA_START_LABEL {
+                    Some(OlaOperand::ImmediateOperand {
+                        value: ImmediateValue::from_str(DATA_START_ADDR.to_string().as_str())
+                            .unwrap(),
+                    })
                 } else {
                     return Err(format!(
                         "relocated asm to binary error: invalid identifier {}",
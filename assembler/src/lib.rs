@@ -2,6 +2,9 @@ mod asm;
 pub mod encoder;
 pub mod operands;
 mod relocate;
+pub mod stdlib;
 mod test_binary_program_print;
 mod test_data_generator;
+#[cfg(feature = "testprograms")]
+pub mod testprograms;
 mod tests;
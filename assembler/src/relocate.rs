@@ -1,14 +1,19 @@
 use crate::asm::{AsmRow, OlaAsmInstruction};
-use core::program::binary_program::{OlaProphetInput, OlaProphetOutput};
+use core::program::binary_program::{HostAssert, OlaProphetInput, OlaProphetOutput};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::str::FromStr;
 
+/// What a `brk` line assembles to: it leaves every register as it is.
+const BREAKPOINT_NOP: &str = "add r0 r0 0";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct AsmBundle {
     program: String,
     prophets: Vec<OlaAsmProphet>,
+    #[serde(default)]
+    data: Vec<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -18,7 +23,7 @@ struct AsmScope {
 }
 
 impl AsmBundle {
-    fn generate_sorted_asm_scopes(&self) -> Result<Vec<AsmScope>, String> {
+    fn generate_sorted_asm_scopes(&self, annotate: bool) -> Result<Vec<AsmScope>, String> {
         let mut lines = self.program.lines();
         let mut scopes: Vec<AsmScope> = vec![];
         let mut current_scope_label: String = String::new();
@@ -48,10 +53,10 @@ impl AsmBundle {
                         }
                         current_scope_label = label;
                         current_scope_lines.clear();
-                        current_scope_lines.push(processed_line.to_string())
+                        current_scope_lines.push(scope_line(line, annotate))
                     }
                     _ => {
-                        current_scope_lines.push(processed_line.to_string());
+                        current_scope_lines.push(scope_line(line, annotate));
                     }
                 };
             } else {
@@ -106,10 +111,19 @@ pub(crate) struct RelocatedAsmBundle {
     pub(crate) prophets: HashMap<usize, OlaAsmProphet>,
     pub(crate) mapper_label_call: HashMap<String, usize>,
     pub(crate) mapper_label_jmp: HashMap<String, usize>,
+    pub(crate) source_lines: BTreeMap<usize, String>,
+    pub(crate) host_asserts: BTreeMap<usize, Vec<HostAssert>>,
+    pub(crate) breakpoints: BTreeSet<usize>,
+    pub(crate) data: Vec<u64>,
 }
 
-pub(crate) fn asm_relocate(bundle: AsmBundle) -> Result<RelocatedAsmBundle, String> {
-    let scopes_res = bundle.generate_sorted_asm_scopes();
+/// Relocate the scopes of `bundle`, `main` first. With `annotate` the
+/// comment of each instruction line is kept as its source line.
+pub(crate) fn asm_relocate(
+    bundle: AsmBundle,
+    annotate: bool,
+) -> Result<RelocatedAsmBundle, String> {
+    let scopes_res = bundle.generate_sorted_asm_scopes(annotate);
     if scopes_res.is_err() {
         return Err(format!(
             "asm relocate err ==> {}",
@@ -124,6 +138,9 @@ pub(crate) fn asm_relocate(bundle: AsmBundle) -> Result<RelocatedAsmBundle, Stri
     let mut mapper_label_call: HashMap<String, usize> = HashMap::new();
     let mut mapper_label_jmp: HashMap<String, usize> = HashMap::new();
     let mut mapper_label_prophet: HashMap<String, usize> = HashMap::new();
+    let mut source_lines: BTreeMap<usize, String> = BTreeMap::new();
+    let mut host_asserts: BTreeMap<usize, Vec<HostAssert>> = BTreeMap::new();
+    let mut breakpoints: BTreeSet<usize> = BTreeSet::new();
 
     let mut counter: usize = 0;
     let mut ori_counter: usize = 0;
@@ -133,12 +150,16 @@ pub(crate) fn asm_relocate(bundle: AsmBundle) -> Result<RelocatedAsmBundle, Stri
 
     loop {
         if let Some(line) = lines.next() {
-            let row_res = AsmRow::from_str(line);
+            let row_res = AsmRow::from_str(line_pre_process(line));
             if row_res.is_err() {
                 let err_msg = row_res.err().unwrap();
                 return Err(format!("{} ==> {}", line, err_msg));
             }
-            let row = row_res.unwrap();
+            let mut row = row_res.unwrap();
+            if row == AsmRow::Breakpoint {
+                breakpoints.insert(counter);
+                row = AsmRow::Instruction(OlaAsmInstruction::from_str(BREAKPOINT_NOP)?);
+            }
             match row {
                 AsmRow::Instruction(instruction) => {
                     label_stack.iter().for_each(|cached_row| match cached_row {
@@ -154,6 +175,9 @@ pub(crate) fn asm_relocate(bundle: AsmBundle) -> Result<RelocatedAsmBundle, Stri
                         _ => {}
                     });
                     label_stack.clear();
+                    if let Some(comment) = line_comment(line) {
+                        source_lines.insert(counter, comment.to_string());
+                    }
                     instructions.push(instruction.clone());
                     ori_counter = counter;
                     counter += instruction.binary_length() as usize;
@@ -200,6 +224,11 @@ pub(crate) fn asm_relocate(bundle: AsmBundle) -> Result<RelocatedAsmBundle, Stri
                     // }
                     label_stack.push(row);
                 }
+                AsmRow::HostAssert(assert) => {
+                    // Checked before the next instruction executes.
+                    host_asserts.entry(counter).or_default().push(assert);
+                }
+                AsmRow::Breakpoint => unreachable!(),
             }
         } else {
             break;
@@ -224,6 +253,10 @@ pub(crate) fn asm_relocate(bundle: AsmBundle) -> Result<RelocatedAsmBundle, Stri
         prophets,
         mapper_label_call,
         mapper_label_jmp,
+        source_lines,
+        host_asserts,
+        breakpoints,
+        data: bundle.data,
     })
 }
 
@@ -238,6 +271,24 @@ fn line_pre_process(line: &str) -> &str {
     without_comment.trim()
 }
 
+// keep the comment when annotating, it is the source line of the instruction
+fn scope_line(line: &str, annotate: bool) -> String {
+    if annotate {
+        line.trim().to_string()
+    } else {
+        line_pre_process(line).to_string()
+    }
+}
+
+fn line_comment(line: &str) -> Option<&str> {
+    let comment = line[line.find(";")? + 1..].trim();
+    if comment.is_empty() {
+        None
+    } else {
+        Some(comment)
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use crate::relocate::{asm_relocate, AsmBundle};
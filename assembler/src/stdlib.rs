@@ -0,0 +1,934 @@
+//! Hand written subroutines which can be linked after a `main` function.
+//!
+//! Calling convention, the same one the compiler emits for `call`/`ret`:
+//! - arguments are passed in r1, r2, r3 and the result is returned in r0;
+//! - before `call`, the caller stores its fp at `[r9,-2]`, and `call` stores
+//!   the return pc at `[r9,-1]`, so the caller must reserve both slots in its
+//!   frame;
+//! - the routines here are leaves, they don't touch r9 and clobber r0..r7.
+//!
+//! Jump labels must be unique in a linked program, so stdlib routines use
+//! `.LBL1000_*` and up, which is out of the compiler's range in practice.
+//!
+//! None of these is an instruction. Those standing in for one, such as
+//! `mulhi` or `sext`, cover a narrower case than the instruction would, and
+//! their docs say which.
+
+use crate::encoder::encode_asm_from_json_str;
+use core::program::binary_program::BinaryProgram;
+use core::vm::vm_state::CALL_DEPTH;
+
+/// `memcpy(dst: r1, src: r2, len: r3)`: copy `len` words from `src` to
+/// `dst`. Returns nothing; r0 is left unspecified.
+pub const MEMCPY: &str = "memcpy:
+.LBL1000_0:
+  mov r4 0
+  jmp .LBL1000_1
+.LBL1000_1:
+  eq r5 r4 r3
+  cjmp r5 .LBL1000_2
+  add r6 r2 r4
+  mload r6 [r6,0]
+  add r7 r1 r4
+  mstore [r7,0] r6
+  add r4 r4 1
+  jmp .LBL1000_1
+.LBL1000_2:
+  ret
+";
+
+/// `cmp(a: r1, b: r2) -> r0`: 1 if `a > b`, 0 if `a == b`, -1 if `a < b`.
+/// Both operands must be u32 as required by `gte`.
+pub const CMP: &str = "cmp:
+.LBL1001_0:
+  eq r0 r1 r2
+  cjmp r0 .LBL1001_1
+  gte r0 r1 r2
+  cjmp r0 .LBL1001_2
+  mov r0 -1
+  ret
+.LBL1001_1:
+  mov r0 0
+  ret
+.LBL1001_2:
+  mov r0 1
+  ret
+";
+
+/// `mul_const_<k>(a: r1) -> r0`: multiply `a` by the constant `k`. The
+/// routine name carries the constant so several instances can be linked
+/// together.
+pub fn mul_const(k: u64) -> String {
+    format!(
+        "mul_const_{k}:
+.LBL1002_{k}:
+  mul r0 r1 {k}
+  ret
+"
+    )
+}
+
+/// `byte_<idx>(a: r1) -> r0`: the `idx`-th least significant byte of the
+/// 64 bits word `a`, `idx` below 8. r1 is kept, r2 to r4 are clobbered.
+///
+/// The high half of `a` comes from the `byte_prophet(idx)` hint and is
+/// checked as in `ABS`. The byte is masked off its half with `and`, which
+/// decomposes the half into bytes in the bitwise table. Above the lowest
+/// byte of a half, the byte itself is a hint too, checked to be the masked
+/// value over `2^(8 * (idx % 4))`: the product of the two is exact and
+/// matches the masked value only for that byte. Nothing is shifted down with
+/// a field inverse, so under `execute_strict` only `a` from `2^63` up, whose
+/// high half times `2^32` reads as a word overflow, is refused. Link it with
+/// `link_with_prophets`.
+pub fn byte(idx: u64) -> String {
+    assert!(idx < 8, "byte {} of a 64 bits word", idx);
+    let half = if idx < 4 { "r3" } else { "r2" };
+    let shift = 8 * (idx % 4);
+    let mask = 0xffu64 << shift;
+    let select = if shift == 0 {
+        format!("  and r0 {half} {mask}\n")
+    } else {
+        format!(
+            "  and r4 {half} {mask}
+  mul r3 r0 {pow}
+  eq r3 r3 r4
+  assert r3
+",
+            pow = 1u64 << shift
+        )
+    };
+    let load_byte = if shift == 0 {
+        ""
+    } else {
+        "  mload r0 [r0,1]\n"
+    };
+    format!(
+        "byte_{idx}:
+.LBL1003_{idx}:
+.PROPHET1003_{idx}:
+  mov r0 psp
+  mload r2 [r0,0]
+{load_byte}  range r2
+  mul r3 r2 -4294967296
+  add r3 r1 r3
+  range r3
+  eq r4 r2 4294967295
+  mul r4 r4 r3
+  eq r4 r4 0
+  assert r4
+{select}  ret
+"
+    )
+}
+
+/// Prophet computing the high half of `a` and, above the lowest byte of a
+/// half, byte `idx` of `a` for `byte(idx)`.
+pub fn byte_prophet(idx: u64) -> serde_json::Value {
+    let mut code = "%{\n    entry() {\n        cid.hi = cid.x / 4294967296;\n".to_string();
+    let mut outputs = vec!["cid.hi"];
+    if idx % 4 != 0 {
+        code.push_str(&format!(
+            "        cid.b = (cid.x / {}) % 256;\n",
+            1u64 << (8 * idx)
+        ));
+        outputs.push("cid.b");
+    }
+    code.push_str("    }\n%}");
+    let outputs = outputs
+        .into_iter()
+        .map(|name| {
+            serde_json::json!({"name": name, "length": 1, "is_ref": false, "is_input_output": false})
+        })
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "label": format!(".PROPHET1003_{}", idx),
+        "code": code,
+        "inputs": [
+            {"name": "cid.x", "length": 1, "is_ref": false, "is_input_output": false}
+        ],
+        "outputs": outputs
+    })
+}
+
+/// Body of `a: r1` rotated left by `k` bits as a u32, in r0, the routine
+/// starting at `label`. The high `k` bits shifted down come from the prophet
+/// at `.PROPHET<label>` and are checked to be the masked high bits over
+/// `2^(32 - k)`, the low `32 - k` bits are shifted up with `mul`. Both
+/// products are exact and below `2^32`, and the halves don't overlap.
+fn rotl_body(label: &str, k: u64) -> String {
+    if k == 0 {
+        return format!("  and r0 r1 {}\n  ret\n", u32::MAX);
+    }
+    let low_mask = (1u64 << (32 - k)) - 1;
+    let high_mask = u32::MAX as u64 ^ low_mask;
+    format!(
+        ".PROPHET{label}:
+  mov r0 psp
+  mload r0 [r0,0]
+  and r2 r1 {high_mask}
+  mul r3 r0 {down}
+  eq r3 r3 r2
+  assert r3
+  and r2 r1 {low_mask}
+  mul r2 r2 {up}
+  add r0 r0 r2
+  ret
+",
+        down = 1u64 << (32 - k),
+        up = 1u64 << k
+    )
+}
+
+/// Prophet shifting the high `k` bits of `a` down for `rotl_body`.
+fn rotl_prophet_at(label: &str, k: u64) -> serde_json::Value {
+    serde_json::json!({
+        "label": format!(".PROPHET{}", label),
+        "code": format!(
+            "%{{\n    entry() {{\n        cid.hi = cid.x / {};\n    }}\n%}}",
+            1u64 << (32 - k)
+        ),
+        "inputs": [
+            {"name": "cid.x", "length": 1, "is_ref": false, "is_input_output": false}
+        ],
+        "outputs": [
+            {"name": "cid.hi", "length": 1, "is_ref": false, "is_input_output": false}
+        ]
+    })
+}
+
+/// `rotl_<k>(a: r1) -> r0`: `a` rotated left by `k` bits. The rotation is
+/// on 32 bits only, not on 64 bits words: `a` must be a u32, which the
+/// bitwise table checks, and `k` below 32, which is asserted. `k` is fixed
+/// when linking, there is no rotation by an amount in a register. r1 is
+/// kept, r2 and r3 are clobbered. Above 0, link it with `rotl_prophet(k)`
+/// using `link_with_prophets`.
+pub fn rotl(k: u64) -> String {
+    assert!(k < 32, "rotation by {} of a u32 word", k);
+    let label = format!("1004_{k}");
+    format!("rotl_{k}:\n.LBL{label}:\n{}", rotl_body(&label, k))
+}
+
+/// Prophet for `rotl(k)`, `k` from 1 to 31.
+pub fn rotl_prophet(k: u64) -> serde_json::Value {
+    assert!((1..32).contains(&k), "rotation by {} needs no prophet", k);
+    rotl_prophet_at(&format!("1004_{k}"), k)
+}
+
+/// `rotr_<k>(a: r1) -> r0`: `a` rotated right by `k` bits, see `rotl`.
+/// Above 0, link it with `rotr_prophet(k)`.
+pub fn rotr(k: u64) -> String {
+    assert!(k < 32, "rotation by {} of a u32 word", k);
+    let label = format!("1005_{k}");
+    format!(
+        "rotr_{k}:\n.LBL{label}:\n{}",
+        rotl_body(&label, (32 - k) % 32)
+    )
+}
+
+/// Prophet for `rotr(k)`, `k` from 1 to 31.
+pub fn rotr_prophet(k: u64) -> serde_json::Value {
+    assert!((1..32).contains(&k), "rotation by {} needs no prophet", k);
+    rotl_prophet_at(&format!("1005_{k}"), 32 - k)
+}
+
+/// `min(a: r1, b: r2) -> r0`: the smaller of the u32 `a` and `b`. The
+/// comparison comes from `gte`, checked by the cmp table, and selects
+/// without branching: `r0 = b + (b >= a) * (a - b)`.
+pub const MIN: &str = "min:
+.LBL1008_0:
+  gte r3 r2 r1
+  mul r4 r2 -1
+  add r4 r1 r4
+  mul r4 r3 r4
+  add r0 r2 r4
+  ret
+";
+
+/// `max(a: r1, b: r2) -> r0`: the larger of the u32 `a` and `b`, as `min`
+/// with `r0 = b + (a >= b) * (a - b)`.
+pub const MAX: &str = "max:
+.LBL1009_0:
+  gte r3 r1 r2
+  mul r4 r2 -1
+  add r4 r1 r4
+  mul r4 r3 r4
+  add r0 r2 r4
+  ret
+";
+
+/// `mulhi(a: r1, b: r2) -> r0`: high 32 bits of the 64 bits product of the
+/// u32 `a` and `b`, with the low 32 bits (mullo) left in r1. This is a 32
+/// by 32 bits multiplication only, not the 64 by 64 bits `mulhi`/`mullo`
+/// pair: `a` and `b` are range checked to be u32, wider operands fail
+/// rather than give the high word of a 128 bits product. The product is
+/// below the field order so `mul` computes it exactly; the high half comes
+/// from the `MULHI_PROPHET` hint, both halves are range checked and the low
+/// one is what the product leaves above `hi * 2^32`. A high half of
+/// `2^32 - 1` is rejected, it would only fit a product wrapped around the
+/// field order. Link it with `link_with_prophets`.
+pub const MULHI: &str = "mulhi:
+.LBL1006_0:
+  range r1
+  range r2
+  mul r3 r1 r2
+  mov r1 r3
+.PROPHET1006_0:
+  mov r0 psp
+  mload r0 [r0,0]
+  range r0
+  mul r4 r0 -4294967296
+  add r1 r3 r4
+  range r1
+  neq r5 r0 4294967295
+  assert r5
+  ret
+";
+
+/// Prophet computing the high half of the product for `MULHI`.
+pub fn mulhi_prophet() -> serde_json::Value {
+    serde_json::json!({
+        "label": ".PROPHET1006_0",
+        "code": "%{\n    entry() {\n        cid.hi = cid.x / 4294967296;\n    }\n%}",
+        "inputs": [
+            {"name": "cid.x", "length": 1, "is_ref": false, "is_input_output": false}
+        ],
+        "outputs": [
+            {"name": "cid.hi", "length": 1, "is_ref": false, "is_input_output": false}
+        ]
+    })
+}
+
+/// Bootloader zeroing r0..r8 before the user program, which then starts
+/// from known registers whatever ran before. r9 holds the initial fp and is
+/// kept. Link it with `link_bootloader`.
+pub const ZERO_REGISTERS: &str = "main:
+.LBL1007_0:
+  mov r0 0
+  mov r1 0
+  mov r2 0
+  mov r3 0
+  mov r4 0
+  mov r5 0
+  mov r6 0
+  mov r7 0
+  mov r8 0
+";
+
+/// `range_bits_<n>(a: r1)`: check `a` fits in `n` bits, for `n` from 1 to
+/// 64. Returns nothing, r1 is kept.
+///
+/// `range` covers 32 bits. Up to 32 bits, `a` is range checked and so is
+/// `a * 2^(32 - n)`, which stays below the field order and fits 32 bits only
+/// if `a` fits `n`. Above, the high half of `a` comes from the
+/// `range_bits_prophet(n)` hint and is checked to fit `n - 32` bits the same
+/// way, then the low half `a - hi * 2^32` to fit 32: the sum of such halves
+/// is below `2^63`, so it can only equal `a` if `a` fits. Any field element
+/// fits 64 bits, that check is empty. Link the routines above 32 bits with
+/// `link_with_prophets`.
+pub fn range_bits(n: u64) -> String {
+    assert!((1..=64).contains(&n), "range check on {} bits", n);
+    let body = match n {
+        1..=31 => format!(
+            "  range r1
+  mul r3 r1 {shift}
+  range r3
+",
+            shift = 1u64 << (32 - n)
+        ),
+        32 => "  range r1\n".to_string(),
+        33..=63 => format!(
+            ".PROPHET1010_{n}:
+  mov r0 psp
+  mload r0 [r0,0]
+  range r0
+  mul r3 r0 {shift}
+  range r3
+  mul r4 r0 -4294967296
+  add r4 r1 r4
+  range r4
+",
+            shift = 1u64 << (64 - n)
+        ),
+        // Every field element fits 64 bits.
+        _ => String::new(),
+    };
+    format!("range_bits_{n}:\n.LBL1010_{n}:\n{body}  ret\n")
+}
+
+/// Prophet computing the high half of `a` for `range_bits(n)`, `n` above 32.
+pub fn range_bits_prophet(n: u64) -> serde_json::Value {
+    serde_json::json!({
+        "label": format!(".PROPHET1010_{}", n),
+        "code": "%{\n    entry() {\n        cid.hi = cid.x / 4294967296;\n    }\n%}",
+        "inputs": [
+            {"name": "cid.x", "length": 1, "is_ref": false, "is_input_output": false}
+        ],
+        "outputs": [
+            {"name": "cid.hi", "length": 1, "is_ref": false, "is_input_output": false}
+        ]
+    })
+}
+
+/// `abs(a: r1) -> r0`: magnitude of `a` read as a 64 bits two's complement
+/// word, wrapping as `i64::wrapping_abs` does, so `i64::MIN` is its own
+/// absolute value. Words from `2^64 - 2^32 + 1` up are no field elements:
+/// -1 down to `-(2^32 - 1)` can't be passed.
+///
+/// The sign bit is the top bit of the high half of `a`, which comes from
+/// the `abs_prophet` hint and is range checked, as is the low half
+/// `a - hi * 2^32`. Halves summing to `a + p` are rejected: their high half
+/// is `2^32 - 1` with a nonzero low half. The sign is taken with `gte` and,
+/// as `2^64 = 2^32 - 1` in the field, the result is
+/// `a + sign * (2^32 - 1 - 2 * a)`, i.e. `a` or `2^64 - a`, below `2^63`
+/// but for `i64::MIN`. Link it with `link_with_prophets`.
+pub const ABS: &str = "abs:
+.LBL1011_0:
+.PROPHET1011_0:
+  mov r0 psp
+  mload r0 [r0,0]
+  range r0
+  mul r4 r0 -4294967296
+  add r4 r1 r4
+  range r4
+  eq r5 r0 4294967295
+  mul r5 r5 r4
+  eq r5 r5 0
+  assert r5
+  gte r3 r0 2147483648
+  mov r6 4294967295
+  mul r7 r1 -2
+  add r6 r6 r7
+  mul r6 r3 r6
+  add r0 r1 r6
+  ret
+";
+
+/// Prophet computing the high half of `a` for `ABS`.
+pub fn abs_prophet() -> serde_json::Value {
+    serde_json::json!({
+        "label": ".PROPHET1011_0",
+        "code": "%{\n    entry() {\n        cid.hi = cid.x / 4294967296;\n    }\n%}",
+        "inputs": [
+            {"name": "cid.x", "length": 1, "is_ref": false, "is_input_output": false}
+        ],
+        "outputs": [
+            {"name": "cid.hi", "length": 1, "is_ref": false, "is_input_output": false}
+        ]
+    })
+}
+
+/// `depth() -> r0`: calls not returned from yet where `depth` is called,
+/// 0 in `main`. It is a hint of `depth_prophet`, from the count the
+/// executor keeps, and nothing constrains it: unlike a `depth` instruction
+/// tied to the fp, the prover can return any value. It helps debugging, but
+/// a proof doesn't bound the recursion with it. Link it with
+/// `link_with_prophets`.
+pub const DEPTH: &str = "depth:
+.LBL1012_0:
+.PROPHET1012_0:
+  mov r0 psp
+  mload r0 [r0,0]
+  ret
+";
+
+/// Prophet reading the call depth for `DEPTH`, less the call to `depth`.
+pub fn depth_prophet() -> serde_json::Value {
+    let code = format!(
+        "%{{\n    entry() {{\n        cid.depth = {} - 1;\n    }}\n%}}",
+        CALL_DEPTH
+    );
+    serde_json::json!({
+        "label": ".PROPHET1012_0",
+        "code": code,
+        "inputs": [],
+        "outputs": [
+            {"name": "cid.depth", "length": 1, "is_ref": false, "is_input_output": false}
+        ]
+    })
+}
+
+/// `sext_<bits>(a: r1) -> r0`: the low `bits` bits of `a` read as a signed
+/// number, i.e. with bit `bits - 1` extended through the upper bits. The
+/// low bits and the sign bit are masked with `and`, which decomposes `a`
+/// in the bitwise table, and the sign bit is taken twice off the low bits:
+/// `low - 2^bits * sign`. Negative results are the field elements `mov`
+/// writes for a negative immediate, e.g. `p - 128` for -128, as their 64
+/// bits two's complement words are above the field order. Bitwise operands
+/// are u32, so `a` must be one and `bits` from 1 to 32: values up to 32
+/// bits wide are extended, wider ones are not.
+pub fn sext(bits: u64) -> String {
+    assert!((1..=32).contains(&bits), "sign extension from bit {}", bits);
+    let low_mask = u32::MAX as u64 >> (32 - bits);
+    let sign_mask = 1u64 << (bits - 1);
+    format!(
+        "sext_{bits}:
+.LBL1013_{bits}:
+  and r0 r1 {low_mask}
+  and r2 r1 {sign_mask}
+  mul r2 r2 -2
+  add r0 r0 r2
+  ret
+"
+    )
+}
+
+/// `memset(dst: r1, value: r2, len: r3)`: write `value` to the `len` words
+/// from `dst`, e.g. 0 to clear a buffer. Returns nothing; r0 is left
+/// unspecified.
+pub const MEMSET: &str = "memset:
+.LBL1014_0:
+  mov r4 0
+  jmp .LBL1014_1
+.LBL1014_1:
+  eq r5 r4 r3
+  cjmp r5 .LBL1014_2
+  add r6 r1 r4
+  mstore [r6,0] r2
+  add r4 r4 1
+  jmp .LBL1014_1
+.LBL1014_2:
+  ret
+";
+
+/// `pack_bytes(src: r1) -> r0`: the word whose bytes, least significant
+/// first, are the 8 words from `src`, the reverse of `unpack_bytes`. Each
+/// byte is checked to fit 8 bits with `and`, decomposing it in the bitwise
+/// table, and shifted in with `mul`. The high half is kept as it is reached,
+/// so bytes summing to `2^64 - 2^32 + 1` or more, above the field order, are
+/// rejected: their high half is `2^32 - 1` with a nonzero low half.
+pub const PACK_BYTES: &str = "pack_bytes:
+.LBL1015_0:
+  mov r0 0
+  mov r7 0
+  mov r2 8
+  jmp .LBL1015_1
+.LBL1015_1:
+  eq r3 r2 0
+  cjmp r3 .LBL1015_2
+  eq r3 r2 4
+  mul r3 r3 r0
+  add r7 r7 r3
+  add r2 r2 -1
+  add r4 r1 r2
+  mload r5 [r4,0]
+  and r6 r5 255
+  eq r6 r6 r5
+  assert r6
+  mul r0 r0 256
+  add r0 r0 r5
+  jmp .LBL1015_1
+.LBL1015_2:
+  mul r4 r7 -4294967296
+  add r4 r0 r4
+  eq r5 r7 4294967295
+  mul r5 r5 r4
+  eq r5 r5 0
+  assert r5
+  ret
+";
+
+/// `unpack_bytes(a: r1, dst: r2)`: write the 8 bytes of `a`, least
+/// significant first, to the words from `dst`. Returns nothing; r0 is left
+/// unspecified.
+///
+/// The high half of `a` comes from the `unpack_bytes_prophet` hint and is
+/// checked as in `ABS`. The bytes are hints too: each one is checked to be
+/// its half masked with `and`, the bitwise decomposition `pack_bytes` checks
+/// its bytes with, over the byte's power of `2^8`, as in `byte`. The checked
+/// value is taken off the half, which takes the high half in once the low
+/// one is spent. Nothing is shifted down with a field inverse, so under
+/// `execute_strict` only `a` from `2^63` up is refused. Link it with
+/// `link_with_prophets`.
+///
+/// Packing is a pair of routines rather than an instruction: the bytes are
+/// checked by the bitwise and range check tables the cpu already looks up.
+pub const UNPACK_BYTES: &str = "unpack_bytes:
+.LBL1016_0:
+.PROPHET1016_0:
+  mov r0 psp
+  mload r3 [r0,0]
+  range r3
+  mul r4 r3 -4294967296
+  add r1 r1 r4
+  range r1
+  eq r4 r3 4294967295
+  mul r4 r4 r1
+  eq r4 r4 0
+  assert r4
+  mov r4 0
+  mov r5 1
+  jmp .LBL1016_1
+.LBL1016_1:
+  eq r6 r4 8
+  cjmp r6 .LBL1016_2
+  eq r6 r4 4
+  mul r7 r6 r3
+  add r1 r1 r7
+  mul r7 r6 r5
+  sub r5 r5 r7
+  add r5 r5 r6
+  add r7 r0 r4
+  mload r7 [r7,1]
+  add r6 r2 r4
+  mstore [r6,0] r7
+  mul r6 r5 255
+  and r6 r1 r6
+  mul r7 r7 r5
+  eq r6 r6 r7
+  assert r6
+  sub r1 r1 r7
+  mul r5 r5 256
+  add r4 r4 1
+  jmp .LBL1016_1
+.LBL1016_2:
+  ret
+";
+
+/// Prophet computing the high half and the 8 bytes of `a` for
+/// `unpack_bytes`.
+pub fn unpack_bytes_prophet() -> serde_json::Value {
+    let mut code = "%{\n    entry() {\n        cid.hi = cid.x / 4294967296;\n".to_string();
+    let mut outputs = vec!["cid.hi".to_string()];
+    for i in 0..8 {
+        code.push_str(&format!(
+            "        cid.b{} = (cid.x / {}) % 256;\n",
+            i,
+            1u64 << (8 * i)
+        ));
+        outputs.push(format!("cid.b{}", i));
+    }
+    code.push_str("    }\n%}");
+    let outputs = outputs
+        .into_iter()
+        .map(|name| {
+            serde_json::json!({"name": name, "length": 1, "is_ref": false, "is_input_output": false})
+        })
+        .collect::<Vec<_>>();
+    serde_json::json!({
+        "label": ".PROPHET1016_0",
+        "code": code,
+        "inputs": [
+            {"name": "cid.x", "length": 1, "is_ref": false, "is_input_output": false}
+        ],
+        "outputs": outputs
+    })
+}
+
+/// `bitrev(a: r1, bits: r2) -> r0`: the low `bits` bits of `a` in reverse
+/// order, e.g. the index of an FFT input in bit-reversed order. Bits of `a`
+/// above them are dropped. Each bit is masked with `and`, decomposing `a` in
+/// the bitwise table, and shifted in from the bottom of r0 as the mask moves
+/// up, so the first bit masked ends up the highest. As for `byte`, `a` must
+/// be a u32 and `bits` at most 32.
+pub const BITREV: &str = "bitrev:
+.LBL1017_0:
+  mov r0 0
+  mov r3 1
+  mov r4 0
+  jmp .LBL1017_1
+.LBL1017_1:
+  eq r5 r4 r2
+  cjmp r5 .LBL1017_2
+  and r5 r1 r3
+  neq r5 r5 0
+  mul r0 r0 2
+  add r0 r0 r5
+  mul r3 r3 2
+  add r4 r4 1
+  jmp .LBL1017_1
+.LBL1017_2:
+  ret
+";
+
+/// `output(value: r1)`: append `value` to the output of the run, the words
+/// `tstore` puts on the tape in order. A proof makes them public, see
+/// `outputs` in the circuits crate's `PublicValues`. The word above the
+/// caller's frame, `[r9,0]`, is used as scratch.
+pub const OUTPUT: &str = "output:
+.LBL1018_0:
+  mstore [r9,0] r1
+  tstore r9 1
+  ret
+";
+
+fn is_scope_label(line: &str) -> bool {
+    let line = line.trim();
+    line.ends_with(':') && !line.starts_with('.')
+}
+
+/// Prepend `bootloader`, a `main` scope falling through at its end, to
+/// `program`. The `main` scope of `program` is moved right after it without
+/// its label, so the entry point is the start of the bootloader and the
+/// user program goes on from there. Both end up in the same bytecode, which
+/// the program table commits to as a whole.
+pub fn link_bootloader(bootloader: &str, program: &str) -> Result<String, String> {
+    let lines = program.lines().collect::<Vec<_>>();
+    let start = lines
+        .iter()
+        .position(|line| line.trim() == "main:")
+        .ok_or_else(|| "link bootloader error, no main scope found".to_string())?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| is_scope_label(line))
+        .map_or(lines.len(), |len| start + 1 + len);
+
+    let mut linked = bootloader.trim_end().to_string();
+    for line in lines[start + 1..end]
+        .iter()
+        .chain(&lines[..start])
+        .chain(&lines[end..])
+    {
+        linked.push('\n');
+        linked.push_str(line);
+    }
+    linked.push('\n');
+    Ok(linked)
+}
+
+/// Whether the instructions of `program` start with the ones of
+/// `bootloader`, i.e. it was linked with `link_bootloader`.
+pub fn has_bootloader(program: &BinaryProgram, bootloader: &str) -> Result<bool, String> {
+    let bootloader = link(bootloader, &[])?;
+    let len = bootloader.bytecode.lines().count();
+    Ok(program
+        .bytecode
+        .lines()
+        .take(len)
+        .eq(bootloader.bytecode.lines()))
+}
+
+/// Append `routines` after `main`, returning the linked asm source.
+pub fn link_asm(main: &str, routines: &[&str]) -> String {
+    let mut program = main.trim_end().to_string();
+    for routine in routines {
+        program.push('\n');
+        program.push_str(routine.trim_end());
+    }
+    program
+}
+
+/// Link `routines` after `main` and encode the result.
+pub fn link(main: &str, routines: &[&str]) -> Result<BinaryProgram, String> {
+    link_with_prophets(main, routines, &[])
+}
+
+/// Same as `link`, for routines relying on `prophets`.
+pub fn link_with_prophets(
+    main: &str,
+    routines: &[&str],
+    prophets: &[serde_json::Value],
+) -> Result<BinaryProgram, String> {
+    let bundle = serde_json::json!({
+        "program": link_asm(main, routines),
+        "prophets": prophets,
+    });
+    encode_asm_from_json_str(bundle.to_string().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{
+        abs_prophet, byte, byte_prophet, depth_prophet, has_bootloader, link, link_asm,
+        link_bootloader, link_with_prophets, mul_const, mulhi_prophet, range_bits,
+        range_bits_prophet, rotl, rotl_prophet, rotr, rotr_prophet, sext, unpack_bytes_prophet,
+        ABS, BITREV, CMP, DEPTH, MAX, MEMCPY, MEMSET, MIN, MULHI, OUTPUT, PACK_BYTES, UNPACK_BYTES,
+        ZERO_REGISTERS,
+    };
+
+    fn fixture_program(file_name: &str) -> String {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/");
+        path.push(file_name);
+        let json_str = std::fs::read_to_string(path).unwrap();
+        let bundle: serde_json::Value = serde_json::from_str(json_str.as_str()).unwrap();
+        bundle["program"].as_str().unwrap().to_string()
+    }
+
+    // The stdlib fixtures executed by the cpu tests must stay in sync with the
+    // routines shipped here.
+    #[test]
+    fn test_stdlib_fixtures_linked() {
+        let mul_7 = mul_const(7);
+        let (byte_0, byte_3, byte_7) = (byte(0), byte(3), byte(7));
+        let (rotl_8, rotr_8, rotl_0) = (rotl(8), rotr(8), rotl(0));
+        let (range_bits_20, range_bits_40) = (range_bits(20), range_bits(40));
+        let (sext_8, sext_16) = (sext(8), sext(16));
+        for (file_name, routines) in [
+            ("stdlib_memcpy.json", vec![MEMCPY]),
+            ("stdlib_cmp.json", vec![CMP]),
+            ("stdlib_mul_const.json", vec![mul_7.as_str()]),
+            (
+                "stdlib_byte.json",
+                vec![byte_0.as_str(), byte_3.as_str(), byte_7.as_str()],
+            ),
+            (
+                "stdlib_rotate.json",
+                vec![rotl_8.as_str(), rotr_8.as_str(), rotl_0.as_str()],
+            ),
+            ("stdlib_mulhi.json", vec![MULHI]),
+            ("stdlib_minmax.json", vec![MIN, MAX]),
+            (
+                "stdlib_range_bits.json",
+                vec![range_bits_20.as_str(), range_bits_40.as_str()],
+            ),
+            ("stdlib_abs.json", vec![ABS]),
+            ("stdlib_depth.json", vec![DEPTH]),
+            ("stdlib_sext.json", vec![sext_8.as_str(), sext_16.as_str()]),
+            ("stdlib_memset.json", vec![MEMSET]),
+            ("memset_fill.json", vec![MEMSET]),
+            ("stdlib_pack_bytes.json", vec![PACK_BYTES, UNPACK_BYTES]),
+            ("stdlib_bitrev.json", vec![BITREV]),
+            ("stdlib_output.json", vec![OUTPUT]),
+            ("output_repeat.json", vec![OUTPUT]),
+            ("sum_loop.json", vec![OUTPUT]),
+            ("sum_folded.json", vec![OUTPUT]),
+        ] {
+            let program = fixture_program(file_name);
+            let main_end = program.find(routines[0]).unwrap();
+            assert_eq!(
+                link_asm(&program[..main_end], &routines),
+                program.trim_end(),
+                "{} is out of sync",
+                file_name
+            );
+        }
+    }
+
+    #[test]
+    fn test_mulhi_prophet_linked() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/stdlib_mulhi.json");
+        let json_str = std::fs::read_to_string(path).unwrap();
+        let bundle: serde_json::Value = serde_json::from_str(json_str.as_str()).unwrap();
+        assert_eq!(bundle["prophets"], serde_json::json!([mulhi_prophet()]));
+
+        let main = "main:\n.LBL0_0:\n  mov r1 7\n  mov r2 9\n  call mulhi\n  end\n";
+        let program = link_with_prophets(main, &[MULHI], &[mulhi_prophet()]).unwrap();
+        assert_eq!(program.prophets.len(), 1);
+    }
+
+    #[test]
+    fn test_range_bits() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/stdlib_range_bits.json");
+        let json_str = std::fs::read_to_string(path).unwrap();
+        let bundle: serde_json::Value = serde_json::from_str(json_str.as_str()).unwrap();
+        assert_eq!(
+            bundle["prophets"],
+            serde_json::json!([range_bits_prophet(40)])
+        );
+
+        assert_eq!(
+            range_bits(32),
+            "range_bits_32:\n.LBL1010_32:\n  range r1\n  ret\n"
+        );
+        assert_eq!(range_bits(64), "range_bits_64:\n.LBL1010_64:\n  ret\n");
+    }
+
+    #[test]
+    fn test_byte_prophet_linked() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/stdlib_byte.json");
+        let json_str = std::fs::read_to_string(path).unwrap();
+        let bundle: serde_json::Value = serde_json::from_str(json_str.as_str()).unwrap();
+        assert_eq!(
+            bundle["prophets"],
+            serde_json::json!([byte_prophet(0), byte_prophet(3), byte_prophet(7)])
+        );
+        // The lowest byte of a half is masked off as it is, no byte hint.
+        assert_eq!(byte_prophet(4)["outputs"].as_array().unwrap().len(), 1);
+        assert!(!byte(4).contains("mul r3 r0"));
+    }
+
+    #[test]
+    fn test_rotate_prophets_linked() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/stdlib_rotate.json");
+        let json_str = std::fs::read_to_string(path).unwrap();
+        let bundle: serde_json::Value = serde_json::from_str(json_str.as_str()).unwrap();
+        assert_eq!(
+            bundle["prophets"],
+            serde_json::json!([rotl_prophet(8), rotr_prophet(8)])
+        );
+        assert_eq!(
+            rotl(0),
+            "rotl_0:\n.LBL1004_0:\n  and r0 r1 4294967295\n  ret\n"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "rotation by 32 of a u32 word")]
+    fn test_rotate_u32_only() {
+        rotl(32);
+    }
+
+    #[test]
+    fn test_abs_prophet_linked() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/stdlib_abs.json");
+        let json_str = std::fs::read_to_string(path).unwrap();
+        let bundle: serde_json::Value = serde_json::from_str(json_str.as_str()).unwrap();
+        assert_eq!(bundle["prophets"], serde_json::json!([abs_prophet()]));
+    }
+
+    #[test]
+    fn test_depth_prophet_linked() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/stdlib_depth.json");
+        let json_str = std::fs::read_to_string(path).unwrap();
+        let bundle: serde_json::Value = serde_json::from_str(json_str.as_str()).unwrap();
+        assert_eq!(bundle["prophets"], serde_json::json!([depth_prophet()]));
+    }
+
+    #[test]
+    fn test_unpack_bytes_prophet_linked() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("test_data/asm/stdlib_pack_bytes.json");
+        let json_str = std::fs::read_to_string(path).unwrap();
+        let bundle: serde_json::Value = serde_json::from_str(json_str.as_str()).unwrap();
+        assert_eq!(
+            bundle["prophets"],
+            serde_json::json!([unpack_bytes_prophet()])
+        );
+    }
+
+    #[test]
+    fn test_link_bootloader() {
+        let main = "main:
+.LBL0_0:
+  mov r1 5
+  add r2 r1 r0
+  eq r3 r2 5
+  assert r3
+  end
+";
+        let linked = link_bootloader(ZERO_REGISTERS, main).unwrap();
+        assert_eq!(linked, fixture_program("bootloader_zero.json"));
+        assert!(link_bootloader(ZERO_REGISTERS, "foo:\n  ret\n").is_err());
+
+        let program = link(&linked, &[]).unwrap();
+        assert!(has_bootloader(&program, ZERO_REGISTERS).unwrap());
+        let program = link(main, &[]).unwrap();
+        assert!(!has_bootloader(&program, ZERO_REGISTERS).unwrap());
+    }
+
+    #[test]
+    fn test_link_stdlib() {
+        let main = "main:
+.LBL0_0:
+  add r9 r9 4
+  mstore [r9,-2] r9
+  mov r1 3
+  call mul_const_7
+  add r9 r9 -4
+  end
+";
+        let mul_7 = mul_const(7);
+        let program = link(main, &[MEMCPY, CMP, mul_7.as_str()]).unwrap();
+        let asm = program.debug_info.unwrap();
+        assert!(asm.values().any(|line| line == "mul r0 r1 7"));
+        assert!(asm.values().any(|line| line == "mload r6 [r6,0]"));
+    }
+}
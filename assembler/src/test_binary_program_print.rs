@@ -13,7 +13,7 @@ mod tests {
         let input_path = format!("test_data/asm/{}", input_file_name);
         let json_str = std::fs::read_to_string(&input_path).unwrap();
         let bundle: AsmBundle = serde_json::from_str(json_str.as_str()).unwrap();
-        let relocated = asm_relocate(bundle).unwrap();
+        let relocated = asm_relocate(bundle, false).unwrap();
         let program = encode_to_binary(relocated).unwrap();
         let instructions = decode_binary_program_to_instructions(program).unwrap();
         println!("============== {} ==================", input_file_name);
@@ -256,7 +256,7 @@ mod tests {
         let input_path = format!("test_data/asm/{}", input_file_name);
         let json_str = std::fs::read_to_string(&input_path).unwrap();
         let bundle: AsmBundle = serde_json::from_str(json_str.as_str()).unwrap();
-        let relocated = asm_relocate(bundle).unwrap();
+        let relocated = asm_relocate(bundle, false).unwrap();
         let program = encode_to_binary(relocated).unwrap();
 
         let output_path = format!("test_data/bin/{}", output_file_name);
@@ -276,7 +276,7 @@ mod tests {
             if path.is_file() {
                 let json_str = std::fs::read_to_string(&path.as_path()).unwrap();
                 let bundle: AsmBundle = serde_json::from_str(json_str.as_str()).unwrap();
-                let relocated = asm_relocate(bundle).unwrap();
+                let relocated = asm_relocate(bundle, false).unwrap();
                 let program = encode_to_binary(relocated).unwrap();
                 let out_file_name = path
                     .file_name()
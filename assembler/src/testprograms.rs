@@ -0,0 +1,139 @@
+//! Small asm programs of `test_data/asm` with the results they must leave,
+//! shared by the executor tests, which check the results of a run, and the
+//! circuits tests, which prove them. Enabled by the `testprograms` feature.
+
+use crate::encoder::encode_asm_from_json_file;
+use core::program::binary_program::BinaryProgram;
+use core::vm::memory::DATA_START_ADDR;
+
+/// What a registered program leaves behind once it reaches `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpectedOutputs {
+    /// `(index, value)` of the registers to check, the others are not
+    /// specified.
+    pub registers: &'static [(usize, u64)],
+    /// `(addr, value)` of the memory words to check, as the run ends.
+    pub memory: &'static [(u64, u64)],
+    /// Words the program outputs to the tape, in order.
+    pub outputs: &'static [u64],
+}
+
+impl ExpectedOutputs {
+    const fn registers(registers: &'static [(usize, u64)]) -> Self {
+        ExpectedOutputs {
+            registers,
+            memory: &[],
+            outputs: &[],
+        }
+    }
+}
+
+const PROGRAMS: [(&str, ExpectedOutputs); 18] = [
+    ("add_imm", ExpectedOutputs::registers(&[(0, 3), (1, 8)])),
+    ("add_mul", ExpectedOutputs::registers(&[(3, 5), (4, 30)])),
+    (
+        "vliw_movs",
+        ExpectedOutputs::registers(&[(1, 1), (2, 2), (3, 3)]),
+    ),
+    (
+        "add_carry",
+        ExpectedOutputs::registers(&[(1, 18446744069414584320), (2, 1), (4, 2)]),
+    ),
+    (
+        "bootloader_zero",
+        ExpectedOutputs::registers(&[(1, 5), (2, 5), (3, 1)]),
+    ),
+    (
+        "stdlib_mulhi",
+        ExpectedOutputs::registers(&[(0, 2793967723), (1, 3635412992)]),
+    ),
+    (
+        "stdlib_minmax",
+        ExpectedOutputs::registers(&[(0, 9), (1, 9), (2, 3)]),
+    ),
+    ("fibo_iter", ExpectedOutputs::registers(&[(1, 55)])),
+    ("rodata", ExpectedOutputs::registers(&[(3, 47)])),
+    ("early_end", ExpectedOutputs::registers(&[(3, 2)])),
+    // The immediate of the mov is the word of `end`, it is data and doesn't
+    // halt the program.
+    (
+        "imm_end",
+        ExpectedOutputs::registers(&[(1, 1048576), (2, 1048577), (3, 7)]),
+    ),
+    (
+        "spill_reload",
+        ExpectedOutputs {
+            registers: &[(0, 42)],
+            memory: &[(1, 42)],
+            outputs: &[],
+        },
+    ),
+    (
+        "lea_data",
+        ExpectedOutputs::registers(&[(0, DATA_START_ADDR), (1, 42)]),
+    ),
+    // Fills the 4 words below the call slots with 7, then zeroes them.
+    (
+        "memset_fill",
+        ExpectedOutputs {
+            registers: &[(0, 0), (8, 7)],
+            memory: &[(0, 0), (1, 0), (2, 0), (3, 0)],
+            outputs: &[],
+        },
+    ),
+    // Outputs in the order of the calls, repeated values included.
+    (
+        "output_repeat",
+        ExpectedOutputs {
+            registers: &[],
+            memory: &[],
+            outputs: &[5, 25, 25],
+        },
+    ),
+    (
+        "stdlib_output",
+        ExpectedOutputs {
+            registers: &[],
+            memory: &[],
+            outputs: &[3, 21, 121],
+        },
+    ),
+    // 5 + 4 + 3 + 2 + 1 summed in a loop and folded to a constant, the same
+    // output from different runs.
+    (
+        "sum_loop",
+        ExpectedOutputs {
+            registers: &[],
+            memory: &[],
+            outputs: &[15],
+        },
+    ),
+    (
+        "sum_folded",
+        ExpectedOutputs {
+            registers: &[],
+            memory: &[],
+            outputs: &[15],
+        },
+    ),
+];
+
+/// Names of the registered programs.
+pub fn names() -> impl Iterator<Item = &'static str> {
+    PROGRAMS.iter().map(|(name, _)| *name)
+}
+
+/// Path of the asm bundle of `name`.
+pub fn path(name: &str) -> String {
+    format!("{}/test_data/asm/{}.json", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+/// Encode the registered program `name`.
+pub fn load(name: &str) -> Result<(BinaryProgram, ExpectedOutputs), String> {
+    let expected = PROGRAMS
+        .iter()
+        .find(|(registered, _)| *registered == name)
+        .map(|(_, expected)| *expected)
+        .ok_or_else(|| format!("no test program named {}", name))?;
+    Ok((encode_asm_from_json_file(path(name))?, expected))
+}
@@ -3,7 +3,7 @@ mod tests {
     use core::program::binary_program::BinaryProgram;
     use std::path::PathBuf;
 
-    use crate::encoder::encode_asm_from_json_file;
+    use crate::encoder::{encode_asm_from_json_file, encode_asm_from_json_str};
     use core::program::decoder::decode_binary_program_to_instructions;
 
     #[test]
@@ -56,6 +56,51 @@ mod tests {
         test_decode("fibo_loop.json".to_string());
     }
 
+    #[test]
+    fn test_negative_immediate_round_trip() {
+        let bundle = serde_json::json!({
+            "program": "main:\n.LBL0_0:\n  add r7 r8 -2\n  end\n",
+            "prophets": [],
+        });
+        let program = encode_asm_from_json_str(bundle.to_string().as_str()).unwrap();
+        assert_eq!(program.bytecode.lines().nth(1), Some("0xfffffffeffffffff"));
+
+        let instructions = decode_binary_program_to_instructions(program).unwrap();
+        assert_eq!(instructions[0].get_asm_form_code(), "add r7 r8 -2");
+    }
+
+    #[test]
+    fn test_pseudo_instructions() {
+        let encode = |program: &str| {
+            let bundle = serde_json::json!({ "program": program, "prophets": [] });
+            encode_asm_from_json_str(bundle.to_string().as_str()).unwrap()
+        };
+        let pseudo = encode("main:\n.LBL0_0:\n  inc r0\n  clr r3\n  nop\n  end\n");
+        let real = encode("main:\n.LBL0_0:\n  add r0 r0 1\n  mov r3 0\n  add r0 r0 0\n  end\n");
+        assert_eq!(pseudo.bytecode, real.bytecode);
+
+        let instructions = decode_binary_program_to_instructions(real).unwrap();
+        let asm = instructions
+            .iter()
+            .map(|instruction| instruction.get_pseudo_asm_form_code())
+            .collect::<Vec<_>>();
+        assert_eq!(asm, vec!["inc r0", "clr r3", "nop", "end"]);
+        assert_eq!(instructions[0].get_asm_form_code(), "add r0 r0 1");
+
+        let pseudo = encode("main:\n.LBL0_0:\n  lea r0 data_start\n  lea r1 main\n  end\n");
+        let real = encode("main:\n.LBL0_0:\n  mov r0 18446744065119617026\n  mov r1 0\n  end\n");
+        assert_eq!(pseudo.bytecode, real.bytecode);
+        let instructions = decode_binary_program_to_instructions(real).unwrap();
+        assert_eq!(
+            instructions[0].get_pseudo_asm_form_code(),
+            "lea r0 data_start"
+        );
+
+        let pseudo = encode("main:\n.LBL0_0:\n  spill r3 -3\n  reload r4 -3\n  end\n");
+        let real = encode("main:\n.LBL0_0:\n  mstore [r9,-3] r3\n  mload r4 [r9,-3]\n  end\n");
+        assert_eq!(pseudo.bytecode, real.bytecode);
+    }
+
     fn test_decode(file_name: String) {
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         path.push("test_data/asm/");
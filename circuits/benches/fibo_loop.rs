@@ -1,7 +1,7 @@
 use assembler::encoder::encode_asm_from_json_file;
 use circuits::generation::{generate_traces, GenerationInputs};
 use circuits::stark::config::StarkConfig;
-use circuits::stark::ola_stark::OlaStark;
+use circuits::stark::ola_stark::{OlaStark, Table};
 use circuits::stark::proof::PublicValues;
 use circuits::stark::prover::prove_with_traces;
 use circuits::stark::verifier::verify_proof;
@@ -72,11 +72,11 @@ pub fn test_by_asm_json(path: String) {
     let mut ola_stark = OlaStark::default();
     let now = Instant::now();
     let (traces, public_values) =
-        generate_traces(program, &mut ola_stark, GenerationInputs::default());
+        generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
     info!(
         "generate_traces time:{}, len{}",
         now.elapsed().as_millis(),
-        traces[0].get(0).unwrap().values.len()
+        traces[Table::Cpu.index()][0].values.len()
     );
     let now = Instant::now();
 
@@ -87,6 +87,7 @@ pub fn test_by_asm_json(path: String) {
         traces,
         public_values,
         &mut TimingTree::default(),
+        None,
     );
     info!("prove_with_traces time:{}", now.elapsed().as_millis());
 
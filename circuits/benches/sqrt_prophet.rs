@@ -66,7 +66,7 @@ pub fn test_by_asm_json(path: String) {
     let mut ola_stark = OlaStark::default();
     let now = Instant::now();
     let (traces, public_values) =
-        generate_traces(program, &mut ola_stark, GenerationInputs::default());
+        generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
     info!("generate_traces time:{}", now.elapsed().as_millis());
     let now = Instant::now();
 
@@ -77,6 +77,7 @@ pub fn test_by_asm_json(path: String) {
         traces,
         public_values,
         &mut TimingTree::default(),
+        None,
     );
     info!("prove_with_traces time:{}", now.elapsed().as_millis());
 
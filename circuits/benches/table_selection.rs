@@ -0,0 +1,107 @@
+use assembler::encoder::encode_asm_from_json_file;
+use circuits::generation::{generate_traces, GenerationInputs};
+use circuits::stark::config::StarkConfig;
+use circuits::stark::ola_stark::{OlaStark, Table};
+use circuits::stark::proof::AllProof;
+use circuits::stark::prover::prove_with_traces;
+use circuits::stark::verifier::verify_proof;
+use core::merkle_tree::tree::AccountTree;
+use core::program::Program;
+use core::types::{Field, GoldilocksField};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use executor::run::execute_program;
+use executor::Process;
+use itertools::Itertools;
+use log::LevelFilter;
+use plonky2::plonk::config::{Blake3GoldilocksConfig, GenericConfig};
+use plonky2::util::timing::TimingTree;
+use std::path::PathBuf;
+
+const D: usize = 2;
+type C = Blake3GoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+/// Execute the asm program at `path`, returning the process to tell the
+/// tables it uses and the program to generate the traces from.
+fn execute(path: String, call_data: Option<Vec<GoldilocksField>>) -> (Process, Program) {
+    let program = encode_asm_from_json_file(path.clone())
+        .unwrap_or_else(|e| panic!("failed to encode {}: {}", path, e));
+    execute_program(
+        Process::new(),
+        &program,
+        call_data,
+        &mut AccountTree::new_test(),
+    )
+    .unwrap_or_else(|e| panic!("failed to execute {}: {:?}", path, e))
+}
+
+/// Prove `program` with the tables `ola_stark` has active.
+fn prove(ola_stark: &OlaStark<F, D>, program: Program) -> AllProof<F, C, D> {
+    let mut ola_stark = ola_stark.clone();
+    let (traces, public_values) =
+        generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+    prove_with_traces::<F, C, D>(
+        &ola_stark,
+        &StarkConfig::standard_fast_config(),
+        traces,
+        public_values,
+        &mut TimingTree::default(),
+        None,
+    )
+    .unwrap_or_else(|e| panic!("failed to prove: {:?}", e))
+}
+
+fn table_selection_benchmark(c: &mut Criterion) {
+    let _ = env_logger::builder()
+        .filter_level(LevelFilter::Info)
+        .try_init();
+    let fib_calldata = [10u64, 1u64, 2, 4185064725u64]
+        .iter()
+        .map(|v| GoldilocksField::from_canonical_u64(*v))
+        .collect_vec();
+    let programs = [
+        ("fibonacci", "benches/asm/fib_asm.json", Some(fib_calldata)),
+        ("bitwise", "../assembler/test_data/asm/bitwise.json", None),
+    ];
+
+    let config = StarkConfig::standard_fast_config();
+    let mut group = c.benchmark_group("table_selection");
+    for (name, file, call_data) in programs {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push(file);
+        let (process, program) = execute(path.display().to_string(), call_data);
+        let variants = [
+            ("all", OlaStark::default()),
+            (
+                "minimal",
+                OlaStark::builder().skip_unused_builtins(&process).build(),
+            ),
+        ];
+        let skipped = Table::all()
+            .iter()
+            .filter(|table| !variants[1].1.is_active(**table))
+            .collect_vec();
+        println!("{}: minimal set skips {:?}", name, skipped);
+
+        for (variant, ola_stark) in variants {
+            // Both sets have to give a valid proof for the timings to compare.
+            let proof = prove(&ola_stark, program.clone());
+            verify_proof(ola_stark.clone(), proof, &config)
+                .unwrap_or_else(|e| panic!("{} {} proof failed to verify: {:?}", name, variant, e));
+
+            group.bench_with_input(
+                BenchmarkId::new(name, variant),
+                &ola_stark,
+                |b, ola_stark| b.iter(|| prove(ola_stark, program.clone())),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group![
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = table_selection_benchmark
+];
+criterion_main!(benches);
@@ -0,0 +1,80 @@
+use assembler::encoder::encode_asm_from_json_str;
+use circuits::generation::predict::PredictTraceSizes;
+use core::merkle_tree::tree::AccountTree;
+use core::program::Program;
+use criterion::{criterion_group, criterion_main, Criterion};
+use executor::{Process, TxScopeCacheManager};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// System allocator counting the allocations and reallocations made.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const LOOP_ASM: &str = r#"{
+  "program": "main:\n.LBL0_0:\n  mov r1 0\n.LBL0_1:\n  add r1 r1 1\n  neq r2 r1 20000\n  cjmp r2 .LBL0_1\n  end\n",
+  "prophets": []
+}"#;
+
+fn loop_program() -> Program {
+    let binary = encode_asm_from_json_str(LOOP_ASM).unwrap();
+    Program::from_bytecode(&binary.bytecode).unwrap()
+}
+
+/// Execute the loop, returning the process and the allocations it took.
+fn execute(mut process: Process) -> (Process, usize) {
+    let mut program = loop_program();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    process
+        .execute(
+            &mut program,
+            &mut AccountTree::new_test(),
+            &mut TxScopeCacheManager::default(),
+        )
+        .unwrap();
+    (process, ALLOCATIONS.load(Ordering::Relaxed) - before)
+}
+
+fn trace_capacity_benchmark(c: &mut Criterion) {
+    let (process, allocations) = execute(Process::new());
+    let capacity = process.predict_capacity();
+    let (_, preallocated) = execute(Process::with_capacity(capacity));
+    println!(
+        "{} steps, {} memory rows: {} allocations, {} with capacity",
+        capacity.exec, capacity.memory, allocations, preallocated
+    );
+
+    let mut group = c.benchmark_group("trace_capacity");
+    group.bench_function("new", |b| b.iter(|| execute(Process::new())));
+    group.bench_function("with_capacity", |b| {
+        b.iter(|| execute(Process::with_capacity(capacity)))
+    });
+    group.finish();
+}
+
+criterion_group![
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = trace_capacity_benchmark
+];
+criterion_main!(benches);
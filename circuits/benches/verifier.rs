@@ -0,0 +1,88 @@
+use assembler::encoder::encode_asm_from_json_file;
+use circuits::generation::{generate_traces, GenerationInputs};
+use circuits::stark::config::StarkConfig;
+use circuits::stark::ola_stark::OlaStark;
+use circuits::stark::proof::AllProof;
+use circuits::stark::prover::prove_with_traces;
+use circuits::stark::verifier::verify_proof;
+use core::merkle_tree::tree::AccountTree;
+use core::types::{Field, GoldilocksField};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use executor::run::execute_program;
+use executor::Process;
+use itertools::Itertools;
+use log::LevelFilter;
+use plonky2::plonk::config::{Blake3GoldilocksConfig, GenericConfig};
+use plonky2::util::timing::TimingTree;
+use std::path::PathBuf;
+
+const D: usize = 2;
+type C = Blake3GoldilocksConfig;
+type F = <C as GenericConfig<D>>::F;
+
+/// Execute and prove the asm program at `path`. Panics if any step fails, so
+/// a broken prover shows up as a failed bench instead of a fast one.
+pub fn get_proof(path: String, call_data: Option<Vec<GoldilocksField>>) -> AllProof<F, C, D> {
+    let program = encode_asm_from_json_file(path.clone())
+        .unwrap_or_else(|e| panic!("failed to encode {}: {}", path, e));
+    let (_, program) = execute_program(
+        Process::new(),
+        &program,
+        call_data,
+        &mut AccountTree::new_test(),
+    )
+    .unwrap_or_else(|e| panic!("failed to execute {}: {:?}", path, e));
+
+    let mut ola_stark = OlaStark::default();
+    let (traces, public_values) =
+        generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+    let config = StarkConfig::standard_fast_config();
+    prove_with_traces::<F, C, D>(
+        &ola_stark,
+        &config,
+        traces,
+        public_values,
+        &mut TimingTree::default(),
+        None,
+    )
+    .unwrap_or_else(|e| panic!("failed to prove {}: {:?}", path, e))
+}
+
+fn verifier_benchmark(c: &mut Criterion) {
+    let _ = env_logger::builder()
+        .filter_level(LevelFilter::Info)
+        .try_init();
+    let fib_calldata = [10u64, 1u64, 2, 4185064725u64]
+        .iter()
+        .map(|v| GoldilocksField::from_canonical_u64(*v))
+        .collect_vec();
+    let programs = [
+        ("fibonacci", "benches/asm/fib_asm.json", Some(fib_calldata)),
+        ("memory", "../assembler/test_data/asm/memory.json", None),
+        ("bitwise", "../assembler/test_data/asm/bitwise.json", None),
+    ];
+
+    let config = StarkConfig::standard_fast_config();
+    let mut group = c.benchmark_group("verify_proof");
+    for (name, file, call_data) in programs {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push(file);
+        let proof = get_proof(path.display().to_string(), call_data);
+        verify_proof(OlaStark::default(), proof.clone(), &config)
+            .unwrap_or_else(|e| panic!("{} proof failed to verify: {:?}", name, e));
+
+        group.bench_with_input(BenchmarkId::from_parameter(name), &proof, |b, proof| {
+            b.iter(|| {
+                verify_proof(OlaStark::default(), proof.clone(), &config).unwrap();
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group![
+    name = benches;
+    config = Criterion::default().sample_size(10);
+    targets = verifier_benchmark
+];
+criterion_main!(benches);
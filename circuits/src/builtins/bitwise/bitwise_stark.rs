@@ -27,6 +27,14 @@ pub struct BitwiseStark<F, const D: usize> {
 impl<F: RichField, const D: usize> BitwiseStark<F, D> {
     const BASE: usize = 1 << 8;
 
+    /// Stark checking its compress columns against `challenge`.
+    pub fn with_compress_challenge(challenge: F) -> Self {
+        Self {
+            compress_challenge: Some(challenge),
+            _phantom: PhantomData,
+        }
+    }
+
     pub fn set_compress_challenge(&mut self, challenge: F) -> Result<()> {
         assert!(self.compress_challenge.is_none(), "already set?");
         self.compress_challenge = Some(challenge);
@@ -413,13 +421,19 @@ pub fn ctl_filter_with_bitwise_fixed<F: Field>() -> Column<F> {
 mod tests {
     use crate::builtins::bitwise::bitwise_stark::BitwiseStark;
     use crate::builtins::bitwise::columns::get_bitwise_col_name_map;
-    use crate::generation::builtin::generate_bitwise_trace;
+    use crate::builtins::bitwise::columns::{FIX_BITWSIE_RES, FIX_TAG};
+    use crate::generation::builtin::{
+        check_bitwise_fixed_table, generate_bitwise_trace, verify_fixed_tables,
+    };
     use crate::stark::constraint_consumer::ConstraintConsumer;
     use crate::stark::stark::Stark;
     use crate::stark::vars::StarkEvaluationVars;
+    use crate::test_utils::execute_asm_path;
     use assembler::encoder::encode_asm_from_json_file;
     use core::merkle_tree::tree::AccountTree;
+    use core::program::instruction::Opcode;
     use core::program::Program;
+    use core::trace::trace::BitwiseRow;
     use core::types::account::Address;
     use executor::{Process, TxScopeCacheManager};
     use plonky2::field::goldilocks_field::GoldilocksField;
@@ -533,4 +547,36 @@ mod tests {
         let program_path = path.display().to_string();
         test_bitwise_stark(program_path);
     }
+
+    #[test]
+    fn test_bitwise_row_limbs() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/bitwise.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+
+        // `and r5 r4 r3` with r4 = 30, r3 = 10.
+        let row = program
+            .trace
+            .bitwise_rows()
+            .find(|row| row.opcode == 1 << Opcode::AND as u64)
+            .unwrap();
+        assert_eq!((row.op0, row.op1, row.res), (30, 10, 10));
+        assert_eq!(BitwiseRow::from_limbs(row.op0_limbs), row.op0);
+        assert_eq!(BitwiseRow::from_limbs(row.op1_limbs), row.op1);
+        assert_eq!(BitwiseRow::from_limbs(row.res_limbs), row.res);
+    }
+
+    #[test]
+    fn test_verify_fixed_tables() {
+        verify_fixed_tables::<GoldilocksField>().unwrap();
+
+        // A wrong xor result, which a lookup of that xor would then match.
+        let (mut rows, _) = generate_bitwise_trace::<GoldilocksField>(&[]);
+        let row = rows[FIX_TAG]
+            .iter()
+            .position(|tag| *tag == GoldilocksField::from_canonical_u64(1 << Opcode::XOR as u64))
+            .unwrap();
+        rows[FIX_BITWSIE_RES][row] += GoldilocksField::ONE;
+        assert!(check_bitwise_fixed_table(&rows).is_err());
+    }
 }
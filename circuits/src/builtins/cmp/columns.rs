@@ -19,7 +19,7 @@ pub(crate) const COL_CMP_GTE: usize = COL_CMP_OP1 + 1;
 pub(crate) const COL_CMP_ABS_DIFF: usize = COL_CMP_GTE + 1;
 pub(crate) const COL_CMP_ABS_DIFF_INV: usize = COL_CMP_ABS_DIFF + 1;
 pub(crate) const COL_CMP_FILTER_LOOKING_RC: usize = COL_CMP_ABS_DIFF_INV + 1;
-pub(crate) const COL_NUM_CMP: usize = COL_CMP_FILTER_LOOKING_RC + 1;
+pub const COL_NUM_CMP: usize = COL_CMP_FILTER_LOOKING_RC + 1;
 
 #[allow(dead_code)]
 pub(crate) fn get_cmp_col_name_map() -> BTreeMap<usize, String> {
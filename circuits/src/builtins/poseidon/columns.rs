@@ -37,7 +37,7 @@ pub(crate) const COL_POSEIDON_FULL_ROUND_1_2_STATE_RANGE: Range<usize> =
 pub(crate) const COL_POSEIDON_FULL_ROUND_1_3_STATE_RANGE: Range<usize> =
     COL_POSEIDON_FULL_ROUND_1_2_STATE_RANGE.end
         ..COL_POSEIDON_FULL_ROUND_1_2_STATE_RANGE.end + POSEIDON_STATE_WIDTH;
-pub(crate) const NUM_POSEIDON_COLS: usize = COL_POSEIDON_FULL_ROUND_1_3_STATE_RANGE.end;
+pub const NUM_POSEIDON_COLS: usize = COL_POSEIDON_FULL_ROUND_1_3_STATE_RANGE.end;
 
 pub(crate) const COL_POSEIDON_CHUNK_TX_IDX: usize = 0;
 pub(crate) const COL_POSEIDON_CHUNK_ENV_IDX: usize = COL_POSEIDON_CHUNK_TX_IDX + 1;
@@ -65,7 +65,7 @@ pub(crate) const COL_POSEIDON_CHUNK_FILTER_LOOKING_POSEIDON: usize =
     COL_POSEIDON_CHUNK_FILTER_LOOKING_MEM_RANGE.end;
 pub(crate) const COL_POSEIDON_CHUNK_IS_PADDING_LINE: usize =
     COL_POSEIDON_CHUNK_FILTER_LOOKING_POSEIDON + 1;
-pub(crate) const NUM_POSEIDON_CHUNK_COLS: usize = COL_POSEIDON_CHUNK_IS_PADDING_LINE + 1;
+pub const NUM_POSEIDON_CHUNK_COLS: usize = COL_POSEIDON_CHUNK_IS_PADDING_LINE + 1;
 
 pub(crate) fn get_poseidon_col_name_map() -> BTreeMap<usize, String> {
     let mut m: BTreeMap<usize, String> = BTreeMap::new();
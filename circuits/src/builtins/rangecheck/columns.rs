@@ -18,6 +18,15 @@ Constraints as follows:
    val = limb_lo + 2^16* limb_hi
 2. Cross Lookup for limbs
     Lookup {<limbs>; RC_FIXED_TABLE}
+
+There is one row per checked value, duplicates included. A multiplicity
+column (distinct values with counts) can't be expressed with the current
+CTLs: `cross_table_lookup` compares grand products over the filtered rows,
+so every looking row needs its own looked row. Shrinking this table needs
+the CTLs to move to a logarithmic derivative argument first, where a looked
+row contributes `multiplicity / (beta + value)`. The table is at least
+RANGE_CHECK_U16_SIZE rows for the fixed column anyway, so duplicates only
+cost rows beyond 2^16 checks.
 */
 //Identify different Rangecheck TABLE
 // 0 => Main TABLE
@@ -39,6 +48,9 @@ pub(crate) const FIX_RANGE_CHECK_U16: usize = LIMB_HI_PERMUTED + 1;
 pub(crate) const FIX_RANGE_CHECK_U16_PERMUTED_LO: usize = FIX_RANGE_CHECK_U16 + 1;
 pub(crate) const FIX_RANGE_CHECK_U16_PERMUTED_HI: usize = FIX_RANGE_CHECK_U16_PERMUTED_LO + 1;
 
-pub(crate) const COL_NUM_RC: usize = FIX_RANGE_CHECK_U16_PERMUTED_HI + 1; //11
+pub const COL_NUM_RC: usize = FIX_RANGE_CHECK_U16_PERMUTED_HI + 1; //11
 
-pub(crate) const RANGE_CHECK_U16_SIZE: usize = 1 << 16; //4
+/// Bits of a limb. A value is checked as two limbs, each looked up in the
+/// fixed column `0..RANGE_CHECK_U16_SIZE`.
+pub const RANGE_CHECK_LIMB_BITS: usize = 16;
+pub const RANGE_CHECK_U16_SIZE: usize = 1 << RANGE_CHECK_LIMB_BITS;
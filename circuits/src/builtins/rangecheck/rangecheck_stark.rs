@@ -14,13 +14,29 @@ use plonky2::hash::hash_types::RichField;
 use plonky2::plonk::circuit_builder::CircuitBuilder;
 use std::marker::PhantomData;
 
-#[derive(Copy, Clone, Default)]
+#[derive(Copy, Clone)]
 pub struct RangeCheckStark<F, const D: usize> {
+    /// Size of the fixed column, each limb is below it and a value is
+    /// `limb_lo + limb_hi * limb_bound`.
+    pub limb_bound: usize,
     pub _phantom: PhantomData<F>,
 }
 
-impl<F: RichField, const D: usize> RangeCheckStark<F, D> {
-    const BASE: usize = 1 << 16;
+impl<F, const D: usize> Default for RangeCheckStark<F, D> {
+    fn default() -> Self {
+        Self::with_limb_bound(RANGE_CHECK_U16_SIZE)
+    }
+}
+
+impl<F, const D: usize> RangeCheckStark<F, D> {
+    /// Stark of a trace generated by `generate_rc_trace_with_bound` with the
+    /// same bound.
+    pub fn with_limb_bound(limb_bound: usize) -> Self {
+        Self {
+            limb_bound,
+            _phantom: PhantomData,
+        }
+    }
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for RangeCheckStark<F, D> {
@@ -42,7 +58,7 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for RangeCheckSta
         let limb_hi = vars.local_values[LIMB_HI];
 
         // Addition check for op0, op1, diff
-        let base = P::Scalar::from_canonical_usize(Self::BASE);
+        let base = P::Scalar::from_canonical_usize(self.limb_bound);
         let sum = limb_lo + limb_hi * base;
 
         yield_constr.constraint(val - sum);
@@ -72,7 +88,7 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for RangeCheckSta
         let limb_hi = vars.local_values[LIMB_HI];
 
         // Addition check for op0, op1, diff
-        let base = builder.constant_extension(F::Extension::from_canonical_usize(Self::BASE));
+        let base = builder.constant_extension(F::Extension::from_canonical_usize(self.limb_bound));
         let sum = builder.mul_add_extension(limb_hi, base, limb_lo);
         let val_sum_diff = builder.sub_extension(val, sum);
         yield_constr.constraint(builder, val_sum_diff);
@@ -147,18 +163,24 @@ pub fn ctl_filter_with_rangecheck_fixed<F: Field>() -> Column<F> {
 }*/
 #[cfg(test)]
 mod tests {
+    use crate::builtins::rangecheck::columns::{
+        COL_NUM_RC, FIX_RANGE_CHECK_U16, RANGE_CHECK_U16_SIZE,
+    };
     use crate::builtins::rangecheck::rangecheck_stark::RangeCheckStark;
-    use crate::generation::builtin::generate_rc_trace;
+    use crate::generation::builtin::{
+        check_rc_fixed_table, generate_rc_trace, generate_rc_trace_with_bound,
+    };
     use crate::stark::constraint_consumer::ConstraintConsumer;
     use crate::stark::stark::Stark;
     use crate::stark::vars::StarkEvaluationVars;
     use assembler::encoder::encode_asm_from_json_file;
     use core::merkle_tree::tree::AccountTree;
     use core::program::Program;
+    use core::trace::trace::RangeCheckRow;
     use core::types::account::Address;
     use executor::{Process, TxScopeCacheManager};
     use plonky2::field::goldilocks_field::GoldilocksField;
-    use plonky2::field::types::Field;
+    use plonky2::field::types::{Field, PrimeField64};
     use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
     use plonky2_util::log2_strict;
     use std::collections::HashMap;
@@ -199,13 +221,19 @@ mod tests {
         );
 
         let rows = generate_rc_trace::<F>(&program.trace.builtin_rangecheck);
-        let len = rows[0].len();
         println!(
             "raw trace len:{}, extended len: {}",
             program.trace.builtin_rangecheck.len(),
-            len
+            rows[0].len()
         );
+        assert!(rc_constraints_hold(&stark, &rows));
+    }
 
+    fn rc_constraints_hold(
+        stark: &RangeCheckStark<GoldilocksField, 2>,
+        rows: &[Vec<GoldilocksField>; COL_NUM_RC],
+    ) -> bool {
+        let len = rows[0].len();
         let last = F::primitive_root_of_unity(log2_strict(len)).inverse();
         let subgroup =
             F::cyclic_subgroup_known_order(F::primitive_root_of_unity(log2_strict(len)), len);
@@ -244,10 +272,53 @@ mod tests {
             for &acc in &constraint_consumer.constraint_accs {
                 if !acc.eq(&GoldilocksField::ZERO) {
                     println!("constraint error in line {}", i);
+                    return false;
                 }
-                assert_eq!(acc, GoldilocksField::ZERO);
             }
         }
+        true
+    }
+
+    #[test]
+    fn test_rangecheck_with_bound() {
+        let stark = RangeCheckStark::<GoldilocksField, 2>::with_limb_bound(1 << 8);
+        let row = |lo: u64, hi: u64| RangeCheckRow {
+            val: GoldilocksField::from_canonical_u64(lo + (hi << 8)),
+            limb_lo: GoldilocksField::from_canonical_u64(lo),
+            limb_hi: GoldilocksField::from_canonical_u64(hi),
+            filter_looked_for_mem_sort: GoldilocksField::ZERO,
+            filter_looked_for_mem_region: GoldilocksField::ZERO,
+            filter_looked_for_cpu: GoldilocksField::ONE,
+            filter_looked_for_comparison: GoldilocksField::ZERO,
+            filter_looked_for_storage: GoldilocksField::ZERO,
+        };
+
+        let rows = generate_rc_trace_with_bound(&[row(200, 0), row(200, 3)], 1 << 8);
+        assert_eq!(rows[0].len(), 1 << 8);
+        assert!(rc_constraints_hold(&stark, &rows));
+
+        let rows = generate_rc_trace_with_bound(&[row(300, 0)], 1 << 8);
+        assert!(!rc_constraints_hold(&stark, &rows));
+
+        // The limbs recombine with the stark's bound, not 2^16.
+        let rows = generate_rc_trace_with_bound(&[row(200, 3)], 1 << 8);
+        assert!(!rc_constraints_hold(&RangeCheckStark::default(), &rows));
+    }
+
+    #[test]
+    fn test_rc_fixed_table() {
+        let mut rows = generate_rc_trace::<GoldilocksField>(&[]);
+        let table = &rows[FIX_RANGE_CHECK_U16];
+        assert_eq!(table.len(), RANGE_CHECK_U16_SIZE);
+        assert!(table
+            .iter()
+            .enumerate()
+            .all(|(i, value)| value.to_canonical_u64() == i as u64));
+        check_rc_fixed_table(&rows, RANGE_CHECK_U16_SIZE).unwrap();
+
+        // A gap in the table lets a limb past it be looked up anywhere.
+        rows[FIX_RANGE_CHECK_U16][5] = rows[FIX_RANGE_CHECK_U16][4];
+        assert!(check_rc_fixed_table(&rows, RANGE_CHECK_U16_SIZE).is_err());
     }
 
     #[test]
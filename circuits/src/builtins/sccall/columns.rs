@@ -15,7 +15,7 @@ pub(crate) const COL_SCCALL_CALLER_REG_RANGE: Range<usize> =
 pub(crate) const COL_SCCALL_CALLEE_ENV_IDX: usize = COL_SCCALL_CALLER_REG_RANGE.end;
 pub(crate) const COL_SCCALL_CLK_CALLEE_END: usize = COL_SCCALL_CALLEE_ENV_IDX + 1;
 pub(crate) const COL_SCCALL_IS_PADDING: usize = COL_SCCALL_CLK_CALLEE_END + 1;
-pub(crate) const NUM_COL_SCCALL: usize = COL_SCCALL_IS_PADDING + 1;
+pub const NUM_COL_SCCALL: usize = COL_SCCALL_IS_PADDING + 1;
 
 #[allow(unused)]
 pub(crate) fn get_sccall_col_name_map() -> BTreeMap<usize, String> {
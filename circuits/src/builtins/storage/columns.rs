@@ -29,8 +29,10 @@ pub(crate) const COL_ST_ACC_LAYER_MARKER: usize = COL_ST_IS_LAYER_256 + 1;
 pub(crate) const COL_ST_FILTER_IS_HASH_BIT_0: usize = COL_ST_ACC_LAYER_MARKER + 1;
 pub(crate) const COL_ST_FILTER_IS_HASH_BIT_1: usize = COL_ST_FILTER_IS_HASH_BIT_0 + 1;
 pub(crate) const COL_ST_FILTER_IS_FOR_PROG: usize = COL_ST_FILTER_IS_HASH_BIT_1 + 1;
-pub(crate) const COL_ST_IS_PADDING: usize = COL_ST_FILTER_IS_FOR_PROG + 1;
-pub(crate) const NUM_COL_ST: usize = COL_ST_IS_PADDING + 1;
+pub(crate) const COL_ST_IS_UNDO: usize = COL_ST_FILTER_IS_FOR_PROG + 1;
+pub(crate) const COL_ST_IS_UNDONE: usize = COL_ST_IS_UNDO + 1;
+pub(crate) const COL_ST_IS_PADDING: usize = COL_ST_IS_UNDONE + 1;
+pub const NUM_COL_ST: usize = COL_ST_IS_PADDING + 1;
 
 #[allow(dead_code)]
 pub(crate) fn get_storage_access_col_name_map() -> BTreeMap<usize, String> {
@@ -91,6 +93,8 @@ pub(crate) fn get_storage_access_col_name_map() -> BTreeMap<usize, String> {
         COL_ST_FILTER_IS_FOR_PROG,
         String::from("FILTER_IS_FOR_PROG"),
     );
+    m.insert(COL_ST_IS_UNDO, String::from("IS_UNDO"));
+    m.insert(COL_ST_IS_UNDONE, String::from("IS_UNDONE"));
     m.insert(COL_ST_IS_PADDING, String::from("IS_PADDING"));
     m
 }
@@ -41,11 +41,39 @@ pub fn ctl_filter_with_cpu_sstore<F: Field>() -> Column<F> {
         [
             (COL_ST_IS_LAYER_256, F::ONE),
             (COL_ST_FILTER_IS_FOR_PROG, F::NEG_ONE),
+            (COL_ST_IS_UNDO, F::NEG_ONE),
         ],
         F::ZERO,
     )
 }
 
+// An undo writes back the leaf the write it undoes overwrote.
+pub fn ctl_data_undo<F: Field>() -> Vec<Column<F>> {
+    Column::singles(
+        COL_ST_ADDR_RANGE
+            .chain(COL_ST_PRE_PATH_RANGE)
+            .chain(COL_ST_PATH_RANGE),
+    )
+    .collect_vec()
+}
+
+pub fn ctl_filter_undo<F: Field>() -> Column<F> {
+    Column::single(COL_ST_IS_UNDO)
+}
+
+pub fn ctl_data_undone<F: Field>() -> Vec<Column<F>> {
+    Column::singles(
+        COL_ST_ADDR_RANGE
+            .chain(COL_ST_PATH_RANGE)
+            .chain(COL_ST_PRE_PATH_RANGE),
+    )
+    .collect_vec()
+}
+
+pub fn ctl_filter_undone<F: Field>() -> Column<F> {
+    Column::single(COL_ST_IS_UNDONE)
+}
+
 pub fn ctl_data_with_poseidon_bit0<F: Field>() -> Vec<Column<F>> {
     let mut res = Column::singles(COL_ST_PATH_RANGE.chain(COL_ST_SIB_RANGE)).collect_vec();
     res.push(Column::single(COL_ST_HASH_TYPE));
@@ -104,6 +132,9 @@ pub fn ctl_filter_with_poseidon_bit1<F: Field>() -> Column<F> {
 }
 #[derive(Copy, Clone, Default)]
 pub struct StorageAccessStark<F, const D: usize> {
+    /// Whether the run reverted, from the public
+    /// `PublicValues::reverted`: every write is then undone.
+    pub reverted: bool,
     pub _phantom: PhantomData<F>,
 }
 
@@ -319,6 +350,28 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for StorageAccess
         yield_constr.constraint(lv[COL_ST_FILTER_IS_FOR_PROG] * lv[COL_ST_IS_WRITE]);
         yield_constr
             .constraint(lv[COL_ST_FILTER_IS_FOR_PROG] * (P::ONES - lv[COL_ST_IS_LAYER_256]));
+
+        // undo constraints: an undo is a write, flagged on its leaf row. In a
+        // reverted run, the leaf row of every other write is undone, which
+        // the undo CTL matches with the undo rows one to one.
+        yield_constr.constraint(lv[COL_ST_IS_UNDO] * (P::ONES - lv[COL_ST_IS_UNDO]));
+        yield_constr.constraint(lv[COL_ST_IS_UNDO] * (P::ONES - lv[COL_ST_IS_LAYER_256]));
+        yield_constr.constraint(lv[COL_ST_IS_UNDO] * (lv[COL_ST_IS_WRITE] - P::ONES));
+        if self.reverted {
+            // is_write is 1 or 2 on a write.
+            yield_constr.constraint(
+                lv[COL_ST_IS_UNDONE]
+                    * (lv[COL_ST_IS_WRITE] - P::ONES)
+                    * (lv[COL_ST_IS_WRITE] - P::Scalar::from_canonical_u64(2)),
+            );
+            yield_constr.constraint(
+                (lv[COL_ST_IS_LAYER_256] - lv[COL_ST_IS_UNDO] - lv[COL_ST_IS_UNDONE])
+                    * lv[COL_ST_IS_WRITE],
+            );
+        } else {
+            yield_constr.constraint(lv[COL_ST_IS_UNDO]);
+            yield_constr.constraint(lv[COL_ST_IS_UNDONE]);
+        }
     }
 
     fn eval_ext_circuit(
@@ -6,7 +6,14 @@ pub(crate) const COL_TAPE_OPCODE: usize = COL_TAPE_IS_INIT_SEG + 1;
 pub(crate) const COL_TAPE_ADDR: usize = COL_TAPE_OPCODE + 1;
 pub(crate) const COL_TAPE_VALUE: usize = COL_TAPE_ADDR + 1;
 pub(crate) const COL_FILTER_LOOKED: usize = COL_TAPE_VALUE + 1;
-pub(crate) const NUM_COL_TAPE: usize = COL_FILTER_LOOKED + 1;
+// Rows after the last init or sccall row of the tx, the segment the return
+// data is written to.
+pub(crate) const COL_TAPE_IS_RET_SEG: usize = COL_FILTER_LOOKED + 1;
+// tstore rows of the return data segment, the outputs.
+pub(crate) const COL_TAPE_IS_OUTPUT: usize = COL_TAPE_IS_RET_SEG + 1;
+// Number of outputs before the row.
+pub(crate) const COL_TAPE_OUTPUT_IDX: usize = COL_TAPE_IS_OUTPUT + 1;
+pub const NUM_COL_TAPE: usize = COL_TAPE_OUTPUT_IDX + 1;
 
 #[allow(unused)]
 pub(crate) fn get_tape_col_name_map() -> BTreeMap<usize, String> {
@@ -17,5 +24,8 @@ pub(crate) fn get_tape_col_name_map() -> BTreeMap<usize, String> {
     m.insert(COL_TAPE_ADDR, "addr".to_string());
     m.insert(COL_TAPE_VALUE, "value".to_string());
     m.insert(COL_FILTER_LOOKED, "filter".to_string());
+    m.insert(COL_TAPE_IS_RET_SEG, "is_ret_seg".to_string());
+    m.insert(COL_TAPE_IS_OUTPUT, "is_output".to_string());
+    m.insert(COL_TAPE_OUTPUT_IDX, "output_idx".to_string());
     m
 }
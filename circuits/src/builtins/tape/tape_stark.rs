@@ -19,8 +19,9 @@ use crate::stark::{
 };
 
 use super::columns::{
-    COL_FILTER_LOOKED, COL_TAPE_ADDR, COL_TAPE_IS_INIT_SEG, COL_TAPE_OPCODE, COL_TAPE_TX_IDX,
-    COL_TAPE_VALUE, NUM_COL_TAPE,
+    COL_FILTER_LOOKED, COL_TAPE_ADDR, COL_TAPE_IS_INIT_SEG, COL_TAPE_IS_OUTPUT,
+    COL_TAPE_IS_RET_SEG, COL_TAPE_OPCODE, COL_TAPE_OUTPUT_IDX, COL_TAPE_TX_IDX, COL_TAPE_VALUE,
+    NUM_COL_TAPE,
 };
 
 pub fn ctl_data_tape<F: Field>() -> Vec<Column<F>> {
@@ -37,6 +38,14 @@ pub fn ctl_filter_tape<F: Field>() -> Column<F> {
     Column::single(COL_FILTER_LOOKED)
 }
 
+pub fn ctl_data_outputs<F: Field>() -> Vec<Column<F>> {
+    Column::singles([COL_TAPE_OUTPUT_IDX, COL_TAPE_VALUE]).collect_vec()
+}
+
+pub fn ctl_filter_outputs<F: Field>() -> Column<F> {
+    Column::single(COL_TAPE_IS_OUTPUT)
+}
+
 #[derive(Copy, Clone, Default)]
 pub struct TapeStark<F, const D: usize> {
     pub _phantom: PhantomData<F>,
@@ -128,6 +137,40 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for TapeStark<F,
                 * (lv[COL_TAPE_OPCODE] - op_tload)
                 * (P::ONES - lv[COL_FILTER_LOOKED]),
         );
+
+        // return data segment is the rows after the last init or sccall row of
+        // the tx: it starts once, holds no such row and runs to the end of the tx
+        let is_ret_seg = lv[COL_TAPE_IS_RET_SEG];
+        let not_ret_seg = P::ONES - is_ret_seg;
+        let is_not_init_or_sccall =
+            (P::ONES - lv[COL_TAPE_IS_INIT_SEG]) * (lv[COL_TAPE_OPCODE] - op_sccall);
+        yield_constr.constraint(is_ret_seg * not_ret_seg);
+        yield_constr.constraint(is_ret_seg * lv[COL_TAPE_IS_INIT_SEG]);
+        yield_constr.constraint(
+            is_ret_seg * (lv[COL_TAPE_OPCODE] - op_tload) * (lv[COL_TAPE_OPCODE] - op_tstore),
+        );
+        yield_constr.constraint_transition(
+            is_in_same_tx * is_ret_seg * (P::ONES - nv[COL_TAPE_IS_RET_SEG]),
+        );
+        yield_constr.constraint_transition(
+            is_in_same_tx * not_ret_seg * nv[COL_TAPE_IS_RET_SEG] * is_not_init_or_sccall,
+        );
+        yield_constr
+            .constraint_transition((P::ONES - is_in_same_tx) * not_ret_seg * is_not_init_or_sccall);
+        yield_constr.constraint_last_row(not_ret_seg * is_not_init_or_sccall);
+        // outputs are the tstore rows of the return data segment, so the data
+        // tstored for an sccall isn't one
+        yield_constr.constraint(
+            lv[COL_TAPE_IS_OUTPUT] * (op_tstore - op_tload)
+                - is_ret_seg * (lv[COL_TAPE_OPCODE] - op_tload),
+        );
+        // output_idx counts the outputs from 0 in each tx
+        yield_constr.constraint_first_row(lv[COL_TAPE_OUTPUT_IDX]);
+        yield_constr.constraint_transition(
+            is_in_same_tx
+                * (nv[COL_TAPE_OUTPUT_IDX] - lv[COL_TAPE_OUTPUT_IDX] - lv[COL_TAPE_IS_OUTPUT]),
+        );
+        yield_constr.constraint_transition((P::ONES - is_in_same_tx) * nv[COL_TAPE_OUTPUT_IDX]);
     }
 
     fn eval_ext_circuit(
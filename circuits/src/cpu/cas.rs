@@ -0,0 +1,37 @@
+use core::program::REGISTER_NUM;
+use core::types::Field;
+
+use plonky2::field::{extension::FieldExtension, packed::PackedField};
+
+use crate::stark::constraint_consumer::ConstraintConsumer;
+
+use super::{
+    columns::{COL_AUX0, COL_DST, COL_FILTER_CAS_WRITE, COL_S_CAS, COL_S_DST},
+    cpu_stark::CpuAdjacentRowWrapper,
+};
+
+// aux0 is the expected value, the dst register before the instruction, and
+// aux1 the value read from op0. Their equality is looked up in the cmp table
+// as aux1 >= aux0, flagged by cas_gte, and aux0 >= aux1, flagged by
+// dst + 1 - cas_gte, so dst is the success flag. The write of op1 is looked up
+// only when it is set.
+pub(crate) fn eval_packed_generic<F, FE, P, const D: usize, const D2: usize>(
+    wrapper: &CpuAdjacentRowWrapper<F, FE, P, D, D2>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) where
+    F: Field,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+{
+    let lv = wrapper.lv;
+    let s_dsts: [P; REGISTER_NUM] = lv[COL_S_DST].try_into().unwrap();
+    let expected: P = s_dsts
+        .iter()
+        .zip(wrapper.regs.iter())
+        .map(|(s, r)| *s * *r)
+        .sum();
+
+    yield_constr.constraint(lv[COL_S_CAS] * (lv[COL_AUX0] - expected));
+    yield_constr.constraint(lv[COL_S_CAS] * lv[COL_DST] * (P::ONES - lv[COL_DST]));
+    yield_constr.constraint(lv[COL_FILTER_CAS_WRITE] - lv[COL_S_CAS] * lv[COL_DST]);
+}
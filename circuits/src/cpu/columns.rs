@@ -86,6 +86,12 @@ pub(crate) const COL_S_DST: Range<usize> = COL_S_DST_START..COL_S_DST_START + RE
 // ┼────────┼────────┼───────|─────────┼──────────┼───────┼
 // │    0   │    0   │   0   |     0   │     0    │   0   │
 // ┴────────┴────────┴───────┴─────────┴──────────┴───────┴
+//
+// The opcode selectors are mutually exclusive (see `exclusive_column_groups`)
+// but stay one column each. They are used as CTL filters, which are linear
+// `Column`s, and gate the opcode constraints; a binary packed opcode index
+// would need a product of log2(NUM_OP_SELECTOR) factors to recover a single
+// selector, which neither a filter nor the degree 7 budget allows.
 pub(crate) const COL_S_SIMPLE_ARITHMATIC_OP: usize = COL_S_DST.end;
 pub(crate) const COL_S_MOV: usize = COL_S_SIMPLE_ARITHMATIC_OP + 1;
 pub(crate) const COL_S_JMP: usize = COL_S_MOV + 1;
@@ -117,9 +123,16 @@ pub(crate) const COL_S_SSTORE: usize = COL_S_SLOAD + 1;
 pub(crate) const COL_S_TLOAD: usize = COL_S_SSTORE + 1;
 pub(crate) const COL_S_TSTORE: usize = COL_S_TLOAD + 1;
 pub(crate) const COL_S_CALL_SC: usize = COL_S_TSTORE + 1;
-pub(crate) const NUM_OP_SELECTOR: usize = COL_S_CALL_SC - COL_S_SIMPLE_ARITHMATIC_OP + 1;
+pub(crate) const COL_S_SUB: usize = COL_S_CALL_SC + 1;
+pub(crate) const COL_S_SWAP: usize = COL_S_SUB + 1;
+pub(crate) const COL_S_ISQRT: usize = COL_S_SWAP + 1;
+pub(crate) const COL_S_CAS: usize = COL_S_ISQRT + 1;
+pub(crate) const COL_S_LOADP: usize = COL_S_CAS + 1;
+// Only a require which doesn't revert, a reverting one is an end row.
+pub(crate) const COL_S_REQUIRE: usize = COL_S_LOADP + 1;
+pub(crate) const NUM_OP_SELECTOR: usize = COL_S_REQUIRE - COL_S_SIMPLE_ARITHMATIC_OP + 1;
 
-pub(crate) const COL_IS_ENTRY_SC: usize = COL_S_CALL_SC + 1;
+pub(crate) const COL_IS_ENTRY_SC: usize = COL_S_REQUIRE + 1;
 pub(crate) const COL_IS_NEXT_LINE_DIFF_INST: usize = COL_IS_ENTRY_SC + 1;
 pub(crate) const COL_IS_NEXT_LINE_SAME_TX: usize = COL_IS_NEXT_LINE_DIFF_INST + 1;
 
@@ -128,9 +141,13 @@ pub(crate) const IS_SCCALL_EXT_LINE: usize = COL_FILTER_TAPE_LOOKING + 1;
 pub(crate) const COL_IS_STORAGE_EXT_LINE: usize = IS_SCCALL_EXT_LINE + 1;
 pub(crate) const COL_FILTER_SCCALL_END: usize = COL_IS_STORAGE_EXT_LINE + 1;
 pub(crate) const COL_FILTER_LOOKING_PROG_IMM: usize = COL_FILTER_SCCALL_END + 1;
-pub(crate) const COL_IS_PADDING: usize = COL_FILTER_LOOKING_PROG_IMM + 1;
+pub(crate) const COL_FILTER_CAS_WRITE: usize = COL_FILTER_LOOKING_PROG_IMM + 1;
+pub(crate) const COL_CAS_GTE: usize = COL_FILTER_CAS_WRITE + 1;
+// u32 flag of the instruction, only set on a subu.
+pub(crate) const COL_SUB_U32: usize = COL_CAS_GTE + 1;
+pub(crate) const COL_IS_PADDING: usize = COL_SUB_U32 + 1;
 
-pub(crate) const NUM_CPU_COLS: usize = COL_IS_PADDING + 1;
+pub const NUM_CPU_COLS: usize = COL_IS_PADDING + 1;
 
 #[allow(unused)]
 pub(crate) fn get_cpu_col_name_map() -> BTreeMap<usize, String> {
@@ -196,6 +213,12 @@ pub(crate) fn get_cpu_col_name_map() -> BTreeMap<usize, String> {
     m.insert(COL_S_TLOAD, "s_tload".to_string());
     m.insert(COL_S_TSTORE, "s_tstore".to_string());
     m.insert(COL_S_CALL_SC, "s_call_sc".to_string());
+    m.insert(COL_S_SUB, "s_sub".to_string());
+    m.insert(COL_S_SWAP, "s_swap".to_string());
+    m.insert(COL_S_ISQRT, "s_isqrt".to_string());
+    m.insert(COL_S_CAS, "s_cas".to_string());
+    m.insert(COL_S_LOADP, "s_loadp".to_string());
+    m.insert(COL_S_REQUIRE, "s_require".to_string());
     m.insert(COL_IS_ENTRY_SC, "is_entry_sc".to_string());
     m.insert(
         COL_IS_NEXT_LINE_DIFF_INST,
@@ -210,10 +233,40 @@ pub(crate) fn get_cpu_col_name_map() -> BTreeMap<usize, String> {
         COL_FILTER_LOOKING_PROG_IMM,
         "filter_looking_prog_imm".to_string(),
     );
+    m.insert(COL_FILTER_CAS_WRITE, "filter_cas_write".to_string());
+    m.insert(COL_CAS_GTE, "cas_gte".to_string());
+    m.insert(COL_SUB_U32, "sub_u32".to_string());
     m.insert(COL_IS_PADDING, "is_padding".to_string());
     m
 }
 
+/// Split `cols` into groups of columns that are never nonzero on the same row
+/// of `trace`, greedily in the given order. Each group could share a single
+/// packed column as far as the trace values are concerned.
+#[cfg(test)]
+fn exclusive_column_groups<F: plonky2::field::types::Field>(
+    trace: &[Vec<F>],
+    cols: &[usize],
+) -> Vec<Vec<usize>> {
+    let exclusive = |a: usize, b: usize| {
+        trace[a]
+            .iter()
+            .zip(trace[b].iter())
+            .all(|(x, y)| x.is_zero() || y.is_zero())
+    };
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for &col in cols {
+        match groups
+            .iter_mut()
+            .find(|group| group.iter().all(|&other| exclusive(col, other)))
+        {
+            Some(group) => group.push(col),
+            None => groups.push(vec![col]),
+        }
+    }
+    groups
+}
+
 #[test]
 fn print_cpu_cols() {
     let m = get_cpu_col_name_map();
@@ -221,3 +274,39 @@ fn print_cpu_cols() {
         println!("{}: {}", col, name);
     }
 }
+
+#[test]
+fn test_op_selectors_exclusive() {
+    use crate::generation::cpu::generate_cpu_trace;
+    use crate::test_utils::asm_fixture_exec;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    // The selector columns a packed opcode index would replace, measured on
+    // programs covering the opcodes.
+    let selectors: Vec<usize> = (COL_S_SIMPLE_ARITHMATIC_OP..=COL_S_REQUIRE).collect();
+    for name in [
+        "fibo_loop",
+        "bitwise",
+        "comparison",
+        "memory",
+        "call",
+        "range_check",
+        "isqrt",
+        "cas",
+        "loadp",
+        "poseidon",
+        "sub_u32",
+        "require_pass",
+    ] {
+        let trace: [Vec<GoldilocksField>; NUM_CPU_COLS] =
+            generate_cpu_trace(&asm_fixture_exec(name));
+        let groups = exclusive_column_groups(&trace, &selectors);
+        println!(
+            "{}: {} selector columns pack into {}",
+            name,
+            selectors.len(),
+            groups.len()
+        );
+        assert_eq!(groups, vec![selectors.clone()], "{}", name);
+    }
+}
@@ -0,0 +1,54 @@
+use super::columns::*;
+use crate::stark::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use plonky2::{
+    field::{extension::Extendable, packed::PackedField, types::Field},
+    hash::hash_types::RichField,
+    iop::ext_target::ExtensionTarget,
+    plonk::circuit_builder::CircuitBuilder,
+};
+
+// dst is the witnessed root r of op1, op1 = r^2 + e with e = 2q + s and q =
+// aux0 + aux1 * 2^31. op0 = s and aux1 are bits. Three range checks make
+// r the floor root without the field wrapping around:
+// - 2 * aux0 is u32, so aux0 < 2^31,
+// - r + aux1 is u32, so r < 2^32 and r = 2^32 - 1 only with q < 2^31, which
+//   keeps r^2 + e below p,
+// - r - q - s is u32, so e <= 2r and op1 < (r + 1)^2.
+pub(crate) fn eval_packed_generic<P: PackedField>(
+    lv: &[P; NUM_CPU_COLS],
+    _nv: &[P; NUM_CPU_COLS],
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    let q_hi = P::Scalar::from_canonical_u64(1 << 31);
+    let q = lv[COL_AUX0] + lv[COL_AUX1] * q_hi;
+    let e = q + q + lv[COL_OP0];
+    yield_constr.constraint(lv[COL_S_ISQRT] * (lv[COL_OP1] - lv[COL_DST] * lv[COL_DST] - e));
+    yield_constr.constraint(lv[COL_S_ISQRT] * lv[COL_OP0] * (P::ONES - lv[COL_OP0]));
+    yield_constr.constraint(lv[COL_S_ISQRT] * lv[COL_AUX1] * (P::ONES - lv[COL_AUX1]));
+}
+
+#[allow(dead_code)]
+pub(crate) fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lv: &[ExtensionTarget<D>; NUM_CPU_COLS],
+    _nv: &[ExtensionTarget<D>; NUM_CPU_COLS],
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let q_hi = builder.constant_extension(F::Extension::from_canonical_u64(1 << 31));
+    let q = builder.mul_add_extension(lv[COL_AUX1], q_hi, lv[COL_AUX0]);
+    let e = builder.add_extension(q, q);
+    let e = builder.add_extension(e, lv[COL_OP0]);
+    let square = builder.mul_extension(lv[COL_DST], lv[COL_DST]);
+    let diff = builder.sub_extension(lv[COL_OP1], square);
+    let diff = builder.sub_extension(diff, e);
+    let cs = builder.mul_extension(lv[COL_S_ISQRT], diff);
+    yield_constr.constraint(builder, cs);
+
+    let one = builder.one_extension();
+    for col in [COL_OP0, COL_AUX1] {
+        let not_bit = builder.sub_extension(one, lv[col]);
+        let bit_bool = builder.mul_extension(lv[col], not_bit);
+        let cs = builder.mul_extension(lv[COL_S_ISQRT], bit_bool);
+        yield_constr.constraint(builder, cs);
+    }
+}
@@ -0,0 +1,38 @@
+use core::types::Field;
+
+use plonky2::field::{extension::FieldExtension, packed::PackedField};
+
+use crate::stark::constraint_consumer::ConstraintConsumer;
+
+use super::{
+    columns::{COL_IS_EXT_LINE, COL_OP0, COL_OP1, COL_OP1_IMM, COL_PC, COL_S_CJMP},
+    cpu_stark::CpuAdjacentRowWrapper,
+};
+
+pub(crate) fn eval_packed_generic<F, FE, P, const D: usize, const D2: usize>(
+    wrapper: &CpuAdjacentRowWrapper<F, FE, P, D, D2>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) where
+    F: Field,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+{
+    let lv = wrapper.lv;
+    let nv = wrapper.nv;
+    let is_main_next = P::ONES - nv[COL_IS_EXT_LINE];
+    let cond = lv[COL_OP0];
+
+    // The condition must be boolean, otherwise a condition of 2 would select
+    // neither target nor fall-through below. Conditions produced by eq/neq are
+    // boolean by their own constraints, so `eq; cjmp` is sound.
+    yield_constr.constraint(is_main_next * lv[COL_S_CJMP] * cond * (P::ONES - cond));
+
+    // Jump to op1 when the condition holds, otherwise step over the
+    // instruction and its immediate, if any.
+    let fall_through = lv[COL_PC] + P::ONES + lv[COL_OP1_IMM];
+    yield_constr.constraint(
+        is_main_next
+            * lv[COL_S_CJMP]
+            * (nv[COL_PC] - (cond * lv[COL_OP1] + (P::ONES - cond) * fall_through)),
+    );
+}
@@ -0,0 +1,47 @@
+use core::program::REGISTER_NUM;
+use core::types::Field;
+
+use itertools::izip;
+use plonky2::field::{extension::FieldExtension, packed::PackedField};
+
+use crate::stark::constraint_consumer::ConstraintConsumer;
+
+use super::{
+    columns::{COL_AUX1, COL_DST, COL_S_DST, COL_S_LOADP, COL_S_OP1},
+    cpu_stark::CpuAdjacentRowWrapper,
+};
+
+// dst register takes the word at op0 and op1 register the word at op0 + 1,
+// which is aux1. Both words are looked up in memory, other registers carry
+// forward.
+pub(crate) fn eval_packed_generic<F, FE, P, const D: usize, const D2: usize>(
+    wrapper: &CpuAdjacentRowWrapper<F, FE, P, D, D2>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) where
+    F: Field,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+{
+    let lv = wrapper.lv;
+    let s_dsts: [P; REGISTER_NUM] = lv[COL_S_DST].try_into().unwrap();
+    let s_op1s: [P; REGISTER_NUM] = lv[COL_S_OP1].try_into().unwrap();
+
+    let overlap: P = s_dsts
+        .iter()
+        .zip(s_op1s.iter())
+        .map(|(s_dst, s_op1)| *s_dst * *s_op1)
+        .sum();
+    yield_constr.constraint(lv[COL_S_LOADP] * overlap);
+
+    for (s_dst, s_op1, l_r, n_r) in izip!(
+        s_dsts.iter(),
+        s_op1s.iter(),
+        wrapper.regs.iter(),
+        wrapper.n_regs.iter()
+    ) {
+        yield_constr.constraint_transition(
+            lv[COL_S_LOADP]
+                * (*n_r - *l_r - *s_dst * (lv[COL_DST] - *l_r) - *s_op1 * (lv[COL_AUX1] - *l_r)),
+        );
+    }
+}
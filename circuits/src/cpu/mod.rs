@@ -1,15 +1,22 @@
 // mod add;
 // mod assert;
 mod call;
+mod cas;
+mod isqrt;
 // mod cmp;
 pub mod columns;
 pub mod cpu_stark;
+mod jump;
+mod loadp;
 mod mload;
 mod mov;
 mod mstore;
+mod require;
 // mod mul;
 mod call_sc;
 mod ret;
 mod simple_arithmatic_op;
 mod storage;
+mod sub;
+mod swap;
 mod tape;
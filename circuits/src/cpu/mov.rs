@@ -7,12 +7,17 @@ use plonky2::{
     plonk::circuit_builder::CircuitBuilder,
 };
 
+// `mov rX clk` selects rX as op0, which no other mov does, and writes the
+// clock instead of op1.
 pub(crate) fn eval_packed_generic<P: PackedField>(
     lv: &[P; NUM_CPU_COLS],
     _nv: &[P; NUM_CPU_COLS],
     yield_constr: &mut ConstraintConsumer<P>,
 ) {
-    yield_constr.constraint(lv[COL_S_MOV] * (lv[COL_DST] - lv[COL_OP1]));
+    let is_clk: P = lv[COL_S_OP0].iter().copied().sum();
+    yield_constr.constraint(
+        lv[COL_S_MOV] * (lv[COL_DST] - lv[COL_OP1] - is_clk * (lv[COL_CLK] - lv[COL_OP1])),
+    );
 }
 
 #[allow(dead_code)]
@@ -22,7 +27,10 @@ pub(crate) fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
     _nv: &[ExtensionTarget<D>; NUM_CPU_COLS],
     yield_constr: &mut RecursiveConstraintConsumer<F, D>,
 ) {
+    let is_clk = builder.add_many_extension(&lv[COL_S_OP0]);
+    let clk_diff = builder.sub_extension(lv[COL_CLK], lv[COL_OP1]);
     let ret = builder.sub_extension(lv[COL_DST], lv[COL_OP1]);
+    let ret = builder.arithmetic_extension(F::NEG_ONE, F::ONE, is_clk, clk_diff, ret);
     let cs = builder.mul_extension(lv[COL_S_MOV], ret);
     yield_constr.constraint(builder, cs);
 }
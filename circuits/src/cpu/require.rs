@@ -0,0 +1,32 @@
+use super::columns::*;
+use crate::stark::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use plonky2::{
+    field::{extension::Extendable, packed::PackedField},
+    hash::hash_types::RichField,
+    iop::ext_target::ExtensionTarget,
+    plonk::circuit_builder::CircuitBuilder,
+};
+
+// A require row which continues has a nonzero op1, witnessed by its inverse
+// in aux0. A require on zero is an end row, see `constraint_opcode_selector`.
+pub(crate) fn eval_packed_generic<P: PackedField>(
+    lv: &[P; NUM_CPU_COLS],
+    _nv: &[P; NUM_CPU_COLS],
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    yield_constr.constraint(lv[COL_S_REQUIRE] * (P::ONES - lv[COL_OP1] * lv[COL_AUX0]));
+}
+
+#[allow(dead_code)]
+pub(crate) fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lv: &[ExtensionTarget<D>; NUM_CPU_COLS],
+    _nv: &[ExtensionTarget<D>; NUM_CPU_COLS],
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let one = builder.one_extension();
+    let product = builder.mul_extension(lv[COL_OP1], lv[COL_AUX0]);
+    let not_inverse = builder.sub_extension(one, product);
+    let cs = builder.mul_extension(lv[COL_S_REQUIRE], not_inverse);
+    yield_constr.constraint(builder, cs);
+}
@@ -38,6 +38,9 @@ pub(crate) fn eval_packed_generic<F, FE, P, const D2: usize>(
         * (lv[COL_OPCODE] - P::Scalar::from_canonical_u64(OlaOpcode::EQ.binary_bit_mask()))
         * (lv[COL_OPCODE] - P::Scalar::from_canonical_u64(OlaOpcode::NEQ.binary_bit_mask()));
 
+    // op1 is either a register or the immediate, which is bound to COL_OP1 by
+    // the instruction encoding constraint, so `add r1 r0 5` is checked here as
+    // well.
     yield_constr.constraint(is_add * (lv[COL_DST] - (lv[COL_OP0] + lv[COL_OP1])));
     yield_constr.constraint(is_mul * (lv[COL_DST] - lv[COL_OP0] * lv[COL_OP1]));
 
@@ -48,6 +51,9 @@ pub(crate) fn eval_packed_generic<F, FE, P, const D2: usize>(
     let eq_cs = is_eq * (res * op_diff + (P::ONES - res) * (P::ONES - diff_aux));
     let neq_cs = is_neq * ((P::ONES - res) * op_diff + res * (P::ONES - diff_aux));
     yield_constr.constraint(eq_cs + neq_cs);
+    // the result feeds cjmp, keep it boolean
+    yield_constr.constraint((is_eq + is_neq) * res * (P::ONES - res));
 
+    yield_constr.section("assert");
     yield_constr.constraint(is_assert * (P::ONES - lv[COL_OP1]));
 }
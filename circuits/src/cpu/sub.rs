@@ -0,0 +1,52 @@
+use super::columns::*;
+use crate::stark::constraint_consumer::{ConstraintConsumer, RecursiveConstraintConsumer};
+use plonky2::{
+    field::{extension::Extendable, packed::PackedField, types::Field},
+    hash::hash_types::RichField,
+    iop::ext_target::ExtensionTarget,
+    plonk::circuit_builder::CircuitBuilder,
+};
+
+pub(crate) fn eval_packed_generic<P: PackedField>(
+    lv: &[P; NUM_CPU_COLS],
+    _nv: &[P; NUM_CPU_COLS],
+    yield_constr: &mut ConstraintConsumer<P>,
+) {
+    // dst = op0 - op1 + borrow * 2^32, with aux0 the borrow, which only a
+    // subu may set: a sub is the field subtraction. The dst of a subu is
+    // range checked through the rangecheck table, so only the borrow set when
+    // op1 > op0 gives a u32.
+    let wrap = P::Scalar::from_canonical_u64(1 << 32);
+    yield_constr.constraint(
+        lv[COL_S_SUB] * (lv[COL_DST] + lv[COL_OP1] - lv[COL_OP0] - lv[COL_AUX0] * wrap),
+    );
+    yield_constr.constraint(lv[COL_S_SUB] * lv[COL_AUX0] * (P::ONES - lv[COL_AUX0]));
+    yield_constr.constraint(lv[COL_S_SUB] * lv[COL_AUX0] * (P::ONES - lv[COL_SUB_U32]));
+}
+
+#[allow(dead_code)]
+pub(crate) fn eval_ext_circuit<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    lv: &[ExtensionTarget<D>; NUM_CPU_COLS],
+    _nv: &[ExtensionTarget<D>; NUM_CPU_COLS],
+    yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+) {
+    let dst_add_op1 = builder.add_extension(lv[COL_DST], lv[COL_OP1]);
+    let diff = builder.sub_extension(dst_add_op1, lv[COL_OP0]);
+    let wrap = builder.constant_extension(F::Extension::from_canonical_u64(1 << 32));
+    let borrow = builder.mul_extension(lv[COL_AUX0], wrap);
+    let diff = builder.sub_extension(diff, borrow);
+    let cs = builder.mul_extension(lv[COL_S_SUB], diff);
+    yield_constr.constraint(builder, cs);
+
+    let one = builder.one_extension();
+    let not_borrow = builder.sub_extension(one, lv[COL_AUX0]);
+    let borrow_bool = builder.mul_extension(lv[COL_AUX0], not_borrow);
+    let cs = builder.mul_extension(lv[COL_S_SUB], borrow_bool);
+    yield_constr.constraint(builder, cs);
+
+    let not_u32 = builder.sub_extension(one, lv[COL_SUB_U32]);
+    let field_borrow = builder.mul_extension(lv[COL_AUX0], not_u32);
+    let cs = builder.mul_extension(lv[COL_S_SUB], field_borrow);
+    yield_constr.constraint(builder, cs);
+}
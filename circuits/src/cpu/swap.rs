@@ -0,0 +1,38 @@
+use core::program::REGISTER_NUM;
+use core::types::Field;
+
+use itertools::izip;
+use plonky2::field::{extension::FieldExtension, packed::PackedField};
+
+use crate::stark::constraint_consumer::ConstraintConsumer;
+
+use super::{
+    columns::{COL_OP0, COL_OP1, COL_S_OP0, COL_S_OP1, COL_S_SWAP},
+    cpu_stark::CpuAdjacentRowWrapper,
+};
+
+pub(crate) fn eval_packed_generic<F, FE, P, const D: usize, const D2: usize>(
+    wrapper: &CpuAdjacentRowWrapper<F, FE, P, D, D2>,
+    yield_constr: &mut ConstraintConsumer<P>,
+) where
+    F: Field,
+    FE: FieldExtension<D2, BaseField = F>,
+    P: PackedField<Scalar = FE>,
+{
+    let lv = wrapper.lv;
+    let s_op0s: [P; REGISTER_NUM] = lv[COL_S_OP0].try_into().unwrap();
+    let s_op1s: [P; REGISTER_NUM] = lv[COL_S_OP1].try_into().unwrap();
+
+    // op0 register takes op1 value and op1 register takes op0 value, others
+    // carry forward. Swapping a register with itself leaves it unchanged.
+    for (s_op0, s_op1, l_r, n_r) in izip!(
+        s_op0s.iter(),
+        s_op1s.iter(),
+        wrapper.regs.iter(),
+        wrapper.n_regs.iter()
+    ) {
+        let diff = lv[COL_OP1] - lv[COL_OP0];
+        yield_constr
+            .constraint_transition(lv[COL_S_SWAP] * (*n_r - *l_r - *s_op0 * diff + *s_op1 * diff));
+    }
+}
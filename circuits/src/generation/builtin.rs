@@ -1,3 +1,4 @@
+use anyhow::{bail, ensure, Result};
 use core::program::instruction::Opcode;
 use core::trace::trace::{BitwiseCombinedRow, CmpRow, RangeCheckRow};
 use plonky2::field::types::PrimeField64;
@@ -13,6 +14,7 @@ use crate::builtins::cmp::columns::{
 };
 use crate::builtins::rangecheck::columns as rangecheck;
 use crate::stark::lookup::permuted_cols;
+use std::collections::HashSet;
 
 // add by xb 2023-1-5
 // case 1:
@@ -130,32 +132,7 @@ pub fn generate_bitwise_trace<F: RichField>(
     }
     let beta = challenger.get_challenge();
 
-    for i in 0..trace[0].len() {
-        trace[bitwise::COMPRESS_LIMBS.start][i] = trace[bitwise::TAG][i]
-            + trace[bitwise::OP0_LIMBS.start][i] * beta
-            + trace[bitwise::OP1_LIMBS.start][i] * beta * beta
-            + trace[bitwise::RES_LIMBS.start][i] * beta * beta * beta;
-
-        trace[bitwise::COMPRESS_LIMBS.start + 1][i] = trace[bitwise::TAG][i]
-            + trace[bitwise::OP0_LIMBS.start + 1][i] * beta
-            + trace[bitwise::OP1_LIMBS.start + 1][i] * beta * beta
-            + trace[bitwise::RES_LIMBS.start + 1][i] * beta * beta * beta;
-
-        trace[bitwise::COMPRESS_LIMBS.start + 2][i] = trace[bitwise::TAG][i]
-            + trace[bitwise::OP0_LIMBS.start + 2][i] * beta
-            + trace[bitwise::OP1_LIMBS.start + 2][i] * beta * beta
-            + trace[bitwise::RES_LIMBS.start + 2][i] * beta * beta * beta;
-
-        trace[bitwise::COMPRESS_LIMBS.start + 3][i] = trace[bitwise::TAG][i]
-            + trace[bitwise::OP0_LIMBS.start + 3][i] * beta
-            + trace[bitwise::OP1_LIMBS.start + 3][i] * beta * beta
-            + trace[bitwise::RES_LIMBS.start + 3][i] * beta * beta * beta;
-
-        trace[bitwise::FIX_COMPRESS][i] = trace[bitwise::FIX_TAG][i]
-            + trace[bitwise::FIX_BITWSIE_OP0][i] * beta
-            + trace[bitwise::FIX_BITWSIE_OP1][i] * beta * beta
-            + trace[bitwise::FIX_BITWSIE_RES][i] * beta * beta * beta;
-    }
+    fill_bitwise_compress_cols(&mut trace, beta);
 
     // add the permutation information
     for i in 0..4 {
@@ -183,15 +160,6 @@ pub fn generate_bitwise_trace<F: RichField>(
 
         trace[bitwise::RES_LIMBS_PERMUTED.start + i] = permuted_inputs;
         trace[bitwise::FIX_RANGE_CHECK_U8_PERMUTED.start + 8 + i] = permuted_table;
-
-        // permutation for bitwise
-        let (permuted_inputs, permuted_table) = permuted_cols(
-            &trace[bitwise::COMPRESS_LIMBS.start + i],
-            &trace[bitwise::FIX_COMPRESS],
-        );
-
-        trace[bitwise::COMPRESS_PERMUTED.start + i] = permuted_inputs;
-        trace[bitwise::FIX_COMPRESS_PERMUTED.start + i] = permuted_table;
     }
 
     let trace_row_vecs = trace.try_into().unwrap_or_else(|v: Vec<Vec<F>>| {
@@ -205,6 +173,48 @@ pub fn generate_bitwise_trace<F: RichField>(
     (trace_row_vecs, beta)
 }
 
+/// Fill the compress columns of a bitwise trace, and their permutations,
+/// with the challenge `beta`. The prover refills them with the challenge it
+/// draws once the other tables are committed.
+pub(crate) fn fill_bitwise_compress_cols<F: RichField>(trace: &mut [Vec<F>], beta: F) {
+    for i in 0..trace[0].len() {
+        trace[bitwise::COMPRESS_LIMBS.start][i] = trace[bitwise::TAG][i]
+            + trace[bitwise::OP0_LIMBS.start][i] * beta
+            + trace[bitwise::OP1_LIMBS.start][i] * beta * beta
+            + trace[bitwise::RES_LIMBS.start][i] * beta * beta * beta;
+
+        trace[bitwise::COMPRESS_LIMBS.start + 1][i] = trace[bitwise::TAG][i]
+            + trace[bitwise::OP0_LIMBS.start + 1][i] * beta
+            + trace[bitwise::OP1_LIMBS.start + 1][i] * beta * beta
+            + trace[bitwise::RES_LIMBS.start + 1][i] * beta * beta * beta;
+
+        trace[bitwise::COMPRESS_LIMBS.start + 2][i] = trace[bitwise::TAG][i]
+            + trace[bitwise::OP0_LIMBS.start + 2][i] * beta
+            + trace[bitwise::OP1_LIMBS.start + 2][i] * beta * beta
+            + trace[bitwise::RES_LIMBS.start + 2][i] * beta * beta * beta;
+
+        trace[bitwise::COMPRESS_LIMBS.start + 3][i] = trace[bitwise::TAG][i]
+            + trace[bitwise::OP0_LIMBS.start + 3][i] * beta
+            + trace[bitwise::OP1_LIMBS.start + 3][i] * beta * beta
+            + trace[bitwise::RES_LIMBS.start + 3][i] * beta * beta * beta;
+
+        trace[bitwise::FIX_COMPRESS][i] = trace[bitwise::FIX_TAG][i]
+            + trace[bitwise::FIX_BITWSIE_OP0][i] * beta
+            + trace[bitwise::FIX_BITWSIE_OP1][i] * beta * beta
+            + trace[bitwise::FIX_BITWSIE_RES][i] * beta * beta * beta;
+    }
+
+    for i in 0..4 {
+        let (permuted_inputs, permuted_table) = permuted_cols(
+            &trace[bitwise::COMPRESS_LIMBS.start + i],
+            &trace[bitwise::FIX_COMPRESS],
+        );
+
+        trace[bitwise::COMPRESS_PERMUTED.start + i] = permuted_inputs;
+        trace[bitwise::FIX_COMPRESS_PERMUTED.start + i] = permuted_table;
+    }
+}
+
 pub fn generate_cmp_trace<F: RichField>(cells: &[CmpRow]) -> [Vec<F>; cmp::COL_NUM_CMP] {
     let trace_len = cells.len();
     let ext_trace_len = if !trace_len.is_power_of_two() || trace_len < 2 {
@@ -248,9 +258,19 @@ pub fn generate_cmp_trace<F: RichField>(cells: &[CmpRow]) -> [Vec<F>; cmp::COL_N
 
 pub fn generate_rc_trace<F: RichField>(
     cells: &[RangeCheckRow],
+) -> [Vec<F>; rangecheck::COL_NUM_RC] {
+    generate_rc_trace_with_bound(cells, rangecheck::RANGE_CHECK_U16_SIZE)
+}
+
+/// Range check trace whose fixed column is `0..bound`, so each limb is
+/// checked to be below `bound`. It is proven by
+/// `RangeCheckStark::with_limb_bound(bound)`.
+pub fn generate_rc_trace_with_bound<F: RichField>(
+    cells: &[RangeCheckRow],
+    bound: usize,
 ) -> [Vec<F>; rangecheck::COL_NUM_RC] {
     let trace_len = cells.len();
-    let max_trace_len = trace_len.max(rangecheck::RANGE_CHECK_U16_SIZE);
+    let max_trace_len = trace_len.max(bound);
     let ext_trace_len = if !max_trace_len.is_power_of_two() || max_trace_len < 2 {
         if max_trace_len < 2 {
             2
@@ -275,9 +295,8 @@ pub fn generate_rc_trace<F: RichField>(
         trace[rangecheck::LIMB_HI][i] = F::from_canonical_u64(c.limb_hi.to_canonical_u64());
     }
     // add fix rangecheck info
-    trace[rangecheck::FIX_RANGE_CHECK_U16] = (0..rangecheck::RANGE_CHECK_U16_SIZE)
-        .map(|i| F::from_canonical_usize(i))
-        .collect();
+    trace[rangecheck::FIX_RANGE_CHECK_U16] =
+        (0..bound).map(|i| F::from_canonical_usize(i)).collect();
     if trace[rangecheck::FIX_RANGE_CHECK_U16].len() < ext_trace_len {
         let append_start = trace[rangecheck::FIX_RANGE_CHECK_U16].len();
         let append_end_exclusive = ext_trace_len;
@@ -314,3 +333,113 @@ pub fn generate_rc_trace<F: RichField>(
         )
     })
 }
+
+/// Check the fixed columns of a bitwise trace: the u8 column is `0..2^8`
+/// then zeros, and the bitwise one holds the and, or and xor of every pair
+/// of bytes exactly once, its other rows being all zero with no tag.
+pub fn check_bitwise_fixed_table<F: RichField>(
+    trace: &[Vec<F>; bitwise::COL_NUM_BITWISE],
+) -> Result<()> {
+    for (i, value) in trace[bitwise::FIX_RANGE_CHECK_U8].iter().enumerate() {
+        let expected = if i < bitwise::RANGE_CHECK_U8_SIZE {
+            i
+        } else {
+            0
+        };
+        ensure!(
+            value.to_canonical_u64() == expected as u64,
+            "fixed u8 column holds {} at row {}",
+            value,
+            i
+        );
+    }
+
+    let mut combinations = HashSet::new();
+    for i in 0..trace[bitwise::FIX_TAG].len() {
+        let tag = trace[bitwise::FIX_TAG][i].to_canonical_u64();
+        let op0 = trace[bitwise::FIX_BITWSIE_OP0][i].to_canonical_u64();
+        let op1 = trace[bitwise::FIX_BITWSIE_OP1][i].to_canonical_u64();
+        let res = trace[bitwise::FIX_BITWSIE_RES][i].to_canonical_u64();
+        if tag == 0 {
+            ensure!(
+                op0 == 0 && op1 == 0 && res == 0,
+                "untagged bitwise row {} is not zero",
+                i
+            );
+            continue;
+        }
+        let expected = match tag {
+            t if t == 1 << Opcode::AND as u8 => op0 & op1,
+            t if t == 1 << Opcode::OR as u8 => op0 | op1,
+            t if t == 1 << Opcode::XOR as u8 => op0 ^ op1,
+            _ => bail!("unknown bitwise tag {} at row {}", tag, i),
+        };
+        ensure!(
+            op0 < bitwise::RANGE_CHECK_U8_SIZE as u64 && op1 < bitwise::RANGE_CHECK_U8_SIZE as u64,
+            "bitwise operands {} and {} at row {} are not bytes",
+            op0,
+            op1,
+            i
+        );
+        ensure!(
+            res == expected,
+            "bitwise row {} of tag {} gives {} for {} and {}, not {}",
+            i,
+            tag,
+            res,
+            op0,
+            op1,
+            expected
+        );
+        ensure!(
+            combinations.insert((tag, op0, op1)),
+            "bitwise row {} repeats tag {} on {} and {}",
+            i,
+            tag,
+            op0,
+            op1
+        );
+    }
+    ensure!(
+        combinations.len() == bitwise::BITWISE_U8_SIZE,
+        "bitwise fixed table has {} of the {} byte combinations",
+        combinations.len(),
+        bitwise::BITWISE_U8_SIZE
+    );
+    Ok(())
+}
+
+/// Check the fixed column of a range check trace is `0..bound`, padded with
+/// `bound - 1`.
+pub fn check_rc_fixed_table<F: RichField>(
+    trace: &[Vec<F>; rangecheck::COL_NUM_RC],
+    bound: usize,
+) -> Result<()> {
+    for (i, value) in trace[rangecheck::FIX_RANGE_CHECK_U16].iter().enumerate() {
+        let expected = i.min(bound - 1);
+        ensure!(
+            value.to_canonical_u64() == expected as u64,
+            "fixed range check column holds {} at row {}, not {}",
+            value,
+            i,
+            expected
+        );
+    }
+    ensure!(
+        trace[rangecheck::FIX_RANGE_CHECK_U16].len() >= bound,
+        "fixed range check column stops before {}",
+        bound
+    );
+    Ok(())
+}
+
+/// Regenerate the fixed tables of the bitwise and range check traces and
+/// check their content, as a lookup into a wrong table holds vacuously.
+pub fn verify_fixed_tables<F: RichField>() -> Result<()> {
+    let (bitwise_trace, _) = generate_bitwise_trace::<F>(&[]);
+    check_bitwise_fixed_table(&bitwise_trace)?;
+    check_rc_fixed_table(
+        &generate_rc_trace::<F>(&[]),
+        rangecheck::RANGE_CHECK_U16_SIZE,
+    )
+}
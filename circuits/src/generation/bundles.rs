@@ -0,0 +1,92 @@
+use core::trace::trace::Step;
+use core::vm::opcodes::OlaOpcode;
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::Field;
+use std::ops::Range;
+
+/// Opcodes using only registers, which a slot can execute next to others.
+const SLOT_OPCODES: [OlaOpcode; 7] = [
+    OlaOpcode::ADD,
+    OlaOpcode::MUL,
+    OlaOpcode::SUB,
+    OlaOpcode::MOV,
+    OlaOpcode::NOT,
+    OlaOpcode::EQ,
+    OlaOpcode::NEQ,
+];
+
+/// Opcodes changing the pc, allowed in the last slot of a bundle only.
+const LAST_SLOT_OPCODES: [OlaOpcode; 3] = [OlaOpcode::JMP, OlaOpcode::CJMP, OlaOpcode::END];
+
+fn is_one_of(step: &Step, opcodes: &[OlaOpcode]) -> bool {
+    opcodes
+        .iter()
+        .any(|opcode| step.opcode() == opcode.binary_bit_mask())
+}
+
+fn registers(selectors: &[GoldilocksField]) -> impl Iterator<Item = usize> + '_ {
+    selectors
+        .iter()
+        .enumerate()
+        .filter(|(_, sel)| !sel.is_zero())
+        .map(|(index, _)| index)
+}
+
+/// Split the rows of `exec` into bundles of up to `width` consecutive steps
+/// a cpu row with `width` instruction slots could execute in one cycle:
+/// every slot reads the registers as they were before the bundle, so no
+/// step may read or write a register written by an earlier step of its
+/// bundle. Steps touching memory, builtins or the tape keep a row of their
+/// own.
+///
+/// The cpu table has a single slot, and slots would need their own
+/// selectors, operands and register lookups, so this only tells how many
+/// rows a wider fetch would take.
+pub fn bundle_rows(exec: &[Step], width: usize) -> Vec<Range<usize>> {
+    let mut bundles = Vec::new();
+    let mut start = 0;
+    let mut written = vec![];
+    for (row, step) in exec.iter().enumerate() {
+        let selectors = &step.register_selector;
+        let slot = is_one_of(step, &SLOT_OPCODES);
+        let last_slot = is_one_of(step, &LAST_SLOT_OPCODES);
+        let independent = registers(&selectors.op0_reg_sel)
+            .chain(registers(&selectors.op1_reg_sel))
+            .chain(registers(&selectors.dst_reg_sel))
+            .all(|index| !written.contains(&index));
+        let joins = row > start
+            && row - start < width
+            && (slot || last_slot)
+            && is_one_of(&exec[row - 1], &SLOT_OPCODES)
+            && independent;
+        if !joins && row > start {
+            bundles.push(start..row);
+            start = row;
+            written.clear();
+        }
+        written.extend(registers(&selectors.dst_reg_sel));
+    }
+    if start < exec.len() {
+        bundles.push(start..exec.len());
+    }
+    bundles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bundle_rows;
+    use crate::test_utils::asm_fixture_exec;
+
+    #[test]
+    fn test_bundle_independent_movs() {
+        // mov r1 1; mov r2 2 | add r3 r1 r2; end
+        let exec = asm_fixture_exec("vliw_movs");
+        assert_eq!(exec.len(), 4);
+        assert_eq!(bundle_rows(&exec, 2), vec![0..2, 2..4]);
+        assert_eq!(bundle_rows(&exec, 1).len(), exec.len());
+
+        // mov r1 2; mov r2 3 | add r3 r1 r2 | mul r4 r3 6; end
+        let exec = asm_fixture_exec("add_mul");
+        assert_eq!(bundle_rows(&exec, 2), vec![0..2, 2..3, 3..5]);
+    }
+}
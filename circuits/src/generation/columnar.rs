@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+use plonky2::field::types::PrimeField64;
+
+use crate::cpu::columns::get_cpu_col_name_map;
+use crate::memory::columns::get_memory_col_name_map;
+
+const MAGIC: &[u8; 8] = b"OLACOLS1";
+
+/// A trace as named columns of canonical u64 values, for offline analysis.
+///
+/// The binary format is little endian: the `OLACOLS1` magic, the number of
+/// columns as u32 and of rows as u64, then the name of every column as a
+/// u32 length followed by its utf8 bytes, then the values column after
+/// column. A column `i` is a contiguous run of `rows` u64s, e.g. for
+/// `numpy.frombuffer` at the offset the header ends plus `8 * i * rows`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnarTrace {
+    pub names: Vec<String>,
+    pub columns: Vec<Vec<u64>>,
+}
+
+impl ColumnarTrace {
+    fn from_trace<F: PrimeField64>(trace: &[Vec<F>], names: BTreeMap<usize, String>) -> Self {
+        let names = (0..trace.len())
+            .map(|col| {
+                names
+                    .get(&col)
+                    .cloned()
+                    .unwrap_or_else(|| format!("col_{}", col))
+            })
+            .collect();
+        let columns = trace
+            .iter()
+            .map(|column| column.iter().map(|v| v.to_canonical_u64()).collect())
+            .collect();
+        Self { names, columns }
+    }
+
+    /// Columns of a trace made by `generate_cpu_trace`, named as in
+    /// `get_cpu_col_name_map`.
+    pub fn from_cpu_trace<F: PrimeField64>(trace: &[Vec<F>]) -> Self {
+        Self::from_trace(trace, get_cpu_col_name_map())
+    }
+
+    /// Columns of a trace made by `generate_memory_trace`, named as in
+    /// `get_memory_col_name_map`.
+    pub fn from_memory_trace<F: PrimeField64>(trace: &[Vec<F>]) -> Self {
+        Self::from_trace(trace, get_memory_col_name_map())
+    }
+
+    pub fn column(&self, name: &str) -> Option<&[u64]> {
+        let index = self.names.iter().position(|n| n == name)?;
+        Some(&self.columns[index])
+    }
+
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+        let rows = self.columns.first().map_or(0, |column| column.len());
+        writer.write_all(MAGIC)?;
+        writer.write_all(&(self.names.len() as u32).to_le_bytes())?;
+        writer.write_all(&(rows as u64).to_le_bytes())?;
+        for name in &self.names {
+            writer.write_all(&(name.len() as u32).to_le_bytes())?;
+            writer.write_all(name.as_bytes())?;
+        }
+        for column in &self.columns {
+            for value in column {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn read_from(reader: &mut impl Read) -> Result<Self> {
+        let mut magic = [0; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "not a columnar trace"));
+        }
+        let num_cols = read_u32(reader)? as usize;
+        let rows = read_u64(reader)? as usize;
+        let names = (0..num_cols)
+            .map(|_| {
+                let mut name = vec![0; read_u32(reader)? as usize];
+                reader.read_exact(&mut name)?;
+                String::from_utf8(name).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let columns = (0..num_cols)
+            .map(|_| (0..rows).map(|_| read_u64(reader)).collect())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { names, columns })
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    let mut buf = [0; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64> {
+    let mut buf = [0; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use super::ColumnarTrace;
+    use crate::cpu::columns::NUM_CPU_COLS;
+    use crate::generation::cpu::generate_cpu_trace;
+    use crate::generation::memory::generate_memory_trace;
+    use crate::memory::columns::NUM_MEM_COLS;
+    use crate::test_utils::execute_asm_path;
+
+    #[test]
+    fn test_columnar_round_trip() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let cpu: [Vec<GoldilocksField>; NUM_CPU_COLS] = generate_cpu_trace(&program.trace.exec);
+        let memory: [Vec<GoldilocksField>; NUM_MEM_COLS] =
+            generate_memory_trace(&program.trace.memory);
+
+        for table in [
+            ColumnarTrace::from_cpu_trace(&cpu),
+            ColumnarTrace::from_memory_trace(&memory),
+        ] {
+            let mut bytes = vec![];
+            table.write_to(&mut bytes).unwrap();
+            let read = ColumnarTrace::read_from(&mut bytes.as_slice()).unwrap();
+            assert_eq!(read, table);
+        }
+
+        let table = ColumnarTrace::from_cpu_trace(&cpu);
+        let pcs = program
+            .trace
+            .exec
+            .iter()
+            .map(|step| step.pc)
+            .collect::<Vec<_>>();
+        assert_eq!(&table.column("pc").unwrap()[..pcs.len()], pcs.as_slice());
+        assert!(ColumnarTrace::read_from(&mut &b"OLACOLS0"[..]).is_err());
+    }
+}
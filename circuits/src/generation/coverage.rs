@@ -0,0 +1,64 @@
+use core::vm::opcodes::OlaOpcode;
+use std::collections::HashMap;
+
+use enum_iterator::all;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::hash::hash_types::RichField;
+
+use crate::cpu::columns::{COL_IS_PADDING, COL_OPCODE};
+use crate::stark::ola_stark::{Table, NUM_TABLES};
+
+/// Report which opcodes were ever selected by a non-padding row of the cpu
+/// trace. Opcodes sharing a selector column (e.g. add/mul/eq) are told apart
+/// by the opcode column, so every opcode gets its own entry.
+pub fn coverage<F: RichField>(
+    traces: &[Vec<PolynomialValues<F>>; NUM_TABLES],
+) -> HashMap<&'static str, bool> {
+    let cpu_trace = &traces[Table::Cpu.index()];
+    let opcodes = &cpu_trace[COL_OPCODE].values;
+    let is_padding = &cpu_trace[COL_IS_PADDING].values;
+
+    let mut res: HashMap<&'static str, bool> =
+        all::<OlaOpcode>().map(|op| (op.as_str(), false)).collect();
+    for op in all::<OlaOpcode>() {
+        let mask = F::from_canonical_u64(op.binary_bit_mask());
+        let covered = opcodes
+            .iter()
+            .zip(is_padding.iter())
+            .any(|(opcode, padding)| padding.is_zero() && *opcode == mask);
+        res.insert(op.as_str(), covered);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use core::types::{Field, GoldilocksField};
+    use itertools::Itertools;
+
+    use super::coverage;
+    use crate::generation::{generate_traces, GenerationInputs};
+    use crate::stark::ola_stark::OlaStark;
+    use crate::test_utils::execute_asm_path;
+
+    #[test]
+    fn test_fibo_coverage() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/fib_asm.json");
+        let calldata = [10u64, 1u64, 2, 4185064725u64]
+            .iter()
+            .map(|v| GoldilocksField::from_canonical_u64(*v))
+            .collect_vec();
+        let program = execute_asm_path(path.display().to_string(), Some(calldata), None);
+
+        let mut ola_stark = OlaStark::default();
+        let (traces, _) =
+            generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+        let res = coverage(&traces);
+        for op in ["eq", "cjmp", "add"] {
+            assert!(res[op], "{} selector is never set", op);
+        }
+    }
+}
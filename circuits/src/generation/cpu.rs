@@ -58,6 +58,12 @@ pub fn generate_cpu_trace<F: RichField>(steps: &Vec<Step>) -> [Vec<F>; cpu::NUM_
     opcode_to_selector.insert(OlaOpcode::TLOAD.binary_bit_mask(), cpu::COL_S_TLOAD);
     opcode_to_selector.insert(OlaOpcode::TSTORE.binary_bit_mask(), cpu::COL_S_TSTORE);
     opcode_to_selector.insert(OlaOpcode::SCCALL.binary_bit_mask(), cpu::COL_S_CALL_SC);
+    opcode_to_selector.insert(OlaOpcode::SUB.binary_bit_mask(), cpu::COL_S_SUB);
+    opcode_to_selector.insert(OlaOpcode::SWAP.binary_bit_mask(), cpu::COL_S_SWAP);
+    opcode_to_selector.insert(OlaOpcode::ISQRT.binary_bit_mask(), cpu::COL_S_ISQRT);
+    opcode_to_selector.insert(OlaOpcode::CAS.binary_bit_mask(), cpu::COL_S_CAS);
+    opcode_to_selector.insert(OlaOpcode::LOADP.binary_bit_mask(), cpu::COL_S_LOADP);
+    opcode_to_selector.insert(OlaOpcode::REQUIRE.binary_bit_mask(), cpu::COL_S_REQUIRE);
 
     for (i, s) in steps.iter().enumerate() {
         // env related columns.
@@ -84,6 +90,7 @@ pub fn generate_cpu_trace<F: RichField>(steps: &Vec<Step>) -> [Vec<F>; cpu::NUM_
         // Instruction related columns.
         trace[cpu::COL_INST][i] = F::from_canonical_u64(s.instruction.0);
         trace[cpu::COL_OP1_IMM][i] = F::from_canonical_u64(s.op1_imm.0);
+        trace[cpu::COL_SUB_U32][i] = F::from_bool(s.u32_mode());
         trace[cpu::COL_OPCODE][i] = F::from_canonical_u64(s.opcode.0);
         trace[cpu::COL_IMM_VAL][i] = F::from_canonical_u64(s.immediate_data.0);
 
@@ -104,10 +111,15 @@ pub fn generate_cpu_trace<F: RichField>(steps: &Vec<Step>) -> [Vec<F>; cpu::NUM_
                 F::from_canonical_u64(s.register_selector.dst_reg_sel[j].0);
         }
 
-        // Selectors of opcode related columns.
-        match opcode_to_selector.get(&s.opcode.0) {
-            Some(selector) => trace[selector.clone()][i] = F::from_canonical_u64(1),
-            None => (),
+        // Selectors of opcode related columns. A require on zero halts the
+        // program, its row is an end row.
+        let is_end = s.opcode.0 == OlaOpcode::END.binary_bit_mask()
+            || (s.opcode.0 == OlaOpcode::REQUIRE.binary_bit_mask()
+                && s.register_selector.op1.0 == 0);
+        if is_end {
+            trace[cpu::COL_S_END][i] = F::ONE;
+        } else if let Some(selector) = opcode_to_selector.get(&s.opcode.0) {
+            trace[selector.clone()][i] = F::ONE;
         }
 
         trace[COL_IS_ENTRY_SC][i] = if trace[cpu::COL_ENV_IDX][i].is_zero() {
@@ -119,8 +131,7 @@ pub fn generate_cpu_trace<F: RichField>(steps: &Vec<Step>) -> [Vec<F>; cpu::NUM_
         let ext_length = if s.opcode.0 == OlaOpcode::SLOAD.binary_bit_mask()
             || s.opcode.0 == OlaOpcode::SSTORE.binary_bit_mask()
             || s.opcode.0 == OlaOpcode::SCCALL.binary_bit_mask()
-            || (s.opcode.0 == OlaOpcode::END.binary_bit_mask()
-                && !trace[cpu::COL_ENV_IDX][i].is_zero())
+            || (is_end && !trace[cpu::COL_ENV_IDX][i].is_zero())
         {
             1
         } else if s.opcode.0 == OlaOpcode::TLOAD.binary_bit_mask() {
@@ -136,8 +147,7 @@ pub fn generate_cpu_trace<F: RichField>(steps: &Vec<Step>) -> [Vec<F>; cpu::NUM_
         } else {
             F::ZERO
         };
-        trace[cpu::COL_IS_NEXT_LINE_SAME_TX][i] = if trace[cpu::COL_ENV_IDX][i].is_zero()
-            && s.opcode.0 == OlaOpcode::END.binary_bit_mask()
+        trace[cpu::COL_IS_NEXT_LINE_SAME_TX][i] = if trace[cpu::COL_ENV_IDX][i].is_zero() && is_end
         {
             F::ZERO
         } else {
@@ -159,12 +169,11 @@ pub fn generate_cpu_trace<F: RichField>(steps: &Vec<Step>) -> [Vec<F>; cpu::NUM_
         } else {
             F::ZERO
         };
-        trace[cpu::COL_FILTER_SCCALL_END][i] =
-            if s.opcode.0 == OlaOpcode::END.binary_bit_mask() && s.is_ext_line.0 == 1 {
-                F::ONE
-            } else {
-                F::ZERO
-            };
+        trace[cpu::COL_FILTER_SCCALL_END][i] = if is_end && s.is_ext_line.0 == 1 {
+            F::ONE
+        } else {
+            F::ZERO
+        };
         trace[cpu::COL_FILTER_LOOKING_PROG_IMM][i] = if s.is_ext_line.0 == 1 {
             F::ZERO
         } else if s.opcode.0 == OlaOpcode::MLOAD.binary_bit_mask()
@@ -176,6 +185,11 @@ pub fn generate_cpu_trace<F: RichField>(steps: &Vec<Step>) -> [Vec<F>; cpu::NUM_
         } else {
             F::ZERO
         };
+        if s.opcode.0 == OlaOpcode::CAS.binary_bit_mask() {
+            trace[cpu::COL_FILTER_CAS_WRITE][i] = F::from_canonical_u64(s.register_selector.dst.0);
+            trace[cpu::COL_CAS_GTE][i] =
+                F::from_bool(s.register_selector.aux1.0 >= s.register_selector.aux0.0);
+        }
     }
     // fill in padding.
     let inst_end = if trace_len == 0 {
@@ -33,13 +33,15 @@ fn print_cpu_program_ctl_info() {
         &trace.exec,
         progs_for_program,
         trace.start_end_roots,
-    );
+        0,
+    )
+    .unwrap();
     let program_rows = get_rows_vec_from_trace(program_cols);
 
     let insts_looking_cols: Vec<usize> = COL_ADDR_CODE_RANGE.chain([COL_PC, COL_INST]).collect();
     let imm_looking_cols: Vec<usize> = COL_ADDR_CODE_RANGE.chain([COL_PC, COL_IMM_VAL]).collect();
     let prog_looked_cols: Vec<usize> = COL_PROG_EXEC_CODE_ADDR_RANGE
-        .chain([COL_PROG_EXEC_PC, COL_PROG_EXEC_INST])
+        .chain([COL_PROG_EXEC_PC, COL_PROG_EXEC_INST, COL_PROG_EXEC_IS_IMM])
         .collect();
 
     print_title_data(
@@ -29,7 +29,8 @@ fn print_prog_chunk_program_ctl_info() {
     let prog_chunk_rows = get_rows_vec_from_trace(prog_chunk_cols);
 
     let (program_cols, _) =
-        generate_prog_trace::<GoldilocksField>(&trace.exec, progs, trace.start_end_roots);
+        generate_prog_trace::<GoldilocksField>(&trace.exec, progs, trace.start_end_roots, 0)
+            .unwrap();
     let program_rows = get_rows_vec_from_trace(program_cols);
 
     (0..8).for_each(|i| {
@@ -0,0 +1,97 @@
+use core::trace::trace::Step;
+use std::collections::HashMap;
+
+/// A run of identical iterations found in the cpu trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoopSummary {
+    /// Cpu row of the first instruction of the first iteration.
+    pub first_row: usize,
+    /// Pc of the first instruction of the body.
+    pub head_pc: u64,
+    /// Rows of one iteration, ext rows included.
+    pub body_len: usize,
+    pub iterations: usize,
+}
+
+/// Find the loops of an executed trace, as maximal runs of at least two
+/// consecutive iterations going through the same pcs.
+///
+/// This is the measurement half of a uniform AIR for loops: the body would
+/// be proven once with the iteration count range-checked, instead of one
+/// row per executed instruction. The cpu table can't take such a folded
+/// trace yet, registers and memory differ between iterations and every
+/// row is looked up by the memory, builtin and program tables, so
+/// `folded_cpu_rows` only tells what it would save.
+pub fn detect_loops(exec: &[Step]) -> Vec<LoopSummary> {
+    // Row of the next step at the same pc, if any.
+    let mut next_same_pc = vec![None; exec.len()];
+    let mut last_seen = HashMap::new();
+    for (row, step) in exec.iter().enumerate().rev() {
+        next_same_pc[row] = last_seen.insert(step.pc, row);
+    }
+
+    let same_pcs =
+        |a: usize, b: usize, len: usize| (0..len).all(|i| exec[a + i].pc == exec[b + i].pc);
+    let mut loops = Vec::new();
+    let mut row = 0;
+    while row < exec.len() {
+        let next = match next_same_pc[row] {
+            Some(next) => next,
+            None => {
+                row += 1;
+                continue;
+            }
+        };
+        let body_len = next - row;
+        let mut iterations = 1;
+        while row + (iterations + 1) * body_len <= exec.len()
+            && same_pcs(row, row + iterations * body_len, body_len)
+        {
+            iterations += 1;
+        }
+        if iterations < 2 {
+            row += 1;
+            continue;
+        }
+        loops.push(LoopSummary {
+            first_row: row,
+            head_pc: exec[row].pc,
+            body_len,
+            iterations,
+        });
+        row += iterations * body_len;
+    }
+    loops
+}
+
+/// Cpu rows of `exec` if every loop of `loops` kept a single iteration plus
+/// one row for its range-checked count.
+pub fn folded_cpu_rows(exec: &[Step], loops: &[LoopSummary]) -> usize {
+    loops.iter().fold(exec.len(), |rows, summary| {
+        rows - (summary.iterations - 1) * summary.body_len + 1
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_loops, folded_cpu_rows};
+    use crate::test_utils::asm_fixture_exec;
+
+    #[test]
+    fn test_fold_counting_loop() {
+        let looped = asm_fixture_exec("count_loop");
+        let unrolled = asm_fixture_exec("count_unrolled");
+
+        let loops = detect_loops(&looped);
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].first_row, 1);
+        assert_eq!(loops[0].body_len, 3);
+        assert_eq!(loops[0].iterations, 10);
+        assert!(detect_loops(&unrolled).is_empty());
+
+        assert_eq!(looped.len(), 32);
+        assert_eq!(unrolled.len(), 12);
+        assert_eq!(folded_cpu_rows(&looped, &loops), 6);
+        assert!(folded_cpu_rows(&looped, &loops) < unrolled.len());
+    }
+}
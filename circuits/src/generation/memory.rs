@@ -1,9 +1,78 @@
+use core::vm::memory::{MEM_OP_DATA, MEM_OP_INIT};
 use core::{trace::trace::MemoryTraceCell, vm::opcodes::OlaOpcode};
 use std::{collections::HashMap, ops::Sub};
 
-use plonky2::{field::types::PrimeField64, hash::hash_types::RichField};
+use anyhow::{bail, ensure, Result};
+use plonky2::{
+    field::types::{Field, PrimeField64},
+    hash::hash_types::RichField,
+};
 
 use crate::memory::columns::{self as memory, COL_MEM_S_PROPHET};
+use crate::stark::proof::MemoryClaim;
+
+/// Check every claim of `claims` against the last read/write access to its
+/// address in the sorted memory trace `cells`, returning the rows of those
+/// accesses, the ones the claims look up. An address is claimed once.
+pub fn check_memory_claims(
+    cells: &[MemoryTraceCell],
+    claims: &[MemoryClaim],
+) -> Result<Vec<usize>> {
+    let mut rows = Vec::with_capacity(claims.len());
+    for claim in claims {
+        let last = cells
+            .iter()
+            .rposition(|cell| cell.is_rw.is_one() && cell.addr.to_canonical_u64() == claim.addr);
+        let row = match last {
+            Some(row) => row,
+            None => bail!("memory at {} is never accessed", claim.addr),
+        };
+        let value = cells[row].value.to_canonical_u64();
+        ensure!(
+            value == claim.value,
+            "memory at {} holds {}, not {}",
+            claim.addr,
+            value,
+            claim.value
+        );
+        ensure!(
+            !rows.contains(&row),
+            "memory at {} is claimed twice",
+            claim.addr
+        );
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Whether row `i` of the sorted memory trace `cells` is the last access to
+/// its stack address, the word it holds in the final memory.
+fn is_final_stack_access(cells: &[MemoryTraceCell], i: usize) -> bool {
+    let cell = &cells[i];
+    cell.region_heap.is_zero()
+        && cell.region_prophet.is_zero()
+        && cells.get(i + 1).map_or(true, |next| next.addr != cell.addr)
+}
+
+/// `(addr, value)` of the init rows of the sorted memory trace `cells`, the
+/// memory the run starts from, and of the last access to each stack address,
+/// the memory it ends with.
+pub fn public_memory(cells: &[MemoryTraceCell]) -> (Vec<MemoryClaim>, Vec<MemoryClaim>) {
+    let word = |cell: &MemoryTraceCell| MemoryClaim {
+        addr: cell.addr.to_canonical_u64(),
+        value: cell.value.to_canonical_u64(),
+    };
+    let initial = cells
+        .iter()
+        .filter(|cell| cell.op.to_canonical_u64() == MEM_OP_INIT)
+        .map(word)
+        .collect();
+    let last = (0..cells.len())
+        .filter(|&i| is_final_stack_access(cells, i))
+        .map(|i| word(&cells[i]))
+        .collect();
+    (initial, last)
+}
 
 pub fn generate_memory_trace<F: RichField>(
     cells: &[MemoryTraceCell],
@@ -46,6 +115,8 @@ pub fn generate_memory_trace<F: RichField>(
     );
     opcode_to_selector.insert(OlaOpcode::SLOAD.binary_bit_mask(), memory::COL_MEM_S_SLOAD);
     opcode_to_selector.insert(0, memory::COL_MEM_S_PROPHET);
+    opcode_to_selector.insert(MEM_OP_DATA, memory::COL_MEM_S_DATA);
+    opcode_to_selector.insert(MEM_OP_INIT, memory::COL_MEM_S_INIT);
 
     let mut trace: Vec<Vec<F>> = vec![vec![F::ZERO; num_padded_rows]; memory::NUM_MEM_COLS];
     for (i, c) in cells.iter().enumerate() {
@@ -91,6 +162,7 @@ pub fn generate_memory_trace<F: RichField>(
             } else {
                 F::from_canonical_u64(0)
             };
+        trace[memory::COL_MEM_FINAL][i] = F::from_bool(is_final_stack_access(cells, i));
     }
 
     if num_filled_row_len == 0 {
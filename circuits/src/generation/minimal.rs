@@ -0,0 +1,217 @@
+//! The smallest traces each table accepts on its own, to start a test of a
+//! table from a valid trace and break it, without running a program. The
+//! cpu and program tables run the one instruction program `end`, the others
+//! are what their generator pads an unused table to. CTLs between them are
+//! not meant to hold.
+
+use core::program::REGISTER_NUM;
+use core::trace::trace::{RegisterSelector, Step};
+use core::types::account::Address;
+use core::vm::opcodes::OlaOpcode;
+use plonky2::field::extension::Extendable;
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+
+use super::builtin::{generate_bitwise_trace, generate_cmp_trace, generate_rc_trace};
+use super::cpu::generate_cpu_trace;
+use super::memory::generate_memory_trace;
+use super::poseidon::generate_poseidon_trace;
+use super::poseidon_chunk::generate_poseidon_chunk_trace;
+use super::prog::{generate_prog_chunk_trace, generate_prog_trace};
+use super::sccall::generate_sccall_trace;
+use super::storage::generate_storage_access_trace;
+use super::tape::generate_tape_trace;
+use crate::builtins::bitwise::bitwise_stark::BitwiseStark;
+use crate::builtins::cmp::cmp_stark::CmpStark;
+use crate::builtins::poseidon::poseidon_chunk_stark::PoseidonChunkStark;
+use crate::builtins::poseidon::poseidon_stark::PoseidonStark;
+use crate::builtins::rangecheck::rangecheck_stark::RangeCheckStark;
+use crate::builtins::sccall::sccall_stark::SCCallStark;
+use crate::builtins::storage::storage_access_stark::StorageAccessStark;
+use crate::builtins::tape::tape_stark::TapeStark;
+use crate::cpu::cpu_stark::CpuStark;
+use crate::memory::memory_stark::MemoryStark;
+use crate::program::prog_chunk_stark::ProgChunkStark;
+use crate::program::program_stark::ProgramStark;
+use crate::stark::util::trace_to_poly_values;
+
+/// The single step of the program `end`.
+fn end_step() -> Step {
+    let end = GoldilocksField::from_canonical_u64(OlaOpcode::END.binary_bit_mask());
+    Step {
+        env_idx: GoldilocksField::ZERO,
+        call_sc_cnt: GoldilocksField::ZERO,
+        clk: 0,
+        pc: 0,
+        tp: GoldilocksField::ZERO,
+        addr_storage: Address::default(),
+        addr_code: Address::default(),
+        instruction: end,
+        immediate_data: GoldilocksField::ZERO,
+        opcode: end,
+        op1_imm: GoldilocksField::ZERO,
+        regs: [GoldilocksField::ZERO; REGISTER_NUM],
+        register_selector: RegisterSelector::default(),
+        is_ext_line: GoldilocksField::ZERO,
+        ext_cnt: GoldilocksField::ZERO,
+        filter_tape_looking: GoldilocksField::ZERO,
+        storage_access_idx: GoldilocksField::ZERO,
+    }
+}
+
+/// The code of the program `end`, at the zero address.
+fn end_program() -> Vec<(Address, Vec<GoldilocksField>)> {
+    vec![(Address::default(), vec![end_step().instruction])]
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CpuStark<F, D> {
+    /// Trace of the program `end`, its single end row.
+    pub fn minimal_trace() -> Vec<PolynomialValues<F>> {
+        trace_to_poly_values(generate_cpu_trace(&vec![end_step()]))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> MemoryStark<F, D> {
+    /// Trace of no memory access.
+    pub fn minimal_trace() -> Vec<PolynomialValues<F>> {
+        trace_to_poly_values(generate_memory_trace(&[]))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> BitwiseStark<F, D> {
+    /// Trace of no bitwise operation, which still holds the fixed u8
+    /// tables. Sets the compress challenge it was made with, which must not
+    /// be set yet.
+    pub fn minimal_trace(&mut self) -> Vec<PolynomialValues<F>> {
+        let (trace, beta) = generate_bitwise_trace(&[]);
+        self.set_compress_challenge(beta).unwrap();
+        trace_to_poly_values(trace)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> CmpStark<F, D> {
+    /// Trace of no comparison.
+    pub fn minimal_trace() -> Vec<PolynomialValues<F>> {
+        trace_to_poly_values(generate_cmp_trace(&[]))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> RangeCheckStark<F, D> {
+    /// Trace of no range check, which still holds the fixed u16 table.
+    pub fn minimal_trace() -> Vec<PolynomialValues<F>> {
+        trace_to_poly_values(generate_rc_trace(&[]))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PoseidonStark<F, D> {
+    /// Trace of no permutation.
+    pub fn minimal_trace() -> Vec<PolynomialValues<F>> {
+        trace_to_poly_values(generate_poseidon_trace(&[]))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> PoseidonChunkStark<F, D> {
+    /// Trace of no hashed chunk.
+    pub fn minimal_trace() -> Vec<PolynomialValues<F>> {
+        trace_to_poly_values(generate_poseidon_chunk_trace(&[]))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> StorageAccessStark<F, D> {
+    /// Trace of no storage access.
+    pub fn minimal_trace() -> Vec<PolynomialValues<F>> {
+        trace_to_poly_values(generate_storage_access_trace(&[], &[]))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> TapeStark<F, D> {
+    /// Trace of an empty tape.
+    pub fn minimal_trace() -> Vec<PolynomialValues<F>> {
+        trace_to_poly_values(generate_tape_trace(&[]))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> SCCallStark<F, D> {
+    /// Trace of no contract call.
+    pub fn minimal_trace() -> Vec<PolynomialValues<F>> {
+        trace_to_poly_values(generate_sccall_trace(&[]))
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> ProgramStark<F, D> {
+    /// Trace of the program `end` fetching its only instruction. Sets the
+    /// compress challenge it was made with, which must not be set yet.
+    pub fn minimal_trace(&mut self) -> Vec<PolynomialValues<F>> {
+        let (trace, beta) = generate_prog_trace(
+            &[end_step()],
+            end_program(),
+            (Address::default(), Address::default()),
+            0,
+        )
+        .unwrap();
+        self.set_compress_challenge(beta).unwrap();
+        trace_to_poly_values(trace)
+    }
+}
+
+impl<F: RichField + Extendable<D>, const D: usize> ProgChunkStark<F, D> {
+    /// Trace of the code of the program `end`.
+    pub fn minimal_trace() -> Vec<PolynomialValues<F>> {
+        trace_to_poly_values(generate_prog_chunk_trace(end_program()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use crate::builtins::bitwise::bitwise_stark::BitwiseStark;
+    use crate::builtins::cmp::cmp_stark::CmpStark;
+    use crate::builtins::poseidon::poseidon_chunk_stark::PoseidonChunkStark;
+    use crate::builtins::poseidon::poseidon_stark::PoseidonStark;
+    use crate::builtins::rangecheck::rangecheck_stark::RangeCheckStark;
+    use crate::builtins::sccall::sccall_stark::SCCallStark;
+    use crate::builtins::storage::storage_access_stark::StorageAccessStark;
+    use crate::builtins::tape::tape_stark::TapeStark;
+    use crate::cpu::cpu_stark::CpuStark;
+    use crate::memory::memory_stark::MemoryStark;
+    use crate::program::prog_chunk_stark::ProgChunkStark;
+    use crate::program::program_stark::ProgramStark;
+    use crate::stark::explain::check_trace;
+
+    type F = GoldilocksField;
+    const D: usize = 2;
+
+    #[test]
+    fn test_minimal_traces() {
+        let trace = CpuStark::<F, D>::minimal_trace();
+        assert_eq!(trace[0].len(), 1);
+        check_trace(&CpuStark::<F, D>::default(), &trace).unwrap();
+        let trace = MemoryStark::<F, D>::minimal_trace();
+        check_trace(&MemoryStark::<F, D>::default(), &trace).unwrap();
+        let mut bitwise = BitwiseStark::<F, D>::default();
+        let trace = bitwise.minimal_trace();
+        check_trace(&bitwise, &trace).unwrap();
+        let trace = CmpStark::<F, D>::minimal_trace();
+        check_trace(&CmpStark::<F, D>::default(), &trace).unwrap();
+        let trace = RangeCheckStark::<F, D>::minimal_trace();
+        check_trace(&RangeCheckStark::<F, D>::default(), &trace).unwrap();
+        let trace = PoseidonStark::<F, D>::minimal_trace();
+        check_trace(&PoseidonStark::<F, D>::default(), &trace).unwrap();
+        let trace = PoseidonChunkStark::<F, D>::minimal_trace();
+        check_trace(&PoseidonChunkStark::<F, D>::default(), &trace).unwrap();
+        let trace = StorageAccessStark::<F, D>::minimal_trace();
+        check_trace(&StorageAccessStark::<F, D>::default(), &trace).unwrap();
+        let trace = TapeStark::<F, D>::minimal_trace();
+        check_trace(&TapeStark::<F, D>::default(), &trace).unwrap();
+        let trace = SCCallStark::<F, D>::minimal_trace();
+        check_trace(&SCCallStark::<F, D>::default(), &trace).unwrap();
+        let mut program = ProgramStark::<F, D>::default();
+        let trace = program.minimal_trace();
+        check_trace(&program, &trace).unwrap();
+        let trace = ProgChunkStark::<F, D>::minimal_trace();
+        check_trace(&ProgChunkStark::<F, D>::default(), &trace).unwrap();
+    }
+}
@@ -1,8 +1,13 @@
 //use std::collections::HashMap;
 
+use anyhow::{anyhow, Result};
 use core::program::Program;
 use core::types::merkle_tree::decode_addr;
-use std::collections::HashMap;
+use core::types::merkle_tree::TREE_VALUE_LEN;
+use core::vm::memory::{memory_root, MEM_OP_DATA};
+use core::vm::opcodes::OlaOpcode;
+use executor::trace::check_memory_trace;
+use std::collections::{BTreeMap, HashMap};
 
 use std::sync::mpsc::channel;
 use std::thread;
@@ -13,28 +18,37 @@ use ethereum_types::{Address, H256};
 //use eth_trie_utils::partial_trie::PartialTrie;
 use plonky2::field::extension::Extendable;
 use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::PrimeField64;
 use plonky2::hash::hash_types::RichField;
 use serde::{Deserialize, Serialize};
 
-use crate::stark::ola_stark::{OlaStark, NUM_TABLES};
-use crate::stark::proof::{BlockMetadata, PublicValues, TrieRoots};
+use crate::cpu::cpu_stark::CpuStark;
+use crate::memory::columns::COL_MEM_CLAIMED;
+use crate::stark::ola_stark::{OlaStark, Table, NUM_TABLES};
+use crate::stark::proof::{BlockMetadata, MemoryClaim, PublicValues, TrieRoots};
 use crate::stark::util::trace_to_poly_values;
 
 use self::builtin::{generate_bitwise_trace, generate_cmp_trace, generate_rc_trace};
 use self::cpu::generate_cpu_trace;
-use self::memory::generate_memory_trace;
+use self::memory::{check_memory_claims, generate_memory_trace, public_memory};
 use self::poseidon::generate_poseidon_trace;
 use self::poseidon_chunk::generate_poseidon_chunk_trace;
 use self::sccall::generate_sccall_trace;
 use self::storage::generate_storage_access_trace;
-use self::tape::generate_tape_trace;
+use self::tape::{generate_tape_trace, tape_outputs};
 
 pub mod builtin;
+pub mod bundles;
+pub mod columnar;
+pub mod coverage;
 pub mod cpu;
 mod ctl_test;
+pub mod loops;
 pub mod memory;
+pub mod minimal;
 pub mod poseidon;
 pub mod poseidon_chunk;
+pub mod predict;
 pub mod prog;
 pub mod sccall;
 pub mod storage;
@@ -49,6 +63,9 @@ pub struct GenerationInputs {
     pub contract_code: HashMap<H256, Vec<u8>>,
     pub block_metadata: BlockMetadata,
     pub addresses: Vec<Address>,
+    /// Final memory values to make public, checked against the run.
+    #[serde(default)]
+    pub memory_claims: Vec<MemoryClaim>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, Default)]
@@ -74,13 +91,35 @@ pub struct TrieInputs {
     pub storage_tries: Vec<(H256, HashedPartialTrie)>,
 }
 
+/// Traces of `program` for the tables of `ola_stark` and their public
+/// values. Fails, before setting up `ola_stark`, if the memory trace is
+/// inconsistent or a claim of `inputs` doesn't hold on it, and fails if the
+/// code executed first is shorter than the data it loads.
 pub fn generate_traces<F: RichField + Extendable<D>, const D: usize>(
     mut program: Program,
     ola_stark: &mut OlaStark<F, D>,
     inputs: GenerationInputs,
-) -> ([Vec<PolynomialValues<F>>; NUM_TABLES], PublicValues) {
+) -> Result<([Vec<PolynomialValues<F>>; NUM_TABLES], PublicValues)> {
+    let memory = std::mem::replace(&mut program.trace.memory, Vec::new());
+    check_memory_trace(&memory).map_err(|err| anyhow!("inconsistent memory trace: {}", err))?;
+    let claimed_rows = check_memory_claims(&memory, &inputs.memory_claims)
+        .map_err(|err| anyhow!("false memory claim: {}", err))?;
+
     let (cpu_tx, cpu_rx) = channel();
     let exec = std::mem::replace(&mut program.trace.exec, Vec::new());
+    // The registers of a seeded run are made public, the cpu table's first
+    // row is constrained to them.
+    let initial_registers = exec
+        .first()
+        .map(|step| step.regs.map(|reg| reg.to_canonical_u64()))
+        .filter(|registers| *registers != CpuStark::<F, D>::default_registers());
+    ola_stark.cpu_stark.initial_registers = initial_registers;
+    // A run ending on a require of zero reverted.
+    let reverted = exec.last().map_or(false, |step| {
+        step.opcode.0 == OlaOpcode::REQUIRE.binary_bit_mask() && step.register_selector.op1.0 == 0
+    });
+    ola_stark.cpu_stark.reverted = reverted;
+    ola_stark.storage_access_stark.reverted = reverted;
     let exec_for_cpu = exec.clone();
     thread::spawn(move || {
         let cpu_rows = generate_cpu_trace::<F>(&exec_for_cpu);
@@ -88,9 +127,19 @@ pub fn generate_traces<F: RichField + Extendable<D>, const D: usize>(
     });
 
     let (memory_tx, memory_rx) = channel();
-    let memory = std::mem::replace(&mut program.trace.memory, Vec::new());
+    let (initial_memory, final_memory) = public_memory(&memory);
+    // Only the data of the code executed first is mapped by the program
+    // table, callees load theirs in their own env.
+    let entry_env = exec.first().map(|step| step.env_idx);
+    let data_len = memory
+        .iter()
+        .filter(|cell| cell.op.0 == MEM_OP_DATA && Some(cell.env_idx) == entry_env)
+        .count();
     thread::spawn(move || {
-        let memory_rows = generate_memory_trace::<F>(&memory);
+        let mut memory_rows = generate_memory_trace::<F>(&memory);
+        for row in claimed_rows {
+            memory_rows[COL_MEM_CLAIMED][row] = F::ONE;
+        }
         let _ = memory_tx.send(trace_to_poly_values(memory_rows));
     });
 
@@ -109,8 +158,21 @@ pub fn generate_traces<F: RichField + Extendable<D>, const D: usize>(
         let _ = cmp_tx.send(trace_to_poly_values(cmp_rows));
     });
 
+    let progs = program
+        .trace
+        .addr_program_hash
+        .into_iter()
+        .map(|(addr, hash)| (decode_addr(addr), hash))
+        .collect::<Vec<_>>();
+
     let (rc_tx, rc_rx) = channel();
-    let builtin_rangecheck = std::mem::replace(&mut program.trace.builtin_rangecheck, Vec::new());
+    let mut builtin_rangecheck =
+        std::mem::replace(&mut program.trace.builtin_rangecheck, Vec::new());
+    builtin_rangecheck.extend(prog::generate_code_rangecheck_rows(
+        &progs,
+        exec.first().map(|step| step.addr_code),
+        data_len,
+    )?);
     thread::spawn(move || {
         let rc_rows = generate_rc_trace(&builtin_rangecheck);
         let _ = rc_tx.send(trace_to_poly_values(rc_rows));
@@ -145,6 +207,13 @@ pub fn generate_traces<F: RichField + Extendable<D>, const D: usize>(
 
     let (tape_tx, tape_rx) = channel();
     let tape = std::mem::replace(&mut program.trace.tape, Vec::new());
+    // The outputs are looked up in the tape table, a run proven without it
+    // outputs nothing.
+    let outputs = if ola_stark.is_active(Table::Tape) {
+        tape_outputs(&tape)
+    } else {
+        vec![]
+    };
     thread::spawn(move || {
         let tape_rows = generate_tape_trace(&tape);
         let _ = tape_tx.send(trace_to_poly_values(tape_rows));
@@ -158,17 +227,16 @@ pub fn generate_traces<F: RichField + Extendable<D>, const D: usize>(
     });
 
     let (program_tx, program_rx) = channel();
-    let progs = program
-        .trace
-        .addr_program_hash
-        .into_iter()
-        .map(|(addr, hash)| (decode_addr(addr), hash))
-        .collect::<Vec<_>>();
     let progs_for_program = progs.clone();
     thread::spawn(move || {
-        let (program_rows, program_beta) =
-            prog::generate_prog_trace::<F>(&exec, progs_for_program, program.trace.start_end_roots);
-        let _ = program_tx.send((trace_to_poly_values(program_rows), program_beta));
+        let program_trace = prog::generate_prog_trace::<F>(
+            &exec,
+            progs_for_program,
+            program.trace.start_end_roots,
+            data_len,
+        )
+        .map(|(program_rows, program_beta)| (trace_to_poly_values(program_rows), program_beta));
+        let _ = program_tx.send(program_trace);
     });
 
     let (prog_chunk_tx, prog_chunk_rx) = channel();
@@ -177,12 +245,15 @@ pub fn generate_traces<F: RichField + Extendable<D>, const D: usize>(
         let _ = prog_chunk_tx.send(trace_to_poly_values(prog_chunk_rows));
     });
 
+    // The compress challenges the traces are generated with, so they can be
+    // checked as they are, see `explain`. The prover refills the compress
+    // columns with the challenges it draws from its transcript.
     let (bitwise_trace, bitwise_beta) = bitwise_rx.recv().unwrap();
     ola_stark
         .bitwise_stark
         .set_compress_challenge(bitwise_beta)
         .unwrap();
-    let (program_trace, program_beta) = program_rx.recv().unwrap();
+    let (program_trace, program_beta) = program_rx.recv().unwrap()?;
     ola_stark
         .program_stark
         .set_compress_challenge(program_beta)
@@ -208,6 +279,20 @@ pub fn generate_traces<F: RichField + Extendable<D>, const D: usize>(
         trie_roots_before: TrieRoots::default(),
         trie_roots_after: TrieRoots::default(),
         block_metadata: inputs.block_metadata,
+        memory_claims: inputs.memory_claims,
+        initial_registers,
+        outputs,
+        reverted,
+        initial_memory_root: words_root(&initial_memory),
+        final_memory_root: words_root(&final_memory),
+        initial_memory,
+        final_memory,
     };
-    (traces, public_values)
+    Ok((traces, public_values))
+}
+
+/// `memory_root` of the memory words `words`, as the public values hold it.
+fn words_root(words: &[MemoryClaim]) -> [u64; TREE_VALUE_LEN] {
+    let values: BTreeMap<_, _> = words.iter().map(|word| (word.addr, word.value)).collect();
+    memory_root(&values).map(|word| word.0)
 }
@@ -0,0 +1,241 @@
+use core::types::merkle_tree::constant::ROOT_TREE_DEPTH;
+use core::vm::opcodes::OlaOpcode;
+use executor::{Process, TraceCapacity};
+use itertools::izip;
+
+use crate::builtins::bitwise::columns::{BITWISE_U8_SIZE, RANGE_CHECK_U8_SIZE};
+use crate::builtins::rangecheck::columns::RANGE_CHECK_U16_SIZE;
+use crate::stark::ola_stark::{Table, NUM_TABLES};
+
+/// Number of code words hashed by a single poseidon permutation.
+const PROG_CHUNK_LEN: usize = 8;
+
+/// Estimate the padded row count of each table from the opcode histogram
+/// of an executed `Process`, without generating the traces.
+pub trait PredictTraceSizes {
+    /// Row count of each table before padding.
+    fn predict_rows(&self) -> [usize; NUM_TABLES];
+
+    fn predict_trace_sizes(&self) -> [usize; NUM_TABLES] {
+        let rows = self.predict_rows();
+        let mut min_rows = [0; NUM_TABLES];
+        min_rows[Table::Bitwise.index()] = RANGE_CHECK_U8_SIZE.max(BITWISE_U8_SIZE);
+        min_rows[Table::RangeCheck.index()] = RANGE_CHECK_U16_SIZE;
+        std::array::from_fn(|i| padded_len(rows[i], min_rows[i]))
+    }
+
+    /// Rows of each table for `Process::with_capacity` to reserve in the
+    /// trace vectors filling it.
+    fn predict_capacity(&self) -> TraceCapacity {
+        let rows = self.predict_rows();
+        TraceCapacity {
+            exec: rows[Table::Cpu.index()],
+            memory: rows[Table::Memory.index()],
+            builtin_rangecheck: rows[Table::RangeCheck.index()],
+            builtin_bitwise_combined: rows[Table::Bitwise.index()],
+            builtin_cmp: rows[Table::Cmp.index()],
+            builtin_poseidon: rows[Table::Poseidon.index()],
+            builtin_poseidon_chunk: rows[Table::PoseidonChunk.index()],
+            builtin_storage_hash: rows[Table::StorageAccess.index()],
+            tape: rows[Table::Tape.index()],
+            sc_call: rows[Table::SCCall.index()],
+        }
+    }
+
+    /// Builtin tables no row is predicted for, which a proof can leave out
+    /// with `OlaStarkBuilder::skip_unused_builtins`.
+    fn unused_builtins(&self) -> Vec<Table> {
+        let rows = self.predict_rows();
+        BUILTIN_TABLES
+            .into_iter()
+            .filter(|table| rows[table.index()] == 0)
+            .collect()
+    }
+}
+
+const BUILTIN_TABLES: [Table; 8] = [
+    Table::Bitwise,
+    Table::Cmp,
+    Table::RangeCheck,
+    Table::Poseidon,
+    Table::PoseidonChunk,
+    Table::StorageAccess,
+    Table::Tape,
+    Table::SCCall,
+];
+
+/// Rows added to each table by one execution of `opcode`. Rows depending on
+/// runtime values (e.g. tload length) are accounted by the cpu ext rows.
+fn opcode_rows(opcode: &str) -> [usize; NUM_TABLES] {
+    let mut rows = [0; NUM_TABLES];
+    match opcode {
+        "mload" | "mstore" => {
+            rows[Table::Memory.index()] = 1;
+            rows[Table::RangeCheck.index()] = 1;
+        }
+        "call" | "ret" => {
+            rows[Table::Memory.index()] = 2;
+            rows[Table::RangeCheck.index()] = 2;
+        }
+        "and" | "or" | "xor" => rows[Table::Bitwise.index()] = 1,
+        "gte" => {
+            rows[Table::Cmp.index()] = 1;
+            rows[Table::RangeCheck.index()] = 1;
+        }
+        "isqrt" => rows[Table::RangeCheck.index()] = 3,
+        // Upper bound, the write only happens on success. Both comparisons
+        // range check their difference.
+        "cas" => {
+            rows[Table::Memory.index()] = 2;
+            rows[Table::Cmp.index()] = 2;
+            rows[Table::RangeCheck.index()] = 4;
+        }
+        "loadp" => {
+            rows[Table::Memory.index()] = 2;
+            rows[Table::RangeCheck.index()] = 2;
+        }
+        // subu range checks its dst. The histogram counts it as a sub, so
+        // this is an upper bound for the field subs.
+        "range" | "sub" => rows[Table::RangeCheck.index()] = 1,
+        "poseidon" => {
+            rows[Table::Memory.index()] = PROG_CHUNK_LEN;
+            rows[Table::Poseidon.index()] = 1;
+            rows[Table::PoseidonChunk.index()] = 1;
+        }
+        "sstore" | "sload" => {
+            rows[Table::Memory.index()] = 8;
+            rows[Table::RangeCheck.index()] = 8;
+            rows[Table::Poseidon.index()] = 2 * ROOT_TREE_DEPTH + 1;
+            rows[Table::StorageAccess.index()] = ROOT_TREE_DEPTH;
+        }
+        "sccall" => {
+            rows[Table::SCCall.index()] = 1;
+            rows[Table::Poseidon.index()] = 2 * ROOT_TREE_DEPTH;
+            rows[Table::StorageAccess.index()] = ROOT_TREE_DEPTH;
+        }
+        _ => {}
+    }
+    rows
+}
+
+/// Rows added to each table by one cpu ext row of `opcode`, i.e. one word
+/// moved by the tape or the memcpy-like loops of the storage opcodes.
+fn ext_row_rows(opcode: &str) -> [usize; NUM_TABLES] {
+    let mut rows = [0; NUM_TABLES];
+    rows[Table::Cpu.index()] = 1;
+    match opcode {
+        "tload" | "tstore" => {
+            rows[Table::Memory.index()] = 1;
+            rows[Table::RangeCheck.index()] = 1;
+            rows[Table::Tape.index()] = 1;
+        }
+        _ => {}
+    }
+    rows
+}
+
+fn padded_len(rows: usize, min_rows: usize) -> usize {
+    rows.max(min_rows).max(2).next_power_of_two()
+}
+
+impl PredictTraceSizes for Process {
+    fn predict_rows(&self) -> [usize; NUM_TABLES] {
+        let mut rows = [0; NUM_TABLES];
+        for op in OlaOpcode::all() {
+            let opcode = op.as_str();
+            let opcode_count = self.opcode_histogram[op.binary_bit_shift() as usize];
+            let per_op = opcode_rows(opcode);
+            let per_ext_row = ext_row_rows(opcode);
+            for (row, op_rows, ext_rows) in izip!(rows.iter_mut(), per_op, per_ext_row) {
+                *row += op_rows * opcode_count.count + ext_rows * opcode_count.ext_rows;
+            }
+            rows[Table::Cpu.index()] += opcode_count.count;
+            // The program table looks up immediates and memory offsets as a
+            // second row.
+            let second_rows = match opcode {
+                "mload" | "mstore" => opcode_count.count,
+                _ => opcode_count.imm,
+            };
+            rows[Table::Program.index()] += opcode_count.count + second_rows;
+        }
+
+        // The code of the entry contract is read from the storage tree and
+        // hashed, which can't be seen from the histogram. Estimate its length
+        // with the executed instructions.
+        let code_len = rows[Table::Program.index()];
+        let code_chunks = (code_len + PROG_CHUNK_LEN - 1) / PROG_CHUNK_LEN;
+        rows[Table::Poseidon.index()] += code_chunks + 2 * ROOT_TREE_DEPTH;
+        rows[Table::StorageAccess.index()] += ROOT_TREE_DEPTH;
+        rows[Table::ProgChunk.index()] += code_chunks;
+        // The program table range checks three limbs of each instruction.
+        rows[Table::RangeCheck.index()] += 3 * code_len;
+        rows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::PredictTraceSizes;
+    use crate::generation::{generate_traces, GenerationInputs};
+    use crate::stark::ola_stark::{OlaStark, Table, NUM_TABLES};
+    use crate::test_utils::execute_asm_path_with_process;
+    use core::vm::opcodes::OlaOpcode;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    #[test]
+    fn test_predict_memory_trace_sizes() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let (process, program) =
+            execute_asm_path_with_process(path.display().to_string(), None, None);
+        let predicted = process.predict_trace_sizes();
+
+        let mut ola_stark = OlaStark::default();
+        let (traces, _) = generate_traces::<GoldilocksField, 2>(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+        )
+        .unwrap();
+        // Sizes are powers of two, allow the prediction to be off by one of
+        // them.
+        for i in 0..NUM_TABLES {
+            let actual = traces[i][0].values.len();
+            assert!(
+                predicted[i] <= 2 * actual && actual <= 2 * predicted[i],
+                "table {}: predicted {} rows, got {}",
+                i,
+                predicted[i],
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn test_unused_builtins_sub() {
+        // A disabled table is empty in its CTLs, so the range check table
+        // must stay for the dst of subu and the code words.
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/sub_u32.json");
+        let (process, _) = execute_asm_path_with_process(path.display().to_string(), None, None);
+        let unused = process.unused_builtins();
+        assert!(!unused.contains(&Table::RangeCheck));
+        assert!(unused.contains(&Table::Bitwise));
+    }
+
+    #[test]
+    fn test_opcode_histogram() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/sub_wrap.json");
+        let (process, _) = execute_asm_path_with_process(path.display().to_string(), None, None);
+        let count = |op: OlaOpcode| process.opcode_histogram[op.binary_bit_shift() as usize];
+        assert_eq!(count(OlaOpcode::MOV).count, 2);
+        assert_eq!(count(OlaOpcode::MOV).imm, 2);
+        assert_eq!(count(OlaOpcode::SUB).count, 1);
+        assert_eq!(count(OlaOpcode::SUB).imm, 0);
+        assert_eq!(count(OlaOpcode::END).count, 1);
+        assert_eq!(count(OlaOpcode::GTE).count, 0);
+    }
+}
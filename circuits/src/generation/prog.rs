@@ -1,11 +1,14 @@
 use core::{
     crypto::poseidon_trace::calculate_poseidon,
-    trace::trace::Step,
+    program::compress_program_row,
+    trace::trace::{RangeCheckRow, Step},
     types::{Field, GoldilocksField},
-    vm::opcodes::OlaOpcode,
+    utils::split_u16_limbs_from_field,
+    vm::{memory::DATA_START_ADDR, opcodes::OlaOpcode},
 };
 use std::cmp::max;
 
+use anyhow::{anyhow, Result};
 use itertools::Itertools;
 use plonky2::{
     hash::hash_types::RichField,
@@ -15,11 +18,16 @@ use plonky2::{
 
 use crate::{program::columns::*, stark::lookup::permuted_cols};
 
+/// Program trace of the code `progs` fetched by `execs`. The last `data_len`
+/// words of the code executed first are its data section, they can't be
+/// fetched and are looked up by the memory writes mapping them. Fails if
+/// that code is shorter than its data.
 pub fn generate_prog_trace<F: RichField>(
     execs: &[Step],
     progs: Vec<([GoldilocksField; 4], Vec<GoldilocksField>)>,
     start_end_roots: ([GoldilocksField; 4], [GoldilocksField; 4]),
-) -> ([Vec<F>; NUM_PROG_COLS], F) {
+    data_len: usize,
+) -> Result<([Vec<F>; NUM_PROG_COLS], F)> {
     let mut challenger =
         Challenger::<F, <PoseidonGoldilocksConfig as GenericConfig<2>>::Hasher>::new();
     for limb_idx in 0..4 {
@@ -67,17 +75,6 @@ pub fn generate_prog_trace<F: RichField>(
         trace[COL_PROG_EXEC_PC][exec_index] = F::from_canonical_u64(e.pc);
         trace[COL_PROG_EXEC_INST][exec_index] = F::from_canonical_u64(e.instruction.0);
         trace[COL_PROG_FILTER_EXEC][exec_index] = F::ONE;
-        trace[COL_PROG_EXEC_COMP_PROG][exec_index] = compress(
-            [
-                trace[COL_PROG_EXEC_CODE_ADDR_RANGE.start][exec_index],
-                trace[COL_PROG_EXEC_CODE_ADDR_RANGE.start + 1][exec_index],
-                trace[COL_PROG_EXEC_CODE_ADDR_RANGE.start + 2][exec_index],
-                trace[COL_PROG_EXEC_CODE_ADDR_RANGE.start + 3][exec_index],
-                trace[COL_PROG_EXEC_PC][exec_index],
-                trace[COL_PROG_EXEC_INST][exec_index],
-            ],
-            beta,
-        );
         exec_index += 1;
 
         // for immediate value
@@ -92,49 +89,45 @@ pub fn generate_prog_trace<F: RichField>(
             trace[COL_PROG_EXEC_PC][exec_index] = F::from_canonical_u64(e.pc + 1);
             trace[COL_PROG_EXEC_INST][exec_index] = F::from_canonical_u64(e.immediate_data.0);
             trace[COL_PROG_FILTER_EXEC][exec_index] = F::ONE;
-            trace[COL_PROG_EXEC_COMP_PROG][exec_index] = compress(
-                [
-                    trace[COL_PROG_EXEC_CODE_ADDR_RANGE.start][exec_index],
-                    trace[COL_PROG_EXEC_CODE_ADDR_RANGE.start + 1][exec_index],
-                    trace[COL_PROG_EXEC_CODE_ADDR_RANGE.start + 2][exec_index],
-                    trace[COL_PROG_EXEC_CODE_ADDR_RANGE.start + 3][exec_index],
-                    trace[COL_PROG_EXEC_PC][exec_index],
-                    trace[COL_PROG_EXEC_INST][exec_index],
-                ],
-                beta,
-            );
+            trace[COL_PROG_EXEC_IS_IMM][exec_index] = F::ONE;
             exec_index += 1;
         }
     }
 
-    let mut prog_index = 0;
-    for (addr, prog) in progs {
-        for (pc, inst) in prog.iter().enumerate() {
-            for j in 0..4 {
-                trace[COL_PROG_CODE_ADDR_RANGE.start + j][prog_index] =
-                    F::from_canonical_u64(addr[j].0);
-            }
-            trace[COL_PROG_PC][prog_index] = F::from_canonical_u64(pc as u64);
-            trace[COL_PROG_INST][prog_index] = F::from_canonical_u64(inst.0);
-            trace[COL_PROG_FILTER_PROG_CHUNK][prog_index] = F::ONE;
-            trace[COL_PROG_COMP_PROG][prog_index] = compress(
-                [
-                    trace[COL_PROG_CODE_ADDR_RANGE.start][prog_index],
-                    trace[COL_PROG_CODE_ADDR_RANGE.start + 1][prog_index],
-                    trace[COL_PROG_CODE_ADDR_RANGE.start + 2][prog_index],
-                    trace[COL_PROG_CODE_ADDR_RANGE.start + 3][prog_index],
-                    trace[COL_PROG_PC][prog_index],
-                    trace[COL_PROG_INST][prog_index],
-                ],
-                beta,
-            );
-            prog_index += 1;
+    let entry_addr = execs.first().map(|e| e.addr_code);
+    let words = decode_code(&progs, entry_addr, data_len)?;
+    for (prog_index, word) in words.iter().enumerate() {
+        for j in 0..4 {
+            trace[COL_PROG_CODE_ADDR_RANGE.start + j][prog_index] =
+                F::from_canonical_u64(word.addr[j].0);
+        }
+        trace[COL_PROG_PC][prog_index] = F::from_canonical_u64(word.pc as u64);
+        trace[COL_PROG_INST][prog_index] = F::from_canonical_u64(word.inst);
+        trace[COL_PROG_FILTER_PROG_CHUNK][prog_index] = F::ONE;
+        if let Some(data_addr) = word.data_addr {
+            trace[COL_PROG_IS_DATA][prog_index] = F::ONE;
+            trace[COL_PROG_DATA_ADDR][prog_index] = F::from_canonical_u64(data_addr);
+        }
+        trace[COL_PROG_IS_IMM][prog_index] = F::from_bool(word.is_imm);
+        if word.is_decoded() {
+            let (u32_flag, imm_flag, hi, lo) = split_inst(word.inst);
+            trace[COL_PROG_INST_U32][prog_index] = F::from_canonical_u64(u32_flag);
+            trace[COL_PROG_INST_IMM][prog_index] = F::from_canonical_u64(imm_flag);
+            trace[COL_PROG_INST_HI][prog_index] = F::from_canonical_u64(hi);
+            trace[COL_PROG_INST_LO][prog_index] = F::from_canonical_u64(lo);
         }
     }
-    let (permuted_inputs, permuted_table) =
-        permuted_cols(&trace[COL_PROG_EXEC_COMP_PROG], &trace[COL_PROG_COMP_PROG]);
-    trace[COL_PROG_EXEC_COMP_PROG_PERM] = permuted_inputs;
-    trace[COL_PROG_COMP_PROG_PERM] = permuted_table;
+    for row in 0..num_padded_rows {
+        let lo = trace[COL_PROG_INST_LO][row];
+        let mem = (lo - F::from_canonical_u64(OlaOpcode::MLOAD.binary_bit_mask()))
+            * (lo - F::from_canonical_u64(OlaOpcode::MSTORE.binary_bit_mask()));
+        if mem.is_zero() {
+            trace[COL_PROG_INST_IS_MEM][row] = F::ONE;
+        } else {
+            trace[COL_PROG_INST_MEM_INV][row] = mem.inverse();
+        }
+    }
+    fill_prog_compress_cols(&mut trace, beta);
 
     let trace_row_vecs = trace.try_into().unwrap_or_else(|v: Vec<Vec<F>>| {
         panic!(
@@ -143,16 +136,140 @@ pub fn generate_prog_trace<F: RichField>(
             v.len()
         )
     });
-    (trace_row_vecs, beta)
+    Ok((trace_row_vecs, beta))
+}
+
+/// A word of the code as the program table decodes it.
+struct CodeWord {
+    addr: [GoldilocksField; 4],
+    pc: usize,
+    inst: u64,
+    /// Whether it is the immediate of the instruction before it.
+    is_imm: bool,
+    /// Memory address of a word of the data section.
+    data_addr: Option<u64>,
 }
 
-fn compress<F: RichField>(values: [F; 6], beta: F) -> F {
-    values[0]
-        + values[1] * beta
-        + values[2] * beta * beta
-        + values[3] * beta * beta * beta
-        + values[4] * beta * beta * beta * beta
-        + values[5] * beta * beta * beta * beta * beta
+impl CodeWord {
+    /// Whether the word is an instruction, split into its flags and limbs.
+    fn is_decoded(&self) -> bool {
+        !self.is_imm && self.data_addr.is_none()
+    }
+
+    /// Whether the word following an instruction is its immediate.
+    fn has_imm(&self) -> bool {
+        let (_, imm_flag, _, lo) = split_inst(self.inst);
+        self.is_decoded()
+            && (imm_flag == 1
+                || lo == OlaOpcode::MLOAD.binary_bit_mask()
+                || lo == OlaOpcode::MSTORE.binary_bit_mask())
+    }
+}
+
+/// The u32 flag, immediate flag, bits 32 to 61 and bits 0 to 31 of the
+/// instruction word `inst`.
+fn split_inst(inst: u64) -> (u64, u64, u64, u64) {
+    (
+        inst >> 63,
+        inst >> 62 & 1,
+        inst >> 32 & ((1 << 30) - 1),
+        inst & u32::MAX as u64,
+    )
+}
+
+/// Words of the code `progs`, decoded from the start of each one. The last
+/// `data_len` words of the code of `entry_addr` are its data section.
+fn decode_code(
+    progs: &[([GoldilocksField; 4], Vec<GoldilocksField>)],
+    entry_addr: Option<[GoldilocksField; 4]>,
+    data_len: usize,
+) -> Result<Vec<CodeWord>> {
+    let mut words: Vec<CodeWord> = vec![];
+    for (addr, prog) in progs {
+        let data_start = if Some(*addr) == entry_addr {
+            prog.len().checked_sub(data_len).ok_or_else(|| {
+                anyhow!(
+                    "{} data words mapped from a code of {} words",
+                    data_len,
+                    prog.len()
+                )
+            })?
+        } else {
+            prog.len()
+        };
+        for (pc, inst) in prog.iter().enumerate() {
+            let is_imm = words.last().map_or(false, |prev| prev.has_imm());
+            words.push(CodeWord {
+                addr: *addr,
+                pc,
+                inst: inst.0,
+                is_imm,
+                data_addr: (pc >= data_start).then(|| DATA_START_ADDR + (pc - data_start) as u64),
+            });
+        }
+    }
+    Ok(words)
+}
+
+/// Range checks of the instruction words the program table splits: lo, hi
+/// and `hi + u32 + 2^32 - 2^30`, so hi is below 2^30 and below 2^30 - 1 in
+/// u32 mode. The split is then below the field order, which makes it the
+/// one of the word. They are looked up along with the cpu ones.
+pub fn generate_code_rangecheck_rows(
+    progs: &[([GoldilocksField; 4], Vec<GoldilocksField>)],
+    entry_addr: Option<[GoldilocksField; 4]>,
+    data_len: usize,
+) -> Result<Vec<RangeCheckRow>> {
+    Ok(decode_code(progs, entry_addr, data_len)?
+        .iter()
+        .filter(|word| word.is_decoded())
+        .flat_map(|word| {
+            let (u32_flag, _, hi, lo) = split_inst(word.inst);
+            [lo, hi, hi + u32_flag + (1 << 32) - (1 << 30)]
+        })
+        .map(|value| {
+            let val = GoldilocksField(value);
+            let (limb_lo, limb_hi) = split_u16_limbs_from_field(&val);
+            RangeCheckRow {
+                val,
+                limb_lo: GoldilocksField(limb_lo),
+                limb_hi: GoldilocksField(limb_hi),
+                filter_looked_for_mem_sort: GoldilocksField::ZERO,
+                filter_looked_for_mem_region: GoldilocksField::ZERO,
+                filter_looked_for_cpu: GoldilocksField::ONE,
+                filter_looked_for_comparison: GoldilocksField::ZERO,
+                filter_looked_for_storage: GoldilocksField::ZERO,
+            }
+        })
+        .collect())
+}
+
+/// Fill the compress columns of a program trace, and their permutations,
+/// with the challenge `beta`. The prover refills them with the challenge it
+/// draws once the other tables are committed.
+pub(crate) fn fill_prog_compress_cols<F: RichField>(trace: &mut [Vec<F>], beta: F) {
+    for row in 0..trace[COL_PROG_COMP_PROG].len() {
+        trace[COL_PROG_EXEC_COMP_PROG][row] = compress_program_row(
+            std::array::from_fn(|j| trace[COL_PROG_EXEC_CODE_ADDR_RANGE.start + j][row]),
+            trace[COL_PROG_EXEC_PC][row],
+            trace[COL_PROG_EXEC_INST][row],
+            F::ZERO,
+            trace[COL_PROG_EXEC_IS_IMM][row],
+            beta,
+        );
+        trace[COL_PROG_COMP_PROG][row] = compress_program_row(
+            std::array::from_fn(|j| trace[COL_PROG_CODE_ADDR_RANGE.start + j][row]),
+            trace[COL_PROG_PC][row],
+            trace[COL_PROG_INST][row],
+            trace[COL_PROG_IS_DATA][row],
+            trace[COL_PROG_IS_IMM][row],
+            beta,
+        );
+    }
+    let (permuted_inputs, permuted_table) =
+        permuted_cols(&trace[COL_PROG_EXEC_COMP_PROG], &trace[COL_PROG_COMP_PROG]);
+    trace[COL_PROG_EXEC_COMP_PROG_PERM] = permuted_inputs;
+    trace[COL_PROG_COMP_PROG_PERM] = permuted_table;
 }
 
 pub fn generate_prog_chunk_trace<F: RichField>(
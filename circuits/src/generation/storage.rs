@@ -20,6 +20,8 @@ pub fn generate_storage_access_trace<F: RichField>(
     };
 
     let mut trace: Vec<Vec<F>> = vec![vec![F::ZERO; num_padded_rows]; NUM_COL_ST];
+    // The writes of a reverted run are undone after them.
+    let reverted = accesses.iter().any(|c| c.is_undo.0 == 1);
     for (i, c) in accesses.iter().chain(prog_hash_read).enumerate() {
         trace[COL_ST_ACCESS_IDX][i] = F::from_canonical_u64(c.storage_access_idx);
         for j in 0..4 {
@@ -78,6 +80,13 @@ pub fn generate_storage_access_trace<F: RichField>(
         } else {
             F::ZERO
         };
+        trace[COL_ST_IS_UNDO][i] = F::from_canonical_u64(c.is_undo.0);
+        trace[COL_ST_IS_UNDONE][i] =
+            if reverted && c.layer == 256 && c.is_write.0 != 0 && c.is_undo.0 == 0 {
+                F::ONE
+            } else {
+                F::ZERO
+            };
         trace[COL_ST_IS_PADDING][i] = F::ZERO;
     }
 
@@ -3,10 +3,33 @@ use core::{trace::trace::TapeRow, types::PrimeField64, vm::opcodes::OlaOpcode};
 use plonky2::hash::hash_types::RichField;
 
 use crate::builtins::tape::columns::{
-    COL_FILTER_LOOKED, COL_TAPE_ADDR, COL_TAPE_IS_INIT_SEG, COL_TAPE_OPCODE, COL_TAPE_TX_IDX,
-    COL_TAPE_VALUE, NUM_COL_TAPE,
+    COL_FILTER_LOOKED, COL_TAPE_ADDR, COL_TAPE_IS_INIT_SEG, COL_TAPE_IS_OUTPUT,
+    COL_TAPE_IS_RET_SEG, COL_TAPE_OPCODE, COL_TAPE_OUTPUT_IDX, COL_TAPE_TX_IDX, COL_TAPE_VALUE,
+    NUM_COL_TAPE,
 };
 
+/// Whether a tape cell comes before the return data: the init cells and the
+/// ones an sccall writes. The data tstored before an sccall is its calldata,
+/// so the return data starts after the last of these cells.
+fn precedes_ret_data(is_init: bool, opcode: u64) -> bool {
+    is_init || opcode == OlaOpcode::SCCALL.binary_bit_mask()
+}
+
+/// Words the run output, i.e. the return data it wrote to the tape with
+/// `tstore`, in tape order.
+pub fn tape_outputs(cells: &[TapeRow]) -> Vec<u64> {
+    let op_tstore = OlaOpcode::TSTORE.binary_bit_mask();
+    let ret_start = cells
+        .iter()
+        .rposition(|cell| precedes_ret_data(cell.is_init, cell.opcode.to_canonical_u64()))
+        .map_or(0, |i| i + 1);
+    cells[ret_start..]
+        .iter()
+        .filter(|cell| cell.opcode.to_canonical_u64() == op_tstore)
+        .map(|cell| cell.value.to_canonical_u64())
+        .collect()
+}
+
 pub fn generate_tape_trace<F: RichField>(cells: &[TapeRow]) -> [Vec<F>; NUM_COL_TAPE] {
     let num_filled_row_len: usize = cells.len();
     let num_padded_rows = if !num_filled_row_len.is_power_of_two() || num_filled_row_len < 2 {
@@ -63,6 +86,33 @@ pub fn generate_tape_trace<F: RichField>(cells: &[TapeRow]) -> [Vec<F>; NUM_COL_
         }
     }
 
+    // The return data segment runs from the last init or sccall row of the tx
+    // to its end, so fill it backwards.
+    let op_tstore = F::from_canonical_u64(OlaOpcode::TSTORE.binary_bit_mask());
+    let mut is_ret_seg = true;
+    for i in (0..num_padded_rows).rev() {
+        if i + 1 < num_padded_rows && trace[COL_TAPE_TX_IDX][i] != trace[COL_TAPE_TX_IDX][i + 1] {
+            is_ret_seg = true;
+        }
+        if precedes_ret_data(
+            trace[COL_TAPE_IS_INIT_SEG][i].is_one(),
+            trace[COL_TAPE_OPCODE][i].to_canonical_u64(),
+        ) {
+            is_ret_seg = false;
+        }
+        trace[COL_TAPE_IS_RET_SEG][i] = F::from_bool(is_ret_seg);
+        trace[COL_TAPE_IS_OUTPUT][i] =
+            F::from_bool(is_ret_seg && trace[COL_TAPE_OPCODE][i] == op_tstore);
+    }
+    for i in 1..num_padded_rows {
+        trace[COL_TAPE_OUTPUT_IDX][i] =
+            if trace[COL_TAPE_TX_IDX][i] == trace[COL_TAPE_TX_IDX][i - 1] {
+                trace[COL_TAPE_OUTPUT_IDX][i - 1] + trace[COL_TAPE_IS_OUTPUT][i - 1]
+            } else {
+                F::ZERO
+            };
+    }
+
     trace.try_into().unwrap_or_else(|v: Vec<Vec<F>>| {
         panic!(
             "Expected a Vec of length {} but it was {}",
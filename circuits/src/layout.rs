@@ -0,0 +1,59 @@
+//! Column counts of the tables. Code relying on the trace layout, e.g. to
+//! build or read traces outside of this crate, can check
+//! `layout_fingerprint` to notice a change.
+
+use sha2::{Digest, Sha256};
+
+pub use crate::builtins::bitwise::columns::COL_NUM_BITWISE;
+pub use crate::builtins::cmp::columns::COL_NUM_CMP;
+pub use crate::builtins::poseidon::columns::{NUM_POSEIDON_CHUNK_COLS, NUM_POSEIDON_COLS};
+pub use crate::builtins::rangecheck::columns::COL_NUM_RC;
+pub use crate::builtins::sccall::columns::NUM_COL_SCCALL;
+pub use crate::builtins::storage::columns::NUM_COL_ST;
+pub use crate::builtins::tape::columns::NUM_COL_TAPE;
+pub use crate::cpu::columns::NUM_CPU_COLS;
+pub use crate::memory::columns::NUM_MEM_COLS;
+pub use crate::program::columns::{NUM_PROG_CHUNK_COLS, NUM_PROG_COLS};
+use crate::stark::ola_stark::NUM_TABLES;
+
+/// Number of columns of each table, indexed by `Table`.
+pub const TABLE_WIDTHS: [usize; NUM_TABLES] = [
+    NUM_CPU_COLS,
+    NUM_MEM_COLS,
+    COL_NUM_BITWISE,
+    COL_NUM_CMP,
+    COL_NUM_RC,
+    NUM_POSEIDON_COLS,
+    NUM_POSEIDON_CHUNK_COLS,
+    NUM_COL_ST,
+    NUM_COL_TAPE,
+    NUM_COL_SCCALL,
+    NUM_PROG_COLS,
+    NUM_PROG_CHUNK_COLS,
+];
+
+/// First 8 bytes, little endian, of the sha256 of `TABLE_WIDTHS` as u64s.
+pub fn layout_fingerprint() -> u64 {
+    let mut hasher = Sha256::new();
+    for width in TABLE_WIDTHS {
+        hasher.update((width as u64).to_le_bytes());
+    }
+    let digest = hasher.finalize();
+    u64::from_le_bytes(digest[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{layout_fingerprint, TABLE_WIDTHS};
+
+    // Changing a table layout breaks this test on purpose: update the
+    // fingerprint and mention the change to users of the layout.
+    #[test]
+    fn test_layout_fingerprint() {
+        assert_eq!(
+            TABLE_WIDTHS,
+            [103, 33, 59, 6, 12, 134, 53, 50, 9, 26, 28, 40]
+        );
+        assert_eq!(layout_fingerprint(), 0x61a1c1708e062bbc);
+    }
+}
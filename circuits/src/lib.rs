@@ -5,7 +5,15 @@ pub mod builtins;
 pub mod cpu;
 pub mod fixed_table;
 pub mod generation;
+pub mod layout;
 pub mod memory;
+pub mod prelude;
 pub mod program;
 pub mod stark;
 pub mod test_utils;
+
+pub use layout::{
+    layout_fingerprint, COL_NUM_BITWISE, COL_NUM_CMP, COL_NUM_RC, NUM_COL_SCCALL, NUM_COL_ST,
+    NUM_COL_TAPE, NUM_CPU_COLS, NUM_MEM_COLS, NUM_POSEIDON_CHUNK_COLS, NUM_POSEIDON_COLS,
+    NUM_PROG_CHUNK_COLS, NUM_PROG_COLS, TABLE_WIDTHS,
+};
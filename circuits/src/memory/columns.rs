@@ -26,7 +26,9 @@ pub(crate) const COL_MEM_S_POSEIDON: usize = COL_MEM_S_SCCALL + 1;
 pub(crate) const COL_MEM_S_SSTORE: usize = COL_MEM_S_POSEIDON + 1;
 pub(crate) const COL_MEM_S_SLOAD: usize = COL_MEM_S_SSTORE + 1;
 pub(crate) const COL_MEM_S_PROPHET: usize = COL_MEM_S_SLOAD + 1;
-pub(crate) const COL_MEM_IS_WRITE: usize = COL_MEM_S_PROPHET + 1;
+pub(crate) const COL_MEM_S_DATA: usize = COL_MEM_S_PROPHET + 1;
+pub(crate) const COL_MEM_S_INIT: usize = COL_MEM_S_DATA + 1;
+pub(crate) const COL_MEM_IS_WRITE: usize = COL_MEM_S_INIT + 1;
 pub(crate) const COL_MEM_VALUE: usize = COL_MEM_IS_WRITE + 1;
 pub(crate) const COL_MEM_DIFF_ADDR: usize = COL_MEM_VALUE + 1;
 pub(crate) const COL_MEM_DIFF_ADDR_INV: usize = COL_MEM_DIFF_ADDR + 1;
@@ -42,7 +44,12 @@ pub(crate) const COL_MEM_REGION_HEAP: usize = COL_MEM_REGION_PROPHET + 1;
 pub(crate) const COL_MEM_RC_VALUE: usize = COL_MEM_REGION_HEAP + 1;
 pub(crate) const COL_MEM_FILTER_LOOKING_RC: usize = COL_MEM_RC_VALUE + 1;
 pub(crate) const COL_MEM_FILTER_LOOKING_RC_COND: usize = COL_MEM_FILTER_LOOKING_RC + 1;
-pub(crate) const NUM_MEM_COLS: usize = COL_MEM_FILTER_LOOKING_RC_COND + 1;
+// The row is the last access to an address a public memory claim is about.
+pub(crate) const COL_MEM_CLAIMED: usize = COL_MEM_FILTER_LOOKING_RC_COND + 1;
+// The row is the last access to a stack address, its word in the public
+// final memory.
+pub(crate) const COL_MEM_FINAL: usize = COL_MEM_CLAIMED + 1;
+pub const NUM_MEM_COLS: usize = COL_MEM_FINAL + 1;
 
 #[allow(dead_code)]
 pub(crate) fn get_memory_col_name_map() -> BTreeMap<usize, String> {
@@ -64,6 +71,8 @@ pub(crate) fn get_memory_col_name_map() -> BTreeMap<usize, String> {
     m.insert(COL_MEM_S_SSTORE, String::from("S_SSTORE"));
     m.insert(COL_MEM_S_SLOAD, String::from("S_SLOAD"));
     m.insert(COL_MEM_S_PROPHET, String::from("S_PROPHET"));
+    m.insert(COL_MEM_S_DATA, String::from("S_DATA"));
+    m.insert(COL_MEM_S_INIT, String::from("S_INIT"));
     m.insert(COL_MEM_IS_WRITE, String::from("IS_WRITE"));
     m.insert(COL_MEM_VALUE, String::from("VALUE"));
     m.insert(COL_MEM_DIFF_ADDR, String::from("DIFF_ADDR"));
@@ -79,6 +88,8 @@ pub(crate) fn get_memory_col_name_map() -> BTreeMap<usize, String> {
         COL_MEM_FILTER_LOOKING_RC_COND,
         String::from("FILTER_LOOKING_RC_COND"),
     );
+    m.insert(COL_MEM_CLAIMED, String::from("CLAIMED"));
+    m.insert(COL_MEM_FINAL, String::from("FINAL"));
     m
 }
 
@@ -1,3 +1,4 @@
+use core::vm::memory::{MEM_OP_DATA, MEM_OP_INIT};
 use core::vm::opcodes::OlaOpcode;
 use std::ops::Sub;
 
@@ -78,6 +79,38 @@ pub fn ctl_filter_with_poseidon_chunk<F: Field>() -> Column<F> {
     Column::single(COL_MEM_S_POSEIDON)
 }
 
+// The data section writes are the data rows of the code.
+pub fn ctl_data_with_program<F: Field>() -> Vec<Column<F>> {
+    Column::singles([COL_MEM_ADDR, COL_MEM_VALUE]).collect()
+}
+
+pub fn ctl_filter_with_program<F: Field>() -> Column<F> {
+    Column::single(COL_MEM_S_DATA)
+}
+
+// The public memory claims are the last accesses to their addresses.
+pub fn ctl_data_with_claims<F: Field>() -> Vec<Column<F>> {
+    Column::singles([COL_MEM_ADDR, COL_MEM_VALUE]).collect()
+}
+
+pub fn ctl_filter_with_claims<F: Field>() -> Column<F> {
+    Column::single(COL_MEM_CLAIMED)
+}
+
+// The public initial memory is written by the init rows, and the public final
+// memory is the last accesses to the stack addresses.
+pub fn ctl_data_with_public_memory<F: Field>() -> Vec<Column<F>> {
+    Column::singles([COL_MEM_ADDR, COL_MEM_VALUE]).collect()
+}
+
+pub fn ctl_filter_with_initial_memory<F: Field>() -> Column<F> {
+    Column::single(COL_MEM_S_INIT)
+}
+
+pub fn ctl_filter_with_final_memory<F: Field>() -> Column<F> {
+    Column::single(COL_MEM_FINAL)
+}
+
 const ADDR_HEAP_PTR: u64 = 18446744060824649731u64;
 const INIT_VALUE_HEAP_PTR: u64 = ADDR_HEAP_PTR + 1;
 
@@ -150,6 +183,8 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
         let op_sstore = P::Scalar::from_canonical_u64(OlaOpcode::SSTORE.binary_bit_mask());
         let op_sload = P::Scalar::from_canonical_u64(OlaOpcode::SLOAD.binary_bit_mask());
         let op_prophet = P::ZEROS;
+        let op_data = P::Scalar::from_canonical_u64(MEM_OP_DATA);
+        let op_init = P::Scalar::from_canonical_u64(MEM_OP_INIT);
 
         // constraint opcode and selector matches, selector is binary and only one is
         // selected.
@@ -164,6 +199,8 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
         yield_constr.constraint((lv[COL_MEM_OP] - op_sstore) * lv[COL_MEM_S_SSTORE]);
         yield_constr.constraint((lv[COL_MEM_OP] - op_sload) * lv[COL_MEM_S_SLOAD]);
         yield_constr.constraint((lv[COL_MEM_OP] - op_prophet) * lv[COL_MEM_S_PROPHET]);
+        yield_constr.constraint((lv[COL_MEM_OP] - op_data) * lv[COL_MEM_S_DATA]);
+        yield_constr.constraint((lv[COL_MEM_OP] - op_init) * lv[COL_MEM_S_INIT]);
         yield_constr.constraint((P::ONES - lv[COL_MEM_S_MLOAD]) * lv[COL_MEM_S_MLOAD]);
         yield_constr.constraint((P::ONES - lv[COL_MEM_S_MSTORE]) * lv[COL_MEM_S_MSTORE]);
         yield_constr.constraint((P::ONES - lv[COL_MEM_S_CALL]) * lv[COL_MEM_S_CALL]);
@@ -175,6 +212,8 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
         yield_constr.constraint((P::ONES - lv[COL_MEM_S_SSTORE]) * lv[COL_MEM_S_SSTORE]);
         yield_constr.constraint((P::ONES - lv[COL_MEM_S_SLOAD]) * lv[COL_MEM_S_SLOAD]);
         yield_constr.constraint((P::ONES - lv[COL_MEM_S_PROPHET]) * lv[COL_MEM_S_PROPHET]);
+        yield_constr.constraint((P::ONES - lv[COL_MEM_S_DATA]) * lv[COL_MEM_S_DATA]);
+        yield_constr.constraint((P::ONES - lv[COL_MEM_S_INIT]) * lv[COL_MEM_S_INIT]);
         yield_constr.constraint(
             P::ONES
                 - lv[COL_MEM_S_MLOAD]
@@ -187,14 +226,17 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
                 - lv[COL_MEM_S_POSEIDON]
                 - lv[COL_MEM_S_SSTORE]
                 - lv[COL_MEM_S_SLOAD]
-                - lv[COL_MEM_S_PROPHET],
+                - lv[COL_MEM_S_PROPHET]
+                - lv[COL_MEM_S_DATA]
+                - lv[COL_MEM_S_INIT],
         );
 
         // constraint is_rw region
         yield_constr.constraint(is_rw * (P::ONES - is_rw));
-        yield_constr.constraint(lv[COL_MEM_IS_RW] * lv[COL_MEM_S_PROPHET]);
+        yield_constr.constraint(lv[COL_MEM_IS_RW] * (lv[COL_MEM_S_PROPHET] + lv[COL_MEM_S_DATA]));
         yield_constr.constraint(
-            (P::ONES - lv[COL_MEM_IS_RW]) * (P::ONES - lv[COL_MEM_S_PROPHET] - lv[COL_MEM_S_MLOAD]),
+            (P::ONES - lv[COL_MEM_IS_RW])
+                * (P::ONES - lv[COL_MEM_S_PROPHET] - lv[COL_MEM_S_DATA] - lv[COL_MEM_S_MLOAD]),
         );
         // constraint is_write
         yield_constr.constraint(
@@ -205,7 +247,9 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
                     - lv[COL_MEM_S_TLOAD]
                     - lv[COL_MEM_S_POSEIDON]
                     - lv[COL_MEM_S_SLOAD]
-                    - lv[COL_MEM_S_PROPHET]),
+                    - lv[COL_MEM_S_PROPHET]
+                    - lv[COL_MEM_S_DATA]
+                    - lv[COL_MEM_S_INIT]),
         );
         yield_constr.constraint(
             (P::ONES - lv[COL_MEM_IS_WRITE])
@@ -228,6 +272,12 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
         // make sure region addr is right
         yield_constr.constraint(region_prophet * (p - addr - diff_addr_cond));
         yield_constr.constraint(region_heap * (p - span - addr - diff_addr_cond));
+        // the data section is in the prophet region
+        yield_constr.constraint(lv[COL_MEM_S_DATA] * (P::ONES - region_prophet));
+        // the initial memory is in the stack, written by the first access to
+        // its address
+        yield_constr.constraint(lv[COL_MEM_S_INIT] * (P::ONES - region_stack));
+        yield_constr.constraint(lv[COL_MEM_S_INIT] * rw_addr_unchanged);
 
         // if not prophet write, must be looked up.
         // yield_constr
@@ -271,19 +321,23 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
         // * (nv[COL_MEM_ENV_IDX] - lv[COL_MEM_ENV_IDX]
 
         // read/write constraint:
-        // 1. first operation for each addr must be write(except heap ptr);
+        // 1. memory is zero initialized, a first read for each addr must return
+        //    zero(except heap ptr);
         // 2. next value does not change if it is read(except heap ptr).
         // 3. if heap ptr first op is read, it must be heap_ptr + 1.
-        yield_constr.constraint_first_row(is_rw * (P::ONES - is_write) * (addr - addr_heap_ptr));
+        yield_constr
+            .constraint_first_row(is_rw * (P::ONES - is_write) * (addr - addr_heap_ptr) * value);
         yield_constr.constraint(
             (nv[COL_MEM_TX_IDX] - lv[COL_MEM_TX_IDX])
                 * (nv[COL_MEM_ENV_IDX] - lv[COL_MEM_ENV_IDX])
                 * nv[COL_MEM_IS_RW]
                 * (P::ONES - nv_is_write)
-                * (nv_addr - addr_heap_ptr),
+                * (nv_addr - addr_heap_ptr)
+                * nv_value,
+        );
+        yield_constr.constraint(
+            (nv_addr - addr) * (P::ONES - nv_is_write) * (nv_addr - addr_heap_ptr) * nv_value,
         );
-        yield_constr
-            .constraint((nv_addr - addr) * (P::ONES - nv_is_write) * (nv_addr - addr_heap_ptr));
         yield_constr
             .constraint((P::ONES - nv_is_write) * (nv_value - value) * (nv_addr - addr_heap_ptr));
 
@@ -324,6 +378,23 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for MemoryStark<F
                 * (P::ONES - filter_looking_rc),
         );
 
+        // a claimed row is a read/write one and the last access to its
+        // address: the next row of the same address has rw_addr_unchanged.
+        let claimed = lv[COL_MEM_CLAIMED];
+        yield_constr.constraint(claimed * (P::ONES - claimed));
+        yield_constr.constraint(claimed * (P::ONES - is_rw));
+        yield_constr.constraint_transition(claimed * nv_rw_addr_unchanged);
+
+        // the final rows are exactly the last accesses to the stack addresses:
+        // a final row is followed by another address, and a stack row which
+        // isn't final by the same address.
+        let is_final = lv[COL_MEM_FINAL];
+        yield_constr.constraint(is_final * (P::ONES - is_final));
+        yield_constr.constraint(is_final * (P::ONES - region_stack));
+        yield_constr.constraint_transition(is_final * nv_rw_addr_unchanged);
+        yield_constr.constraint_transition(region_stack * (P::ONES - is_final) * (nv_addr - addr));
+        yield_constr.constraint_last_row(region_stack * (P::ONES - is_final));
+
         // heap and prophet read, diff_cond must rc
         yield_constr.constraint((P::ONES - lv_filter_looking_rc_cond) * region_heap);
         yield_constr.constraint(
@@ -0,0 +1,53 @@
+//! The common execution and proving API in one place, for
+//! `use circuits::prelude::*`. The set of all the tables is `OlaStark` here.
+//!
+//! Execute, prove and verify the `add_imm` test program:
+//!
+//! ```
+//! use circuits::prelude::*;
+//!
+//! const D: usize = 2;
+//! type C = Blake3GoldilocksConfig;
+//! type F = <C as GenericConfig<D>>::F;
+//!
+//! let path = format!(
+//!     "{}/../assembler/test_data/asm/add_imm.json",
+//!     env!("CARGO_MANIFEST_DIR")
+//! );
+//! let program = encode_asm_from_json_file(path).unwrap();
+//! let (_, program) = execute_program(
+//!     Process::new(),
+//!     &program,
+//!     None,
+//!     &mut AccountTree::new_test(),
+//! )
+//! .unwrap();
+//!
+//! let mut ola_stark = OlaStark::<F, D>::default();
+//! let config = StarkConfig::standard_fast_config();
+//! let proof = prove::<F, C, D>(
+//!     program,
+//!     &mut ola_stark,
+//!     GenerationInputs::default(),
+//!     &config,
+//!     &mut TimingTree::default(),
+//! )
+//! .unwrap();
+//! verify_proof(ola_stark, proof, &config).unwrap();
+//! ```
+
+pub use assembler::encoder::{encode_asm_from_json_file, encode_asm_from_json_str};
+pub use core::merkle_tree::tree::AccountTree;
+pub use core::program::Program;
+pub use core::types::{Field, GoldilocksField};
+pub use executor::run::{execute_program, load_program, run_program, RunResult};
+pub use executor::{Process, TxScopeCacheManager};
+pub use plonky2::plonk::config::{Blake3GoldilocksConfig, GenericConfig, PoseidonGoldilocksConfig};
+pub use plonky2::util::timing::TimingTree;
+
+pub use crate::generation::{generate_traces, GenerationInputs};
+pub use crate::stark::config::StarkConfig;
+pub use crate::stark::ola_stark::{OlaStark, Table};
+pub use crate::stark::proof::{AllProof, PublicValues};
+pub use crate::stark::prover::{prove, prove_with_traces};
+pub use crate::stark::verifier::verify_proof;
@@ -13,7 +13,26 @@ pub(crate) const COL_PROG_EXEC_COMP_PROG: usize = COL_PROG_EXEC_INST + 1;
 pub(crate) const COL_PROG_EXEC_COMP_PROG_PERM: usize = COL_PROG_EXEC_COMP_PROG + 1;
 pub(crate) const COL_PROG_FILTER_EXEC: usize = COL_PROG_EXEC_COMP_PROG_PERM + 1;
 pub(crate) const COL_PROG_FILTER_PROG_CHUNK: usize = COL_PROG_FILTER_EXEC + 1;
-pub(crate) const NUM_PROG_COLS: usize = COL_PROG_FILTER_PROG_CHUNK + 1;
+// Code rows holding the data section, at `DATA_ADDR` in memory.
+pub(crate) const COL_PROG_IS_DATA: usize = COL_PROG_FILTER_PROG_CHUNK + 1;
+pub(crate) const COL_PROG_DATA_ADDR: usize = COL_PROG_IS_DATA + 1;
+// Code rows holding the immediate word following an instruction, decoded from
+// the code: the instruction before has the immediate flag or is an mload or
+// mstore, whose offset always follows.
+pub(crate) const COL_PROG_IS_IMM: usize = COL_PROG_DATA_ADDR + 1;
+// Instruction words split as `u32 * 2^63 + imm * 2^62 + hi * 2^32 + lo`, hi
+// and lo range checked.
+pub(crate) const COL_PROG_INST_U32: usize = COL_PROG_IS_IMM + 1;
+pub(crate) const COL_PROG_INST_IMM: usize = COL_PROG_INST_U32 + 1;
+pub(crate) const COL_PROG_INST_HI: usize = COL_PROG_INST_IMM + 1;
+pub(crate) const COL_PROG_INST_LO: usize = COL_PROG_INST_HI + 1;
+// lo is the opcode of an mload or mstore, and the inverse of
+// `(lo - mload) * (lo - mstore)` otherwise.
+pub(crate) const COL_PROG_INST_IS_MEM: usize = COL_PROG_INST_LO + 1;
+pub(crate) const COL_PROG_INST_MEM_INV: usize = COL_PROG_INST_IS_MEM + 1;
+// 1 for exec rows holding the immediate word following an instruction.
+pub(crate) const COL_PROG_EXEC_IS_IMM: usize = COL_PROG_INST_MEM_INV + 1;
+pub const NUM_PROG_COLS: usize = COL_PROG_EXEC_IS_IMM + 1;
 
 #[allow(dead_code)]
 pub(crate) fn get_prog_col_name_map() -> BTreeMap<usize, String> {
@@ -42,6 +61,16 @@ pub(crate) fn get_prog_col_name_map() -> BTreeMap<usize, String> {
         COL_PROG_FILTER_PROG_CHUNK,
         String::from("FILTER_PROG_CHUNK"),
     );
+    m.insert(COL_PROG_IS_DATA, String::from("IS_DATA"));
+    m.insert(COL_PROG_DATA_ADDR, String::from("DATA_ADDR"));
+    m.insert(COL_PROG_IS_IMM, String::from("IS_IMM"));
+    m.insert(COL_PROG_INST_U32, String::from("INST_U32"));
+    m.insert(COL_PROG_INST_IMM, String::from("INST_IMM"));
+    m.insert(COL_PROG_INST_HI, String::from("INST_HI"));
+    m.insert(COL_PROG_INST_LO, String::from("INST_LO"));
+    m.insert(COL_PROG_INST_IS_MEM, String::from("INST_IS_MEM"));
+    m.insert(COL_PROG_INST_MEM_INV, String::from("INST_MEM_INV"));
+    m.insert(COL_PROG_EXEC_IS_IMM, String::from("EXEC_IS_IMM"));
     m
 }
 
@@ -59,7 +88,7 @@ pub(crate) const COL_PROG_CHUNK_FILTER_LOOKING_PROG_RANGE: Range<usize> =
     COL_PROG_CHUNK_IS_RESULT_LINE + 1..COL_PROG_CHUNK_IS_RESULT_LINE + 1 + 8;
 pub(crate) const COL_PROG_CHUNK_IS_PADDING_LINE: usize =
     COL_PROG_CHUNK_FILTER_LOOKING_PROG_RANGE.end;
-pub(crate) const NUM_PROG_CHUNK_COLS: usize = COL_PROG_CHUNK_IS_PADDING_LINE + 1;
+pub const NUM_PROG_CHUNK_COLS: usize = COL_PROG_CHUNK_IS_PADDING_LINE + 1;
 
 #[allow(dead_code)]
 pub(crate) fn get_prog_chunk_col_name_map() -> BTreeMap<usize, String> {
@@ -21,10 +21,24 @@ use crate::stark::{
     vars::{StarkEvaluationTargets, StarkEvaluationVars},
 };
 use anyhow::Result;
+use core::vm::{memory::DATA_START_ADDR, opcodes::OlaOpcode};
 
+// Code is immutable during a run: memory is a separate address space, so no
+// mstore can reach it, and every instruction and immediate fetched by the cpu
+// is looked up here at its (code address, pc). The table itself is tied to the
+// code hash by the ProgChunk lookups. The code is decoded from the start of
+// each program, so a fetch is tagged as an instruction or an immediate and
+// only matches a word of its kind: a jump to the pc of an immediate can't
+// fetch it as an instruction. The data section ending the code is flagged
+// too: its rows are compressed with the flag, so no fetch matches them, and
+// are looked up by the memory writes mapping them.
 pub fn ctl_data_by_cpu<F: Field>() -> Vec<Column<F>> {
-    Column::singles(COL_PROG_EXEC_CODE_ADDR_RANGE.chain([COL_PROG_EXEC_PC, COL_PROG_EXEC_INST]))
-        .collect_vec()
+    Column::singles(COL_PROG_EXEC_CODE_ADDR_RANGE.chain([
+        COL_PROG_EXEC_PC,
+        COL_PROG_EXEC_INST,
+        COL_PROG_EXEC_IS_IMM,
+    ]))
+    .collect_vec()
 }
 
 pub fn ctl_filter_by_cpu<F: Field>() -> Column<F> {
@@ -39,6 +53,36 @@ pub fn ctl_filter_by_program_chunk<F: Field>() -> Column<F> {
     Column::single(COL_PROG_FILTER_PROG_CHUNK)
 }
 
+pub fn ctl_data_by_memory<F: Field>() -> Vec<Column<F>> {
+    Column::singles([COL_PROG_DATA_ADDR, COL_PROG_INST]).collect_vec()
+}
+
+pub fn ctl_filter_by_memory<F: Field>() -> Column<F> {
+    Column::single(COL_PROG_IS_DATA)
+}
+
+// The limbs of the instruction words, see `generate_code_rangecheck_rows`.
+pub fn ctl_data_with_rangecheck<F: Field>(i: usize) -> Vec<Column<F>> {
+    let value = match i {
+        0 => Column::single(COL_PROG_INST_LO),
+        1 => Column::single(COL_PROG_INST_HI),
+        _ => Column::linear_combination_with_constant(
+            [(COL_PROG_INST_HI, F::ONE), (COL_PROG_INST_U32, F::ONE)],
+            F::from_canonical_u64((1 << 32) - (1 << 30)),
+        ),
+    };
+    vec![value]
+}
+
+// Instruction rows: code rows neither an immediate nor data.
+pub fn ctl_filter_with_rangecheck<F: Field>() -> Column<F> {
+    Column::linear_combination([
+        (COL_PROG_FILTER_PROG_CHUNK, F::ONE),
+        (COL_PROG_IS_IMM, F::NEG_ONE),
+        (COL_PROG_IS_DATA, F::NEG_ONE),
+    ])
+}
+
 #[derive(Copy, Clone, Default)]
 pub struct ProgramStark<F, const D: usize> {
     compress_challenge: Option<F>,
@@ -46,6 +90,14 @@ pub struct ProgramStark<F, const D: usize> {
 }
 
 impl<F: RichField, const D: usize> ProgramStark<F, D> {
+    /// Stark checking its compress columns against `challenge`.
+    pub fn with_compress_challenge(challenge: F) -> Self {
+        Self {
+            compress_challenge: Some(challenge),
+            _phantom: PhantomData,
+        }
+    }
+
     pub fn set_compress_challenge(&mut self, challenge: F) -> Result<()> {
         assert!(self.compress_challenge.is_none(), "already set?");
         self.compress_challenge = Some(challenge);
@@ -68,7 +120,11 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ProgramStark<
         FE: FieldExtension<D2, BaseField = F>,
         P: PackedField<Scalar = FE>,
     {
+        let lv = vars.local_values;
+        let nv = vars.next_values;
         let beta = FE::from_basefield(self.get_compress_challenge().unwrap());
+        // The data flag is compressed with the code rows only, so no fetch
+        // matches a data row.
         yield_constr.constraint(
             vars.local_values[COL_PROG_CODE_ADDR_RANGE.start]
                 + vars.local_values[COL_PROG_CODE_ADDR_RANGE.start + 1] * beta
@@ -76,8 +132,22 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ProgramStark<
                 + vars.local_values[COL_PROG_CODE_ADDR_RANGE.start + 3] * beta.cube()
                 + vars.local_values[COL_PROG_PC] * beta.square() * beta.square()
                 + vars.local_values[COL_PROG_INST] * beta.square() * beta.cube()
+                + vars.local_values[COL_PROG_IS_DATA] * beta.cube() * beta.cube()
+                + vars.local_values[COL_PROG_IS_IMM] * beta.cube() * beta.cube() * beta
                 - vars.local_values[COL_PROG_COMP_PROG],
         );
+        // Data rows are code rows, mapped one after the other from
+        // DATA_START_ADDR.
+        let data_start = P::Scalar::from_canonical_u64(DATA_START_ADDR);
+        yield_constr.constraint(lv[COL_PROG_IS_DATA] * (P::ONES - lv[COL_PROG_IS_DATA]));
+        yield_constr.constraint(lv[COL_PROG_IS_DATA] * (P::ONES - lv[COL_PROG_FILTER_PROG_CHUNK]));
+        yield_constr.constraint_first_row(lv[COL_PROG_IS_DATA]);
+        yield_constr.constraint_transition(
+            nv[COL_PROG_IS_DATA]
+                * (nv[COL_PROG_DATA_ADDR]
+                    - lv[COL_PROG_IS_DATA] * (lv[COL_PROG_DATA_ADDR] + P::ONES)
+                    - (P::ONES - lv[COL_PROG_IS_DATA]) * data_start),
+        );
         yield_constr.constraint(
             vars.local_values[COL_PROG_EXEC_CODE_ADDR_RANGE.start]
                 + vars.local_values[COL_PROG_EXEC_CODE_ADDR_RANGE.start + 1] * beta
@@ -85,8 +155,63 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ProgramStark<
                 + vars.local_values[COL_PROG_EXEC_CODE_ADDR_RANGE.start + 3] * beta.cube()
                 + vars.local_values[COL_PROG_EXEC_PC] * beta.square() * beta.square()
                 + vars.local_values[COL_PROG_EXEC_INST] * beta.square() * beta.cube()
+                + vars.local_values[COL_PROG_EXEC_IS_IMM] * beta.cube() * beta.cube() * beta
                 - vars.local_values[COL_PROG_EXEC_COMP_PROG],
         );
+
+        // Code rows are the words of each program from pc 0, so the word
+        // before one is the one before it in the code.
+        for j in 0..4 {
+            yield_constr.constraint_transition(
+                nv[COL_PROG_FILTER_PROG_CHUNK]
+                    * nv[COL_PROG_PC]
+                    * (nv[COL_PROG_CODE_ADDR_RANGE.start + j]
+                        - lv[COL_PROG_CODE_ADDR_RANGE.start + j]),
+            );
+        }
+        yield_constr.constraint_transition(
+            nv[COL_PROG_FILTER_PROG_CHUNK]
+                * nv[COL_PROG_PC]
+                * (nv[COL_PROG_PC] - lv[COL_PROG_PC] - P::ONES),
+        );
+        yield_constr.constraint_transition(
+            nv[COL_PROG_FILTER_PROG_CHUNK]
+                * nv[COL_PROG_PC]
+                * (P::ONES - lv[COL_PROG_FILTER_PROG_CHUNK]),
+        );
+
+        // Instruction words are split as `u32 * 2^63 + imm * 2^62 + hi * 2^32
+        // + lo`, the range checks of hi and lo making it the only split.
+        let is_inst = lv[COL_PROG_FILTER_PROG_CHUNK] - lv[COL_PROG_IS_IMM] - lv[COL_PROG_IS_DATA];
+        yield_constr.constraint(lv[COL_PROG_IS_IMM] * (P::ONES - lv[COL_PROG_IS_IMM]));
+        yield_constr.constraint(lv[COL_PROG_IS_IMM] * (P::ONES - lv[COL_PROG_FILTER_PROG_CHUNK]));
+        yield_constr.constraint(lv[COL_PROG_IS_IMM] * lv[COL_PROG_IS_DATA]);
+        yield_constr.constraint(lv[COL_PROG_INST_U32] * (P::ONES - lv[COL_PROG_INST_U32]));
+        yield_constr.constraint(lv[COL_PROG_INST_IMM] * (P::ONES - lv[COL_PROG_INST_IMM]));
+        yield_constr.constraint(
+            is_inst
+                * (lv[COL_PROG_INST]
+                    - lv[COL_PROG_INST_U32] * P::Scalar::from_canonical_u64(1 << 63)
+                    - lv[COL_PROG_INST_IMM] * P::Scalar::from_canonical_u64(1 << 62)
+                    - lv[COL_PROG_INST_HI] * P::Scalar::from_canonical_u64(1 << 32)
+                    - lv[COL_PROG_INST_LO]),
+        );
+        yield_constr.constraint((P::ONES - is_inst) * lv[COL_PROG_INST_IMM]);
+        yield_constr.constraint((P::ONES - is_inst) * lv[COL_PROG_INST_LO]);
+        // is_mem is whether lo is the opcode of an mload or mstore.
+        let mem = (lv[COL_PROG_INST_LO]
+            - P::Scalar::from_canonical_u64(OlaOpcode::MLOAD.binary_bit_mask()))
+            * (lv[COL_PROG_INST_LO]
+                - P::Scalar::from_canonical_u64(OlaOpcode::MSTORE.binary_bit_mask()));
+        yield_constr.constraint(lv[COL_PROG_INST_IS_MEM] * mem);
+        yield_constr
+            .constraint(lv[COL_PROG_INST_IS_MEM] + mem * lv[COL_PROG_INST_MEM_INV] - P::ONES);
+        // The word after an instruction with an immediate is its immediate.
+        yield_constr.constraint_first_row(lv[COL_PROG_IS_IMM]);
+        yield_constr.constraint_transition(
+            nv[COL_PROG_IS_IMM] - lv[COL_PROG_INST_IMM] - lv[COL_PROG_INST_IS_MEM]
+                + lv[COL_PROG_INST_IMM] * lv[COL_PROG_INST_IS_MEM],
+        );
         eval_lookups(
             vars,
             yield_constr,
@@ -118,14 +243,22 @@ impl<F: RichField + Extendable<D>, const D: usize> Stark<F, D> for ProgramStark<
 #[cfg(test)]
 mod tests {
     use crate::generation::prog::generate_prog_trace;
-    use crate::program::columns::NUM_PROG_COLS;
+    use crate::program::columns::{
+        COL_PROG_COMP_PROG, COL_PROG_DATA_ADDR, COL_PROG_EXEC_COMP_PROG, COL_PROG_EXEC_IS_IMM,
+        COL_PROG_EXEC_PC, COL_PROG_FILTER_EXEC, COL_PROG_FILTER_PROG_CHUNK, COL_PROG_INST,
+        COL_PROG_IS_DATA, COL_PROG_IS_IMM, COL_PROG_PC, NUM_PROG_COLS,
+    };
+    use crate::test_utils::execute_asm_path;
     use crate::{program::program_stark::ProgramStark, stark::stark::Stark};
     use assembler::encoder::encode_asm_from_json_file;
+    use core::types::merkle_tree::decode_addr;
+    use core::vm::memory::DATA_START_ADDR;
+    use core::vm::opcodes::OlaOpcode;
     use core::vm::transaction::init_tx_context_mock;
     use core::{
         merkle_tree::tree::AccountTree,
         program::Program,
-        types::{Field, GoldilocksField},
+        types::{Field, GoldilocksField, PrimeField64},
         vm::vm_state::Address,
     };
     use executor::TxScopeCacheManager;
@@ -145,6 +278,105 @@ mod tests {
         test_program_with_asm_file_name("storage_u32.json".to_string(), Some(call_data));
     }
 
+    #[test]
+    fn test_program_imm_not_executed() {
+        // the immediate of `mov r0 1048576` is the encoding of `end`.
+        let file_name = "imm_like_opcode.json".to_string();
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/");
+        path.push(file_name.clone());
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        assert_eq!(
+            u64::from_str_radix(program.instructions[1].trim_start_matches("0x"), 16).unwrap(),
+            OlaOpcode::END.binary_bit_mask()
+        );
+        assert!(program
+            .trace
+            .exec
+            .iter()
+            .all(|step| step.is_ext_line.0 == 1 || step.pc != 1));
+
+        let insts = program
+            .instructions
+            .iter()
+            .map(|inst| {
+                GoldilocksField::from_canonical_u64(
+                    u64::from_str_radix(inst.trim_start_matches("0x"), 16).unwrap(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let (rows, _) = generate_prog_trace::<GoldilocksField>(
+            &program.trace.exec,
+            vec![([GoldilocksField::ZERO; 4], insts)],
+            ([GoldilocksField::ZERO; 4], [GoldilocksField::ZERO; 4]),
+            0,
+        )
+        .unwrap();
+        // The code decodes pc 1 as the immediate, and so does the fetch.
+        for i in 0..rows[0].len() {
+            if rows[COL_PROG_FILTER_PROG_CHUNK][i].is_one() {
+                let is_imm = rows[COL_PROG_PC][i] == GoldilocksField::ONE;
+                assert_eq!(rows[COL_PROG_IS_IMM][i].is_one(), is_imm);
+            }
+            if rows[COL_PROG_FILTER_EXEC][i].is_one() {
+                let is_imm = rows[COL_PROG_EXEC_PC][i] == GoldilocksField::ONE;
+                assert_eq!(rows[COL_PROG_EXEC_IS_IMM][i].is_one(), is_imm);
+            }
+        }
+
+        test_program_with_asm_file_name(file_name, None);
+    }
+
+    #[test]
+    fn test_program_data_rows() {
+        // rodata.json maps the data words 7 and 42.
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/rodata.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let progs = program
+            .trace
+            .addr_program_hash
+            .into_iter()
+            .map(|(addr, words)| (decode_addr(addr), words))
+            .collect::<Vec<_>>();
+        // More data than code is refused rather than wrapping around.
+        let code_len = progs.iter().map(|(_, words)| words.len()).sum::<usize>();
+        assert!(generate_prog_trace::<GoldilocksField>(
+            &program.trace.exec,
+            progs.clone(),
+            program.trace.start_end_roots,
+            code_len + 1,
+        )
+        .is_err());
+        let (rows, _) = generate_prog_trace::<GoldilocksField>(
+            &program.trace.exec,
+            progs,
+            program.trace.start_end_roots,
+            2,
+        )
+        .unwrap();
+
+        let data_rows = (0..rows[0].len())
+            .filter(|&i| rows[COL_PROG_IS_DATA][i].is_one())
+            .map(|i| {
+                (
+                    rows[COL_PROG_DATA_ADDR][i].to_canonical_u64(),
+                    rows[COL_PROG_INST][i].to_canonical_u64(),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            data_rows,
+            vec![(DATA_START_ADDR, 7), (DATA_START_ADDR + 1, 42)]
+        );
+        // A fetch of a data word at its pc would not match its row.
+        for i in 0..rows[0].len() {
+            if rows[COL_PROG_IS_DATA][i].is_one() {
+                assert!(!rows[COL_PROG_EXEC_COMP_PROG].contains(&rows[COL_PROG_COMP_PROG][i]));
+            }
+        }
+    }
+
     #[allow(unused)]
     fn test_program_with_asm_file_name(file_name: String, call_data: Option<Vec<GoldilocksField>>) {
         let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -207,7 +439,9 @@ mod tests {
             &program.trace.exec,
             vec![(process.addr_storage, insts)],
             ([GoldilocksField::ZERO; 4], [GoldilocksField::ZERO; 4]),
-        );
+            0,
+        )
+        .unwrap();
         let len = rows[0].len();
         println!(
             "raw trace len:{}, extended len: {}",
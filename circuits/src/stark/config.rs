@@ -1,5 +1,99 @@
+use std::fmt;
+
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::Field;
 use plonky2::fri::reduction_strategies::FriReductionStrategy;
 use plonky2::fri::{FriConfig, FriParams};
+use plonky2_util::log2_ceil;
+
+/// The poseidon constraints have degree 7, so the quotients have degree up to
+/// 6 times the trace length and need a blowup of 8.
+const MIN_RATE_BITS: usize = 3;
+
+const DEFAULT_FRI_ARITY_BITS: usize = 4;
+const DEFAULT_FINAL_POLY_BITS: usize = 5;
+
+/// Why a `StarkConfig` can't prove traces of some length.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    RateTooLow {
+        rate_bits: usize,
+        min_rate_bits: usize,
+    },
+    /// Queries and grinding give less than `security_bits` of conjectured
+    /// security.
+    InsufficientSecurity {
+        conjectured_bits: usize,
+        security_bits: usize,
+    },
+    /// The LDE of the trace doesn't fit in the two-adic subgroup of the field.
+    TraceTooLong {
+        trace_len: usize,
+        max_trace_len: usize,
+    },
+    CapTooHigh {
+        cap_height: usize,
+        lde_bits: usize,
+    },
+    /// More queries than points in the LDE, the extra ones can't add
+    /// security.
+    TooManyQueries {
+        num_query_rounds: usize,
+        lde_size: usize,
+    },
+    /// A FRI folding of arity 1 never reduces the degree.
+    ZeroFriArityBits,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::RateTooLow {
+                rate_bits,
+                min_rate_bits,
+            } => write!(
+                f,
+                "rate_bits {} is lower than the {} the constraints need",
+                rate_bits, min_rate_bits
+            ),
+            ConfigError::InsufficientSecurity {
+                conjectured_bits,
+                security_bits,
+            } => write!(
+                f,
+                "config gives {} bits of conjectured security, {} required",
+                conjectured_bits, security_bits
+            ),
+            ConfigError::TraceTooLong {
+                trace_len,
+                max_trace_len,
+            } => write!(
+                f,
+                "trace of {} rows is longer than the {} rows the field allows",
+                trace_len, max_trace_len
+            ),
+            ConfigError::CapTooHigh {
+                cap_height,
+                lde_bits,
+            } => write!(
+                f,
+                "cap_height {} is higher than the {} bits of the LDE",
+                cap_height, lde_bits
+            ),
+            ConfigError::TooManyQueries {
+                num_query_rounds,
+                lde_size,
+            } => write!(
+                f,
+                "{} query rounds for an LDE of {} points",
+                num_query_rounds, lde_size
+            ),
+            ConfigError::ZeroFriArityBits => write!(f, "FRI folding arity bits must be positive"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
 
 pub struct StarkConfig {
     pub security_bits: usize,
@@ -9,6 +103,13 @@ pub struct StarkConfig {
     pub num_challenges: usize,
 
     pub fri_config: FriConfig,
+
+    /// Prove the tables on a thread each. The proof is the same either way.
+    /// Off by default: every thread holds the low-degree extensions of its
+    /// table's quotient and permutation polynomials, along with twiddles of
+    /// its own, so the peak memory is about the sum over the tables rather
+    /// than the largest table.
+    pub parallel_tables: bool,
 }
 
 impl StarkConfig {
@@ -23,13 +124,123 @@ impl StarkConfig {
                 rate_bits: 3,
                 cap_height: 4,
                 proof_of_work_bits: 16,
-                reduction_strategy: FriReductionStrategy::ConstantArityBits(4, 5),
+                reduction_strategy: FriReductionStrategy::ConstantArityBits(
+                    DEFAULT_FRI_ARITY_BITS,
+                    DEFAULT_FINAL_POLY_BITS,
+                ),
                 num_query_rounds: 28,
             },
+            parallel_tables: false,
+        }
+    }
+
+    /// Prove the tables on a thread each, or one after the other, see
+    /// `parallel_tables` for the memory cost.
+    pub fn with_parallel_tables(mut self, parallel: bool) -> Self {
+        self.parallel_tables = parallel;
+        self
+    }
+
+    /// Fold by `2^arity_bits` at each FRI round instead of the default 16,
+    /// down to the same final polynomial. Larger arities give fewer rounds,
+    /// so fewer merkle caps and paths, but open more values per query.
+    pub fn with_fri_arity_bits(mut self, arity_bits: usize) -> Self {
+        let final_poly_bits = match self.fri_config.reduction_strategy {
+            FriReductionStrategy::ConstantArityBits(_, final_poly_bits) => final_poly_bits,
+            _ => DEFAULT_FINAL_POLY_BITS,
+        };
+        self.fri_config.reduction_strategy =
+            FriReductionStrategy::ConstantArityBits(arity_bits, final_poly_bits);
+        self
+    }
+
+    /// The FRI folding arity in bits, if it is the same for every round.
+    pub fn fri_arity_bits(&self) -> Option<usize> {
+        match self.fri_config.reduction_strategy {
+            FriReductionStrategy::ConstantArityBits(arity_bits, _) => Some(arity_bits),
+            _ => None,
+        }
+    }
+
+    /// Check this config can soundly prove traces of up to `max_trace_len`
+    /// rows.
+    pub fn validate(&self, max_trace_len: usize) -> Result<(), ConfigError> {
+        let fri = &self.fri_config;
+        if self.fri_arity_bits() == Some(0) {
+            return Err(ConfigError::ZeroFriArityBits);
+        }
+        if fri.rate_bits < MIN_RATE_BITS {
+            return Err(ConfigError::RateTooLow {
+                rate_bits: fri.rate_bits,
+                min_rate_bits: MIN_RATE_BITS,
+            });
+        }
+        let conjectured_bits =
+            fri.rate_bits * fri.num_query_rounds + fri.proof_of_work_bits as usize;
+        if conjectured_bits < self.security_bits {
+            return Err(ConfigError::InsufficientSecurity {
+                conjectured_bits,
+                security_bits: self.security_bits,
+            });
+        }
+        let degree_bits = log2_ceil(max_trace_len.max(1));
+        let lde_bits = degree_bits + fri.rate_bits;
+        if lde_bits > GoldilocksField::TWO_ADICITY {
+            return Err(ConfigError::TraceTooLong {
+                trace_len: max_trace_len,
+                max_trace_len: 1 << (GoldilocksField::TWO_ADICITY - fri.rate_bits),
+            });
+        }
+        if fri.cap_height > lde_bits {
+            return Err(ConfigError::CapTooHigh {
+                cap_height: fri.cap_height,
+                lde_bits,
+            });
+        }
+        if fri.num_query_rounds > 1 << lde_bits {
+            return Err(ConfigError::TooManyQueries {
+                num_query_rounds: fri.num_query_rounds,
+                lde_size: 1 << lde_bits,
+            });
         }
+        Ok(())
     }
 
     pub(crate) fn fri_params(&self, degree_bits: usize) -> FriParams {
         self.fri_config.fri_params(degree_bits, false)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ConfigError, StarkConfig};
+
+    #[test]
+    fn test_validate_query_count() {
+        let mut config = StarkConfig::standard_fast_config();
+        assert_eq!(config.validate(1 << 16), Ok(()));
+
+        config.fri_config.num_query_rounds = 1;
+        assert_eq!(
+            config.validate(1 << 16),
+            Err(ConfigError::InsufficientSecurity {
+                conjectured_bits: 19,
+                security_bits: 100,
+            })
+        );
+    }
+
+    #[test]
+    fn test_fri_arity_bits() {
+        let config = StarkConfig::standard_fast_config();
+        assert_eq!(config.fri_arity_bits(), Some(4));
+        assert_eq!(config.fri_params(10).reduction_arity_bits, vec![4, 4]);
+
+        let config = config.with_fri_arity_bits(1);
+        assert_eq!(config.fri_params(10).reduction_arity_bits, vec![1; 5]);
+        assert_eq!(
+            config.with_fri_arity_bits(0).validate(1 << 16),
+            Err(ConfigError::ZeroFriArityBits)
+        );
+    }
+}
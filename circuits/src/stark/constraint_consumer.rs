@@ -29,6 +29,13 @@ pub struct ConstraintConsumer<P: PackedField> {
     /// point associated with the last trace row, and zero at other points
     /// in the subgroup.
     lagrange_basis_last: P,
+
+    /// Every constraint emitted so far with its section, only kept when built
+    /// by `new_debug`.
+    debug_constraints: Option<Vec<(&'static str, P)>>,
+
+    /// Name of the constraints emitted next.
+    section: &'static str,
 }
 
 impl<P: PackedField> ConstraintConsumer<P> {
@@ -44,6 +51,17 @@ impl<P: PackedField> ConstraintConsumer<P> {
             z_last,
             lagrange_basis_first,
             lagrange_basis_last,
+            debug_constraints: None,
+            section: "",
+        }
+    }
+
+    /// Consumer keeping the emitted constraints apart instead of combining
+    /// them, to tell which one doesn't hold.
+    pub fn new_debug(z_last: P, lagrange_basis_first: P, lagrange_basis_last: P) -> Self {
+        Self {
+            debug_constraints: Some(Vec::new()),
+            ..Self::new(vec![], z_last, lagrange_basis_first, lagrange_basis_last)
         }
     }
 
@@ -51,6 +69,17 @@ impl<P: PackedField> ConstraintConsumer<P> {
         self.constraint_accs
     }
 
+    /// Constraints emitted so far with their section, in order. Empty unless
+    /// built by `new_debug`.
+    pub fn debug_constraints(&self) -> &[(&'static str, P)] {
+        self.debug_constraints.as_deref().unwrap_or_default()
+    }
+
+    /// Name the constraints emitted from now on, for debugging.
+    pub fn section(&mut self, name: &'static str) {
+        self.section = name;
+    }
+
     /// Add one constraint valid on all rows except the last.
     pub fn constraint_transition(&mut self, constraint: P) {
         self.constraint(constraint * self.z_last);
@@ -62,6 +91,9 @@ impl<P: PackedField> ConstraintConsumer<P> {
             *acc *= alpha;
             *acc += constraint;
         }
+        if let Some(constraints) = &mut self.debug_constraints {
+            constraints.push((self.section, constraint));
+        }
     }
 
     /// Add one constraint, but first multiply it by a filter such that it will
@@ -20,7 +20,7 @@ use super::ola_stark::{Table, NUM_TABLES};
 use super::permutation::{
     get_grand_product_challenge_set, GrandProductChallenge, GrandProductChallengeSet,
 };
-use super::proof::{StarkProof, StarkProofTarget};
+use super::proof::{AllProof, MemoryClaim, PublicValues, StarkProof, StarkProofTarget};
 use super::stark::Stark;
 use super::vars::{StarkEvaluationTargets, StarkEvaluationVars};
 
@@ -156,12 +156,79 @@ impl<F: Field> TableWithColumns<F> {
             filter_column,
         }
     }
+
+    /// Whether the filter selects some row of `trace`, the trace of `table`.
+    fn selects_any_row(&self, trace: &[PolynomialValues<F>]) -> bool {
+        let degree = trace.first().map_or(0, |column| column.len());
+        match &self.filter_column {
+            Some(column) => (0..degree).any(|i| column.eval_table(trace, i) != F::ZERO),
+            None => degree > 0,
+        }
+    }
+}
+
+/// Rows the verifier adds to the looking side of a lookup from the public
+/// values, so the looked table must select exactly these rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PublicRows {
+    /// `(addr, value)` of each memory claim.
+    MemoryClaims,
+    /// `(i, word)` of the `i`-th output.
+    Outputs,
+    /// `(addr, value)` of each word of the initial memory.
+    InitialMemory,
+    /// `(addr, value)` of each word of the final memory.
+    FinalMemory,
+}
+
+impl PublicRows {
+    pub(crate) fn rows<F: Field>(&self, public_values: &PublicValues) -> Vec<Vec<F>> {
+        let words = |words: &[MemoryClaim]| {
+            words
+                .iter()
+                .map(|word| {
+                    vec![
+                        F::from_noncanonical_u64(word.addr),
+                        F::from_noncanonical_u64(word.value),
+                    ]
+                })
+                .collect()
+        };
+        match self {
+            PublicRows::MemoryClaims => words(&public_values.memory_claims),
+            PublicRows::Outputs => public_values
+                .outputs
+                .iter()
+                .enumerate()
+                .map(|(i, &word)| vec![F::from_canonical_usize(i), F::from_noncanonical_u64(word)])
+                .collect(),
+            PublicRows::InitialMemory => words(&public_values.initial_memory),
+            PublicRows::FinalMemory => words(&public_values.final_memory),
+        }
+    }
+
+    /// Product of the public rows combined with `challenge`, what a looking
+    /// table selecting them would end its partial products with.
+    pub(crate) fn product<F: Field>(
+        &self,
+        public_values: &PublicValues,
+        challenge: GrandProductChallenge<F>,
+    ) -> F {
+        self.rows(public_values)
+            .iter()
+            .map(|row| challenge.combine(row.iter()))
+            .product()
+    }
 }
 
 #[derive(Clone)]
 pub struct CrossTableLookup<F: Field> {
     looking_tables: Vec<TableWithColumns<F>>,
-    looked_table: TableWithColumns<F>,
+    /// `None` once the looked table is left out of the proof: it is then an
+    /// empty multiset, so the looking tables must select no row.
+    looked_table: Option<TableWithColumns<F>>,
+    /// Rows looked up from the public values besides the looking tables.
+    public_rows: Option<PublicRows>,
 }
 
 impl<F: Field> CrossTableLookup<F> {
@@ -174,15 +241,71 @@ impl<F: Field> CrossTableLookup<F> {
             .all(|twc| twc.columns.len() == looked_table.columns.len()));
         Self {
             looking_tables,
-            looked_table,
+            looked_table: Some(looked_table),
+            public_rows: None,
         }
     }
 
+    /// `looked_table` must select exactly the `public_rows`, e.g. to bind
+    /// public values to the trace.
+    pub fn with_public_rows(looked_table: TableWithColumns<F>, public_rows: PublicRows) -> Self {
+        Self {
+            looking_tables: vec![],
+            looked_table: Some(looked_table),
+            public_rows: Some(public_rows),
+        }
+    }
+
+    /// This lookup between the `active` tables only. An inactive looking
+    /// table adds no row, and an inactive looked table is empty, so the
+    /// lookup still fails if an inactive table is used. `None` if no table
+    /// of the lookup is active.
+    pub(crate) fn restrict_to(&self, active: &[bool; NUM_TABLES]) -> Option<Self> {
+        let is_active = |twc: &&TableWithColumns<F>| active[twc.table.index()];
+        let looking_tables = self
+            .looking_tables
+            .iter()
+            .filter(is_active)
+            .cloned()
+            .collect::<Vec<_>>();
+        let looked_table = self.looked_table.as_ref().filter(is_active).cloned();
+        (!looking_tables.is_empty() || looked_table.is_some() || self.public_rows.is_some())
+            .then_some(Self {
+                looking_tables,
+                looked_table,
+                public_rows: self.public_rows,
+            })
+    }
+
+    /// All tables taking part in this lookup, looked table last.
+    pub(crate) fn tables(&self) -> impl Iterator<Item = Table> + '_ {
+        self.looking_tables
+            .iter()
+            .chain(&self.looked_table)
+            .map(|twc| twc.table)
+    }
+
+    /// Whether the lookup checks nothing on `traces`: no looking table or
+    /// the looked table never selects a row, so it passes whatever the
+    /// columns hold. The public rows aren't known from the traces, a lookup
+    /// of public rows is vacuous when the looked table selects none.
+    pub(crate) fn is_vacuous(&self, traces: &[Vec<PolynomialValues<F>>; NUM_TABLES]) -> bool {
+        (self.public_rows.is_none()
+            && !self
+                .looking_tables
+                .iter()
+                .any(|twc| twc.selects_any_row(&traces[twc.table.index()])))
+            || !self
+                .looked_table
+                .as_ref()
+                .map_or(true, |twc| twc.selects_any_row(&traces[twc.table.index()]))
+    }
+
     #[allow(unused)]
     pub(crate) fn num_ctl_zs(ctls: &[Self], table: Table, num_challenges: usize) -> usize {
         let mut num_ctls = 0;
         for ctl in ctls {
-            let all_tables = std::iter::once(&ctl.looked_table).chain(&ctl.looking_tables);
+            let all_tables = ctl.looked_table.iter().chain(&ctl.looking_tables);
             num_ctls += all_tables.filter(|twc| twc.table == table).count();
         }
         num_ctls * num_challenges
@@ -225,6 +348,7 @@ pub fn cross_table_lookup_data<F: RichField, C: GenericConfig<D, F = F>, const D
     config: &StarkConfig,
     trace_poly_values: &[Vec<PolynomialValues<F>>; NUM_TABLES],
     cross_table_lookups: &[CrossTableLookup<F>],
+    public_values: &PublicValues,
     challenger: &mut Challenger<F, C::Hasher>,
 ) -> [CtlData<F>; NUM_TABLES] {
     let challenges = get_grand_product_challenge_set(challenger, config.num_challenges);
@@ -232,34 +356,40 @@ pub fn cross_table_lookup_data<F: RichField, C: GenericConfig<D, F = F>, const D
     for CrossTableLookup {
         looking_tables,
         looked_table,
+        public_rows,
     } in cross_table_lookups
     {
         for &challenge in &challenges.challenges {
             let zs_looking = looking_tables.iter().map(|table| {
                 partial_products(
-                    &trace_poly_values[table.table as usize],
+                    &trace_poly_values[table.table.index()],
                     &table.columns,
                     &table.filter_column,
                     challenge,
                 )
             });
-            let z_looked = partial_products(
-                &trace_poly_values[looked_table.table as usize],
-                &looked_table.columns,
-                &looked_table.filter_column,
-                challenge,
-            );
+            let z_looked = looked_table.as_ref().map(|looked_table| {
+                partial_products(
+                    &trace_poly_values[looked_table.table.index()],
+                    &looked_table.columns,
+                    &looked_table.filter_column,
+                    challenge,
+                )
+            });
 
             debug_assert_eq!(
                 zs_looking
                     .clone()
                     .map(|z| *z.values.last().unwrap())
-                    .product::<F>(),
-                *z_looked.values.last().unwrap()
+                    .product::<F>()
+                    * public_rows.map_or(F::ONE, |rows| rows.product(public_values, challenge)),
+                z_looked
+                    .as_ref()
+                    .map_or(F::ONE, |z| *z.values.last().unwrap())
             );
 
             for (table, z) in looking_tables.iter().zip(zs_looking) {
-                ctl_data_per_table[table.table as usize]
+                ctl_data_per_table[table.table.index()]
                     .zs_columns
                     .push(CtlZData {
                         z,
@@ -268,14 +398,16 @@ pub fn cross_table_lookup_data<F: RichField, C: GenericConfig<D, F = F>, const D
                         filter_column: table.filter_column.clone(),
                     });
             }
-            ctl_data_per_table[looked_table.table as usize]
-                .zs_columns
-                .push(CtlZData {
-                    z: z_looked,
-                    challenge,
-                    columns: looked_table.columns.clone(),
-                    filter_column: looked_table.filter_column.clone(),
-                });
+            if let (Some(looked_table), Some(z)) = (looked_table, z_looked) {
+                ctl_data_per_table[looked_table.table.index()]
+                    .zs_columns
+                    .push(CtlZData {
+                        z,
+                        challenge,
+                        columns: looked_table.columns.clone(),
+                        filter_column: looked_table.filter_column.clone(),
+                    });
+            }
         }
     }
     ctl_data_per_table
@@ -328,7 +460,7 @@ impl<'a, F: RichField + Extendable<D>, const D: usize>
     CtlCheckVars<'a, F, F::Extension, F::Extension, D>
 {
     pub(crate) fn from_proofs<C: GenericConfig<D, F = F>>(
-        proofs: &[StarkProof<F, C, D>; NUM_TABLES],
+        proofs: &[Option<StarkProof<F, C, D>>; NUM_TABLES],
         cross_table_lookups: &'a [CrossTableLookup<F>],
         ctl_challenges: &'a GrandProductChallengeSet<F>,
         num_permutation_zs: &[usize; NUM_TABLES],
@@ -337,10 +469,18 @@ impl<'a, F: RichField + Extendable<D>, const D: usize>
             .iter()
             .zip(num_permutation_zs)
             .map(|(p, &num_perms)| {
-                let openings = &p.openings;
-                let ctl_zs = openings.permutation_ctl_zs.iter().skip(num_perms);
-                let ctl_zs_next = openings.permutation_ctl_zs_next.iter().skip(num_perms);
-                ctl_zs.zip(ctl_zs_next)
+                // inactive tables take part in no lookup
+                let (ctl_zs, ctl_zs_next) = match p {
+                    Some(p) => (
+                        &p.openings.permutation_ctl_zs[..],
+                        &p.openings.permutation_ctl_zs_next[..],
+                    ),
+                    None => (&[][..], &[][..]),
+                };
+                ctl_zs
+                    .iter()
+                    .skip(num_perms)
+                    .zip(ctl_zs_next.iter().skip(num_perms))
             })
             .collect::<Vec<_>>();
 
@@ -353,8 +493,8 @@ impl<'a, F: RichField + Extendable<D>, const D: usize>
         {
             for &challenges in &ctl_challenges.challenges {
                 for table in looking_tables {
-                    let (looking_z, looking_z_next) = ctl_zs[table.table as usize].next().unwrap();
-                    ctl_vars_per_table[table.table as usize].push(Self {
+                    let (looking_z, looking_z_next) = ctl_zs[table.table.index()].next().unwrap();
+                    ctl_vars_per_table[table.table.index()].push(Self {
                         local_z: *looking_z,
                         next_z: *looking_z_next,
                         challenges,
@@ -363,14 +503,17 @@ impl<'a, F: RichField + Extendable<D>, const D: usize>
                     });
                 }
 
-                let (looked_z, looked_z_next) = ctl_zs[looked_table.table as usize].next().unwrap();
-                ctl_vars_per_table[looked_table.table as usize].push(Self {
-                    local_z: *looked_z,
-                    next_z: *looked_z_next,
-                    challenges,
-                    columns: &looked_table.columns,
-                    filter_column: &looked_table.filter_column,
-                });
+                if let Some(looked_table) = looked_table {
+                    let (looked_z, looked_z_next) =
+                        ctl_zs[looked_table.table.index()].next().unwrap();
+                    ctl_vars_per_table[looked_table.table.index()].push(Self {
+                        local_z: *looked_z,
+                        next_z: *looked_z_next,
+                        challenges,
+                        columns: &looked_table.columns,
+                        filter_column: &looked_table.filter_column,
+                    });
+                }
             }
         }
         ctl_vars_per_table
@@ -469,7 +612,7 @@ impl<'a, F: Field, const D: usize> CtlCheckVarsTarget<'a, F, D> {
                     }
                 }
 
-                if looked_table.table == table {
+                if let Some(looked_table) = looked_table.as_ref().filter(|t| t.table == table) {
                     let (looked_z, looked_z_next) = ctl_zs.next().unwrap();
                     ctl_vars.push(Self {
                         local_z: *looked_z,
@@ -559,19 +702,27 @@ pub(crate) fn verify_cross_table_lookups<
     config: &StarkConfig,
 ) -> Result<()> {
     let mut ctl_zs_openings = ctl_zs_lasts.iter().map(|v| v.iter()).collect::<Vec<_>>();
-    for CrossTableLookup {
-        looking_tables,
-        looked_table,
-    } in cross_table_lookups.iter()
+    for (
+        CrossTableLookup {
+            looking_tables,
+            looked_table,
+            ..
+        },
+        extra_products,
+    ) in cross_table_lookups.iter().zip(&ctl_extra_looking_products)
     {
-        let extra_product_vec = &ctl_extra_looking_products[looked_table.table as usize];
         for c in 0..config.num_challenges {
+            // An inactive looked table is empty, so it must be looked up by
+            // no row, public ones included.
+            let looked_z = match looked_table {
+                Some(looked_table) => *ctl_zs_openings[looked_table.table.index()].next().unwrap(),
+                None => F::ONE,
+            };
             let looking_zs_prod = looking_tables
                 .iter()
-                .map(|table| *ctl_zs_openings[table.table as usize].next().unwrap())
+                .map(|table| *ctl_zs_openings[table.table.index()].next().unwrap())
                 .product::<F>()
-                * extra_product_vec[c];
-            let looked_z = *ctl_zs_openings[looked_table.table as usize].next().unwrap();
+                * extra_products[c];
             ensure!(
                 looking_zs_prod == looked_z,
                 "Cross-table lookup verification failed."
@@ -583,6 +734,53 @@ pub(crate) fn verify_cross_table_lookups<
     Ok(())
 }
 
+/// Products of the public rows of each of `cross_table_lookups`, for each
+/// challenge of `ctl_challenges`, as the verifier multiplies the looking
+/// tables' with.
+pub(crate) fn public_looking_products<F: Field>(
+    cross_table_lookups: &[CrossTableLookup<F>],
+    public_values: &PublicValues,
+    ctl_challenges: &GrandProductChallengeSet<F>,
+) -> Vec<Vec<F>> {
+    cross_table_lookups
+        .iter()
+        .map(|ctl| {
+            ctl_challenges
+                .challenges
+                .iter()
+                .map(|&challenge| {
+                    ctl.public_rows
+                        .map_or(F::ONE, |rows| rows.product(public_values, challenge))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Check only that the final CTL partial products of `proof` match across
+/// tables, without the per-table constraint checks of `verify_proof`. This
+/// tells a CTL mismatch apart from a constraint violation when debugging.
+/// `cross_table_lookups` must be the ones the proof was made with.
+pub fn verify_ctl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    proof: &AllProof<F, C, D>,
+    cross_table_lookups: &[CrossTableLookup<F>],
+    config: &StarkConfig,
+) -> Result<()> {
+    let ctl_zs_lasts = std::array::from_fn(|i| {
+        proof.stark_proofs[i]
+            .as_ref()
+            .map_or(vec![], |p| p.openings.ctl_zs_last.clone())
+    });
+    let ctl_challenges =
+        get_grand_product_challenge_set(&mut proof.ctl_challenger().0, config.num_challenges);
+    verify_cross_table_lookups::<F, C, D>(
+        cross_table_lookups.to_vec(),
+        ctl_zs_lasts,
+        public_looking_products(cross_table_lookups, &proof.public_values, &ctl_challenges),
+        config,
+    )
+}
+
 #[allow(unused)]
 pub(crate) fn verify_cross_table_lookups_circuit<
     F: RichField + Extendable<D>,
@@ -596,21 +794,28 @@ pub(crate) fn verify_cross_table_lookups_circuit<
     inner_config: &StarkConfig,
 ) {
     let mut ctl_zs_openings = ctl_zs_lasts.iter().map(|v| v.iter()).collect::<Vec<_>>();
-    for CrossTableLookup {
-        looking_tables,
-        looked_table,
-    } in cross_table_lookups.into_iter()
+    for (
+        CrossTableLookup {
+            looking_tables,
+            looked_table,
+            ..
+        },
+        extra_products,
+    ) in cross_table_lookups
+        .into_iter()
+        .zip(ctl_extra_looking_products)
     {
-        let extra_product_vec = &ctl_extra_looking_products[looked_table.table as usize];
         for c in 0..inner_config.num_challenges {
-            let mut looking_zs_prod = builder.mul_many(
+            let looking_zs_prod = builder.mul_many(
                 looking_tables
                     .iter()
-                    .map(|table| *ctl_zs_openings[table.table as usize].next().unwrap()),
+                    .map(|table| *ctl_zs_openings[table.table.index()].next().unwrap()),
             );
-            looking_zs_prod = builder.mul(looking_zs_prod, extra_product_vec[c]);
-
-            let looked_z = *ctl_zs_openings[looked_table.table as usize].next().unwrap();
+            let looking_zs_prod = builder.mul(looking_zs_prod, extra_products[c]);
+            let looked_z = match &looked_table {
+                Some(looked_table) => *ctl_zs_openings[looked_table.table.index()].next().unwrap(),
+                None => builder.one(),
+            };
             builder.connect(looked_z, looking_zs_prod);
         }
     }
@@ -648,7 +853,12 @@ pub(crate) mod testutils {
         let CrossTableLookup {
             looking_tables,
             looked_table,
+            public_rows,
         } = ctl;
+        // The public rows aren't known from the traces.
+        if public_rows.is_some() {
+            return;
+        }
 
         // Maps `m` with `(table, i) in m[row]` iff the `i`-th row of `table` is equal
         // to `row` and the filter is 1. Without default values, the CTL check
@@ -659,7 +869,9 @@ pub(crate) mod testutils {
         for table in looking_tables {
             process_table(trace_poly_values, table, &mut looking_multiset);
         }
-        process_table(trace_poly_values, looked_table, &mut looked_multiset);
+        if let Some(looked_table) = looked_table {
+            process_table(trace_poly_values, looked_table, &mut looked_multiset);
+        }
 
         let empty = &vec![];
         // Check that every row in the looking tables appears in the looked table the
@@ -681,7 +893,7 @@ pub(crate) mod testutils {
         table: &TableWithColumns<F>,
         multiset: &mut MultiSet<F>,
     ) {
-        let trace = &trace_poly_values[table.table as usize];
+        let trace = &trace_poly_values[table.table.index()];
         for i in 0..trace[0].len() {
             let filter = if let Some(column) = &table.filter_column {
                 column.eval_table(trace, i)
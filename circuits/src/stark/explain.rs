@@ -0,0 +1,346 @@
+use anyhow::{bail, Result};
+use core::program::Program;
+use plonky2::field::extension::Extendable;
+use plonky2::field::polynomial::PolynomialValues;
+use plonky2::field::types::Field;
+use plonky2::hash::hash_types::RichField;
+use plonky2::util::transpose;
+use plonky2_util::log2_strict;
+
+use super::constraint_consumer::ConstraintConsumer;
+use super::ola_stark::{OlaStark, Table, NUM_TABLES};
+use super::stark::Stark;
+use super::vars::StarkEvaluationVars;
+
+/// First constraint not holding on the traces of a proof.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureReport {
+    pub table: Table,
+    pub row: usize,
+    /// Index of the constraint, in the order `eval_packed_generic` emits
+    /// them.
+    pub constraint: usize,
+    /// Asm of the instruction executed at a failing cpu row.
+    pub instruction: Option<String>,
+    /// Source annotation of that instruction, if the program has one.
+    pub source_line: Option<String>,
+}
+
+/// Row and constraint index of the first constraint of `stark` not holding
+/// on `trace`.
+fn first_failure<F, S, const D: usize>(
+    stark: &S,
+    trace: &[PolynomialValues<F>],
+) -> Option<(usize, usize)>
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+{
+    let len = trace.first()?.len();
+    if len == 0 {
+        return None;
+    }
+    let rows = transpose(
+        &trace
+            .iter()
+            .map(|column| column.values.clone())
+            .collect::<Vec<_>>(),
+    );
+    let generator = F::primitive_root_of_unity(log2_strict(len));
+    let last = generator.inverse();
+    let mut x = F::ONE;
+    for row in 0..len {
+        let mut consumer = ConstraintConsumer::new_debug(
+            x - last,
+            if row == 0 { F::ONE } else { F::ZERO },
+            if row == len - 1 { F::ONE } else { F::ZERO },
+        );
+        let vars = StarkEvaluationVars {
+            local_values: rows[row].as_slice().try_into().unwrap(),
+            next_values: rows[(row + 1) % len].as_slice().try_into().unwrap(),
+        };
+        stark.eval_packed_base(vars, &mut consumer);
+        if let Some(constraint) = consumer
+            .debug_constraints()
+            .iter()
+            .position(|(_, value)| !value.is_zero())
+        {
+            return Some((row, constraint));
+        }
+        x *= generator;
+    }
+    None
+}
+
+/// Check every constraint of `stark` on `trace` in the clear, CTLs aside.
+pub fn check_trace<F, S, const D: usize>(stark: &S, trace: &[PolynomialValues<F>]) -> Result<()>
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+    [(); S::COLUMNS]:,
+{
+    match first_failure::<F, S, D>(stark, trace) {
+        Some((row, constraint)) => bail!("constraint {} fails at row {}", constraint, row),
+        None => Ok(()),
+    }
+}
+
+/// Residual of every constraint of `stark` on the row `lv` followed by `nv`,
+/// named `section[i]` for the i-th constraint of a section, e.g. to try a
+/// crafted cpu row without running a program. Transition constraints apply,
+/// first and last row ones don't.
+pub fn debug_row<F, S, const D: usize>(
+    stark: &S,
+    lv: &[F; S::COLUMNS],
+    nv: &[F; S::COLUMNS],
+) -> Vec<(String, F)>
+where
+    F: RichField + Extendable<D>,
+    S: Stark<F, D>,
+{
+    let mut consumer = ConstraintConsumer::new_debug(F::ONE, F::ZERO, F::ZERO);
+    let vars = StarkEvaluationVars {
+        local_values: lv,
+        next_values: nv,
+    };
+    stark.eval_packed_base(vars, &mut consumer);
+
+    let mut index = 0;
+    let mut section = None;
+    consumer
+        .debug_constraints()
+        .iter()
+        .map(|(name, value)| {
+            if section != Some(*name) {
+                section = Some(*name);
+                index = 0;
+            }
+            index += 1;
+            (format!("{}[{}]", name, index - 1), *value)
+        })
+        .collect()
+}
+
+/// Tell why a proof of `traces` fails to verify, by checking the constraints
+/// of every active table in the clear. A proof doesn't carry its witness, so
+/// this takes the traces `generate_traces` made from `program`, with
+/// `program` cloned before it was consumed to map cpu rows back to their
+/// instruction. CTLs are not checked.
+pub fn explain_failure<F, const D: usize>(
+    ola_stark: &OlaStark<F, D>,
+    traces: &[Vec<PolynomialValues<F>>; NUM_TABLES],
+    program: &Program,
+) -> Option<FailureReport>
+where
+    F: RichField + Extendable<D>,
+{
+    let failures = [
+        (
+            Table::Cpu,
+            first_failure(&ola_stark.cpu_stark, &traces[Table::Cpu.index()]),
+        ),
+        (
+            Table::Memory,
+            first_failure(&ola_stark.memory_stark, &traces[Table::Memory.index()]),
+        ),
+        (
+            Table::Bitwise,
+            first_failure(&ola_stark.bitwise_stark, &traces[Table::Bitwise.index()]),
+        ),
+        (
+            Table::Cmp,
+            first_failure(&ola_stark.cmp_stark, &traces[Table::Cmp.index()]),
+        ),
+        (
+            Table::RangeCheck,
+            first_failure(
+                &ola_stark.rangecheck_stark,
+                &traces[Table::RangeCheck.index()],
+            ),
+        ),
+        (
+            Table::Poseidon,
+            first_failure(&ola_stark.poseidon_stark, &traces[Table::Poseidon.index()]),
+        ),
+        (
+            Table::PoseidonChunk,
+            first_failure(
+                &ola_stark.poseidon_chunk_stark,
+                &traces[Table::PoseidonChunk.index()],
+            ),
+        ),
+        (
+            Table::StorageAccess,
+            first_failure(
+                &ola_stark.storage_access_stark,
+                &traces[Table::StorageAccess.index()],
+            ),
+        ),
+        (
+            Table::Tape,
+            first_failure(&ola_stark.tape_stark, &traces[Table::Tape.index()]),
+        ),
+        (
+            Table::SCCall,
+            first_failure(&ola_stark.sccall_stark, &traces[Table::SCCall.index()]),
+        ),
+        (
+            Table::Program,
+            first_failure(&ola_stark.program_stark, &traces[Table::Program.index()]),
+        ),
+        (
+            Table::ProgChunk,
+            first_failure(
+                &ola_stark.prog_chunk_stark,
+                &traces[Table::ProgChunk.index()],
+            ),
+        ),
+    ];
+    let (table, (row, constraint)) = failures
+        .into_iter()
+        .filter(|(table, _)| ola_stark.is_active(*table))
+        .find_map(|(table, failure)| Some((table, failure?)))?;
+
+    let step = match table {
+        Table::Cpu => program.trace.exec.get(row),
+        _ => None,
+    };
+    Some(FailureReport {
+        table,
+        row,
+        constraint,
+        instruction: step
+            .and_then(|step| program.trace.instructions.get(&step.pc))
+            .map(|instruction| instruction.0.clone()),
+        source_line: step
+            .and_then(|_| program.source_line(row))
+            .map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use core::vm::opcodes::OlaOpcode;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Field;
+
+    use super::{debug_row, explain_failure};
+    use crate::cpu::columns::{
+        COL_OP1, COL_OPCODE, COL_PC, COL_REGS, COL_S_SIMPLE_ARITHMATIC_OP, NUM_CPU_COLS,
+    };
+    use crate::cpu::cpu_stark::CpuStark;
+    use crate::generation::{generate_traces, GenerationInputs};
+    use crate::stark::ola_stark::{OlaStark, Table};
+    use crate::test_utils::execute_asm_path;
+
+    #[test]
+    fn test_explain_broken_assert() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/mload_uninit.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let assert_row = program
+            .trace
+            .exec
+            .iter()
+            .position(|step| step.opcode.0 == OlaOpcode::ASSERT.binary_bit_mask())
+            .unwrap();
+
+        let mut ola_stark = OlaStark::<GoldilocksField, 2>::default();
+        let (mut traces, _) =
+            generate_traces(program.clone(), &mut ola_stark, GenerationInputs::default()).unwrap();
+        assert_eq!(explain_failure(&ola_stark, &traces, &program), None);
+
+        // The asserted flag is op1, make it false.
+        traces[Table::Cpu.index()][COL_OP1].values[assert_row] = GoldilocksField::ZERO;
+        let report = explain_failure(&ola_stark, &traces, &program).unwrap();
+        assert_eq!(report.table, Table::Cpu);
+        assert_eq!(report.row, assert_row);
+        assert_eq!(report.instruction.as_deref(), Some("assert r5"));
+    }
+
+    #[test]
+    fn test_explain_wrong_entry_pc() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/add_imm.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let mut ola_stark = OlaStark::<GoldilocksField, 2>::default();
+        let (mut traces, _) =
+            generate_traces(program.clone(), &mut ola_stark, GenerationInputs::default()).unwrap();
+
+        // Start the run at the pc of the add, past the entry point.
+        traces[Table::Cpu.index()][COL_PC].values[0] = GoldilocksField::TWO;
+        let report = explain_failure(&ola_stark, &traces, &program).unwrap();
+        assert_eq!(report.table, Table::Cpu);
+        assert_eq!(report.row, 0);
+    }
+
+    #[test]
+    fn test_explain_wrong_initial_fp() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/add_imm.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let mut ola_stark = OlaStark::<GoldilocksField, 2>::default();
+        let (mut traces, _) =
+            generate_traces(program.clone(), &mut ola_stark, GenerationInputs::default()).unwrap();
+
+        // Move the stack of the whole run, the rows still agree with each
+        // other but the run doesn't start from the base of the stack.
+        let fp = &mut traces[Table::Cpu.index()][COL_REGS.end - 1];
+        for value in fp.values.iter_mut() {
+            *value += GoldilocksField::from_canonical_u64(64);
+        }
+        let report = explain_failure(&ola_stark, &traces, &program).unwrap();
+        assert_eq!(report.table, Table::Cpu);
+        assert_eq!(report.row, 0);
+    }
+
+    #[test]
+    fn test_explain_ext_line_pc() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/storage_u32.json");
+        let call_data = vec![
+            GoldilocksField::from_canonical_u64(0),
+            GoldilocksField::from_canonical_u64(2364819430),
+        ];
+        let program = execute_asm_path(path.display().to_string(), Some(call_data), None);
+        let ext_row = program
+            .trace
+            .exec
+            .iter()
+            .position(|step| step.is_ext_line.is_one())
+            .unwrap();
+        let mut ola_stark = OlaStark::<GoldilocksField, 2>::default();
+        let (mut traces, _) =
+            generate_traces(program.clone(), &mut ola_stark, GenerationInputs::default()).unwrap();
+        assert_eq!(explain_failure(&ola_stark, &traces, &program), None);
+
+        // An ext line off the pc of its instruction would move the pc after
+        // it, e.g. onto an immediate word.
+        traces[Table::Cpu.index()][COL_PC].values[ext_row] += GoldilocksField::ONE;
+        let report = explain_failure(&ola_stark, &traces, &program).unwrap();
+        assert_eq!(report.table, Table::Cpu);
+        assert_eq!(report.row, ext_row - 1);
+    }
+
+    #[test]
+    fn test_debug_row_assert() {
+        let assert_residual = |op1: u64| {
+            let mut lv = [GoldilocksField::ZERO; NUM_CPU_COLS];
+            lv[COL_S_SIMPLE_ARITHMATIC_OP] = GoldilocksField::ONE;
+            lv[COL_OPCODE] =
+                GoldilocksField::from_canonical_u64(OlaOpcode::ASSERT.binary_bit_mask());
+            lv[COL_OP1] = GoldilocksField::from_canonical_u64(op1);
+            let nv = [GoldilocksField::ZERO; NUM_CPU_COLS];
+            debug_row(&CpuStark::<GoldilocksField, 2>::default(), &lv, &nv)
+                .into_iter()
+                .find(|(name, _)| name == "assert[0]")
+                .unwrap()
+                .1
+        };
+        assert_eq!(assert_residual(1), GoldilocksField::ZERO);
+        assert_ne!(assert_residual(0), GoldilocksField::ZERO);
+    }
+}
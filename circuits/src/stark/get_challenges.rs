@@ -1,50 +1,119 @@
 use plonky2::field::extension::Extendable;
 use plonky2::fri::proof::{FriProof, FriProofTarget};
 use plonky2::hash::hash_types::RichField;
+use plonky2::hash::hashing::SPONGE_WIDTH;
 use plonky2::iop::challenger::{Challenger, RecursiveChallenger};
 use plonky2::plonk::circuit_builder::CircuitBuilder;
-use plonky2::plonk::config::{AlgebraicHasher, GenericConfig};
+use plonky2::plonk::config::{AlgebraicHasher, GenericConfig, Hasher};
 
 use super::config::StarkConfig;
-use super::ola_stark::{OlaStark, NUM_TABLES};
+use super::ola_stark::{OlaStark, Table, NUM_TABLES};
 use super::permutation::{
     get_grand_product_challenge_set, get_n_grand_product_challenge_sets,
     get_n_grand_product_challenge_sets_target,
 };
 use super::proof::*;
 
+/// Challenger the proof of `table` starts from: a fresh sponge absorbing
+/// `fork`, the compacted state after the CTL challenges, then the table. The
+/// proofs of the tables don't depend on each other and can be made in any
+/// order.
+pub(crate) fn fork_challenger<F: RichField, H: Hasher<F>>(
+    fork: &[F; SPONGE_WIDTH],
+    table: Table,
+) -> Challenger<F, H> {
+    let mut challenger = Challenger::new();
+    challenger.observe_elements(fork);
+    challenger.observe_element(F::from_canonical_usize(table.index()));
+    challenger
+}
+
+/// Draw the compress challenge of each compressed table, zero for the other
+/// ones. `challenger` must have absorbed the trace caps of the tables which
+/// aren't compressed, and the compressed ones are committed after, so their
+/// compress columns can't be chosen knowing the challenge.
+pub(crate) fn get_compress_challenges<F: RichField, H: Hasher<F>>(
+    challenger: &mut Challenger<F, H>,
+) -> [F; NUM_TABLES] {
+    let mut challenges = [F::ZERO; NUM_TABLES];
+    for table in Table::all() {
+        if table.is_compressed() {
+            challenges[table.index()] = challenger.get_challenge();
+        }
+    }
+    challenges
+}
+
 impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> AllProof<F, C, D> {
+    /// Challenger having absorbed the trace caps of the proofs and the
+    /// public values, the state the CTL challenges are drawn from, along
+    /// with the compress challenges drawn on the way.
+    pub(crate) fn ctl_challenger(&self) -> (Challenger<F, C::Hasher>, [F; NUM_TABLES]) {
+        let mut challenger = Challenger::<F, C::Hasher>::new();
+
+        // Which tables are proven, so a proof can't be replayed against
+        // another set of tables.
+        challenger.observe_elements(&self.active_table_mask());
+        for (table, proof) in Table::all().iter().zip(&self.stark_proofs) {
+            if let (false, Some(proof)) = (table.is_compressed(), proof) {
+                challenger.observe_cap(&proof.trace_cap);
+            }
+        }
+        let compress_challenges = get_compress_challenges(&mut challenger);
+        for (table, proof) in Table::all().iter().zip(&self.stark_proofs) {
+            if let (true, Some(proof)) = (table.is_compressed(), proof) {
+                challenger.observe_cap(&proof.trace_cap);
+            }
+        }
+
+        challenger.observe_elements(&self.public_values.to_field_elements());
+        (challenger, compress_challenges)
+    }
+
+    /// The CTL challenges the proof was made with, recomputed from its
+    /// transcript, as `beta, gamma` for each of the `config.num_challenges`
+    /// challenges. E.g. to compare them against a reference run.
+    pub fn ctl_challenges(&self, config: &StarkConfig) -> Vec<F> {
+        let (mut challenger, _) = self.ctl_challenger();
+        get_grand_product_challenge_set(&mut challenger, config.num_challenges)
+            .challenges
+            .iter()
+            .flat_map(|challenge| [challenge.beta, challenge.gamma])
+            .collect()
+    }
+
     /// Computes all Fiat-Shamir challenges used in the STARK proof.
     pub(crate) fn get_challenges(
         &self,
         ola_stark: &OlaStark<F, D>,
         config: &StarkConfig,
     ) -> AllProofChallenges<F, D> {
-        let mut challenger = Challenger::<F, C::Hasher>::new();
-
-        for proof in &self.stark_proofs {
-            challenger.observe_cap(&proof.trace_cap);
-        }
-
-        // TODO: Observe public values.
-
+        let (mut challenger, compress_challenges) = self.ctl_challenger();
         let ctl_challenges =
             get_grand_product_challenge_set(&mut challenger, config.num_challenges);
 
+        let challenger_fork = challenger.compact();
+
         let num_permutation_zs = ola_stark.nums_permutation_zs(config);
         let num_permutation_batch_sizes = ola_stark.permutation_batch_sizes();
 
         AllProofChallenges {
             stark_challenges: std::array::from_fn(|i| {
-                challenger.compact();
-                self.stark_proofs[i].get_challenges(
-                    &mut challenger,
-                    num_permutation_zs[i] > 0,
-                    num_permutation_batch_sizes[i],
-                    config,
-                )
+                self.stark_proofs[i].as_ref().map(|proof| {
+                    let mut challenger =
+                        fork_challenger::<F, C::Hasher>(&challenger_fork, Table::all()[i]);
+                    challenger.compact();
+                    proof.get_challenges(
+                        &mut challenger,
+                        num_permutation_zs[i] > 0,
+                        num_permutation_batch_sizes[i],
+                        config,
+                    )
+                })
             }),
             ctl_challenges,
+            compress_challenges,
+            challenger_fork,
         }
     }
 
@@ -54,30 +123,31 @@ impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> A
         ola_stark: &OlaStark<F, D>,
         config: &StarkConfig,
     ) -> AllChallengerState<F, D> {
-        let mut challenger = Challenger::<F, C::Hasher>::new();
-
-        for proof in &self.stark_proofs {
-            challenger.observe_cap(&proof.trace_cap);
-        }
-
-        // TODO: Observe public values.
-
+        let (mut challenger, _) = self.ctl_challenger();
         let ctl_challenges =
             get_grand_product_challenge_set(&mut challenger, config.num_challenges);
 
         let num_permutation_zs = ola_stark.nums_permutation_zs(config);
         let num_permutation_batch_sizes = ola_stark.permutation_batch_sizes();
 
-        let mut challenger_states = vec![challenger.compact()];
-        for i in 0..NUM_TABLES {
-            self.stark_proofs[i].get_challenges(
-                &mut challenger,
-                num_permutation_zs[i] > 0,
-                num_permutation_batch_sizes[i],
-                config,
-            );
-            challenger_states.push(challenger.compact());
-        }
+        let fork = challenger.compact();
+        let mut challenger_states = Table::all()
+            .iter()
+            .map(|&table| {
+                let mut challenger = fork_challenger::<F, C::Hasher>(&fork, table);
+                let state = challenger.compact();
+                if let Some(proof) = &self.stark_proofs[table.index()] {
+                    proof.get_challenges(
+                        &mut challenger,
+                        num_permutation_zs[table.index()] > 0,
+                        num_permutation_batch_sizes[table.index()],
+                        config,
+                    );
+                }
+                state
+            })
+            .collect::<Vec<_>>();
+        challenger_states.push(fork);
 
         AllChallengerState {
             states: challenger_states.try_into().unwrap(),
@@ -1,6 +1,7 @@
 pub mod config;
 pub mod constraint_consumer;
 pub mod cross_table_lookup;
+pub mod explain;
 mod get_challenges;
 pub mod lookup;
 pub mod ola_stark;
@@ -9,6 +10,7 @@ pub mod proof;
 pub mod prover;
 pub mod serialization;
 pub mod stark;
+pub mod transcript;
 pub mod util;
 pub mod vanishing_poly;
 pub mod vars;
@@ -1,7 +1,9 @@
 use std::iter;
 
+use anyhow::{bail, Result};
+
 use super::config::StarkConfig;
-use super::cross_table_lookup::{CrossTableLookup, TableWithColumns};
+use super::cross_table_lookup::{CrossTableLookup, PublicRows, TableWithColumns};
 use super::stark::Stark;
 use crate::builtins::bitwise::bitwise_stark::{self, BitwiseStark};
 use crate::builtins::cmp::cmp_stark::{self, CmpStark};
@@ -13,6 +15,7 @@ use crate::builtins::storage::storage_access_stark::{self, StorageAccessStark};
 use crate::builtins::tape::tape_stark::{self, TapeStark};
 use crate::cpu::cpu_stark;
 use crate::cpu::cpu_stark::CpuStark;
+use crate::generation::predict::PredictTraceSizes;
 use crate::memory::memory_stark::{
     self, ctl_data as mem_ctl_data, ctl_data_mem_rc_diff_cond, ctl_data_mem_sort_rc,
     ctl_filter as mem_ctl_filter, ctl_filter_mem_rc_diff_cond, ctl_filter_mem_sort_rc, MemoryStark,
@@ -40,6 +43,10 @@ pub struct OlaStark<F: RichField + Extendable<D>, const D: usize> {
     pub prog_chunk_stark: ProgChunkStark<F, D>,
 
     pub cross_table_lookups: Vec<CrossTableLookup<F>>,
+    /// Tables which are proven, indexed by `Table`. Inactive tables get no
+    /// proof and are empty in every CTL touching them, so a trace which
+    /// looks a row up in an inactive table fails to prove.
+    pub active_tables: [bool; NUM_TABLES],
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> Default for OlaStark<F, D> {
@@ -60,11 +67,139 @@ impl<F: RichField + Extendable<D>, const D: usize> Default for OlaStark<F, D> {
             program_stark: ProgramStark::default(),
             prog_chunk_stark: ProgChunkStark::default(),
             cross_table_lookups: all_cross_table_lookups(),
+            active_tables: [true; NUM_TABLES],
+        }
+    }
+}
+
+/// Builds an `OlaStark` proving only a subset of the tables, e.g. leaving out
+/// the bitwise table for a program without bitwise ops. The cpu table is
+/// always active.
+#[derive(Debug, Clone)]
+pub struct OlaStarkBuilder {
+    active_tables: [bool; NUM_TABLES],
+}
+
+impl Default for OlaStarkBuilder {
+    fn default() -> Self {
+        Self {
+            active_tables: [true; NUM_TABLES],
+        }
+    }
+}
+
+impl OlaStarkBuilder {
+    pub fn enable(mut self, table: Table) -> Self {
+        self.active_tables[table.index()] = true;
+        self
+    }
+
+    pub fn disable(mut self, table: Table) -> Self {
+        assert!(table != Table::Cpu, "cpu table can not be disabled");
+        self.active_tables[table.index()] = false;
+        self
+    }
+
+    /// Only keep `tables` (and the cpu table) active.
+    pub fn only(mut self, tables: &[Table]) -> Self {
+        self.active_tables = [false; NUM_TABLES];
+        self.active_tables[Table::Cpu.index()] = true;
+        for table in tables {
+            self.active_tables[table.index()] = true;
+        }
+        self
+    }
+
+    /// Disable the builtin tables `process` is predicted to add no row to,
+    /// e.g. the bitwise table of a program without `and`, `or` and `xor`.
+    /// A wrong prediction is not trusted: the cpu rows looking the table up
+    /// find it empty and the proof fails.
+    pub fn skip_unused_builtins(mut self, process: &impl PredictTraceSizes) -> Self {
+        for table in process.unused_builtins() {
+            self = self.disable(table);
+        }
+        self
+    }
+
+    pub fn build<F: RichField + Extendable<D>, const D: usize>(self) -> OlaStark<F, D> {
+        let mut ola_stark = OlaStark::default();
+        ola_stark.cross_table_lookups = CtlRegistry::with_active_ctls(self.active_tables).into();
+        ola_stark.active_tables = self.active_tables;
+        ola_stark
+    }
+}
+
+/// CTLs between the active tables of an `OlaStark`. Registering a CTL which
+/// involves an inactive table fails, instead of leaving a CTL the prover
+/// has no trace for.
+#[derive(Clone)]
+pub struct CtlRegistry<F: Field> {
+    active_tables: [bool; NUM_TABLES],
+    ctls: Vec<CrossTableLookup<F>>,
+}
+
+impl<F: Field> CtlRegistry<F> {
+    pub fn new(active_tables: [bool; NUM_TABLES]) -> Self {
+        Self {
+            active_tables,
+            ctls: vec![],
         }
     }
+
+    /// Registry of every CTL of `all_cross_table_lookups`, restricted to the
+    /// active tables: an inactive table is kept in its CTLs as an empty one
+    /// rather than dropping the CTLs, so the cpu selectors of a disabled
+    /// table are still constrained to zero.
+    pub fn with_active_ctls(active_tables: [bool; NUM_TABLES]) -> Self {
+        let mut registry = Self::new(active_tables);
+        registry.ctls = all_cross_table_lookups()
+            .iter()
+            .filter_map(|ctl| ctl.restrict_to(&active_tables))
+            .collect();
+        registry
+    }
+
+    pub fn register(&mut self, ctl: CrossTableLookup<F>) -> Result<()> {
+        if let Some(table) = self.inactive_table(&ctl) {
+            bail!("CTL involves inactive table {:?}", table);
+        }
+        self.ctls.push(ctl);
+        Ok(())
+    }
+
+    /// CTLs in which `table` is looking or looked.
+    pub fn involving(&self, table: Table) -> impl Iterator<Item = &CrossTableLookup<F>> + '_ {
+        self.ctls
+            .iter()
+            .filter(move |ctl| ctl.tables().any(|t| t == table))
+    }
+
+    fn inactive_table(&self, ctl: &CrossTableLookup<F>) -> Option<Table> {
+        ctl.tables().find(|t| !self.active_tables[t.index()])
+    }
+}
+
+impl<F: Field> From<CtlRegistry<F>> for Vec<CrossTableLookup<F>> {
+    fn from(registry: CtlRegistry<F>) -> Self {
+        registry.ctls
+    }
 }
 
 impl<F: RichField + Extendable<D>, const D: usize> OlaStark<F, D> {
+    pub fn builder() -> OlaStarkBuilder {
+        OlaStarkBuilder::default()
+    }
+
+    pub fn is_active(&self, table: Table) -> bool {
+        self.active_tables[table.index()]
+    }
+
+    /// Slot of `table` in the `[_; NUM_TABLES]` arrays, `None` if it is not
+    /// proven.
+    pub fn table_index(&self, table: Table) -> Option<usize> {
+        self.is_active(table).then(|| table.index())
+    }
+
     pub(crate) fn nums_permutation_zs(&self, config: &StarkConfig) -> [usize; NUM_TABLES] {
         [
             self.cpu_stark.num_permutation_batches(config),
@@ -119,6 +254,42 @@ pub enum Table {
 
 pub(crate) const NUM_TABLES: usize = 12;
 
+const TABLES: [Table; NUM_TABLES] = [
+    Table::Cpu,
+    Table::Memory,
+    Table::Bitwise,
+    Table::Cmp,
+    Table::RangeCheck,
+    Table::Poseidon,
+    Table::PoseidonChunk,
+    Table::StorageAccess,
+    Table::Tape,
+    Table::SCCall,
+    Table::Program,
+    Table::ProgChunk,
+];
+
+impl Table {
+    /// Every table, in the slot order of the `[_; NUM_TABLES]` arrays of
+    /// traces and proofs. Whether an `OlaStark` proves a table is told by
+    /// `OlaStark::table_index`.
+    pub fn all() -> &'static [Table] {
+        &TABLES
+    }
+
+    /// Slot of the table in the `[_; NUM_TABLES]` arrays.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    /// Whether the table has compress columns, combining its other columns
+    /// with a challenge. Such a table is committed after the other ones and
+    /// its challenge is drawn in between, see `get_compress_challenges`.
+    pub fn is_compressed(self) -> bool {
+        matches!(self, Table::Bitwise | Table::Program)
+    }
+}
+
 pub(crate) fn all_cross_table_lookups<F: Field>() -> Vec<CrossTableLookup<F>> {
     vec![
         ctl_cpu_memory(),
@@ -133,6 +304,7 @@ pub(crate) fn all_cross_table_lookups<F: Field>() -> Vec<CrossTableLookup<F>> {
         ctl_chunk_poseidon(),
         ctl_cpu_poseidon_tree_key(),
         ctl_cpu_storage_access(),
+        ctl_storage_access_undo(),
         ctl_storage_access_poseidon(),
         ctl_cpu_tape(),
         ctl_cpu_sccall(),
@@ -140,6 +312,11 @@ pub(crate) fn all_cross_table_lookups<F: Field>() -> Vec<CrossTableLookup<F>> {
         ctl_cpu_program(),
         ctl_prog_chunk_prog(),
         ctl_prog_chunk_storage(),
+        ctl_memory_program(),
+        ctl_memory_claims(),
+        ctl_tape_outputs(),
+        ctl_initial_memory(),
+        ctl_final_memory(),
     ]
 }
 
@@ -164,6 +341,26 @@ fn ctl_cpu_memory<F: Field>() -> CrossTableLookup<F> {
         cpu_stark::ctl_data_cpu_mem_tload_tstore(),
         Some(cpu_stark::ctl_filter_cpu_mem_tload_tstore()),
     );
+    let cpu_mem_cas_read = TableWithColumns::new(
+        Table::Cpu,
+        cpu_stark::ctl_data_cpu_mem_cas_read(),
+        Some(cpu_stark::ctl_filter_cpu_mem_cas_read()),
+    );
+    let cpu_mem_cas_write = TableWithColumns::new(
+        Table::Cpu,
+        cpu_stark::ctl_data_cpu_mem_cas_write(),
+        Some(cpu_stark::ctl_filter_cpu_mem_cas_write()),
+    );
+    let cpu_mem_loadp_lo = TableWithColumns::new(
+        Table::Cpu,
+        cpu_stark::ctl_data_cpu_mem_loadp_lo(),
+        Some(cpu_stark::ctl_filter_cpu_mem_loadp()),
+    );
+    let cpu_mem_loadp_hi = TableWithColumns::new(
+        Table::Cpu,
+        cpu_stark::ctl_data_cpu_mem_loadp_hi(),
+        Some(cpu_stark::ctl_filter_cpu_mem_loadp()),
+    );
     let cpu_sccall_mems = (0..4).map(|i: usize| {
         TableWithColumns::new(
             Table::Cpu,
@@ -191,6 +388,10 @@ fn ctl_cpu_memory<F: Field>() -> CrossTableLookup<F> {
         cpu_mem_call_ret_pc,
         cpu_mem_call_ret_fp,
         cpu_mem_tload_tstore,
+        cpu_mem_cas_read,
+        cpu_mem_cas_write,
+        cpu_mem_loadp_lo,
+        cpu_mem_loadp_hi,
     ];
     all_cpu_lookers.extend(cpu_sccall_mems);
     all_cpu_lookers.extend(cpu_storage_addr);
@@ -265,12 +466,21 @@ fn ctl_bitwise_cpu<F: Field>() -> CrossTableLookup<F> {
 
 // add CMP cross lookup instance
 fn ctl_cmp_cpu<F: Field>() -> CrossTableLookup<F> {
+    let cas_lookers = (0..2).map(|i| {
+        TableWithColumns::new(
+            Table::Cpu,
+            cpu_stark::ctl_data_with_cmp_cas(i),
+            Some(cpu_stark::ctl_filter_with_cmp_cas()),
+        )
+    });
     CrossTableLookup::new(
-        vec![TableWithColumns::new(
+        iter::once(TableWithColumns::new(
             Table::Cpu,
             cpu_stark::ctl_data_with_cmp(),
             Some(cpu_stark::ctl_filter_with_cmp()),
-        )],
+        ))
+        .chain(cas_lookers)
+        .collect(),
         TableWithColumns::new(
             Table::Cmp,
             cmp_stark::ctl_data_with_cpu(),
@@ -296,12 +506,39 @@ fn ctl_cmp_rangecheck<F: Field>() -> CrossTableLookup<F> {
 
 // add Rangecheck cross lookup instance
 fn ctl_rangecheck_cpu<F: Field>() -> CrossTableLookup<F> {
-    CrossTableLookup::new(
-        vec![TableWithColumns::new(
+    let isqrt_lookers = (0..3).map(|i| {
+        TableWithColumns::new(
             Table::Cpu,
-            cpu_stark::ctl_data_with_rangecheck(),
-            Some(cpu_stark::ctl_filter_with_rangecheck()),
-        )],
+            cpu_stark::ctl_data_with_rangecheck_isqrt(i),
+            Some(cpu_stark::ctl_filter_with_rangecheck_isqrt()),
+        )
+    });
+    // The program table range checks the limbs of the instruction words it
+    // decodes along with the cpu.
+    let code_lookers = (0..3).map(|i| {
+        TableWithColumns::new(
+            Table::Program,
+            program_stark::ctl_data_with_rangecheck(i),
+            Some(program_stark::ctl_filter_with_rangecheck()),
+        )
+    });
+    CrossTableLookup::new(
+        [
+            TableWithColumns::new(
+                Table::Cpu,
+                cpu_stark::ctl_data_with_rangecheck(),
+                Some(cpu_stark::ctl_filter_with_rangecheck()),
+            ),
+            TableWithColumns::new(
+                Table::Cpu,
+                cpu_stark::ctl_data_with_rangecheck_sub(),
+                Some(cpu_stark::ctl_filter_with_rangecheck_sub()),
+            ),
+        ]
+        .into_iter()
+        .chain(isqrt_lookers)
+        .chain(code_lookers)
+        .collect(),
         TableWithColumns::new(
             Table::RangeCheck,
             rangecheck_stark::ctl_data_with_cpu(),
@@ -386,6 +623,22 @@ fn ctl_cpu_storage_access<F: Field>() -> CrossTableLookup<F> {
     )
 }
 
+// Every write of a reverted run is undone by one undo row.
+fn ctl_storage_access_undo<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        vec![TableWithColumns::new(
+            Table::StorageAccess,
+            storage_access_stark::ctl_data_undo(),
+            Some(storage_access_stark::ctl_filter_undo()),
+        )],
+        TableWithColumns::new(
+            Table::StorageAccess,
+            storage_access_stark::ctl_data_undone(),
+            Some(storage_access_stark::ctl_filter_undone()),
+        ),
+    )
+}
+
 fn ctl_storage_access_poseidon<F: Field>() -> CrossTableLookup<F> {
     let looker_bit0 = TableWithColumns::new(
         Table::StorageAccess,
@@ -544,6 +797,65 @@ fn ctl_prog_chunk_prog<F: Field>() -> CrossTableLookup<F> {
     )
 }
 
+fn ctl_memory_program<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::new(
+        vec![TableWithColumns::new(
+            Table::Memory,
+            memory_stark::ctl_data_with_program(),
+            Some(memory_stark::ctl_filter_with_program()),
+        )],
+        TableWithColumns::new(
+            Table::Program,
+            program_stark::ctl_data_by_memory(),
+            Some(program_stark::ctl_filter_by_memory()),
+        ),
+    )
+}
+
+fn ctl_memory_claims<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::with_public_rows(
+        TableWithColumns::new(
+            Table::Memory,
+            memory_stark::ctl_data_with_claims(),
+            Some(memory_stark::ctl_filter_with_claims()),
+        ),
+        PublicRows::MemoryClaims,
+    )
+}
+
+fn ctl_tape_outputs<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::with_public_rows(
+        TableWithColumns::new(
+            Table::Tape,
+            tape_stark::ctl_data_outputs(),
+            Some(tape_stark::ctl_filter_outputs()),
+        ),
+        PublicRows::Outputs,
+    )
+}
+
+fn ctl_initial_memory<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::with_public_rows(
+        TableWithColumns::new(
+            Table::Memory,
+            memory_stark::ctl_data_with_public_memory(),
+            Some(memory_stark::ctl_filter_with_initial_memory()),
+        ),
+        PublicRows::InitialMemory,
+    )
+}
+
+fn ctl_final_memory<F: Field>() -> CrossTableLookup<F> {
+    CrossTableLookup::with_public_rows(
+        TableWithColumns::new(
+            Table::Memory,
+            memory_stark::ctl_data_with_public_memory(),
+            Some(memory_stark::ctl_filter_with_final_memory()),
+        ),
+        PublicRows::FinalMemory,
+    )
+}
+
 fn ctl_prog_chunk_storage<F: Field>() -> CrossTableLookup<F> {
     CrossTableLookup::new(
         vec![TableWithColumns::new(
@@ -642,17 +954,35 @@ fn ctl_prog_chunk_storage<F: Field>() -> CrossTableLookup<F> {
 #[allow(unused_imports)]
 #[cfg(test)]
 mod tests {
+    use crate::cpu::cpu_stark::CpuStark;
+    use crate::generation::cpu::generate_cpu_trace;
+    use crate::generation::memory::check_memory_claims;
     use crate::generation::{generate_traces, GenerationInputs};
+    use crate::program::columns::COL_PROG_COMP_PROG;
     use crate::stark::config::StarkConfig;
-    use crate::stark::ola_stark::OlaStark;
-    use crate::stark::proof::PublicValues;
-    use crate::stark::prover::prove_with_traces;
+    use crate::stark::cross_table_lookup::testutils::check_ctls;
+    use crate::stark::cross_table_lookup::verify_ctl;
+    use crate::stark::ola_stark::{
+        all_cross_table_lookups, CtlRegistry, OlaStark, OlaStarkBuilder, Table, NUM_TABLES,
+    };
+    use crate::stark::proof::{AllProof, MemoryClaim, PublicValues};
+    use crate::stark::prover::{
+        prove_with_traces, prove_with_traces_and_cache, Cancelled, CommitmentCache, EmptyProgram,
+        NoTermination, ProveStats,
+    };
     use crate::stark::serialization::Buffer;
     use crate::stark::stark::Stark;
-    use crate::stark::util::trace_rows_to_poly_values;
-    use crate::stark::verifier::verify_proof;
+    use crate::stark::util::{trace_rows_to_poly_values, trace_to_poly_values};
+    use crate::stark::verifier::{
+        verify_equivalent_runs, verify_memory_transition, verify_outputs, verify_proof,
+        verify_proof_with_residuals,
+    };
+    use crate::test_utils::{
+        check_ctl_filters, execute_asm_path, execute_asm_path_with_process, run_asm_path,
+        vacuous_ctls,
+    };
     use anyhow::Result;
-    use assembler::encoder::encode_asm_from_json_file;
+    use assembler::encoder::{encode_annotated_asm_from_json_file, encode_asm_from_json_file};
     use core::crypto::hash::Hasher;
     use core::crypto::ZkHasher;
     use core::merkle_tree::log::{StorageLog, WitnessStorageLog};
@@ -662,19 +992,24 @@ mod tests {
     use core::types::account::Address;
     use core::types::merkle_tree::{encode_addr, tree_key_default};
     use core::types::{Field, GoldilocksField};
+    use core::vm::memory::memory_root;
+    use core::vm::opcodes::OlaOpcode;
     use core::vm::transaction::init_tx_context_mock;
     use executor::load_tx::init_tape;
+    use executor::run::execute_program;
     use executor::trace::{gen_storage_hash_table, gen_storage_table};
     use executor::{Process, TxScopeCacheManager};
     use itertools::Itertools;
     use log::{debug, LevelFilter};
+    use plonky2::field::extension::Extendable;
     use plonky2::plonk::config::{Blake3GoldilocksConfig, GenericConfig, PoseidonGoldilocksConfig};
     use plonky2::util::timing::TimingTree;
-    use std::collections::HashMap;
+    use std::collections::{BTreeMap, HashMap};
     use std::fs::File;
     use std::io::{BufRead, BufReader};
     use std::mem;
     use std::path::PathBuf;
+    use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::{Arc, Mutex};
     use std::time::{Duration, Instant};
 
@@ -687,6 +1022,41 @@ mod tests {
     #[allow(dead_code)]
     type S = dyn Stark<F, D>;
 
+    /// Leaves out the builtins the small test programs use none of. The
+    /// program, memory and range check tables and the tables hashing the
+    /// code are looked up by every run.
+    fn without_builtins() -> OlaStarkBuilder {
+        OlaStark::<F, D>::builder()
+            .disable(Table::Bitwise)
+            .disable(Table::Cmp)
+            .disable(Table::PoseidonChunk)
+            .disable(Table::Tape)
+            .disable(Table::SCCall)
+    }
+
+    /// Generate the traces of `program` for `ola_stark` with `inputs`, let
+    /// `edit` change their public values and prove them with `config`.
+    /// Returns the proof and the public values it was made for.
+    fn prove_program(
+        program: Program,
+        ola_stark: &mut OlaStark<F, D>,
+        inputs: GenerationInputs,
+        config: &StarkConfig,
+        edit: impl FnOnce(&mut PublicValues),
+    ) -> Result<(AllProof<F, C, D>, PublicValues)> {
+        let (traces, mut public_values) = generate_traces(program, ola_stark, inputs)?;
+        edit(&mut public_values);
+        let proof = prove_with_traces::<F, C, D>(
+            ola_stark,
+            config,
+            traces,
+            public_values.clone(),
+            &mut TimingTree::default(),
+            None,
+        )?;
+        Ok((proof, public_values))
+    }
+
     #[test]
     fn fibo_loop_test() {
         let calldata = [10u64, 1u64, 2, 4185064725u64]
@@ -696,6 +1066,829 @@ mod tests {
         test_by_asm_json("fib_asm.json".to_string(), Some(calldata), None)
     }
 
+    #[test]
+    fn prove_cancelled_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/fib_asm.json");
+        let calldata = [10u64, 1u64, 2, 4185064725u64]
+            .iter()
+            .map(|v| GoldilocksField::from_canonical_u64(*v))
+            .collect_vec();
+        let program = execute_asm_path(path.display().to_string(), Some(calldata), None);
+
+        let mut ola_stark = OlaStark::default();
+        let (traces, public_values) =
+            generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+        let config = StarkConfig::standard_fast_config();
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let canceller = {
+            let cancel = cancel.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(10));
+                cancel.store(true, Ordering::Relaxed);
+            })
+        };
+        let proof = prove_with_traces::<F, C, D>(
+            &ola_stark,
+            &config,
+            traces,
+            public_values,
+            &mut TimingTree::default(),
+            Some(&cancel),
+        );
+        canceller.join().unwrap();
+
+        let err = proof.err().expect("proving should be cancelled");
+        assert!(err.downcast_ref::<Cancelled>().is_some());
+    }
+
+    #[test]
+    fn empty_program_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let mut ola_stark = OlaStark::default();
+        let (mut traces, public_values) =
+            generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+        // No step, a single padding row.
+        traces[Table::Cpu.index()] = trace_to_poly_values(generate_cpu_trace::<F>(&vec![]));
+        let config = StarkConfig::standard_fast_config();
+        let proof = prove_with_traces::<F, C, D>(
+            &ola_stark,
+            &config,
+            traces,
+            public_values,
+            &mut TimingTree::default(),
+            None,
+        );
+        let err = proof.err().expect("an empty program has nothing to prove");
+        assert!(err.downcast_ref::<EmptyProgram>().is_some());
+    }
+
+    #[test]
+    fn no_termination_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let mut program = execute_asm_path(path.display().to_string(), None, None);
+        // Drop the end row, as if the run had been stopped before it.
+        let end = program.trace.exec.pop().unwrap();
+        assert_eq!(end.opcode.0, OlaOpcode::END.binary_bit_mask());
+        let mut ola_stark = OlaStark::default();
+        let (traces, public_values) =
+            generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+        let config = StarkConfig::standard_fast_config();
+        let proof = prove_with_traces::<F, C, D>(
+            &ola_stark,
+            &config,
+            traces,
+            public_values,
+            &mut TimingTree::default(),
+            None,
+        );
+        let err = proof.err().expect("a run cut before its end is not proven");
+        assert!(err.downcast_ref::<NoTermination>().is_some());
+    }
+
+    #[test]
+    fn ctl_registry_inactive_table_test() {
+        let active = OlaStark::<F, D>::builder()
+            .disable(Table::Bitwise)
+            .build()
+            .active_tables;
+        let mut registry = CtlRegistry::<F>::new(active);
+        assert!(registry.register(super::ctl_bitwise_cpu()).is_err());
+        assert!(registry.register(super::ctl_cpu_memory()).is_ok());
+
+        // The cpu side of the bitwise CTL is kept, looking into an empty
+        // table.
+        let registry = CtlRegistry::<F>::with_active_ctls(active);
+        assert_eq!(registry.involving(Table::Bitwise).count(), 0);
+        assert!(registry.involving(Table::Cmp).count() > 0);
+        let all = CtlRegistry::<F>::with_active_ctls([true; NUM_TABLES]);
+        assert_eq!(
+            registry.involving(Table::Cpu).count(),
+            all.involving(Table::Cpu).count()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "CTL #")]
+    fn disabled_used_builtin_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/bitwise.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let mut ola_stark = OlaStark::<F, D>::builder().disable(Table::Bitwise).build();
+        let (traces, _) =
+            generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+        check_ctls(&traces, &ola_stark.cross_table_lookups);
+    }
+
+    #[test]
+    fn table_index_test() {
+        assert_eq!(Table::Cpu.index(), 0);
+        for (i, table) in Table::all().iter().enumerate() {
+            assert_eq!(table.index(), i);
+            assert_eq!(*table as usize, i);
+        }
+        let ola_stark = OlaStark::<F, D>::builder().disable(Table::Bitwise).build();
+        assert_eq!(ola_stark.table_index(Table::Cpu), Some(0));
+        assert_eq!(ola_stark.table_index(Table::Bitwise), None);
+        assert_eq!(ola_stark.table_index(Table::Cmp), Some(3));
+    }
+
+    #[test]
+    fn disabled_builtins_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+
+        let mut ola_stark = without_builtins().build();
+        assert_eq!(
+            ola_stark.cross_table_lookups.len(),
+            all_cross_table_lookups::<F>().len()
+        );
+        let config = StarkConfig::standard_fast_config();
+        let (proof, _) = prove_program(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+            &config,
+            |_| (),
+        )
+        .unwrap();
+        assert!(proof.stark_proofs[Table::Cpu.index()].is_some());
+        assert!(proof.stark_proofs[Table::Memory.index()].is_some());
+        assert!(proof.stark_proofs[Table::Bitwise.index()].is_none());
+
+        let ola_stark = without_builtins().build();
+        verify_proof(ola_stark, proof, &config).unwrap();
+    }
+
+    #[test]
+    fn proof_id_test() {
+        let config = StarkConfig::standard_fast_config();
+        let prove = |file_name: &str| {
+            let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            path.push("../assembler/test_data/asm/");
+            path.push(file_name);
+            let program = execute_asm_path(path.display().to_string(), None, None);
+            let mut ola_stark = without_builtins().build();
+            prove_program(
+                program,
+                &mut ola_stark,
+                GenerationInputs::default(),
+                &config,
+                |_| (),
+            )
+            .unwrap()
+            .0
+        };
+        let proof = prove("memory.json");
+        let id = proof.id();
+        assert_eq!(prove("memory.json").id(), id);
+        assert_ne!(prove("memory_extended.json").id(), id);
+
+        // The openings are part of the id too.
+        let mut opened_elsewhere = proof;
+        let openings = &mut opened_elsewhere.stark_proofs[Table::Cpu.index()]
+            .as_mut()
+            .unwrap()
+            .openings;
+        openings.local_values[0] += <F as Extendable<D>>::Extension::ONE;
+        assert_ne!(opened_elsewhere.id(), id);
+    }
+
+    #[test]
+    fn skip_unused_builtins_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let (process, program) =
+            execute_asm_path_with_process(path.display().to_string(), None, None);
+
+        let mut ola_stark = OlaStark::builder().skip_unused_builtins(&process).build();
+        assert!(!ola_stark.is_active(Table::Bitwise));
+        assert!(!ola_stark.is_active(Table::Cmp));
+        assert!(ola_stark.is_active(Table::RangeCheck));
+        let config = StarkConfig::standard_fast_config();
+        let (proof, _) = prove_program(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+            &config,
+            |_| (),
+        )
+        .unwrap();
+        assert!(proof.stark_proofs[Table::Bitwise.index()].is_none());
+        verify_proof(ola_stark, proof, &config).unwrap();
+    }
+
+    #[test]
+    fn verify_residuals_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let mut ola_stark = without_builtins().build();
+        let config = StarkConfig::standard_fast_config();
+        let (proof, _) = prove_program(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+            &config,
+            |_| (),
+        )
+        .unwrap();
+
+        let residuals = verify_proof_with_residuals(ola_stark, proof, &config).unwrap();
+        let tables = residuals.iter().map(|r| r.table).collect::<Vec<_>>();
+        assert_eq!(tables, vec![Table::Cpu, Table::Memory]);
+        for residual in residuals {
+            assert_eq!(residual.vanishing.len(), config.num_challenges);
+            assert_eq!(residual.vanishing, residual.quotient_times_z_h);
+        }
+    }
+
+    #[test]
+    fn tampered_public_values_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let mut ola_stark = without_builtins().build();
+        let config = StarkConfig::standard_fast_config();
+        let (proof, _) = prove_program(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+            &config,
+            |_| (),
+        )
+        .unwrap();
+        verify_proof(ola_stark.clone(), proof.clone(), &config).unwrap();
+
+        // The public values are absorbed before any challenge is drawn, so the
+        // untouched stark proofs no longer open to the challenges.
+        let mut tampered = proof;
+        tampered.public_values.block_metadata.block_number = 7.into();
+        assert!(verify_proof(ola_stark, tampered, &config).is_err());
+    }
+
+    #[test]
+    fn memory_claim_test() {
+        // memory_claim.json stores 9 then 8 at 256.
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory_claim.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let claim = |value| MemoryClaim { addr: 256, value };
+        assert!(check_memory_claims(&program.trace.memory, &[claim(8)]).is_ok());
+        assert!(check_memory_claims(&program.trace.memory, &[claim(9)]).is_err());
+        assert!(check_memory_claims(&program.trace.memory, &[claim(8), claim(8)]).is_err());
+
+        let mut ola_stark = without_builtins().build();
+        // No traces are made for a false claim.
+        let false_program = execute_asm_path(path.display().to_string(), None, None);
+        let inputs = GenerationInputs {
+            memory_claims: vec![claim(9)],
+            ..Default::default()
+        };
+        assert!(generate_traces(false_program, &mut ola_stark, inputs).is_err());
+        let inputs = GenerationInputs {
+            memory_claims: vec![claim(8)],
+            ..Default::default()
+        };
+        let config = StarkConfig::standard_fast_config();
+        let (proof, public_values) =
+            prove_program(program, &mut ola_stark, inputs, &config, |_| ()).unwrap();
+        assert_eq!(public_values.memory_claims, vec![claim(8)]);
+        verify_proof(ola_stark.clone(), proof, &config).unwrap();
+
+        // The claims are looked up in the memory table, a proof made for a
+        // false one fails.
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let inputs = GenerationInputs {
+            memory_claims: vec![claim(8)],
+            ..Default::default()
+        };
+        let (false_claim, _) = prove_program(program, &mut ola_stark, inputs, &config, |values| {
+            values.memory_claims = vec![claim(9)]
+        })
+        .unwrap();
+        assert!(verify_proof(ola_stark, false_claim, &config).is_err());
+    }
+
+    #[test]
+    fn initial_registers_test() {
+        // initial_registers.json is `add r2 r0 r1`.
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/initial_registers.json");
+        let mut registers = CpuStark::<F, D>::default_registers();
+        registers[0] = 10;
+        registers[1] = 20;
+        let process = Process::with_initial_registers(registers);
+        let (process, program) =
+            run_asm_path(process, path.display().to_string(), None, None).unwrap();
+        assert_eq!(
+            process.registers[2],
+            GoldilocksField::from_canonical_u64(30)
+        );
+
+        let mut ola_stark = without_builtins().build();
+        let config = StarkConfig::standard_fast_config();
+        let (proof, public_values) = prove_program(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+            &config,
+            |_| (),
+        )
+        .unwrap();
+        assert_eq!(public_values.initial_registers, Some(registers));
+        verify_proof(without_builtins().build(), proof, &config).unwrap();
+
+        // The first row is bound to the public registers, a proof made for
+        // the default ones fails.
+        let process = Process::with_initial_registers(registers);
+        let (_, program) = run_asm_path(process, path.display().to_string(), None, None).unwrap();
+        let (false_registers, _) = prove_program(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+            &config,
+            |values| values.initial_registers = None,
+        )
+        .unwrap();
+        assert!(verify_proof(ola_stark, false_registers, &config).is_err());
+    }
+
+    #[test]
+    fn memory_transition_test() {
+        // memory_transition.json increments the word at address 100.
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory_transition.json");
+        let initial_memory = BTreeMap::from([(100, 41)]);
+        let process = Process::with_initial_memory(initial_memory.clone());
+        let (process, program) =
+            run_asm_path(process, path.display().to_string(), None, None).unwrap();
+        let final_memory = process.memory.stack_values();
+        assert_eq!(final_memory.get(&100), Some(&42));
+        let root_words = |memory: &BTreeMap<u64, u64>| memory_root(memory).map(|word| word.0);
+        let initial_root = root_words(&initial_memory);
+        let final_root = root_words(&final_memory);
+        assert_ne!(initial_root, final_root);
+
+        let mut ola_stark = without_builtins().build();
+        let config = StarkConfig::standard_fast_config();
+        let (proof, public_values) = prove_program(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+            &config,
+            |_| (),
+        )
+        .unwrap();
+        assert_eq!(public_values.initial_memory_root, initial_root);
+        assert_eq!(public_values.final_memory_root, final_root);
+        verify_memory_transition(
+            ola_stark.clone(),
+            proof.clone(),
+            &config,
+            initial_root,
+            final_root,
+        )
+        .unwrap();
+        assert!(verify_memory_transition(
+            ola_stark.clone(),
+            proof,
+            &config,
+            initial_root,
+            initial_root
+        )
+        .is_err());
+
+        // The final memory is looked up in the last accesses of the memory
+        // table, a proof made for another one with its root fails.
+        let process = Process::with_initial_memory(initial_memory);
+        let (_, program) = run_asm_path(process, path.display().to_string(), None, None).unwrap();
+        let forged_memory = BTreeMap::from([(100, 43)]);
+        let (forged, _) = prove_program(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+            &config,
+            |values| {
+                values.final_memory = vec![MemoryClaim {
+                    addr: 100,
+                    value: 43,
+                }];
+                values.final_memory_root = root_words(&forged_memory);
+            },
+        )
+        .unwrap();
+        assert!(verify_memory_transition(
+            ola_stark,
+            forged,
+            &config,
+            initial_root,
+            root_words(&forged_memory)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn outputs_test() {
+        // stdlib_output.json outputs 3, 3 * 7 and 3 * 7 + 100.
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/stdlib_output.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let mut ola_stark = without_builtins().enable(Table::Tape).build();
+        let config = StarkConfig::standard_fast_config();
+        let (proof, public_values) = prove_program(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+            &config,
+            |_| (),
+        )
+        .unwrap();
+        assert_eq!(public_values.outputs, vec![3, 21, 121]);
+        verify_outputs(ola_stark.clone(), proof.clone(), &config, &[3, 21, 121]).unwrap();
+
+        // The outputs are looked up in the tape, in order: a proof made for
+        // others, or for the same ones reordered, fails.
+        assert!(verify_outputs(ola_stark.clone(), proof, &config, &[21, 3, 121]).is_err());
+        for outputs in [vec![21, 3, 121], vec![3, 21, 122]] {
+            let program = execute_asm_path(path.display().to_string(), None, None);
+            let (forged, _) = prove_program(
+                program,
+                &mut ola_stark,
+                GenerationInputs::default(),
+                &config,
+                |values| values.outputs = outputs.clone(),
+            )
+            .unwrap();
+            assert!(verify_outputs(ola_stark.clone(), forged, &config, &outputs).is_err());
+        }
+    }
+
+    #[test]
+    fn vacuous_ctl_filters_test() {
+        let ctl_bitwise = all_cross_table_lookups::<F>()
+            .iter()
+            .position(|ctl| ctl.tables().any(|table| table == Table::Bitwise))
+            .unwrap();
+        let traces = |file: &str| {
+            let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            path.push("../assembler/test_data/asm");
+            path.push(file);
+            let program = execute_asm_path(path.display().to_string(), None, None);
+            let mut ola_stark = OlaStark::<F, D>::default();
+            let (traces, _) =
+                generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+            (traces, ola_stark.cross_table_lookups)
+        };
+
+        // add_mul has no bitwise instruction, its bitwise CTL checks nothing.
+        let (traces, ctls) = traces("add_mul.json");
+        assert!(vacuous_ctls(&traces, &ctls).contains(&ctl_bitwise));
+        let err = check_ctl_filters(&traces, &ctls).unwrap_err();
+        assert!(err.to_string().contains("Bitwise"), "{}", err);
+
+        let (traces, ctls) = traces("bitwise.json");
+        assert!(!vacuous_ctls(&traces, &ctls).contains(&ctl_bitwise));
+    }
+
+    #[test]
+    fn ctl_challenges_test() {
+        let config = StarkConfig::standard_fast_config();
+        let prove = |file: &str| {
+            let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            path.push(file);
+            let program = execute_asm_path(path.display().to_string(), None, None);
+            let mut ola_stark = without_builtins().build();
+            let (proof, _) = prove_program(
+                program,
+                &mut ola_stark,
+                GenerationInputs::default(),
+                &config,
+                |_| (),
+            )
+            .unwrap();
+            (ola_stark, proof)
+        };
+
+        let (ola_stark, proof) = prove("../assembler/test_data/asm/add_imm.json");
+        let challenges = proof.ctl_challenges(&config);
+        assert_eq!(challenges.len(), 2 * config.num_challenges);
+        let expected = proof
+            .get_challenges(&ola_stark, &config)
+            .ctl_challenges
+            .challenges
+            .iter()
+            .flat_map(|challenge| [challenge.beta, challenge.gamma])
+            .collect::<Vec<_>>();
+        assert_eq!(challenges, expected);
+
+        // Same program, same transcript.
+        let (_, again) = prove("../assembler/test_data/asm/add_imm.json");
+        assert_eq!(again.ctl_challenges(&config), challenges);
+        let (_, other) = prove("../assembler/test_data/asm/fibo_iter.json");
+        assert_ne!(other.ctl_challenges(&config), challenges);
+    }
+
+    #[test]
+    fn fri_arity_test() {
+        // fibo_iter.json runs ~70 cpu rows, padded to 2^7.
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/fibo_iter.json");
+        let mut sizes = vec![];
+        for config in [
+            StarkConfig::standard_fast_config(),
+            StarkConfig::standard_fast_config().with_fri_arity_bits(1),
+        ] {
+            let program = execute_asm_path(path.display().to_string(), None, None);
+            let mut ola_stark = without_builtins().build();
+            let (proof, _) = prove_program(
+                program,
+                &mut ola_stark,
+                GenerationInputs::default(),
+                &config,
+                |_| (),
+            )
+            .unwrap();
+            verify_proof(ola_stark, proof.clone(), &config).unwrap();
+
+            let cpu_proof = proof.stark_proofs[Table::Cpu.index()].as_ref().unwrap();
+            let degree_bits = cpu_proof.recover_degree_bits(&config);
+            assert_eq!(
+                cpu_proof.opening_proof.commit_phase_merkle_caps.len(),
+                config.fri_params(degree_bits).reduction_arity_bits.len()
+            );
+            let mut buffer = Buffer::new(vec![]);
+            buffer.write_all_proof(&proof).unwrap();
+            sizes.push((
+                cpu_proof.opening_proof.commit_phase_merkle_caps.len(),
+                buffer.len(),
+            ));
+        }
+        // Folding by 2 takes more rounds than by 16 and changes the proof size.
+        assert!(sizes[1].0 > sizes[0].0);
+        assert_ne!(sizes[1].1, sizes[0].1);
+    }
+
+    #[test]
+    fn equivalent_runs_test() {
+        // sum_loop.json adds 5 down to 1 in a loop, sum_folded.json outputs
+        // the constant 15, both output 15 once.
+        let prove_run = |name: &str| {
+            let program = execute_asm_path(assembler::testprograms::path(name), None, None);
+            let mut ola_stark = without_builtins().enable(Table::Tape).build();
+            prove_program(
+                program,
+                &mut ola_stark,
+                GenerationInputs::default(),
+                &StarkConfig::standard_fast_config(),
+                |_| (),
+            )
+            .unwrap()
+            .0
+        };
+        let ola_stark = without_builtins().enable(Table::Tape).build();
+        let config = StarkConfig::standard_fast_config();
+        let looped = prove_run("sum_loop");
+        let folded = prove_run("sum_folded");
+        verify_equivalent_runs(ola_stark.clone(), looped.clone(), folded, &config).unwrap();
+
+        // output_repeat.json outputs 5, 25 and 25.
+        let other = prove_run("output_repeat");
+        assert!(verify_equivalent_runs(ola_stark, looped, other, &config).is_err());
+    }
+
+    #[test]
+    fn ctl_cpu_memory_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+
+        let mut ola_stark = without_builtins().build();
+        let config = StarkConfig::standard_fast_config();
+        let (mut proof, _) = prove_program(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+            &config,
+            |_| (),
+        )
+        .unwrap();
+        verify_ctl(&proof, &ola_stark.cross_table_lookups, &config).unwrap();
+
+        let memory_proof = proof.stark_proofs[Table::Memory.index()].as_mut().unwrap();
+        memory_proof.openings.ctl_zs_last[0] += F::ONE;
+        assert!(verify_ctl(&proof, &ola_stark.cross_table_lookups, &config).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "Constraint failed in")]
+    fn jump_into_immediate_test() {
+        // imm_jump.json jumps to the immediate of `mov r0 1048576`, the
+        // encoding of `end`, which the executor runs. The code decodes pc 1
+        // as an immediate, so its fetch as an instruction can't be proven.
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/imm_jump.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        assert_eq!(program.trace.exec.last().unwrap().pc, 1);
+
+        let _ = prove_program(
+            program,
+            &mut OlaStark::default(),
+            GenerationInputs::default(),
+            &StarkConfig::standard_fast_config(),
+            |_| (),
+        );
+    }
+
+    #[test]
+    fn patched_code_test() {
+        // Execute add_imm_patched.json, add_imm.json with a different
+        // immediate, against the program table of add_imm.json as if the code
+        // had been overwritten during the run.
+        let mut traces_by_file = vec![];
+        for file_name in ["add_imm.json", "add_imm_patched.json"] {
+            let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            path.push("../assembler/test_data/asm/");
+            path.push(file_name);
+            let program = execute_asm_path(path.display().to_string(), None, None);
+            let mut ola_stark = OlaStark::default();
+            let (traces, public_values) =
+                generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+            traces_by_file.push((ola_stark, traces, public_values));
+        }
+        let (_, patched_traces, _) = traces_by_file.pop().unwrap();
+        let (ola_stark, mut traces, public_values) = traces_by_file.pop().unwrap();
+        traces[Table::Cpu.index()] = patched_traces[Table::Cpu.index()].clone();
+
+        let config = StarkConfig::standard_fast_config();
+        let proof = prove_with_traces::<F, C, D>(
+            &ola_stark,
+            &config,
+            traces,
+            public_values,
+            &mut TimingTree::default(),
+            None,
+        )
+        .unwrap();
+        assert!(verify_ctl(&proof, &ola_stark.cross_table_lookups, &config).is_err());
+        assert!(verify_proof(ola_stark, proof, &config).is_err());
+    }
+
+    #[test]
+    fn parallel_tables_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let mut ola_stark = OlaStark::default();
+        let (traces, public_values) =
+            generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+
+        let proofs = [true, false].map(|parallel| {
+            let config = StarkConfig::standard_fast_config().with_parallel_tables(parallel);
+            let proof = prove_with_traces::<F, C, D>(
+                &ola_stark,
+                &config,
+                traces.clone(),
+                public_values.clone(),
+                &mut TimingTree::default(),
+                None,
+            )
+            .unwrap();
+            verify_proof(ola_stark.clone(), proof.clone(), &config).unwrap();
+            proof
+        });
+        assert_eq!(proofs[0].id(), proofs[1].id());
+    }
+
+    #[test]
+    fn forged_program_compress_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/add_imm.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let mut ola_stark = OlaStark::default();
+        let config = StarkConfig::standard_fast_config();
+        let (mut proof, _) = prove_program(
+            program,
+            &mut ola_stark,
+            GenerationInputs::default(),
+            &config,
+            |_| (),
+        )
+        .unwrap();
+
+        let program_proof = proof.stark_proofs[Table::Program.index()].as_mut().unwrap();
+        program_proof.openings.local_values[COL_PROG_COMP_PROG] +=
+            <F as Extendable<D>>::Extension::ONE;
+        let err = verify_proof(ola_stark, proof, &config).unwrap_err();
+        assert!(err.to_string().contains("ProgramStark"));
+    }
+
+    #[test]
+    fn prove_stats_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+
+        let mut ola_stark = without_builtins().build();
+        let (traces, public_values) =
+            generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+        let config = StarkConfig::standard_fast_config();
+        let mut stats = ProveStats::default();
+        prove_with_traces_and_cache::<F, C, D>(
+            &ola_stark,
+            &config,
+            traces,
+            public_values,
+            &mut TimingTree::default(),
+            None,
+            None,
+            Some(&mut stats),
+        )
+        .unwrap();
+
+        assert!(stats.trace_commitments_ns > 0);
+        for (table_stats, active) in stats.tables.iter().zip(ola_stark.active_tables) {
+            assert_eq!(table_stats.is_some(), active);
+            if let Some(table_stats) = table_stats {
+                assert!(table_stats.total_ns >= table_stats.quotient_ns);
+                assert!(table_stats.total_ns > 0);
+            }
+        }
+    }
+
+    fn prove_cached(file_name: &str, cache: &CommitmentCache<F, C, D>) -> AllProof<F, C, D> {
+        let config = StarkConfig::standard_fast_config();
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/");
+        path.push(file_name);
+        let program = execute_asm_path(path.display().to_string(), None, None);
+
+        let mut ola_stark = OlaStark::default();
+        let (traces, public_values) =
+            generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+        let proof = prove_with_traces_and_cache::<F, C, D>(
+            &ola_stark,
+            &config,
+            traces,
+            public_values,
+            &mut TimingTree::default(),
+            None,
+            Some(cache),
+            None,
+        )
+        .unwrap();
+        verify_proof(ola_stark, proof.clone(), &config).unwrap();
+        proof
+    }
+
+    #[test]
+    fn commitment_cache_test() {
+        // memory_extended.json is memory.json with one more add after the
+        // last memory access: both runs share the memory table, the cpu
+        // tables differ.
+        let cache = CommitmentCache::<F, C, D>::default();
+        let base = prove_cached("memory.json", &cache);
+        assert_eq!(cache.hits(), 0);
+        let extended = prove_cached("memory_extended.json", &cache);
+
+        let cap = |proof: &AllProof<F, C, D>, table: Table| {
+            proof.stark_proofs[table.index()]
+                .as_ref()
+                .unwrap()
+                .trace_cap
+                .clone()
+        };
+        assert_eq!(cap(&base, Table::Memory), cap(&extended, Table::Memory));
+        assert_ne!(cap(&base, Table::Cpu), cap(&extended, Table::Cpu));
+        // Every table whose trace is shared was served from the cache, and
+        // only those.
+        let shared = Table::all()
+            .iter()
+            .filter(|table| cap(&base, **table) == cap(&extended, **table))
+            .count();
+        assert_eq!(cache.hits(), shared);
+        assert!(cache.len() <= NUM_TABLES);
+    }
+
+    #[test]
+    fn commitment_cache_eviction_test() {
+        let cache = CommitmentCache::<F, C, D>::with_capacity(2);
+        prove_cached("memory.json", &cache);
+        assert_eq!(cache.len(), 2);
+
+        // Nothing is kept without capacity.
+        let cache = CommitmentCache::<F, C, D>::with_capacity(0);
+        prove_cached("memory.json", &cache);
+        prove_cached("memory.json", &cache);
+        assert_eq!(cache.hits(), 0);
+        assert!(cache.is_empty());
+    }
+
     #[test]
     fn fibo_recursive_decode() {
         test_by_asm_json("fibo_recursive.json".to_string(), None, None)
@@ -706,6 +1899,197 @@ mod tests {
         test_by_asm_json("memory.json".to_string(), None, None)
     }
 
+    #[test]
+    fn isqrt_test() {
+        prove_asm_json("isqrt.json");
+        prove_asm_json("isqrt_non_square.json");
+        prove_asm_json("isqrt_large.json");
+    }
+
+    #[test]
+    fn cas_test() {
+        prove_asm_json("cas.json");
+        prove_asm_json("cas_mismatch.json");
+    }
+
+    #[test]
+    fn loadp_test() {
+        prove_asm_json("loadp.json");
+    }
+
+    #[test]
+    fn rodata_test() {
+        prove_asm_json("rodata.json");
+    }
+
+    #[test]
+    fn mem_sparse_test() {
+        // Addresses 2^33 apart, bridged by two memory rows.
+        prove_asm_json("mem_sparse.json");
+    }
+
+    #[test]
+    fn clk_test() {
+        prove_asm_json("clk.json");
+    }
+
+    #[test]
+    fn test_programs_test() {
+        let config = StarkConfig::standard_fast_config();
+        for name in assembler::testprograms::names() {
+            let (_, expected) = assembler::testprograms::load(name).unwrap();
+            let program = execute_asm_path(assembler::testprograms::path(name), None, None);
+            let (proof, public_values) = prove_program(
+                program,
+                &mut OlaStark::default(),
+                GenerationInputs::default(),
+                &config,
+                |_| (),
+            )
+            .unwrap();
+            assert_eq!(
+                public_values.outputs, expected.outputs,
+                "outputs of {}",
+                name
+            );
+            verify_outputs(OlaStark::default(), proof, &config, expected.outputs)
+                .unwrap_or_else(|err| panic!("{} doesn't verify: {}", name, err));
+        }
+    }
+
+    #[test]
+    fn require_test() {
+        prove_asm_json("require.json");
+        prove_asm_json("require_pass.json");
+    }
+
+    #[test]
+    fn require_reverted_test() {
+        // require.json ends on a require of zero, require_pass.json on an end.
+        let config = StarkConfig::standard_fast_config();
+        for (file_name, reverted) in [("require.json", true), ("require_pass.json", false)] {
+            let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            path.push("../assembler/test_data/asm/");
+            path.push(file_name);
+            let program = execute_asm_path(path.display().to_string(), None, None);
+            let mut ola_stark = OlaStark::default();
+            let (proof, public_values) = prove_program(
+                program,
+                &mut ola_stark,
+                GenerationInputs::default(),
+                &config,
+                |_| (),
+            )
+            .unwrap();
+            assert_eq!(public_values.reverted, reverted);
+            verify_proof(OlaStark::default(), proof, &config).unwrap();
+
+            // The last executed row is bound to the public status, a proof
+            // made for the other one fails.
+            let program = execute_asm_path(path.display().to_string(), None, None);
+            let (false_status, _) = prove_program(
+                program,
+                &mut ola_stark,
+                GenerationInputs::default(),
+                &config,
+                |values| values.reverted = !reverted,
+            )
+            .unwrap();
+            assert!(verify_proof(OlaStark::default(), false_status, &config).is_err());
+        }
+    }
+
+    #[test]
+    fn reverted_storage_test() {
+        // storage_reverted.json is storage.json ending on a require of zero:
+        // the storage trace undoes its writes and ends on the root it started
+        // from.
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/storage_reverted.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let (start, end) = program.trace.start_end_roots;
+        assert_eq!(start, end);
+        let undo_rows = program
+            .trace
+            .builtin_storage_hash
+            .iter()
+            .filter(|row| row.is_undo.is_one())
+            .count();
+        assert_eq!(undo_rows, 4);
+        prove_asm_json("storage_reverted.json");
+    }
+
+    #[test]
+    fn cas_memory_rows_test() {
+        // Both programs store 7 once, cas writes 9 only when it matches.
+        let mstore = GoldilocksField::from_canonical_u64(OlaOpcode::MSTORE.binary_bit_mask());
+        for (file, writes) in [("cas.json", 2), ("cas_mismatch.json", 1)] {
+            let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            path.push("../assembler/test_data/asm/");
+            path.push(file);
+            let program = execute_asm_path(path.display().to_string(), None, None);
+            let stores = program
+                .trace
+                .memory
+                .iter()
+                .filter(|cell| cell.op == mstore && cell.is_write.is_one())
+                .count();
+            assert_eq!(stores, writes, "{}", file);
+            // The cas write is a clk after its read, no address is accessed
+            // twice at one clk.
+            assert!(program
+                .trace
+                .memory
+                .iter()
+                .filter(|cell| cell.filter_looked_for_main.is_one())
+                .map(|cell| (cell.addr, cell.clk))
+                .all_unique());
+        }
+    }
+
+    #[test]
+    fn annotated_trace_test() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/annotated.json");
+        let path = path.display().to_string();
+        let annotated = encode_annotated_asm_from_json_file(path.clone()).unwrap();
+        let (_, program) = execute_program(
+            Process::new(),
+            &annotated,
+            None,
+            &mut AccountTree::new_test(),
+        )
+        .unwrap();
+        assert_eq!(program.trace.exec.len(), 3);
+        assert_eq!(program.source_line(0), Some("main.ola:2 a = 3"));
+        assert_eq!(program.source_line(1), Some("main.ola:3 b = a + 5"));
+        assert_eq!(program.source_line(2), None);
+        // The comments are only kept when asked for.
+        let program = execute_asm_path(path, None, None);
+        assert_eq!(program.source_line(0), None);
+    }
+
+    #[test]
+    fn uninit_memory_test() {
+        // The program asserts the loads from untouched addresses return zero.
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/mload_uninit.json");
+        let program = execute_asm_path(path.display().to_string(), None, None);
+        let memory = &program.trace.memory;
+        let uninit_reads = memory
+            .iter()
+            .filter(|cell| {
+                !memory
+                    .iter()
+                    .any(|c| c.addr == cell.addr && c.is_write.is_one())
+            })
+            .collect_vec();
+        assert_eq!(uninit_reads.len(), 2);
+        assert!(uninit_reads.iter().all(|cell| cell.value.is_zero()));
+
+        test_by_asm_json("mload_uninit.json".to_string(), None, None)
+    }
+
     #[test]
     fn call_test() {
         test_by_asm_json("call.json".to_string(), None, None)
@@ -815,6 +2199,26 @@ mod tests {
     //     test_by_asm_json("string_assert.json".to_string(), None);
     // }
 
+    /// Execute, prove and verify the fixture `file_name`, failing on any
+    /// error unlike `test_by_asm_json`.
+    fn prove_asm_json(file_name: &str) {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/");
+        path.push(file_name);
+        let program = execute_asm_path(path.display().to_string(), None, None);
+
+        let config = StarkConfig::standard_fast_config();
+        let (proof, _) = prove_program(
+            program,
+            &mut OlaStark::default(),
+            GenerationInputs::default(),
+            &config,
+            |_| (),
+        )
+        .unwrap();
+        verify_proof(OlaStark::default(), proof, &config).unwrap();
+    }
+
     #[allow(unused)]
     pub fn test_by_asm_json(
         file_name: String,
@@ -840,21 +2244,21 @@ mod tests {
         let program = encode_asm_from_json_file(program_path).unwrap();
         let hash = ZkHasher::default();
         let instructions = program.bytecode.split("\n");
-        let code: Vec<_> = instructions
-            .clone()
-            .map(|e| GoldilocksField::from_canonical_u64(u64::from_str_radix(&e[2..], 16).unwrap()))
-            .collect();
-        let code_hash = hash.hash_bytes(&code);
         let mut prophets = HashMap::new();
         for item in program.prophets {
             prophets.insert(item.host as u64, item);
         }
 
-        let mut program: Program = Program::default();
+        let mut program: Program = Program {
+            data: program.data,
+            ..Default::default()
+        };
 
         for inst in instructions {
             program.instructions.push(inst.to_string());
         }
+        let code = program.code_words().unwrap();
+        let code_hash = hash.hash_bytes(&code);
 
         let mut process = Process::new();
         process.addr_storage = Address::default();
@@ -922,24 +2326,16 @@ mod tests {
                 return;
             }
         }
-        let hash_roots = gen_storage_hash_table(&mut process, &mut program, &mut db);
+        let hash_roots = gen_storage_hash_table(&mut process, &mut program, &mut db, false);
         gen_storage_table(&mut process, &mut program, hash_roots).unwrap();
         program.trace.start_end_roots = (start, db.root_hash());
 
         let inputs = GenerationInputs::default();
 
-        let mut ola_stark = OlaStark::default();
-        let (traces, public_values) = generate_traces(program, &mut ola_stark, inputs);
         let config = StarkConfig::standard_fast_config();
-        let proof = prove_with_traces::<F, C, D>(
-            &ola_stark,
-            &config,
-            traces,
-            public_values,
-            &mut TimingTree::default(),
-        );
+        let proof = prove_program(program, &mut OlaStark::default(), inputs, &config, |_| ());
 
-        if let Ok(proof) = proof {
+        if let Ok((proof, _)) = proof {
             let ola_stark = OlaStark::default();
             let verify_res = verify_proof(ola_stark, proof, &config);
             println!("verify result:{:?}", verify_res);
@@ -1,7 +1,12 @@
+use anyhow::{ensure, Result};
+use core::program::REGISTER_NUM;
+use core::types::merkle_tree::TREE_VALUE_LEN;
+use core::vm::memory::memory_root;
 use ethereum_types::{Address, H256, U256};
 use itertools::Itertools;
 use maybe_rayon::*;
 use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::types::Field;
 use plonky2::fri::oracle::PolynomialBatch;
 use plonky2::fri::proof::{FriChallenges, FriChallengesTarget, FriProof, FriProofTarget};
 use plonky2::fri::structure::{
@@ -14,6 +19,8 @@ use plonky2::iop::ext_target::ExtensionTarget;
 use plonky2::iop::target::Target;
 use plonky2::plonk::config::GenericConfig;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 
 use super::config::StarkConfig;
 use super::ola_stark::NUM_TABLES;
@@ -22,27 +29,48 @@ use super::permutation::GrandProductChallengeSet;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(bound = "")]
 pub struct AllProof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
-    pub stark_proofs: [StarkProof<F, C, D>; NUM_TABLES],
-    pub compress_challenges: [F; NUM_TABLES],
+    /// `None` for tables left out by `OlaStarkBuilder`.
+    pub stark_proofs: [Option<StarkProof<F, C, D>>; NUM_TABLES],
     pub public_values: PublicValues,
 }
 
 impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> AllProof<F, C, D> {
-    pub fn degree_bits(&self, config: &StarkConfig) -> [usize; NUM_TABLES] {
-        std::array::from_fn(|i| self.stark_proofs[i].recover_degree_bits(config))
+    pub fn degree_bits(&self, config: &StarkConfig) -> [Option<usize>; NUM_TABLES] {
+        std::array::from_fn(|i| {
+            self.stark_proofs[i]
+                .as_ref()
+                .map(|p| p.recover_degree_bits(config))
+        })
+    }
+
+    /// 1 for each table with a proof, 0 for each left out one, as absorbed
+    /// first into the transcript.
+    pub fn active_table_mask(&self) -> [F; NUM_TABLES] {
+        std::array::from_fn(|i| F::from_bool(self.stark_proofs[i].is_some()))
+    }
+
+    /// Sha256 of the serialized proof, openings and FRI proofs included, so
+    /// two proofs have the same id only if they are the same. The prover is
+    /// deterministic, so proving the same traces with the same config gives
+    /// the same id.
+    pub fn id(&self) -> [u8; 32] {
+        Sha256::digest(serde_json::to_vec(self).unwrap()).into()
     }
 }
 
 pub(crate) struct AllProofChallenges<F: RichField + Extendable<D>, const D: usize> {
-    pub stark_challenges: [StarkProofChallenges<F, D>; NUM_TABLES],
+    pub stark_challenges: [Option<StarkProofChallenges<F, D>>; NUM_TABLES],
     pub ctl_challenges: GrandProductChallengeSet<F>,
+    /// Indexed by `Table`, zero for the tables which aren't compressed.
+    pub compress_challenges: [F; NUM_TABLES],
+    /// Sponge state after the CTL challenges, see `fork_challenger`.
+    pub challenger_fork: [F; SPONGE_WIDTH],
 }
 
 #[allow(unused)] // TODO: should be used soon
 pub(crate) struct AllChallengerState<F: RichField + Extendable<D>, const D: usize> {
-    /// Sponge state of the challenger before starting each proof,
-    /// along with the final state after all proofs are done. This final state
-    /// isn't strictly needed.
+    /// Sponge state of the challenger before starting each proof, along
+    /// with the state after the CTL challenges every proof is forked from.
     pub states: [[F; SPONGE_WIDTH]; NUM_TABLES + 1],
     pub ctl_challenges: GrandProductChallengeSet<F>,
 }
@@ -58,16 +86,192 @@ pub struct PublicValues {
     pub trie_roots_before: TrieRoots,
     pub trie_roots_after: TrieRoots,
     pub block_metadata: BlockMetadata,
+    /// Values memory holds at the end of the run, see `MemoryClaim`.
+    #[serde(default)]
+    pub memory_claims: Vec<MemoryClaim>,
+    /// Registers the run was seeded with, `None` for zero but fp. The cpu
+    /// table's first row is constrained to them.
+    #[serde(default)]
+    pub initial_registers: Option<[u64; REGISTER_NUM]>,
+    /// Words the run output, in order: the return data `tstore` wrote to the
+    /// tape after the last sccall, e.g. by the stdlib `output` routine. A CTL
+    /// binds them to the tape table, so they are empty if it isn't proven,
+    /// see `verify_outputs`.
+    #[serde(default)]
+    pub outputs: Vec<u64>,
+    /// Whether the run ends on a require of zero, i.e. the tx reverted and
+    /// its storage writes are to be discarded. The cpu table's last executed
+    /// row is constrained to the matching opcode.
+    #[serde(default)]
+    pub reverted: bool,
+    /// Stack words the run starts from, by address, e.g. the state a state
+    /// transition applies to. They are written by the init rows of the
+    /// memory table, which a CTL binds to them.
+    #[serde(default)]
+    pub initial_memory: Vec<MemoryClaim>,
+    /// Last word of every stack address the run accesses, the memory it
+    /// ends with. A CTL binds them to the last accesses of the memory table.
+    #[serde(default)]
+    pub final_memory: Vec<MemoryClaim>,
+    /// `memory_root` of `initial_memory`, checked by the verifier.
+    #[serde(default)]
+    pub initial_memory_root: [u64; TREE_VALUE_LEN],
+    /// `memory_root` of `final_memory`, checked by the verifier.
+    #[serde(default)]
+    pub final_memory_root: [u64; TREE_VALUE_LEN],
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Claim that memory at `addr` holds `value` at the end of the run, i.e.
+/// that the last read/write access to `addr` in the memory trace has that
+/// value.
+///
+/// The verifier looks the claims up in the memory table, which must select
+/// exactly their rows, and the memory table constrains a selected row to be
+/// the last access to its address. `generate_traces` refuses to make traces
+/// a claim of its inputs doesn't hold on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryClaim {
+    pub addr: u64,
+    pub value: u64,
+}
+
+impl PublicValues {
+    /// Public values of a proof of segment `a` followed by segment `b`. The
+    /// segments must belong to the same block and `b` must start from the
+    /// tries and memory `a` ends with.
+    pub fn chain(a: &PublicValues, b: &PublicValues) -> Result<PublicValues> {
+        ensure!(
+            a.block_metadata == b.block_metadata,
+            "segments belong to different blocks"
+        );
+        ensure!(
+            a.trie_roots_after == b.trie_roots_before,
+            "segment starts from {:?}, previous one ends with {:?}",
+            b.trie_roots_before,
+            a.trie_roots_after
+        );
+        ensure!(
+            a.final_memory_root == b.initial_memory_root,
+            "segment starts from memory {:?}, previous one ends with {:?}",
+            b.initial_memory_root,
+            a.final_memory_root
+        );
+        Ok(PublicValues {
+            trie_roots_before: a.trie_roots_before.clone(),
+            trie_roots_after: b.trie_roots_after.clone(),
+            block_metadata: a.block_metadata.clone(),
+            memory_claims: b.memory_claims.clone(),
+            initial_registers: a.initial_registers,
+            outputs: [a.outputs.as_slice(), b.outputs.as_slice()].concat(),
+            reverted: b.reverted,
+            initial_memory: a.initial_memory.clone(),
+            final_memory: b.final_memory.clone(),
+            initial_memory_root: a.initial_memory_root,
+            final_memory_root: b.final_memory_root,
+        })
+    }
+
+    /// Check the memory roots are the `memory_root`s of the initial and
+    /// final memory, which give an address a word once.
+    pub fn check_memory_roots(&self) -> Result<()> {
+        for (words, root) in [
+            (&self.initial_memory, self.initial_memory_root),
+            (&self.final_memory, self.final_memory_root),
+        ] {
+            let mut values = BTreeMap::new();
+            for word in words {
+                ensure!(
+                    values.insert(word.addr, word.value).is_none(),
+                    "memory at {} is given twice",
+                    word.addr
+                );
+            }
+            let expected = memory_root(&values).map(|word| word.0);
+            ensure!(
+                expected == root,
+                "memory root {:?} doesn't commit to the memory, {:?} does",
+                root,
+                expected
+            );
+        }
+        Ok(())
+    }
+
+    /// The public values as little endian 32-bit limbs, the way the prover
+    /// and the verifier absorb them into the transcript before drawing any
+    /// challenge. Every variable length field is prefixed by its length and
+    /// every optional one by whether it is there, so two different public
+    /// values never give the same limbs.
+    pub fn to_field_elements<F: Field>(&self) -> Vec<F> {
+        let bytes_limbs = |bytes: &[u8]| {
+            bytes
+                .chunks(4)
+                .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+                .collect::<Vec<_>>()
+        };
+        let u256_limbs = |value: &U256| {
+            value
+                .0
+                .iter()
+                .flat_map(|limb| [*limb as u32, (*limb >> 32) as u32])
+                .collect::<Vec<_>>()
+        };
+        let mut limbs = vec![];
+        for roots in [&self.trie_roots_before, &self.trie_roots_after] {
+            limbs.extend(bytes_limbs(roots.state_root.as_bytes()));
+            limbs.extend(bytes_limbs(roots.transactions_root.as_bytes()));
+            limbs.extend(bytes_limbs(roots.receipts_root.as_bytes()));
+        }
+        let metadata = &self.block_metadata;
+        limbs.extend(bytes_limbs(metadata.block_beneficiary.as_bytes()));
+        for value in [
+            &metadata.block_timestamp,
+            &metadata.block_number,
+            &metadata.block_difficulty,
+            &metadata.block_gaslimit,
+            &metadata.block_chain_id,
+            &metadata.block_base_fee,
+        ] {
+            limbs.extend(u256_limbs(value));
+        }
+        limbs.push(self.memory_claims.len() as u32);
+        for claim in &self.memory_claims {
+            for word in [claim.addr, claim.value] {
+                limbs.extend([word as u32, (word >> 32) as u32]);
+            }
+        }
+        limbs.push(self.initial_registers.is_some() as u32);
+        for register in self.initial_registers.iter().flatten() {
+            limbs.extend([*register as u32, (*register >> 32) as u32]);
+        }
+        limbs.push(self.outputs.len() as u32);
+        for word in &self.outputs {
+            limbs.extend([*word as u32, (*word >> 32) as u32]);
+        }
+        limbs.push(self.reverted as u32);
+        for words in [&self.initial_memory, &self.final_memory] {
+            limbs.push(words.len() as u32);
+            for word in words {
+                for word in [word.addr, word.value] {
+                    limbs.extend([word as u32, (word >> 32) as u32]);
+                }
+            }
+        }
+        for word in [self.initial_memory_root, self.final_memory_root].concat() {
+            limbs.extend([word as u32, (word >> 32) as u32]);
+        }
+        limbs.into_iter().map(F::from_canonical_u32).collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TrieRoots {
     pub state_root: H256,
     pub transactions_root: H256,
     pub receipts_root: H256,
 }
 
-#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
 pub struct BlockMetadata {
     pub block_beneficiary: Address,
     pub block_timestamp: U256,
@@ -310,3 +514,66 @@ impl<const D: usize> StarkOpeningSetTarget<D> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::program::REGISTER_NUM;
+    use ethereum_types::H256;
+    use plonky2::field::goldilocks_field::GoldilocksField;
+
+    use super::{MemoryClaim, PublicValues, TrieRoots};
+
+    fn segment(before: u64, after: u64) -> PublicValues {
+        let roots = |state: u64| TrieRoots {
+            state_root: H256::from_low_u64_be(state),
+            ..Default::default()
+        };
+        PublicValues {
+            trie_roots_before: roots(before),
+            trie_roots_after: roots(after),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_chain_public_values() {
+        let chained = PublicValues::chain(&segment(1, 2), &segment(2, 3)).unwrap();
+        assert_eq!(chained.trie_roots_before, segment(1, 3).trie_roots_before);
+        assert_eq!(chained.trie_roots_after, segment(1, 3).trie_roots_after);
+
+        assert!(PublicValues::chain(&segment(1, 2), &segment(4, 5)).is_err());
+
+        let mut other_block = segment(2, 3);
+        other_block.block_metadata.block_number = 1.into();
+        assert!(PublicValues::chain(&segment(1, 2), &other_block).is_err());
+
+        let (mut a, mut b) = (segment(1, 2), segment(2, 3));
+        a.outputs = vec![1, 2];
+        b.outputs = vec![3];
+        let chained = PublicValues::chain(&a, &b).unwrap();
+        assert_eq!(chained.outputs, vec![1, 2, 3]);
+
+        let mut other_memory = segment(1, 2);
+        other_memory.final_memory_root = [1, 0, 0, 0];
+        assert!(PublicValues::chain(&other_memory, &segment(2, 3)).is_err());
+    }
+
+    #[test]
+    fn test_public_values_limbs_are_unambiguous() {
+        let limbs = |values: &PublicValues| values.to_field_elements::<GoldilocksField>();
+
+        // Zero registers aren't the same as no registers.
+        let zero_registers = PublicValues {
+            initial_registers: Some([0; REGISTER_NUM]),
+            ..Default::default()
+        };
+        assert_ne!(limbs(&zero_registers), limbs(&PublicValues::default()));
+
+        // Claims can't be read as the registers after them, as many limbs.
+        let claimed = PublicValues {
+            memory_claims: vec![MemoryClaim::default(); REGISTER_NUM / 2],
+            ..Default::default()
+        };
+        assert_ne!(limbs(&claimed), limbs(&zero_registers));
+    }
+}
@@ -1,6 +1,10 @@
 use core::program::Program;
 use std::any::type_name;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use anyhow::{ensure, Result};
 use log::info;
@@ -14,7 +18,7 @@ use plonky2::field::zero_poly_coset::ZeroPolyOnCoset;
 use plonky2::fri::oracle::PolynomialBatch;
 use plonky2::hash::hash_types::RichField;
 use plonky2::iop::challenger::Challenger;
-use plonky2::plonk::config::{GenericConfig, Hasher};
+use plonky2::plonk::config::{GenericConfig, GenericHashOut, Hasher};
 use plonky2::timed;
 use plonky2::util::timing::TimingTree;
 use plonky2::util::transpose;
@@ -34,18 +38,196 @@ use crate::program::program_stark::ProgramStark;
 use super::config::StarkConfig;
 use super::constraint_consumer::ConstraintConsumer;
 use super::cross_table_lookup::{cross_table_lookup_data, CtlCheckVars, CtlData};
+use super::get_challenges::{fork_challenger, get_compress_challenges};
 use super::permutation::PermutationCheckVars;
 use super::permutation::{
     compute_permutation_z_polys, get_n_grand_product_challenge_sets, GrandProductChallengeSet,
 };
 use super::proof::{AllProof, PublicValues, StarkOpeningSet, StarkProof};
 use super::stark::Stark;
+use super::transcript::Transcript;
 use super::vanishing_poly::eval_vanishing_poly;
 use super::vars::StarkEvaluationVars;
+use crate::cpu::columns::{COL_IS_PADDING, COL_S_END};
 use crate::cpu::cpu_stark::CpuStark;
+use crate::generation::builtin::fill_bitwise_compress_cols;
+use crate::generation::prog::fill_prog_compress_cols;
 use crate::generation::{generate_traces, GenerationInputs};
 use crate::memory::memory_stark::MemoryStark;
 
+/// Returned, wrapped in an `anyhow::Error`, when proving is aborted through
+/// the cancellation flag. Callers can tell it apart with `downcast_ref`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "proving cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Returned, wrapped in an `anyhow::Error`, when the cpu trace has padding
+/// rows only, i.e. no instruction was executed. There is nothing to prove.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyProgram;
+
+impl fmt::Display for EmptyProgram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "empty program, the cpu trace has no executed row")
+    }
+}
+
+impl std::error::Error for EmptyProgram {}
+
+/// Returned, wrapped in an `anyhow::Error`, when the last executed row of the
+/// cpu trace is no end, i.e. the run was cut before it halted. A trace is
+/// always proven as a whole run, there are no segments, so such a trace
+/// would be proven as if the program had completed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoTermination;
+
+impl fmt::Display for NoTermination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no termination, the run was cut before an end")
+    }
+}
+
+impl std::error::Error for NoTermination {}
+
+fn check_cancelled(cancel: Option<&AtomicBool>) -> Result<()> {
+    match cancel {
+        Some(flag) if flag.load(Ordering::Relaxed) => Err(Cancelled.into()),
+        _ => Ok(()),
+    }
+}
+
+type CommitmentKey = (Vec<u8>, usize, usize);
+
+/// Trace commitments shared between proofs, e.g. when batch proving programs
+/// which share a bootloader. A commitment covers a whole table, so it is
+/// reused when a table's trace is identical to one seen before; the key is a
+/// hash of the trace together with the commitment parameters.
+///
+/// A commitment holds the low-degree extension of its table, `2^rate_bits`
+/// times the trace, so the cache keeps at most `capacity` of them and
+/// evicts the oldest first. The default capacity is one proof's tables.
+pub struct CommitmentCache<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+{
+    commitments: Mutex<CachedCommitments<F, C, D>>,
+    capacity: usize,
+    hits: AtomicUsize,
+}
+
+struct CachedCommitments<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> {
+    by_key: HashMap<CommitmentKey, Arc<PolynomialBatch<F, C, D>>>,
+    /// Keys in insertion order, oldest first.
+    order: VecDeque<CommitmentKey>,
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> Default
+    for CommitmentCache<F, C, D>
+{
+    fn default() -> Self {
+        Self::with_capacity(NUM_TABLES)
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>
+    CommitmentCache<F, C, D>
+{
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            commitments: Mutex::new(CachedCommitments {
+                by_key: HashMap::new(),
+                order: VecDeque::new(),
+            }),
+            capacity,
+            hits: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of commitments served from the cache so far.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        self.commitments.lock().unwrap().by_key.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn get_or_commit(
+        &self,
+        trace: &[PolynomialValues<F>],
+        rate_bits: usize,
+        cap_height: usize,
+        timing: &mut TimingTree,
+        twiddle_map: &mut BTreeMap<usize, Vec<F>>,
+    ) -> Arc<PolynomialBatch<F, C, D>>
+    where
+        [(); C::Hasher::HASH_SIZE]:,
+    {
+        let mut values = vec![F::from_canonical_usize(trace.len())];
+        values.extend(trace.iter().flat_map(|poly| poly.values.iter().copied()));
+        let key = (
+            C::Hasher::hash_no_pad(&values).to_bytes(),
+            rate_bits,
+            cap_height,
+        );
+        if let Some(commitment) = self.commitments.lock().unwrap().by_key.get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return commitment.clone();
+        }
+
+        let commitment = Arc::new(PolynomialBatch::<F, C, D>::from_values(
+            trace.to_vec(),
+            rate_bits,
+            false,
+            cap_height,
+            timing,
+            twiddle_map,
+        ));
+        if self.capacity == 0 {
+            return commitment;
+        }
+        let mut commitments = self.commitments.lock().unwrap();
+        if commitments.by_key.contains_key(&key) {
+            return commitment;
+        }
+        if commitments.order.len() == self.capacity {
+            let oldest = commitments.order.pop_front().unwrap();
+            commitments.by_key.remove(&oldest);
+        }
+        commitments.order.push_back(key.clone());
+        commitments.by_key.insert(key, commitment.clone());
+        commitment
+    }
+}
+
+/// Time spent proving a single table, in nanoseconds per phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TableStats {
+    pub permutation_ctl_zs_ns: u128,
+    pub quotient_ns: u128,
+    pub openings_ns: u128,
+    pub total_ns: u128,
+}
+
+/// Time spent in each proving phase, in nanoseconds. Filled by
+/// `prove_with_traces_and_cache` when asked for.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProveStats {
+    pub trace_commitments_ns: u128,
+    pub ctl_data_ns: u128,
+    /// Indexed by `Table`, `None` for the tables which weren't proven.
+    pub tables: [Option<TableStats>; NUM_TABLES],
+}
+
 /// Generate traces, then create all STARK proofs.
 pub fn prove<F, C, const D: usize>(
     program: Program,
@@ -71,17 +253,21 @@ where
     [(); ProgramStark::<F, D>::COLUMNS]:,
     [(); ProgChunkStark::<F, D>::COLUMNS]:,
 {
-    let (traces, public_values) = generate_traces(program, ola_stark, inputs);
-    prove_with_traces(ola_stark, config, traces, public_values, timing)
+    let (traces, public_values) = generate_traces(program, ola_stark, inputs)?;
+    prove_with_traces(ola_stark, config, traces, public_values, timing, None)
 }
 
-/// Compute all STARK proofs.
+/// Compute all STARK proofs, on a thread per table if
+/// `config.parallel_tables`, at the memory cost documented there. If `cancel`
+/// is given it is polled between tables and proving stages, and once set
+/// proving stops with a `Cancelled` error.
 pub fn prove_with_traces<F, C, const D: usize>(
     ola_stark: &OlaStark<F, D>,
     config: &StarkConfig,
     trace_poly_values: [Vec<PolynomialValues<F>>; NUM_TABLES],
     public_values: PublicValues,
     timing: &mut TimingTree,
+    cancel: Option<&AtomicBool>,
 ) -> Result<AllProof<F, C, D>>
 where
     F: RichField + Extendable<D>,
@@ -100,228 +286,418 @@ where
     [(); ProgramStark::<F, D>::COLUMNS]:,
     [(); ProgChunkStark::<F, D>::COLUMNS]:,
 {
+    prove_with_traces_and_cache(
+        ola_stark,
+        config,
+        trace_poly_values,
+        public_values,
+        timing,
+        cancel,
+        None,
+        None,
+    )
+}
+
+/// Same as `prove_with_traces`, also returning the Fiat-Shamir transcript of
+/// the proof for debugging other verifiers, see `Transcript`.
+pub fn prove_with_transcript<F, C, const D: usize>(
+    ola_stark: &OlaStark<F, D>,
+    config: &StarkConfig,
+    trace_poly_values: [Vec<PolynomialValues<F>>; NUM_TABLES],
+    public_values: PublicValues,
+    timing: &mut TimingTree,
+) -> Result<(AllProof<F, C, D>, Transcript)>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+    [(); CpuStark::<F, D>::COLUMNS]:,
+    [(); MemoryStark::<F, D>::COLUMNS]:,
+    [(); BitwiseStark::<F, D>::COLUMNS]:,
+    [(); CmpStark::<F, D>::COLUMNS]:,
+    // [(); RangeCheckStark::<F, D>::COLUMNS]:,
+    [(); PoseidonStark::<F, D>::COLUMNS]:,
+    [(); PoseidonChunkStark::<F, D>::COLUMNS]:,
+    [(); StorageAccessStark::<F, D>::COLUMNS]:,
+    // [(); TapeStark::<F, D>::COLUMNS]:,
+    [(); SCCallStark::<F, D>::COLUMNS]:,
+    [(); ProgramStark::<F, D>::COLUMNS]:,
+    [(); ProgChunkStark::<F, D>::COLUMNS]:,
+{
+    let proof = prove_with_traces(
+        ola_stark,
+        config,
+        trace_poly_values,
+        public_values,
+        timing,
+        None,
+    )?;
+    let transcript = proof.transcript(ola_stark, config);
+    Ok((proof, transcript))
+}
+
+/// Like `prove_with_traces`, reusing the trace commitments found in `cache`
+/// and adding the new ones to it. If `stats` is given the time spent in each
+/// table and phase is recorded in it.
+pub fn prove_with_traces_and_cache<F, C, const D: usize>(
+    ola_stark: &OlaStark<F, D>,
+    config: &StarkConfig,
+    mut trace_poly_values: [Vec<PolynomialValues<F>>; NUM_TABLES],
+    public_values: PublicValues,
+    timing: &mut TimingTree,
+    cancel: Option<&AtomicBool>,
+    cache: Option<&CommitmentCache<F, C, D>>,
+    stats: Option<&mut ProveStats>,
+) -> Result<AllProof<F, C, D>>
+where
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    [(); C::Hasher::HASH_SIZE]:,
+    [(); CpuStark::<F, D>::COLUMNS]:,
+    [(); MemoryStark::<F, D>::COLUMNS]:,
+    [(); BitwiseStark::<F, D>::COLUMNS]:,
+    [(); CmpStark::<F, D>::COLUMNS]:,
+    // [(); RangeCheckStark::<F, D>::COLUMNS]:,
+    [(); PoseidonStark::<F, D>::COLUMNS]:,
+    [(); PoseidonChunkStark::<F, D>::COLUMNS]:,
+    [(); StorageAccessStark::<F, D>::COLUMNS]:,
+    // [(); TapeStark::<F, D>::COLUMNS]:,
+    [(); SCCallStark::<F, D>::COLUMNS]:,
+    [(); ProgramStark::<F, D>::COLUMNS]:,
+    [(); ProgChunkStark::<F, D>::COLUMNS]:,
+{
+    let is_padding = trace_poly_values[Table::Cpu.index()]
+        .get(COL_IS_PADDING)
+        .map_or(&[][..], |column| column.values.as_slice());
+    if is_padding.iter().all(|row| row.is_one()) {
+        return Err(EmptyProgram.into());
+    }
+    let last_executed = is_padding.iter().rposition(|row| row.is_zero());
+    let s_end = &trace_poly_values[Table::Cpu.index()][COL_S_END].values;
+    if last_executed.map_or(false, |row| !s_end[row].is_one()) {
+        return Err(NoTermination.into());
+    }
+    let max_trace_len = trace_poly_values
+        .iter()
+        .zip(ola_stark.active_tables)
+        .filter(|(_, active)| *active)
+        .filter_map(|(trace, _)| Some(trace.first()?.len()))
+        .max()
+        .unwrap_or(0);
+    config.validate(max_trace_len)?;
+
     let rate_bits = config.fri_config.rate_bits;
     let cap_height = config.fri_config.cap_height;
 
     let mut twiddle_map = BTreeMap::new();
 
-    #[cfg(feature = "benchmark")]
     let start = Instant::now();
 
-    let trace_commitments = timed!(
+    let commit = |trace: &Vec<PolynomialValues<F>>,
+                  timing: &mut TimingTree,
+                  twiddle_map: &mut BTreeMap<usize, Vec<F>>| match cache {
+        Some(cache) => cache.get_or_commit(trace, rate_bits, cap_height, timing, twiddle_map),
+        None => Arc::new(PolynomialBatch::<F, C, D>::from_values(
+            // TODO: Cloning this isn't great; consider having `from_values` accept a
+            // reference, or having `compute_permutation_z_polys` read
+            // trace values from the `PolynomialBatch`.
+            trace.clone(),
+            rate_bits,
+            false,
+            cap_height,
+            timing,
+            twiddle_map,
+        )),
+    };
+
+    // The compressed tables are committed last, once their compress
+    // challenges are drawn from the caps of the other tables.
+    let mut trace_commitments = timed!(
         timing,
         "compute trace commitments",
-        trace_poly_values
+        Table::all()
             .iter()
-            .map(|trace| {
-                PolynomialBatch::<F, C, D>::from_values(
-                    // TODO: Cloning this isn't great; consider having `from_values` accept a
-                    // reference, or having `compute_permutation_z_polys` read
-                    // trace values from the `PolynomialBatch`.
-                    trace.clone(),
-                    rate_bits,
-                    false,
-                    cap_height,
-                    timing,
-                    &mut twiddle_map,
-                )
+            .map(|&table| {
+                (ola_stark.is_active(table) && !table.is_compressed())
+                    .then(|| commit(&trace_poly_values[table.index()], timing, &mut twiddle_map))
             })
             .collect::<Vec<_>>()
     );
 
-    #[cfg(feature = "benchmark")]
-    info!("trace_commitments total time: {:?}", start.elapsed());
-
-    let trace_caps = trace_commitments
-        .iter()
-        .map(|c| c.merkle_tree.cap.clone())
-        .collect::<Vec<_>>();
     let mut challenger = Challenger::<F, C::Hasher>::new();
-    for cap in &trace_caps {
-        challenger.observe_cap(cap);
+    challenger.observe_elements(&ola_stark.active_tables.map(F::from_bool));
+    for commitment in trace_commitments.iter().flatten() {
+        challenger.observe_cap(&commitment.merkle_tree.cap);
     }
+    let compress_challenges = get_compress_challenges(&mut challenger);
 
+    for &table in Table::all() {
+        if !ola_stark.is_active(table) || !table.is_compressed() {
+            continue;
+        }
+        let i = table.index();
+        let mut columns = std::mem::take(&mut trace_poly_values[i])
+            .into_iter()
+            .map(|poly| poly.values)
+            .collect::<Vec<_>>();
+        match table {
+            Table::Bitwise => fill_bitwise_compress_cols(&mut columns, compress_challenges[i]),
+            Table::Program => fill_prog_compress_cols(&mut columns, compress_challenges[i]),
+            _ => unreachable!("{:?} has no compress columns", table),
+        }
+        trace_poly_values[i] = columns.into_iter().map(PolynomialValues::new).collect();
+        let commitment = timed!(
+            timing,
+            "compute compressed trace commitments",
+            commit(&trace_poly_values[i], timing, &mut twiddle_map)
+        );
+        challenger.observe_cap(&commitment.merkle_tree.cap);
+        trace_commitments[i] = Some(commitment);
+    }
+    challenger.observe_elements(&public_values.to_field_elements());
+
+    let trace_commitments_ns = start.elapsed().as_nanos();
     #[cfg(feature = "benchmark")]
+    info!("trace_commitments total time: {:?}", start.elapsed());
+
+    check_cancelled(cancel)?;
+
     let start = Instant::now();
 
     let ctl_data_per_table = cross_table_lookup_data::<F, C, D>(
         config,
         &trace_poly_values,
         &ola_stark.cross_table_lookups,
+        &public_values,
         &mut challenger,
     );
 
+    let ctl_data_ns = start.elapsed().as_nanos();
     #[cfg(feature = "benchmark")]
     info!("cross_table_lookup_data total time: {:?}", start.elapsed());
 
-    #[cfg(feature = "benchmark")]
-    let start = Instant::now();
-
-    let cpu_proof = prove_single_table(
-        &ola_stark.cpu_stark,
-        config,
-        &trace_poly_values[Table::Cpu as usize],
-        &trace_commitments[Table::Cpu as usize],
-        &ctl_data_per_table[Table::Cpu as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-
-    #[cfg(feature = "benchmark")]
-    info!("prove_cpu_table total time: {:?}", start.elapsed());
+    let bitwise_stark =
+        BitwiseStark::<F, D>::with_compress_challenge(compress_challenges[Table::Bitwise.index()]);
+    let program_stark =
+        ProgramStark::<F, D>::with_compress_challenge(compress_challenges[Table::Program.index()]);
+
+    // Every table is proven from its own fork of the challenger, so the
+    // tables are independent once the CTL challenges are drawn.
+    let fork = challenger.compact();
+    let prove_table = |table: Table,
+                       timing: &mut TimingTree,
+                       twiddle_map: &mut BTreeMap<usize, Vec<F>>|
+     -> Result<Option<(StarkProof<F, C, D>, TableStats)>> {
+        if !ola_stark.is_active(table) {
+            return Ok(None);
+        }
+        let i = table.index();
+        let trace = &trace_poly_values[i];
+        let commitment = trace_commitments[i].as_ref().unwrap();
+        let ctl_data = &ctl_data_per_table[i];
+        let mut challenger = fork_challenger::<F, C::Hasher>(&fork, table);
+        let mut stats = TableStats::default();
+        let proof = match table {
+            Table::Cpu => prove_single_table(
+                &ola_stark.cpu_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+            Table::Memory => prove_single_table(
+                &ola_stark.memory_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+            Table::Bitwise => prove_single_table(
+                &bitwise_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+            Table::Cmp => prove_single_table(
+                &ola_stark.cmp_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+            Table::RangeCheck => prove_single_table(
+                &ola_stark.rangecheck_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+            Table::Poseidon => prove_single_table(
+                &ola_stark.poseidon_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+            Table::PoseidonChunk => prove_single_table(
+                &ola_stark.poseidon_chunk_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+            Table::StorageAccess => prove_single_table(
+                &ola_stark.storage_access_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+            Table::Tape => prove_single_table(
+                &ola_stark.tape_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+            Table::SCCall => prove_single_table(
+                &ola_stark.sccall_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+            Table::Program => prove_single_table(
+                &program_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+            Table::ProgChunk => prove_single_table(
+                &ola_stark.prog_chunk_stark,
+                config,
+                trace,
+                commitment,
+                ctl_data,
+                &mut challenger,
+                timing,
+                twiddle_map,
+                cancel,
+                &mut stats,
+            ),
+        }?;
+        Ok(Some((proof, stats)))
+    };
 
     #[cfg(feature = "benchmark")]
     let start = Instant::now();
 
-    let memory_proof = prove_single_table(
-        &ola_stark.memory_stark,
-        config,
-        &trace_poly_values[Table::Memory as usize],
-        &trace_commitments[Table::Memory as usize],
-        &ctl_data_per_table[Table::Memory as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-
-    let bitwise_proof = prove_single_table(
-        &ola_stark.bitwise_stark,
-        config,
-        &trace_poly_values[Table::Bitwise as usize],
-        &trace_commitments[Table::Bitwise as usize],
-        &ctl_data_per_table[Table::Bitwise as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let cmp_proof = prove_single_table(
-        &ola_stark.cmp_stark,
-        config,
-        &trace_poly_values[Table::Cmp as usize],
-        &trace_commitments[Table::Cmp as usize],
-        &ctl_data_per_table[Table::Cmp as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let rangecheck_proof = prove_single_table(
-        &ola_stark.rangecheck_stark,
-        config,
-        &trace_poly_values[Table::RangeCheck as usize],
-        &trace_commitments[Table::RangeCheck as usize],
-        &ctl_data_per_table[Table::RangeCheck as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let poseidon_proof = prove_single_table(
-        &ola_stark.poseidon_stark,
-        config,
-        &trace_poly_values[Table::Poseidon as usize],
-        &trace_commitments[Table::Poseidon as usize],
-        &ctl_data_per_table[Table::Poseidon as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let poseidon_chunk_proof = prove_single_table(
-        &ola_stark.poseidon_chunk_stark,
-        config,
-        &trace_poly_values[Table::PoseidonChunk as usize],
-        &trace_commitments[Table::PoseidonChunk as usize],
-        &ctl_data_per_table[Table::PoseidonChunk as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let storage_access_proof = prove_single_table(
-        &ola_stark.storage_access_stark,
-        config,
-        &trace_poly_values[Table::StorageAccess as usize],
-        &trace_commitments[Table::StorageAccess as usize],
-        &ctl_data_per_table[Table::StorageAccess as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let tape_proof = prove_single_table(
-        &ola_stark.tape_stark,
-        config,
-        &trace_poly_values[Table::Tape as usize],
-        &trace_commitments[Table::Tape as usize],
-        &ctl_data_per_table[Table::Tape as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let sccall_proof = prove_single_table(
-        &ola_stark.sccall_stark,
-        config,
-        &trace_poly_values[Table::SCCall as usize],
-        &trace_commitments[Table::SCCall as usize],
-        &ctl_data_per_table[Table::SCCall as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let program_proof = prove_single_table(
-        &ola_stark.program_stark,
-        config,
-        &trace_poly_values[Table::Program as usize],
-        &trace_commitments[Table::Program as usize],
-        &ctl_data_per_table[Table::Program as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
-    let prog_chunk_proof = prove_single_table(
-        &ola_stark.prog_chunk_stark,
-        config,
-        &trace_poly_values[Table::ProgChunk as usize],
-        &trace_commitments[Table::ProgChunk as usize],
-        &ctl_data_per_table[Table::ProgChunk as usize],
-        &mut challenger,
-        timing,
-        &mut twiddle_map,
-    )?;
+    let proofs = if config.parallel_tables {
+        // A thread has its own timing tree and twiddles, only the time of
+        // the whole phase is reported in `timing`. The time of each table
+        // is in its `TableStats`.
+        let prove_table = &prove_table;
+        timed!(
+            timing,
+            "prove tables in parallel",
+            std::thread::scope(|scope| {
+                Table::all()
+                    .iter()
+                    .map(|&table| {
+                        scope.spawn(move || {
+                            prove_table(table, &mut TimingTree::default(), &mut BTreeMap::new())
+                        })
+                    })
+                    .collect::<Vec<_>>()
+                    .into_iter()
+                    .map(|handle| handle.join().unwrap())
+                    .collect::<Result<Vec<_>>>()
+            })
+        )?
+    } else {
+        Table::all()
+            .iter()
+            .map(|&table| prove_table(table, timing, &mut twiddle_map))
+            .collect::<Result<Vec<_>>>()?
+    };
 
     #[cfg(feature = "benchmark")]
-    info!("prove_other_table total time: {:?}", start.elapsed());
-
-    let stark_proofs = [
-        cpu_proof,
-        memory_proof,
-        bitwise_proof,
-        cmp_proof,
-        rangecheck_proof,
-        poseidon_proof,
-        poseidon_chunk_proof,
-        storage_access_proof,
-        tape_proof,
-        sccall_proof,
-        program_proof,
-        prog_chunk_proof,
-    ];
+    info!("prove tables total time: {:?}", start.elapsed());
+
+    let mut proofs = proofs.into_iter();
+    let mut table_stats = [TableStats::default(); NUM_TABLES];
+    let stark_proofs: [Option<StarkProof<F, C, D>>; NUM_TABLES] = std::array::from_fn(|i| {
+        proofs.next().unwrap().map(|(proof, stats)| {
+            table_stats[i] = stats;
+            proof
+        })
+    });
 
-    let compress_challenges = [
-        F::ZERO,
-        F::ZERO,
-        ola_stark.bitwise_stark.get_compress_challenge().unwrap(),
-        F::ZERO,
-        F::ZERO,
-        F::ZERO,
-        F::ZERO,
-        F::ZERO,
-        F::ZERO,
-        F::ZERO,
-        ola_stark.program_stark.get_compress_challenge().unwrap(),
-        F::ZERO,
-    ];
+    if let Some(stats) = stats {
+        stats.trace_commitments_ns = trace_commitments_ns;
+        stats.ctl_data_ns = ctl_data_ns;
+        stats.tables = std::array::from_fn(|i| stark_proofs[i].as_ref().map(|_| table_stats[i]));
+    }
 
     Ok(AllProof {
         stark_proofs,
-        compress_challenges,
         public_values,
     })
 }
@@ -336,6 +712,8 @@ pub(crate) fn prove_single_table<F, C, S, const D: usize>(
     challenger: &mut Challenger<F, C::Hasher>,
     timing: &mut TimingTree,
     twiddle_map: &mut BTreeMap<usize, Vec<F>>,
+    cancel: Option<&AtomicBool>,
+    stats: &mut TableStats,
 ) -> Result<StarkProof<F, C, D>>
 where
     F: RichField + Extendable<D>,
@@ -344,6 +722,9 @@ where
     [(); C::Hasher::HASH_SIZE]:,
     [(); S::COLUMNS]:,
 {
+    check_cancelled(cancel)?;
+    let table_start = Instant::now();
+
     let degree = trace_poly_values[0].len();
     let degree_bits = log2_strict(degree);
     let fri_params = config.fri_params(degree_bits);
@@ -357,6 +738,7 @@ where
     challenger.compact();
 
     // Permutation arguments.
+    let phase_start = Instant::now();
     let permutation_challenges = stark.uses_permutation_args().then(|| {
         get_n_grand_product_challenge_sets(
             challenger,
@@ -421,7 +803,11 @@ where
 
     let permutation_ctl_zs_cap = permutation_ctl_zs_commitment.merkle_tree.cap.clone();
     challenger.observe_cap(&permutation_ctl_zs_cap);
+    stats.permutation_ctl_zs_ns = phase_start.elapsed().as_nanos();
+
+    check_cancelled(cancel)?;
 
+    let phase_start = Instant::now();
     let alphas = challenger.get_n_challenges(config.num_challenges);
     if cfg!(test) {
         check_constraints(
@@ -498,7 +884,9 @@ where
 
     let quotient_polys_cap = quotient_commitment.merkle_tree.cap.clone();
     challenger.observe_cap(&quotient_polys_cap);
+    stats.quotient_ns = phase_start.elapsed().as_nanos();
 
+    let phase_start = Instant::now();
     let zeta = challenger.get_extension_challenge::<D>();
     // To avoid leaking witness data, we want to ensure that our opening locations,
     // `zeta` and `g * zeta`, are not in our subgroup `H`. It suffices to check
@@ -530,6 +918,8 @@ where
 
     challenger.observe_openings(&openings.to_fri_openings());
 
+    check_cancelled(cancel)?;
+
     let initial_merkle_trees = vec![
         trace_commitment,
         &permutation_ctl_zs_commitment,
@@ -556,6 +946,8 @@ where
     if S::COLUMNS == 76 {
         info!("opening_proof total time: {:?}", start.elapsed());
     }
+    stats.openings_ns = phase_start.elapsed().as_nanos();
+    stats.total_ns = table_start.elapsed().as_nanos();
 
     Ok(StarkProof {
         trace_cap: trace_commitment.merkle_tree.cap.clone(),
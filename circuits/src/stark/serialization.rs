@@ -384,10 +384,16 @@ impl Buffer {
     ) -> Result<()> {
         self.write_u32(proof.stark_proofs.len() as u32)?;
         for p in &proof.stark_proofs {
-            self.write_proof(p)?;
+            // 0 marks an inactive table without proof
+            match p {
+                Some(p) => {
+                    self.write_u32(1)?;
+                    self.write_proof(p)?;
+                }
+                None => self.write_u32(0)?,
+            }
         }
 
-        self.write_field_vec(&proof.compress_challenges)?;
         // PublicValues
         Ok(())
     }
@@ -401,12 +407,14 @@ impl Buffer {
         let mut stark_proofs = vec![];
         let len = self.read_u32()? as usize;
         for _ in 0..len {
-            stark_proofs.push(self.read_proof()?);
+            let proof = match self.read_u32()? {
+                0 => None,
+                _ => Some(self.read_proof()?),
+            };
+            stark_proofs.push(proof);
         }
-        let compress_challenges = self.read_field_vec()?;
         Ok(AllProof {
             stark_proofs: stark_proofs.try_into().unwrap(),
-            compress_challenges: compress_challenges.try_into().unwrap(),
             public_values: PublicValues::default(),
         })
     }
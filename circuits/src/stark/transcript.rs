@@ -0,0 +1,258 @@
+use plonky2::field::extension::{Extendable, FieldExtension};
+use plonky2::field::types::{Field, PrimeField64};
+use plonky2::hash::hash_types::RichField;
+use plonky2::hash::merkle_tree::MerkleCap;
+use plonky2::plonk::config::{GenericConfig, GenericHashOut, Hasher};
+use serde::{Deserialize, Serialize};
+
+use super::config::StarkConfig;
+use super::ola_stark::{OlaStark, Table};
+use super::permutation::GrandProductChallengeSet;
+use super::proof::AllProof;
+
+/// One step of a Fiat-Shamir transcript, values as canonical u64s with
+/// extension elements flattened into their base field coefficients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TranscriptEntry {
+    /// Elements absorbed by the challenger.
+    Observe { label: String, elements: Vec<u64> },
+    /// Challenges drawn from it.
+    Challenge { label: String, values: Vec<u64> },
+}
+
+/// The Fiat-Shamir transcript of an `AllProof`, in the order the prover and
+/// the verifier run it, for another implementation to replay and check it
+/// draws the same challenges.
+///
+/// The transcript starts with `active_tables`, 1 for each proven table and
+/// 0 for each left out one. Labels are `table_<i>/<name>` for the steps of
+/// the proof of table `i`.
+/// The challenger is a plonky2 `Challenger` over the proof's hasher. The
+/// steps of each table run on a fresh challenger, which absorbs the sponge
+/// state after `ctl_challenges` and `i` as `table_<i>/fork` and is compacted
+/// before the rest. `fri_pow_response` is the hash of the sponge state with
+/// the witness, not a draw.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    fn observe<F: PrimeField64>(&mut self, label: String, elements: &[F]) {
+        let elements = elements.iter().map(|e| e.to_canonical_u64()).collect();
+        self.entries
+            .push(TranscriptEntry::Observe { label, elements });
+    }
+
+    fn observe_cap<F: RichField, H: Hasher<F>>(&mut self, label: String, cap: &MerkleCap<F, H>) {
+        let elements = cap
+            .0
+            .iter()
+            .flat_map(|hash| hash.to_vec())
+            .collect::<Vec<_>>();
+        self.observe(label, &elements);
+    }
+
+    fn observe_ext<F: RichField + Extendable<D>, const D: usize>(
+        &mut self,
+        label: String,
+        elements: &[F::Extension],
+    ) {
+        let elements = elements
+            .iter()
+            .flat_map(|e| e.to_basefield_array())
+            .collect::<Vec<F>>();
+        self.observe(label, &elements);
+    }
+
+    fn challenge<F: PrimeField64>(&mut self, label: String, values: &[F]) {
+        let values = values.iter().map(|v| v.to_canonical_u64()).collect();
+        self.entries
+            .push(TranscriptEntry::Challenge { label, values });
+    }
+
+    fn challenge_ext<F: RichField + Extendable<D>, const D: usize>(
+        &mut self,
+        label: String,
+        values: &[F::Extension],
+    ) {
+        let values = values
+            .iter()
+            .flat_map(|v| v.to_basefield_array())
+            .collect::<Vec<F>>();
+        self.challenge(label, &values);
+    }
+
+    fn challenge_sets<F: RichField>(
+        &mut self,
+        label: String,
+        sets: &[GrandProductChallengeSet<F>],
+    ) {
+        let values = sets
+            .iter()
+            .flat_map(|set| &set.challenges)
+            .flat_map(|challenge| [challenge.beta, challenge.gamma])
+            .collect::<Vec<_>>();
+        self.challenge(label, &values);
+    }
+}
+
+impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize> AllProof<F, C, D> {
+    /// Replay the transcript of the proof as `get_challenges` does.
+    pub fn transcript(&self, ola_stark: &OlaStark<F, D>, config: &StarkConfig) -> Transcript {
+        let challenges = self.get_challenges(ola_stark, config);
+        let mut transcript = Transcript::default();
+        transcript.observe("active_tables".to_string(), &self.active_table_mask());
+        // The compressed tables are committed after their compress
+        // challenges are drawn.
+        let observe_caps = |transcript: &mut Transcript, compressed: bool| {
+            for (i, proof) in self.stark_proofs.iter().enumerate() {
+                match proof {
+                    Some(proof) if Table::all()[i].is_compressed() == compressed => {
+                        transcript.observe_cap(format!("table_{}/trace_cap", i), &proof.trace_cap)
+                    }
+                    _ => {}
+                }
+            }
+        };
+        observe_caps(&mut transcript, false);
+        let compress_challenges = Table::all()
+            .iter()
+            .filter(|table| table.is_compressed())
+            .map(|table| challenges.compress_challenges[table.index()])
+            .collect::<Vec<_>>();
+        transcript.challenge("compress_challenges".to_string(), &compress_challenges);
+        observe_caps(&mut transcript, true);
+        transcript.observe(
+            "public_values".to_string(),
+            &self.public_values.to_field_elements::<F>(),
+        );
+        transcript.challenge_sets("ctl_challenges".to_string(), &[challenges.ctl_challenges]);
+        let fork = challenges.challenger_fork;
+
+        for (i, (proof, challenges)) in self
+            .stark_proofs
+            .iter()
+            .zip(challenges.stark_challenges)
+            .enumerate()
+        {
+            let (proof, challenges) = match (proof, challenges) {
+                (Some(proof), Some(challenges)) => (proof, challenges),
+                _ => continue,
+            };
+            let label = |name: &str| format!("table_{}/{}", i, name);
+            let mut elements = fork.to_vec();
+            elements.push(F::from_canonical_usize(i));
+            transcript.observe(label("fork"), &elements);
+            if let Some(sets) = &challenges.permutation_challenge_sets {
+                transcript.challenge_sets(label("permutation_challenges"), sets);
+            }
+            transcript.observe_cap(
+                label("permutation_ctl_zs_cap"),
+                &proof.permutation_ctl_zs_cap,
+            );
+            transcript.challenge(label("stark_alphas"), &challenges.stark_alphas);
+            transcript.observe_cap(label("quotient_polys_cap"), &proof.quotient_polys_cap);
+            transcript.challenge_ext::<F, D>(label("stark_zeta"), &[challenges.stark_zeta]);
+            for (k, batch) in proof.openings.to_fri_openings().batches.iter().enumerate() {
+                transcript.observe_ext::<F, D>(label(&format!("openings_{}", k)), &batch.values);
+            }
+
+            let fri = &challenges.fri_challenges;
+            let fri_proof = &proof.opening_proof;
+            transcript.challenge_ext::<F, D>(label("fri_alpha"), &[fri.fri_alpha]);
+            for (k, (cap, beta)) in fri_proof
+                .commit_phase_merkle_caps
+                .iter()
+                .zip(&fri.fri_betas)
+                .enumerate()
+            {
+                transcript.observe_cap(label(&format!("fri_commit_cap_{}", k)), cap);
+                transcript.challenge_ext::<F, D>(label(&format!("fri_beta_{}", k)), &[*beta]);
+            }
+            transcript.observe_ext::<F, D>(label("fri_final_poly"), &fri_proof.final_poly.coeffs);
+            transcript.observe(label("fri_pow_witness"), &[fri_proof.pow_witness]);
+            transcript.challenge(label("fri_pow_response"), &[fri.fri_pow_response]);
+            let indices = fri
+                .fri_query_indices
+                .iter()
+                .map(|&index| F::from_canonical_usize(index))
+                .collect::<Vec<_>>();
+            transcript.challenge(label("fri_query_indices"), &indices);
+        }
+        transcript
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use plonky2::plonk::config::{GenericConfig, PoseidonGoldilocksConfig};
+    use plonky2::util::timing::TimingTree;
+
+    use super::{Transcript, TranscriptEntry};
+    use crate::generation::{generate_traces, GenerationInputs};
+    use crate::stark::config::StarkConfig;
+    use crate::stark::ola_stark::{OlaStark, Table};
+    use crate::stark::prover::prove_with_transcript;
+    use crate::test_utils::execute_asm_path;
+
+    const D: usize = 2;
+    type C = PoseidonGoldilocksConfig;
+    type F = <C as GenericConfig<D>>::F;
+
+    #[test]
+    fn test_transcript_deterministic() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("../assembler/test_data/asm/memory.json");
+        let config = StarkConfig::standard_fast_config();
+        // The prover draws no randomness of its own, the same traces always
+        // give the same transcript.
+        let transcripts = (0..2)
+            .map(|_| {
+                let program = execute_asm_path(path.display().to_string(), None, None);
+                let mut ola_stark = OlaStark::builder()
+                    .disable(Table::Bitwise)
+                    .disable(Table::Cmp)
+                    .build();
+                let (traces, public_values) =
+                    generate_traces(program, &mut ola_stark, GenerationInputs::default()).unwrap();
+                prove_with_transcript::<F, C, D>(
+                    &ola_stark,
+                    &config,
+                    traces,
+                    public_values,
+                    &mut TimingTree::default(),
+                )
+                .unwrap()
+                .1
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(transcripts[0], transcripts[1]);
+
+        let json = serde_json::to_string(&transcripts[0]).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Transcript>(&json).unwrap(),
+            transcripts[0]
+        );
+        let labels = transcripts[0]
+            .entries
+            .iter()
+            .map(|entry| match entry {
+                TranscriptEntry::Observe { label, .. }
+                | TranscriptEntry::Challenge { label, .. } => label.as_str(),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            labels[..4],
+            [
+                "table_0/trace_cap",
+                "table_1/trace_cap",
+                "public_values",
+                "ctl_challenges"
+            ]
+        );
+        assert_eq!(labels.last(), Some(&"table_1/fri_query_indices"));
+    }
+}
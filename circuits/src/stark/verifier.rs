@@ -1,3 +1,4 @@
+use core::types::merkle_tree::TREE_VALUE_LEN;
 use std::any::type_name;
 
 use anyhow::{ensure, Result};
@@ -10,7 +11,9 @@ use plonky2::plonk::plonk_common::reduce_with_powers;
 
 use super::config::StarkConfig;
 use super::constraint_consumer::ConstraintConsumer;
-use super::cross_table_lookup::{verify_cross_table_lookups, CtlCheckVars};
+use super::cross_table_lookup::{
+    public_looking_products, verify_cross_table_lookups, CtlCheckVars,
+};
 use super::ola_stark::{OlaStark, Table, NUM_TABLES};
 use super::permutation::{GrandProductChallenge, PermutationCheckVars};
 use super::proof::{
@@ -32,6 +35,22 @@ use crate::memory::memory_stark::MemoryStark;
 use crate::program::prog_chunk_stark::ProgChunkStark;
 use crate::program::program_stark::ProgramStark;
 
+/// Constraints of a table evaluated at the out of domain point `zeta`, as
+/// the verifier computes them to check the quotient openings. For a valid
+/// proof `vanishing[i] == quotient_times_z_h[i]` for every challenge `i`.
+///
+/// The FRI queries only open the committed polynomials, not the constraints,
+/// so `zeta` is the one point where the residuals are known to the verifier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintResiduals<F: RichField + Extendable<D>, const D: usize> {
+    pub table: Table,
+    pub zeta: F::Extension,
+    /// Constraints combined with the challenge alphas, one per challenge.
+    pub vanishing: Vec<F::Extension>,
+    /// Quotient polynomial openings multiplied by `Z_H(zeta)`.
+    pub quotient_times_z_h: Vec<F::Extension>,
+}
+
 pub fn verify_proof<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
     ola_stark: OlaStark<F, D>,
     all_proof: AllProof<F, C, D>,
@@ -52,40 +71,242 @@ where
     [(); ProgramStark::<F, D>::COLUMNS]:,
     [(); ProgChunkStark::<F, D>::COLUMNS]:,
 {
+    verify_proof_impl(ola_stark, all_proof, config, false).map(|_| ())
+}
+
+/// Same as `verify_proof`, also returning the constraint residuals of every
+/// active table for external tools to cross-check.
+pub fn verify_proof_with_residuals<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    ola_stark: OlaStark<F, D>,
+    all_proof: AllProof<F, C, D>,
+    config: &StarkConfig,
+) -> Result<Vec<ConstraintResiduals<F, D>>>
+where
+    [(); C::Hasher::HASH_SIZE]:,
+    [(); CpuStark::<F, D>::COLUMNS]:,
+    [(); MemoryStark::<F, D>::COLUMNS]:,
+    [(); BitwiseStark::<F, D>::COLUMNS]:,
+    [(); CmpStark::<F, D>::COLUMNS]:,
+    [(); RangeCheckStark::<F, D>::COLUMNS]:,
+    [(); PoseidonStark::<F, D>::COLUMNS]:,
+    [(); PoseidonChunkStark::<F, D>::COLUMNS]:,
+    [(); StorageAccessStark::<F, D>::COLUMNS]:,
+    // [(); TapeStark::<F, D>::COLUMNS]:,
+    [(); SCCallStark::<F, D>::COLUMNS]:,
+    [(); ProgramStark::<F, D>::COLUMNS]:,
+    [(); ProgChunkStark::<F, D>::COLUMNS]:,
+{
+    verify_proof_impl(ola_stark, all_proof, config, true)
+}
+
+/// Verify that two runs, e.g. of a program and of its optimized version,
+/// started from the same public inputs and produced the same public outputs:
+/// both proofs must verify, their roots before, block metadata, initial
+/// registers and initial memory must be equal, and so must their roots after,
+/// outputs and status. The memory the runs end with may differ, e.g. in the
+/// stack slots an optimization saves, so it isn't compared.
+///
+/// There is no recursive verifier to fold both into one proof, so the pair
+/// of proofs is the aggregate. The public values are absorbed into each
+/// transcript, so neither side can be changed to make the outputs match.
+pub fn verify_equivalent_runs<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    ola_stark: OlaStark<F, D>,
+    a: AllProof<F, C, D>,
+    b: AllProof<F, C, D>,
+    config: &StarkConfig,
+) -> Result<()>
+where
+    [(); C::Hasher::HASH_SIZE]:,
+    [(); CpuStark::<F, D>::COLUMNS]:,
+    [(); MemoryStark::<F, D>::COLUMNS]:,
+    [(); BitwiseStark::<F, D>::COLUMNS]:,
+    [(); CmpStark::<F, D>::COLUMNS]:,
+    [(); RangeCheckStark::<F, D>::COLUMNS]:,
+    [(); PoseidonStark::<F, D>::COLUMNS]:,
+    [(); PoseidonChunkStark::<F, D>::COLUMNS]:,
+    [(); StorageAccessStark::<F, D>::COLUMNS]:,
+    // [(); TapeStark::<F, D>::COLUMNS]:,
+    [(); SCCallStark::<F, D>::COLUMNS]:,
+    [(); ProgramStark::<F, D>::COLUMNS]:,
+    [(); ProgChunkStark::<F, D>::COLUMNS]:,
+{
+    let (inputs_a, inputs_b) = (&a.public_values, &b.public_values);
+    ensure!(
+        inputs_a.trie_roots_before == inputs_b.trie_roots_before
+            && inputs_a.block_metadata == inputs_b.block_metadata
+            && inputs_a.initial_registers == inputs_b.initial_registers
+            && inputs_a.initial_memory_root == inputs_b.initial_memory_root,
+        "runs start from different public inputs"
+    );
+    ensure!(
+        inputs_a.trie_roots_after == inputs_b.trie_roots_after,
+        "runs end with different roots: {:?} and {:?}",
+        inputs_a.trie_roots_after,
+        inputs_b.trie_roots_after
+    );
+    ensure!(
+        inputs_a.outputs == inputs_b.outputs,
+        "runs output different words: {:?} and {:?}",
+        inputs_a.outputs,
+        inputs_b.outputs
+    );
+    ensure!(
+        inputs_a.reverted == inputs_b.reverted,
+        "only one of the runs reverted"
+    );
+    verify_proof(ola_stark.clone(), a, config)?;
+    verify_proof(ola_stark, b, config)
+}
+
+/// Verify `all_proof` is a run outputting `outputs`, in this order. The
+/// outputs are part of the public values the transcript absorbed, and a CTL
+/// binds them to the `tstore` rows of the tape table they were taken from.
+pub fn verify_outputs<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    ola_stark: OlaStark<F, D>,
+    all_proof: AllProof<F, C, D>,
+    config: &StarkConfig,
+    outputs: &[u64],
+) -> Result<()>
+where
+    [(); C::Hasher::HASH_SIZE]:,
+    [(); CpuStark::<F, D>::COLUMNS]:,
+    [(); MemoryStark::<F, D>::COLUMNS]:,
+    [(); BitwiseStark::<F, D>::COLUMNS]:,
+    [(); CmpStark::<F, D>::COLUMNS]:,
+    [(); RangeCheckStark::<F, D>::COLUMNS]:,
+    [(); PoseidonStark::<F, D>::COLUMNS]:,
+    [(); PoseidonChunkStark::<F, D>::COLUMNS]:,
+    [(); StorageAccessStark::<F, D>::COLUMNS]:,
+    // [(); TapeStark::<F, D>::COLUMNS]:,
+    [(); SCCallStark::<F, D>::COLUMNS]:,
+    [(); ProgramStark::<F, D>::COLUMNS]:,
+    [(); ProgChunkStark::<F, D>::COLUMNS]:,
+{
+    ensure!(
+        all_proof.public_values.outputs == outputs,
+        "run outputs {:?}, not {:?}",
+        all_proof.public_values.outputs,
+        outputs
+    );
+    verify_proof(ola_stark, all_proof, config)
+}
+
+/// Verify `all_proof` is a run taking the stack memory committed to by
+/// `initial_root` to the one committed to by `final_root`. The roots are
+/// part of the public values, checked against the memory words a CTL binds
+/// to the init rows and last accesses of the memory table.
+pub fn verify_memory_transition<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    const D: usize,
+>(
+    ola_stark: OlaStark<F, D>,
+    all_proof: AllProof<F, C, D>,
+    config: &StarkConfig,
+    initial_root: [u64; TREE_VALUE_LEN],
+    final_root: [u64; TREE_VALUE_LEN],
+) -> Result<()>
+where
+    [(); C::Hasher::HASH_SIZE]:,
+    [(); CpuStark::<F, D>::COLUMNS]:,
+    [(); MemoryStark::<F, D>::COLUMNS]:,
+    [(); BitwiseStark::<F, D>::COLUMNS]:,
+    [(); CmpStark::<F, D>::COLUMNS]:,
+    [(); RangeCheckStark::<F, D>::COLUMNS]:,
+    [(); PoseidonStark::<F, D>::COLUMNS]:,
+    [(); PoseidonChunkStark::<F, D>::COLUMNS]:,
+    [(); StorageAccessStark::<F, D>::COLUMNS]:,
+    // [(); TapeStark::<F, D>::COLUMNS]:,
+    [(); SCCallStark::<F, D>::COLUMNS]:,
+    [(); ProgramStark::<F, D>::COLUMNS]:,
+    [(); ProgChunkStark::<F, D>::COLUMNS]:,
+{
+    let public_values = &all_proof.public_values;
+    ensure!(
+        public_values.initial_memory_root == initial_root,
+        "run starts from memory {:?}, not {:?}",
+        public_values.initial_memory_root,
+        initial_root
+    );
+    ensure!(
+        public_values.final_memory_root == final_root,
+        "run ends with memory {:?}, not {:?}",
+        public_values.final_memory_root,
+        final_root
+    );
+    verify_proof(ola_stark, all_proof, config)
+}
+
+fn verify_proof_impl<F: RichField + Extendable<D>, C: GenericConfig<D, F = F>, const D: usize>(
+    ola_stark: OlaStark<F, D>,
+    all_proof: AllProof<F, C, D>,
+    config: &StarkConfig,
+    debug_residuals: bool,
+) -> Result<Vec<ConstraintResiduals<F, D>>>
+where
+    [(); C::Hasher::HASH_SIZE]:,
+    [(); CpuStark::<F, D>::COLUMNS]:,
+    [(); MemoryStark::<F, D>::COLUMNS]:,
+    [(); BitwiseStark::<F, D>::COLUMNS]:,
+    [(); CmpStark::<F, D>::COLUMNS]:,
+    [(); RangeCheckStark::<F, D>::COLUMNS]:,
+    [(); PoseidonStark::<F, D>::COLUMNS]:,
+    [(); PoseidonChunkStark::<F, D>::COLUMNS]:,
+    [(); StorageAccessStark::<F, D>::COLUMNS]:,
+    // [(); TapeStark::<F, D>::COLUMNS]:,
+    [(); SCCallStark::<F, D>::COLUMNS]:,
+    [(); ProgramStark::<F, D>::COLUMNS]:,
+    [(); ProgChunkStark::<F, D>::COLUMNS]:,
+{
+    all_proof.public_values.check_memory_roots()?;
     let AllProofChallenges {
         stark_challenges,
         ctl_challenges,
+        compress_challenges,
+        ..
     } = all_proof.get_challenges(&ola_stark, config);
 
     let nums_permutation_zs = ola_stark.nums_permutation_zs(config);
 
     let OlaStark {
-        cpu_stark,
+        mut cpu_stark,
         memory_stark,
-        mut bitwise_stark,
+        bitwise_stark: _,
         cmp_stark,
         rangecheck_stark,
         poseidon_stark,
         poseidon_chunk_stark,
-        storage_access_stark,
+        mut storage_access_stark,
         tape_stark,
         sccall_stark,
-        mut program_stark,
+        program_stark: _,
         prog_chunk_stark,
         cross_table_lookups,
+        active_tables,
     } = ola_stark;
 
-    if bitwise_stark.get_compress_challenge().is_none() {
-        bitwise_stark
-            .set_compress_challenge(all_proof.compress_challenges[Table::Bitwise as usize])
-            .unwrap();
-    }
-    if program_stark.get_compress_challenge().is_none() {
-        program_stark
-            .set_compress_challenge(all_proof.compress_challenges[Table::Program as usize])
-            .unwrap();
-    }
-
+    // Bind the first row of the cpu table to the public initial registers
+    // and its last executed row to the public status, which also decides
+    // whether the storage writes are undone.
+    cpu_stark.initial_registers = all_proof.public_values.initial_registers;
+    cpu_stark.reverted = all_proof.public_values.reverted;
+    storage_access_stark.reverted = all_proof.public_values.reverted;
+    // The compress challenges are drawn from the transcript, whatever the
+    // starks were set up with.
+    let bitwise_stark =
+        BitwiseStark::<F, D>::with_compress_challenge(compress_challenges[Table::Bitwise.index()]);
+    let program_stark =
+        ProgramStark::<F, D>::with_compress_challenge(compress_challenges[Table::Program.index()]);
+
+    let mut residuals = Vec::new();
     let ctl_vars_per_table = CtlCheckVars::from_proofs(
         &all_proof.stark_proofs,
         &cross_table_lookups,
@@ -93,116 +314,201 @@ where
         &nums_permutation_zs,
     );
 
-    verify_stark_proof_with_challenges(
+    residuals.extend(verify_table_proof(
         cpu_stark,
-        &all_proof.stark_proofs[Table::Cpu as usize],
-        &stark_challenges[Table::Cpu as usize],
-        &ctl_vars_per_table[Table::Cpu as usize],
+        Table::Cpu,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
-    verify_stark_proof_with_challenges(
+        debug_residuals,
+    )?);
+    residuals.extend(verify_table_proof(
         memory_stark,
-        &all_proof.stark_proofs[Table::Memory as usize],
-        &stark_challenges[Table::Memory as usize],
-        &ctl_vars_per_table[Table::Memory as usize],
+        Table::Memory,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
-    verify_stark_proof_with_challenges(
+        debug_residuals,
+    )?);
+    residuals.extend(verify_table_proof(
         bitwise_stark,
-        &all_proof.stark_proofs[Table::Bitwise as usize],
-        &stark_challenges[Table::Bitwise as usize],
-        &ctl_vars_per_table[Table::Bitwise as usize],
+        Table::Bitwise,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
+        debug_residuals,
+    )?);
 
-    verify_stark_proof_with_challenges(
+    residuals.extend(verify_table_proof(
         cmp_stark,
-        &all_proof.stark_proofs[Table::Cmp as usize],
-        &stark_challenges[Table::Cmp as usize],
-        &ctl_vars_per_table[Table::Cmp as usize],
+        Table::Cmp,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
+        debug_residuals,
+    )?);
 
-    verify_stark_proof_with_challenges(
+    residuals.extend(verify_table_proof(
         rangecheck_stark,
-        &all_proof.stark_proofs[Table::RangeCheck as usize],
-        &stark_challenges[Table::RangeCheck as usize],
-        &ctl_vars_per_table[Table::RangeCheck as usize],
+        Table::RangeCheck,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
+        debug_residuals,
+    )?);
 
-    verify_stark_proof_with_challenges(
+    residuals.extend(verify_table_proof(
         poseidon_stark,
-        &all_proof.stark_proofs[Table::Poseidon as usize],
-        &stark_challenges[Table::Poseidon as usize],
-        &ctl_vars_per_table[Table::Poseidon as usize],
+        Table::Poseidon,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
+        debug_residuals,
+    )?);
 
-    verify_stark_proof_with_challenges(
+    residuals.extend(verify_table_proof(
         poseidon_chunk_stark,
-        &all_proof.stark_proofs[Table::PoseidonChunk as usize],
-        &stark_challenges[Table::PoseidonChunk as usize],
-        &ctl_vars_per_table[Table::PoseidonChunk as usize],
+        Table::PoseidonChunk,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
+        debug_residuals,
+    )?);
 
-    verify_stark_proof_with_challenges(
+    residuals.extend(verify_table_proof(
         storage_access_stark,
-        &all_proof.stark_proofs[Table::StorageAccess as usize],
-        &stark_challenges[Table::StorageAccess as usize],
-        &ctl_vars_per_table[Table::StorageAccess as usize],
+        Table::StorageAccess,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
+        debug_residuals,
+    )?);
 
-    verify_stark_proof_with_challenges(
+    residuals.extend(verify_table_proof(
         tape_stark,
-        &all_proof.stark_proofs[Table::Tape as usize],
-        &stark_challenges[Table::Tape as usize],
-        &ctl_vars_per_table[Table::Tape as usize],
+        Table::Tape,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
+        debug_residuals,
+    )?);
 
-    verify_stark_proof_with_challenges(
+    residuals.extend(verify_table_proof(
         sccall_stark,
-        &all_proof.stark_proofs[Table::SCCall as usize],
-        &stark_challenges[Table::SCCall as usize],
-        &ctl_vars_per_table[Table::SCCall as usize],
+        Table::SCCall,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
+        debug_residuals,
+    )?);
 
-    verify_stark_proof_with_challenges(
+    residuals.extend(verify_table_proof(
         program_stark,
-        &all_proof.stark_proofs[Table::Program as usize],
-        &stark_challenges[Table::Program as usize],
-        &ctl_vars_per_table[Table::Program as usize],
+        Table::Program,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
+        debug_residuals,
+    )?);
 
-    verify_stark_proof_with_challenges(
+    residuals.extend(verify_table_proof(
         prog_chunk_stark,
-        &all_proof.stark_proofs[Table::ProgChunk as usize],
-        &stark_challenges[Table::ProgChunk as usize],
-        &ctl_vars_per_table[Table::ProgChunk as usize],
+        Table::ProgChunk,
+        &active_tables,
+        &all_proof.stark_proofs,
+        &stark_challenges,
+        &ctl_vars_per_table,
         config,
-    )?;
+        debug_residuals,
+    )?);
 
-    // TODO:
-    // let public_values = all_proof.public_values;
-    let extra_looking_products = vec![vec![F::ONE; config.num_challenges]; NUM_TABLES];
-    // extra_looking_products.push(Vec::new());
-    // for c in 0..config.num_challenges {
-    //     extra_looking_products[Table::StorageAccess as usize].push(
-    //         get_storagehash_extra_looking_products(&public_values,
-    // ctl_challenges.challenges[c]),     );
-    // }
+    let extra_looking_products = public_looking_products(
+        &cross_table_lookups,
+        &all_proof.public_values,
+        &ctl_challenges,
+    );
 
     verify_cross_table_lookups::<F, C, D>(
         cross_table_lookups,
-        all_proof.stark_proofs.map(|p| p.openings.ctl_zs_last),
+        all_proof
+            .stark_proofs
+            .map(|p| p.map_or(vec![], |p| p.openings.ctl_zs_last)),
         extra_looking_products,
         config,
-    )
+    )?;
+    Ok(residuals)
+}
+
+/// Verify the proof of `table`, which must be present exactly when the table
+/// is active.
+fn verify_table_proof<
+    F: RichField + Extendable<D>,
+    C: GenericConfig<D, F = F>,
+    S: Stark<F, D>,
+    const D: usize,
+>(
+    stark: S,
+    table: Table,
+    active_tables: &[bool; NUM_TABLES],
+    stark_proofs: &[Option<StarkProof<F, C, D>>; NUM_TABLES],
+    stark_challenges: &[Option<StarkProofChallenges<F, D>>; NUM_TABLES],
+    ctl_vars_per_table: &[Vec<CtlCheckVars<F, F::Extension, F::Extension, D>>; NUM_TABLES],
+    config: &StarkConfig,
+    debug_residuals: bool,
+) -> Result<Option<ConstraintResiduals<F, D>>>
+where
+    [(); S::COLUMNS]:,
+    [(); C::Hasher::HASH_SIZE]:,
+{
+    let i = table.index();
+    match (&stark_proofs[i], &stark_challenges[i]) {
+        (Some(proof), Some(challenges)) => {
+            ensure!(
+                active_tables[i],
+                "Unexpected proof for inactive {:?} table.",
+                table
+            );
+            let (vanishing, quotient_times_z_h) = verify_stark_proof_with_challenges(
+                stark,
+                proof,
+                challenges,
+                &ctl_vars_per_table[i],
+                config,
+            )?;
+            Ok(debug_residuals.then(|| ConstraintResiduals {
+                table,
+                zeta: challenges.stark_zeta,
+                vanishing,
+                quotient_times_z_h,
+            }))
+        }
+        _ => {
+            ensure!(!active_tables[i], "Missing proof for {:?} table.", table);
+            Ok(None)
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -228,7 +534,7 @@ pub(crate) fn verify_stark_proof_with_challenges<
     challenges: &StarkProofChallenges<F, D>,
     ctl_vars: &[CtlCheckVars<F, F::Extension, F::Extension, D>],
     config: &StarkConfig,
-) -> Result<()>
+) -> Result<(Vec<F::Extension>, Vec<F::Extension>)>
 where
     [(); S::COLUMNS]:,
     [(); C::Hasher::HASH_SIZE]:,
@@ -288,12 +594,13 @@ where
     // So to reconstruct `t(zeta)` we can compute `reduce_with_powers(chunk,
     // zeta^n)` for each `quotient_degree_factor`-sized chunk of the original
     // evaluations.
-    for (i, chunk) in quotient_polys
+    let quotient_times_z_h = quotient_polys
         .chunks(stark.quotient_degree_factor())
-        .enumerate()
-    {
+        .map(|chunk| z_h_zeta * reduce_with_powers(chunk, zeta_pow_deg))
+        .collect::<Vec<_>>();
+    for (vanishing, quotient) in vanishing_polys_zeta.iter().zip(&quotient_times_z_h) {
         ensure!(
-            vanishing_polys_zeta[i] == z_h_zeta * reduce_with_powers(chunk, zeta_pow_deg),
+            vanishing == quotient,
             "Mismatch between evaluation and opening of quotient polynomial in {}",
             type_name::<S>()
         );
@@ -320,7 +627,7 @@ where
         &config.fri_params(degree_bits),
     )?;
 
-    Ok(())
+    Ok((vanishing_polys_zeta, quotient_times_z_h))
 }
 
 fn validate_proof_shape<F, C, S, const D: usize>(
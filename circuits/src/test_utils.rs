@@ -1,21 +1,23 @@
-use core::crypto::hash::Hasher;
-use core::crypto::ZkHasher;
-use core::merkle_tree::log::{StorageLog, StorageLogKind, WitnessStorageLog};
-use core::types::merkle_tree::{encode_addr, tree_key_default};
-use core::{program::Program, trace::trace::Trace, types::account::Address};
-use std::collections::HashMap;
+use core::{
+    program::Program,
+    trace::trace::{Step, Trace},
+};
 use std::path::PathBuf;
 
+use anyhow::{ensure, Result};
 use assembler::encoder::encode_asm_from_json_file;
-use executor::trace::{gen_storage_hash_table, gen_storage_table};
-use executor::TxScopeCacheManager;
-use executor::{load_tx::init_tape, Process};
+use executor::run::execute_program;
+use executor::Process;
+use itertools::Itertools;
+use plonky2::field::polynomial::PolynomialValues;
 use plonky2::field::{goldilocks_field::GoldilocksField, types::Field};
 use plonky2_util::log2_strict;
 
+use crate::stark::cross_table_lookup::CrossTableLookup;
+use crate::stark::ola_stark::NUM_TABLES;
 use crate::stark::{constraint_consumer::ConstraintConsumer, vars::StarkEvaluationVars};
 use core::merkle_tree::tree::AccountTree;
-use core::vm::transaction::init_tx_context_mock;
+use core::vm::error::ProcessorError;
 
 pub fn test_stark_with_asm_path<Row, const COL_NUM: usize, E, H>(
     path: String,
@@ -32,108 +34,15 @@ pub fn test_stark_with_asm_path<Row, const COL_NUM: usize, E, H>(
     ) -> (),
     H: Fn(usize, StarkEvaluationVars<GoldilocksField, GoldilocksField, COL_NUM>) -> (),
 {
-    let mut db = match db_name {
-        Some(name) => {
-            let mut db_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-            db_path.push("../executor/db_test/");
-            db_path.push(name);
-            AccountTree::new_db_test(db_path.display().to_string())
-        }
-        _ => AccountTree::new_test(),
-    };
-
+    let mut db = test_account_tree(db_name);
     let program = encode_asm_from_json_file(path).unwrap();
-    let hash = ZkHasher::default();
-    let instructions = program.bytecode.split("\n");
-    let code: Vec<_> = instructions
-        .clone()
-        .map(|e| GoldilocksField::from_canonical_u64(u64::from_str_radix(&e[2..], 16).unwrap()))
-        .collect();
-    let code_hash = hash.hash_bytes(&code);
-    let mut prophets = HashMap::new();
-    for item in program.prophets {
-        prophets.insert(item.host as u64, item);
-    }
-
-    let mut program: Program = Program::default();
-
-    for inst in instructions {
-        program.instructions.push(inst.to_string());
-    }
-
-    let mut process = Process::new();
-    process.addr_storage = Address::default();
-
-    let tp_start = 0;
-
-    let callee: Address = [
-        GoldilocksField::from_canonical_u64(9),
-        GoldilocksField::from_canonical_u64(10),
-        GoldilocksField::from_canonical_u64(11),
-        GoldilocksField::from_canonical_u64(12),
-    ];
-    let caller_addr = [
-        GoldilocksField::from_canonical_u64(17),
-        GoldilocksField::from_canonical_u64(18),
-        GoldilocksField::from_canonical_u64(19),
-        GoldilocksField::from_canonical_u64(20),
-    ];
-    let callee_exe_addr = [
-        GoldilocksField::from_canonical_u64(13),
-        GoldilocksField::from_canonical_u64(14),
-        GoldilocksField::from_canonical_u64(15),
-        GoldilocksField::from_canonical_u64(16),
-    ];
-
-    if let Some(calldata) = call_data {
-        process.tp = GoldilocksField::from_canonical_u64(tp_start as u64);
-
-        init_tape(
-            &mut process,
-            calldata,
-            caller_addr,
-            callee,
-            callee_exe_addr,
-            &init_tx_context_mock(),
-        );
-    }
-
-    process.addr_code = callee_exe_addr;
-    process.addr_storage = callee;
-    program
-        .trace
-        .addr_program_hash
-        .insert(encode_addr(&callee_exe_addr), code);
-
-    db.process_block(vec![WitnessStorageLog {
-        storage_log: StorageLog::new_write(
-            StorageLogKind::RepeatedWrite,
-            callee_exe_addr,
-            code_hash,
-        ),
-        previous_value: tree_key_default(),
-    }]);
-    let _ = db.save();
-
-    let start = db.root_hash();
-
-    process.program_log.push(WitnessStorageLog {
-        storage_log: StorageLog::new_read_log(callee_exe_addr, code_hash),
-        previous_value: tree_key_default(),
-    });
-
-    program.prophets = prophets;
-    let res = process.execute(&mut program, &mut db, &mut TxScopeCacheManager::default());
-    match res {
-        Ok(_) => {}
+    let (_, program) = match execute_program(Process::new(), &program, call_data, &mut db) {
+        Ok(res) => res,
         Err(e) => {
             println!("execute err:{:?}", e);
             return;
         }
-    }
-    let hash_roots = gen_storage_hash_table(&mut process, &mut program, &mut db).unwrap();
-    gen_storage_table(&mut process, &mut program, hash_roots).unwrap();
-    program.trace.start_end_roots = (start, db.root_hash());
+    };
 
     let raw_trace_rows = get_trace_rows(program.trace);
     let rows = generate_trace(&raw_trace_rows);
@@ -209,114 +118,15 @@ pub fn simple_test_stark<const COL_NUM: usize, E, H>(
     ) -> (),
     H: Fn(usize, StarkEvaluationVars<GoldilocksField, GoldilocksField, COL_NUM>) -> (),
 {
-    let mut db = match db_name {
-        Some(name) => {
-            let mut db_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-            db_path.push("../executor/db_test/");
-            db_path.push(name);
-            AccountTree::new_db_test(db_path.display().to_string())
-        }
-        _ => AccountTree::new_test(),
-    };
-
+    let mut db = test_account_tree(db_name);
     let program = encode_asm_from_json_file(path).unwrap();
-    let hash = ZkHasher::default();
-    let instructions = program.bytecode.split("\n");
-    let code: Vec<_> = instructions
-        .clone()
-        .map(|e| GoldilocksField::from_canonical_u64(u64::from_str_radix(&e[2..], 16).unwrap()))
-        .collect();
-    let code_hash = hash.hash_bytes(&code);
-    let mut prophets = HashMap::new();
-    for item in program.prophets {
-        prophets.insert(item.host as u64, item);
-    }
-
-    let mut program: Program = Program {
-        instructions: Vec::new(),
-        trace: Default::default(),
-        debug_info: program.debug_info,
-        prophets,
-        pre_exe_flag: false,
-        print_flag: false,
-    };
-
-    for inst in instructions {
-        program.instructions.push(inst.to_string());
-    }
-
-    let mut process = Process::new();
-    process.addr_storage = Address::default();
-
-    let tp_start = 0;
-
-    let callee: Address = [
-        GoldilocksField::from_canonical_u64(9),
-        GoldilocksField::from_canonical_u64(10),
-        GoldilocksField::from_canonical_u64(11),
-        GoldilocksField::from_canonical_u64(12),
-    ];
-    let caller_addr = [
-        GoldilocksField::from_canonical_u64(17),
-        GoldilocksField::from_canonical_u64(18),
-        GoldilocksField::from_canonical_u64(19),
-        GoldilocksField::from_canonical_u64(20),
-    ];
-    let callee_exe_addr = [
-        GoldilocksField::from_canonical_u64(13),
-        GoldilocksField::from_canonical_u64(14),
-        GoldilocksField::from_canonical_u64(15),
-        GoldilocksField::from_canonical_u64(16),
-    ];
-
-    if let Some(calldata) = call_data {
-        process.tp = GoldilocksField::from_canonical_u64(tp_start as u64);
-
-        init_tape(
-            &mut process,
-            calldata,
-            caller_addr,
-            callee,
-            callee_exe_addr,
-            &init_tx_context_mock(),
-        );
-    }
-
-    process.addr_code = callee_exe_addr;
-    process.addr_storage = callee;
-    program
-        .trace
-        .addr_program_hash
-        .insert(encode_addr(&callee_exe_addr), code);
-
-    db.process_block(vec![WitnessStorageLog {
-        storage_log: StorageLog::new_write(
-            StorageLogKind::RepeatedWrite,
-            callee_exe_addr,
-            code_hash,
-        ),
-        previous_value: tree_key_default(),
-    }]);
-    let _ = db.save();
-
-    let start = db.root_hash();
-
-    process.program_log.push(WitnessStorageLog {
-        storage_log: StorageLog::new_read_log(callee_exe_addr, code_hash),
-        previous_value: tree_key_default(),
-    });
-
-    let res = process.execute(&mut program, &mut db, &mut TxScopeCacheManager::default());
-    match res {
-        Ok(_) => {}
+    let (_, program) = match execute_program(Process::new(), &program, call_data, &mut db) {
+        Ok(res) => res,
         Err(e) => {
             println!("execute err:{:?}", e);
             return;
         }
-    }
-    let hash_roots = gen_storage_hash_table(&mut process, &mut program, &mut db).unwrap();
-    gen_storage_table(&mut process, &mut program, hash_roots).unwrap();
-    program.trace.start_end_roots = (start, db.root_hash());
+    };
 
     let rows = generate_trace(program.trace);
     let len = rows[0].len();
@@ -372,3 +182,88 @@ pub fn simple_test_stark<const COL_NUM: usize, E, H>(
         }
     }
 }
+
+/// Execute the asm program at `path` the same way the stark tests do and
+/// return the program with its trace filled in.
+pub fn execute_asm_path(
+    path: String,
+    call_data: Option<Vec<GoldilocksField>>,
+    db_name: Option<String>,
+) -> Program {
+    execute_asm_path_with_process(path, call_data, db_name).1
+}
+
+/// Like `execute_asm_path`, also returning the process which executed it.
+pub fn execute_asm_path_with_process(
+    path: String,
+    call_data: Option<Vec<GoldilocksField>>,
+    db_name: Option<String>,
+) -> (Process, Program) {
+    run_asm_path(Process::new(), path, call_data, db_name).unwrap()
+}
+
+/// Cpu trace rows of the asm fixture `name` of the assembler test data.
+pub fn asm_fixture_exec(name: &str) -> Vec<Step> {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push(format!("../assembler/test_data/asm/{}.json", name));
+    execute_asm_path(path.display().to_string(), None, None)
+        .trace
+        .exec
+}
+
+/// Execute the asm program at `path` with a `process` set up by the caller.
+pub fn run_asm_path(
+    process: Process,
+    path: String,
+    call_data: Option<Vec<GoldilocksField>>,
+    db_name: Option<String>,
+) -> Result<(Process, Program), ProcessorError> {
+    let mut db = test_account_tree(db_name);
+    let program = encode_asm_from_json_file(path).unwrap();
+    execute_program(process, &program, call_data, &mut db)
+}
+
+/// The account tree of the `db_name` test database, an empty one without.
+fn test_account_tree(db_name: Option<String>) -> AccountTree {
+    match db_name {
+        Some(name) => {
+            let mut db_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            db_path.push("../executor/db_test/");
+            db_path.push(name);
+            AccountTree::new_db_test(db_path.display().to_string())
+        }
+        _ => AccountTree::new_test(),
+    }
+}
+
+/// Indices in `cross_table_lookups` of the CTLs whose filters select no row
+/// of the looking tables or of the looked table in `traces`. Such a CTL
+/// holds whatever the traces are, e.g. a bitwise lookup of a program without
+/// bitwise instructions.
+pub fn vacuous_ctls<F: Field>(
+    traces: &[Vec<PolynomialValues<F>>; NUM_TABLES],
+    cross_table_lookups: &[CrossTableLookup<F>],
+) -> Vec<usize> {
+    cross_table_lookups
+        .iter()
+        .positions(|ctl| ctl.is_vacuous(traces))
+        .collect()
+}
+
+/// Fail if any CTL of `cross_table_lookups` is vacuous on `traces`, see
+/// `vacuous_ctls`, naming the tables of each.
+pub fn check_ctl_filters<F: Field>(
+    traces: &[Vec<PolynomialValues<F>>; NUM_TABLES],
+    cross_table_lookups: &[CrossTableLookup<F>],
+) -> Result<()> {
+    let vacuous = vacuous_ctls(traces, cross_table_lookups);
+    ensure!(
+        vacuous.is_empty(),
+        "vacuous CTL filters: {}",
+        vacuous
+            .iter()
+            .map(|&i| format!("{} {:?}", i, cross_table_lookups[i].tables().collect_vec()))
+            .join(", ")
+    );
+    Ok(())
+}
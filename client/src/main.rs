@@ -107,8 +107,11 @@ fn main() {
                 prophets.insert(item.host as u64, item);
             }
 
-            let mut program: Program = Program::default();
-            program.prophets = prophets;
+            let mut program: Program = Program {
+                prophets,
+                data: program.data,
+                ..Default::default()
+            };
 
             for inst in instructions {
                 program.instructions.push(inst.to_string());
@@ -183,9 +186,13 @@ fn main() {
                 instructions: trace.raw_binary_instructions.clone(),
                 trace,
                 debug_info: None,
+                source_lines: None,
+                host_asserts: None,
+                breakpoints: None,
                 prophets: HashMap::new(),
                 pre_exe_flag: false,
                 print_flag: false,
+                data: vec![],
             };
 
             let inputs = GenerationInputs::default();
@@ -1,6 +1,6 @@
 use enum_iterator::all;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::num::ParseIntError;
 use std::{
     fmt::{Display, Formatter},
@@ -9,7 +9,8 @@ use std::{
 
 use crate::vm::{
     hardware::{OlaRegister, OlaSpecialRegister},
-    opcodes::OlaOpcode,
+    memory::{DATA_START_ADDR, DATA_START_LABEL},
+    opcodes::{OlaOpcode, SUB_U32_TOKEN},
     operands::{ImmediateValue, OlaOperand},
 };
 
@@ -17,8 +18,31 @@ use crate::vm::{
 pub struct BinaryProgram {
     pub bytecode: String,
     pub prophets: Vec<OlaProphet>,
+    /// Read-only data section, see `Program::data`.
+    #[serde(default)]
+    pub data: Vec<u64>,
     #[serde(skip)]
     pub debug_info: Option<BTreeMap<usize, String>>,
+    /// Trailing `;` comments of the asm instructions keyed by pc, only kept
+    /// by the annotating encoder of the assembler.
+    #[serde(skip)]
+    pub source_lines: Option<BTreeMap<usize, String>>,
+    /// `assert_eq` lines keyed by the pc of the instruction following them.
+    #[serde(skip)]
+    pub host_asserts: Option<BTreeMap<usize, Vec<HostAssert>>>,
+    /// Pcs of the `brk` lines, which are encoded as a nop.
+    #[serde(skip)]
+    pub breakpoints: Option<BTreeSet<usize>>,
+}
+
+/// `assert_eq rX value` in asm: register `register` should hold `expected`
+/// when the instruction following the line is about to execute. It is a
+/// check the executor runs for debugging, it isn't encoded and a proof knows
+/// nothing of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostAssert {
+    pub register: usize,
+    pub expected: u64,
 }
 
 impl BinaryProgram {
@@ -47,13 +71,21 @@ impl BinaryProgram {
             Ok(BinaryProgram {
                 bytecode,
                 prophets,
+                data: vec![],
                 debug_info,
+                source_lines: None,
+                host_asserts: None,
+                breakpoints: None,
             })
         } else {
             Ok(BinaryProgram {
                 bytecode,
                 prophets,
+                data: vec![],
                 debug_info: None,
+                source_lines: None,
+                host_asserts: None,
+                breakpoints: None,
             })
         }
     }
@@ -77,10 +109,13 @@ pub struct BinaryInstruction {
     pub op1: Option<OlaOperand>,
     pub dst: Option<OlaOperand>,
     pub prophet: Option<OlaProphet>,
+    /// The u32 flag, only on a `sub`, written `subu`.
+    pub u32_mode: bool,
 }
 
 impl BinaryInstruction {
     pub const BIT_SHIFT_OP1_IMM: usize = 62;
+    pub const BIT_SHIFT_U32: usize = 63;
 
     pub fn binary_length(&self) -> u8 {
         let mut len = 1;
@@ -139,13 +174,23 @@ impl BinaryInstruction {
                 instruction_u64 |= register.binary_bit_mask_as_op1();
                 imm = Some(offset.clone())
             }
-            Some(OlaOperand::SpecialReg { .. }) => {
+            Some(OlaOperand::SpecialReg { special_reg }) => {
                 if self.opcode != OlaOpcode::MOV {
                     return Err(format!(
                         "encode err, special_reg operand only supported for mov: {}",
                         self
                     ));
                 }
+                // `mov rX clk` selects rX as op0 as well, which a mov doesn't
+                // use otherwise.
+                if *special_reg == OlaSpecialRegister::CLK {
+                    match &self.dst {
+                        Some(OlaOperand::RegisterOperand { register }) => {
+                            instruction_u64 |= register.binary_bit_mask_as_op0();
+                        }
+                        _ => return Err(format!("encode err, clk needs a dst register: {}", self)),
+                    }
+                }
             }
             Some(OlaOperand::RegisterWithFactor { register, factor }) => {
                 instruction_u64 |= register.binary_bit_mask_as_op1();
@@ -182,6 +227,12 @@ impl BinaryInstruction {
         if is_op1_imm {
             instruction_u64 |= 1 << Self::BIT_SHIFT_OP1_IMM;
         }
+        if self.u32_mode {
+            if self.opcode != OlaOpcode::SUB {
+                return Err(format!("encode err, only sub has a u32 mode: {}", self));
+            }
+            instruction_u64 |= 1 << Self::BIT_SHIFT_U32;
+        }
         instruction_u64 |= self.opcode.binary_bit_mask();
         let mut codes: Vec<String> = vec![];
         codes.push(format!("0x{:0>16x}", instruction_u64));
@@ -223,6 +274,13 @@ impl BinaryInstruction {
             ))?;
 
         let is_op1_imm = instruction_u64 & (1 << Self::BIT_SHIFT_OP1_IMM) != 0;
+        let u32_mode = instruction_u64 & (1 << Self::BIT_SHIFT_U32) != 0;
+        if u32_mode && opcode != OlaOpcode::SUB {
+            return Err(format!(
+                "decode binary instruction error, only sub has a u32 mode: {}",
+                instruction_binary
+            ));
+        }
         let instruction_length =
             if is_op1_imm || opcode == OlaOpcode::MLOAD || opcode == OlaOpcode::MSTORE {
                 2
@@ -284,15 +342,26 @@ impl BinaryInstruction {
                         register: matched_op1_reg?,
                     })
                 } else if opcode == OlaOpcode::MOV {
-                    Some(OlaOperand::SpecialReg {
-                        special_reg: OlaSpecialRegister::PSP,
-                    })
+                    let special_reg = if op0.is_some() {
+                        OlaSpecialRegister::CLK
+                    } else {
+                        OlaSpecialRegister::PSP
+                    };
+                    Some(OlaOperand::SpecialReg { special_reg })
                 } else {
                     None
                 }
             }
         };
 
+        // The op0 field of `mov rX clk` only marks the clk operand.
+        let op0 = match op1 {
+            Some(OlaOperand::SpecialReg {
+                special_reg: OlaSpecialRegister::CLK,
+            }) => None,
+            _ => op0,
+        };
+
         let dst = all::<OlaRegister>()
             .collect::<Vec<_>>()
             .iter()
@@ -311,24 +380,36 @@ impl BinaryInstruction {
             op1,
             dst,
             prophet,
+            u32_mode,
         })
     }
 
+    /// Mnemonic of the instruction, `subu` for a `sub` with the u32 flag.
+    pub fn token(&self) -> String {
+        if self.u32_mode {
+            SUB_U32_TOKEN.to_string()
+        } else {
+            self.opcode.token()
+        }
+    }
+
     pub fn get_asm_form_code(&self) -> String {
         match self.opcode {
             OlaOpcode::ADD
             | OlaOpcode::MUL
+            | OlaOpcode::SUB
             | OlaOpcode::AND
             | OlaOpcode::OR
             | OlaOpcode::XOR
             | OlaOpcode::EQ
             | OlaOpcode::NEQ
             | OlaOpcode::GTE
+            | OlaOpcode::CAS
             | OlaOpcode::TLOAD
             | OlaOpcode::POSEIDON => {
                 format!(
                     "{} {} {} {}",
-                    self.opcode.token(),
+                    self.token(),
                     self.dst.clone().unwrap().get_asm_token(),
                     self.op0.clone().unwrap().get_asm_token(),
                     self.op1.clone().unwrap().get_asm_token()
@@ -337,6 +418,7 @@ impl BinaryInstruction {
 
             OlaOpcode::MOV
             | OlaOpcode::NOT
+            | OlaOpcode::ISQRT
             | OlaOpcode::MLOAD
             | OlaOpcode::TSTORE
             | OlaOpcode::SIGCHECK => {
@@ -348,6 +430,16 @@ impl BinaryInstruction {
                 )
             }
 
+            OlaOpcode::LOADP => {
+                format!(
+                    "{} {} {} {}",
+                    self.opcode.token(),
+                    self.dst.clone().unwrap().get_asm_token(),
+                    self.op1.clone().unwrap().get_asm_token(),
+                    self.op0.clone().unwrap().get_asm_token()
+                )
+            }
+
             OlaOpcode::MSTORE => {
                 format!(
                     "{} {} {}",
@@ -357,7 +449,11 @@ impl BinaryInstruction {
                 )
             }
 
-            OlaOpcode::CJMP | OlaOpcode::SCCALL | OlaOpcode::SLOAD | OlaOpcode::SSTORE => {
+            OlaOpcode::CJMP
+            | OlaOpcode::SCCALL
+            | OlaOpcode::SLOAD
+            | OlaOpcode::SSTORE
+            | OlaOpcode::SWAP => {
                 format!(
                     "{} {} {}",
                     self.opcode.token(),
@@ -366,7 +462,11 @@ impl BinaryInstruction {
                 )
             }
 
-            OlaOpcode::JMP | OlaOpcode::CALL | OlaOpcode::RC | OlaOpcode::ASSERT => {
+            OlaOpcode::JMP
+            | OlaOpcode::CALL
+            | OlaOpcode::RC
+            | OlaOpcode::ASSERT
+            | OlaOpcode::REQUIRE => {
                 format!(
                     "{} {}",
                     self.opcode.token(),
@@ -379,6 +479,27 @@ impl BinaryInstruction {
             }
         }
     }
+
+    /// Same as `get_asm_form_code`, with the instructions the assembler
+    /// expands pseudo-instructions to shown as `nop`, `clr rX`, `inc rX` and
+    /// `lea rX data_start`. A `lea` of a code label is a plain `mov` of its
+    /// address, the label name is not in the binary.
+    pub fn get_pseudo_asm_form_code(&self) -> String {
+        let asm = self.get_asm_form_code();
+        let data_start = ImmediateValue::from_str(DATA_START_ADDR.to_string().as_str())
+            .unwrap()
+            .to_signed_decimal();
+        let pseudo = match asm.split_whitespace().collect::<Vec<_>>().as_slice() {
+            ["add", "r0", "r0", "0"] => Some(String::from("nop")),
+            ["mov", reg, "0"] => Some(format!("clr {}", reg)),
+            ["mov", reg, imm] if *imm == data_start => {
+                Some(format!("lea {} {}", reg, DATA_START_LABEL))
+            }
+            ["add", dst, reg, "1"] if dst == reg => Some(format!("inc {}", reg)),
+            _ => None,
+        };
+        pseudo.unwrap_or(asm)
+    }
 }
 
 impl Display for BinaryInstruction {
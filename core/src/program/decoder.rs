@@ -53,7 +53,7 @@ pub fn decode_binary_program_to_instructions(
     Ok(instructions)
 }
 
-fn get_instruction_length(instruction: String) -> Result<u8, String> {
+pub(crate) fn get_instruction_length(instruction: String) -> Result<u8, String> {
     let instruction_without_prefix = instruction.trim_start_matches("0x");
     let instruction_u64 = u64::from_str_radix(instruction_without_prefix, 16)
         .map_err(|err| format!("Convert str to u64 failed {}", err))?;
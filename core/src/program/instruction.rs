@@ -13,9 +13,11 @@ pub const REG0_FIELD_BIT_POSITION: u64 = OPCODE_FLAG_FIELD_BIT_POSITION + OPCODE
 pub const REG1_FIELD_BIT_POSITION: u64 = REG0_FIELD_BIT_POSITION + REG_FIELD_BIT_LEN;
 pub const REG2_FIELD_BIT_POSITION: u64 = REG1_FIELD_BIT_POSITION + REG_FIELD_BIT_LEN;
 pub const IMM_FLAG_FIELD_BIT_POSITION: u64 = REG2_FIELD_BIT_POSITION + REG_FIELD_BIT_LEN;
+pub const U32_FLAG_FIELD_BIT_POSITION: u64 = IMM_FLAG_FIELD_BIT_POSITION + 1;
 
 pub const REG_FIELD_BITS_MASK: u64 = 0x3ff;
 pub const IMM_FLAG_FIELD_BITS_MASK: u64 = 0x1;
+pub const U32_FLAG_FIELD_BITS_MASK: u64 = 0x1;
 pub const OPCODE_FIELD_BITS_MASK: u64 = 0xffff_ffff;
 
 #[warn(non_camel_case_types)]
@@ -25,6 +27,10 @@ pub enum ImmediateOrRegName {
     RegName(usize),
 }
 
+/// One-hot position of each opcode in the low 32 bits of an instruction.
+/// Every position is taken, so another opcode (e.g. a fixed-point `fmul`)
+/// needs a new instruction layout: the register fields and the imm flag use
+/// bits 32 to 62, and bit 63 is the u32 flag of `sub`, see `SUB_U32_TOKEN`.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, TryFromPrimitive)]
 #[repr(u8)]
 pub enum Opcode {
@@ -54,6 +60,12 @@ pub enum Opcode {
     TSTORE = 8,
     SCCALL = 7,
     SIGCHECK = 6,
+    SUB = 5,
+    SWAP = 4,
+    ISQRT = 3,
+    CAS = 2,
+    LOADP = 1,
+    REQUIRE = 0,
 }
 
 impl fmt::Display for Opcode {
@@ -85,6 +97,12 @@ impl fmt::Display for Opcode {
             Opcode::TSTORE => write!(f, "tstore"),
             Opcode::SCCALL => write!(f, "sccall"),
             Opcode::SIGCHECK => write!(f, "sigcheck"),
+            Opcode::SUB => write!(f, "sub"),
+            Opcode::SWAP => write!(f, "swap"),
+            Opcode::ISQRT => write!(f, "isqrt"),
+            Opcode::CAS => write!(f, "cas"),
+            Opcode::LOADP => write!(f, "loadp"),
+            Opcode::REQUIRE => write!(f, "require"),
         }
     }
 }
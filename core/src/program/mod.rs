@@ -1,13 +1,18 @@
-use crate::program::binary_program::OlaProphet;
+use crate::program::binary_program::{HostAssert, OlaProphet};
+use crate::program::decoder::get_instruction_length;
 use crate::trace::trace::Trace;
+use crate::types::account::Address;
+use crate::vm::error::ProcessorError;
 use plonky2::field::goldilocks_field::GoldilocksField;
-use plonky2::field::types::Field64;
+use plonky2::field::types::{Field, Field64};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use thiserror::Error;
 
 pub mod binary_program;
 pub mod decoder;
 pub mod instruction;
+pub mod reachability;
 
 /// fixme: use 10 registers
 pub const REGISTER_NUM: usize = 10;
@@ -15,14 +20,278 @@ pub const REGISTER_NUM: usize = 10;
 pub const CTX_REGISTER_NUM: usize = 4;
 pub const FIELD_ORDER: u64 = GoldilocksField::ORDER;
 
+/// Hex digits of one encoded instruction word.
+pub const INSTRUCTION_HEX_WIDTH: usize = 16;
+
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct Program {
     pub instructions: Vec<String>,
     pub trace: Trace,
     pub debug_info: Option<BTreeMap<usize, String>>,
+    pub source_lines: Option<BTreeMap<usize, String>>,
+    /// Checks of `assert_eq` lines, see `HostAssert`.
+    pub host_asserts: Option<BTreeMap<usize, Vec<HostAssert>>>,
+    /// Pcs of `brk` instructions, see `BinaryProgram::breakpoints`.
+    pub breakpoints: Option<BTreeSet<usize>>,
     pub print_flag: bool,
     pub prophets: HashMap<u64, OlaProphet>,
     pub pre_exe_flag: bool,
+    /// Read-only constants mapped at `DATA_START_ADDR` before execution.
+    #[serde(default)]
+    pub data: Vec<u64>,
 }
 
-impl Program {}
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ParseInstrError {
+    #[error("Empty instruction word")]
+    Empty,
+
+    #[error("Instruction word {0} has an odd number of hex digits")]
+    OddLength(String),
+
+    #[error("Instruction word {0} is not {INSTRUCTION_HEX_WIDTH} hex digits wide")]
+    Width(String),
+
+    #[error("Instruction word {0} has a non hex character {1:?}")]
+    NonHex(String, char),
+}
+
+/// Check that `s` is one encoded word, `0x` followed by 16 hex digits, and
+/// return it without leading or trailing whitespace.
+pub fn parse_instruction(s: &str) -> Result<String, ParseInstrError> {
+    let word = s.trim();
+    let digits = word.strip_prefix("0x").unwrap_or(word);
+    if digits.is_empty() {
+        return Err(ParseInstrError::Empty);
+    }
+    if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(ParseInstrError::NonHex(word.to_string(), c));
+    }
+    if digits.len() % 2 != 0 {
+        return Err(ParseInstrError::OddLength(word.to_string()));
+    }
+    if digits.len() != INSTRUCTION_HEX_WIDTH {
+        return Err(ParseInstrError::Width(word.to_string()));
+    }
+    Ok(format!("0x{}", digits))
+}
+
+/// One row of the program table: the word at `pc` of a contract's code,
+/// whether it is a word of the data section or the immediate of the
+/// instruction before it, and its compressed value as looked up by the
+/// program stark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProgramRow {
+    pub pc: u64,
+    pub ins: GoldilocksField,
+    pub data: bool,
+    pub imm: bool,
+    pub compress: GoldilocksField,
+}
+
+/// Compress `values` into a single element with the challenge `beta`, the
+/// first one with the lowest power.
+pub fn compress<F: Field, const N: usize>(values: [F; N], beta: F) -> F {
+    values
+        .iter()
+        .rev()
+        .fold(F::ZERO, |acc, value| acc * beta + *value)
+}
+
+/// Compress a row of the program table, `(addr_0..addr_3, pc, inst,
+/// is_data, is_imm)`, with the challenge `beta`. The code rows and the
+/// fetches of the cpu, whose data flag is zero, are both compressed with
+/// this, so it must not change independently of the program stark.
+pub fn compress_program_row<F: Field>(
+    addr: [F; 4],
+    pc: F,
+    inst: F,
+    is_data: F,
+    is_imm: F,
+    beta: F,
+) -> F {
+    compress(
+        [
+            addr[0], addr[1], addr[2], addr[3], pc, inst, is_data, is_imm,
+        ],
+        beta,
+    )
+}
+
+impl Program {
+    /// Program with the words of `bytecode`, one per line, as produced by the
+    /// assembler.
+    pub fn from_bytecode(bytecode: &str) -> Result<Self, ParseInstrError> {
+        Ok(Program {
+            instructions: bytecode
+                .split('\n')
+                .map(parse_instruction)
+                .collect::<Result<_, _>>()?,
+            ..Default::default()
+        })
+    }
+
+    /// Source annotation of the instruction executed at cpu trace row `row`,
+    /// looked up by its pc.
+    pub fn source_line(&self, row: usize) -> Option<&str> {
+        let pc = self.trace.exec.get(row)?.pc as usize;
+        self.source_lines.as_ref()?.get(&pc).map(String::as_str)
+    }
+
+    /// Words committed by the code hash: the instructions followed by the
+    /// data section, so the verifier binds the constants with the code.
+    pub fn code_words(&self) -> Result<Vec<GoldilocksField>, ProcessorError> {
+        let mut words = self
+            .instructions
+            .iter()
+            .map(|inst| {
+                u64::from_str_radix(inst.trim_start_matches("0x"), 16)
+                    .map(GoldilocksField::from_canonical_u64)
+                    .map_err(|_| ProcessorError::ParseIntError)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for word in &self.data {
+            words.push(GoldilocksField::from_canonical_u64(*word));
+        }
+        Ok(words)
+    }
+
+    /// Program table rows of the code stored at `addr`, one per word of
+    /// `code_words`, so an instruction with an immediate takes two rows and
+    /// the data section follows the instructions.
+    pub fn to_program_table(
+        &self,
+        addr: Address,
+        beta: GoldilocksField,
+    ) -> Result<Vec<ProgramRow>, String> {
+        let words = self
+            .code_words()
+            .map_err(|err| format!("Convert str to u64 failed {}", err))?;
+        let mut rows = Vec::with_capacity(words.len());
+        let mut is_imm = false;
+        for (pc, ins) in words.into_iter().enumerate() {
+            let data = pc >= self.instructions.len();
+            let imm = is_imm && !data;
+            rows.push(ProgramRow {
+                pc: pc as u64,
+                ins,
+                data,
+                imm,
+                compress: compress_program_row(
+                    addr,
+                    GoldilocksField::from_canonical_usize(pc),
+                    ins,
+                    GoldilocksField::from_bool(data),
+                    GoldilocksField::from_bool(imm),
+                    beta,
+                ),
+            });
+            is_imm = !imm && !data && get_instruction_length(self.instructions[pc].clone())? == 2;
+        }
+        Ok(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::program::instruction::{Opcode, IMM_FLAG_FIELD_BIT_POSITION};
+    use plonky2::field::goldilocks_field::GoldilocksField;
+    use plonky2::field::types::Field;
+
+    use super::{compress, compress_program_row, parse_instruction, ParseInstrError, Program};
+
+    #[test]
+    fn test_program_table_imm_rows() {
+        // mov r0 3; add r1 r0 5; end
+        let imm = 1 << IMM_FLAG_FIELD_BIT_POSITION;
+        let words: [u64; 5] = [
+            imm | 1 << Opcode::MOV as u8,
+            3,
+            imm | 1 << Opcode::ADD as u8,
+            5,
+            1 << Opcode::END as u8,
+        ];
+        // A data word with the value of an instruction taking an immediate.
+        let program = Program {
+            instructions: words.iter().map(|w| format!("0x{:x}", w)).collect(),
+            data: vec![words[0]],
+            ..Default::default()
+        };
+        let addr = [1, 2, 3, 4].map(GoldilocksField::from_canonical_u64);
+        let beta = GoldilocksField::from_canonical_u64(7);
+        let rows = program.to_program_table(addr, beta).unwrap();
+
+        assert_eq!(rows.len(), 6);
+        assert_eq!(
+            rows.iter().map(|r| r.pc).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5]
+        );
+        assert_eq!(
+            rows.iter().map(|r| r.imm).collect::<Vec<_>>(),
+            [false, true, false, true, false, false]
+        );
+        assert_eq!(
+            rows.iter().map(|r| r.data).collect::<Vec<_>>(),
+            [false, false, false, false, false, true]
+        );
+        for row in rows {
+            let pc = GoldilocksField::from_canonical_u64(row.pc);
+            let is_data = GoldilocksField::from_bool(row.data);
+            let is_imm = GoldilocksField::from_bool(row.imm);
+            let expected = [
+                addr[0], addr[1], addr[2], addr[3], pc, row.ins, is_data, is_imm,
+            ]
+            .iter()
+            .enumerate()
+            .map(|(i, value)| *value * beta.exp_u64(i as u64))
+            .sum::<GoldilocksField>();
+            assert_eq!(row.compress, expected);
+            assert_eq!(
+                compress(
+                    [addr[0], addr[1], addr[2], addr[3], pc, row.ins, is_data, is_imm],
+                    beta
+                ),
+                expected
+            );
+            assert_eq!(
+                compress_program_row(addr, pc, row.ins, is_data, is_imm, beta),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_instruction() {
+        assert_eq!(
+            parse_instruction("  0x4000000840000000\n"),
+            Ok("0x4000000840000000".to_string())
+        );
+        assert_eq!(parse_instruction(""), Err(ParseInstrError::Empty));
+        assert_eq!(parse_instruction("0x"), Err(ParseInstrError::Empty));
+        assert_eq!(
+            parse_instruction("0x40000008"),
+            Err(ParseInstrError::Width("0x40000008".to_string()))
+        );
+        assert_eq!(
+            parse_instruction("0x400000084"),
+            Err(ParseInstrError::OddLength("0x400000084".to_string()))
+        );
+        assert_eq!(
+            parse_instruction("0x400000084000000g"),
+            Err(ParseInstrError::NonHex(
+                "0x400000084000000g".to_string(),
+                'g'
+            ))
+        );
+    }
+
+    #[test]
+    fn test_program_from_bytecode() {
+        let program = Program::from_bytecode("0x4000000840000000\n0x0000000000000003").unwrap();
+        assert_eq!(program.instructions.len(), 2);
+        assert_eq!(
+            Program::from_bytecode("0x4000000840000000\n\n0x0000000000000003").unwrap_err(),
+            ParseInstrError::Empty
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use super::decoder::get_instruction_length;
+use super::instruction::IMM_FLAG_FIELD_BIT_POSITION;
+use super::Program;
+use crate::vm::opcodes::OlaOpcode;
+use enum_iterator::all;
+use std::collections::{BTreeMap, BTreeSet};
+
+fn parse_word(word: &str) -> Result<u64, String> {
+    u64::from_str_radix(word.trim_start_matches("0x"), 16)
+        .map_err(|err| format!("Convert str to u64 failed {}", err))
+}
+
+/// Start pcs of the instructions of `program` that can't be reached from pc 0,
+/// e.g. code after an unconditional `jmp` that nothing jumps to. A `jmp`,
+/// `cjmp` or `call` through a register may target any instruction, so none is
+/// reported once one of them is reachable.
+pub fn analyze_reachability(program: &Program) -> Result<Vec<usize>, String> {
+    // pc -> (opcode, immediate operand, length in words)
+    let mut decoded = BTreeMap::new();
+    let mut pc = 0;
+    while pc < program.instructions.len() {
+        let inst = &program.instructions[pc];
+        let word = parse_word(inst)?;
+        let opcode = all::<OlaOpcode>()
+            .find(|op| word & op.binary_bit_mask() != 0)
+            .ok_or(format!("No opcode matched: {}", inst))?;
+        let imm = if word >> IMM_FLAG_FIELD_BIT_POSITION & 1 == 1 {
+            let imm = program
+                .instructions
+                .get(pc + 1)
+                .ok_or(format!("Missing immediate of {} at pc {}", inst, pc))?;
+            Some(parse_word(imm)? as usize)
+        } else {
+            None
+        };
+        let len = get_instruction_length(inst.clone())? as usize;
+        decoded.insert(pc, (opcode, imm, len));
+        pc += len;
+    }
+
+    let mut reachable = BTreeSet::new();
+    let mut pending = vec![0];
+    while let Some(pc) = pending.pop() {
+        let (opcode, imm, len) = match decoded.get(&pc) {
+            Some(inst) => *inst,
+            None => continue,
+        };
+        if !reachable.insert(pc) {
+            continue;
+        }
+        match opcode {
+            OlaOpcode::JMP | OlaOpcode::CJMP | OlaOpcode::CALL => {
+                match imm {
+                    Some(target) => pending.push(target),
+                    None => return Ok(Vec::new()),
+                }
+                if opcode != OlaOpcode::JMP {
+                    pending.push(pc + len);
+                }
+            }
+            OlaOpcode::RET | OlaOpcode::END => {}
+            _ => pending.push(pc + len),
+        }
+    }
+
+    Ok(decoded
+        .keys()
+        .filter(|pc| !reachable.contains(pc))
+        .copied()
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::analyze_reachability;
+    use crate::program::instruction::IMM_FLAG_FIELD_BIT_POSITION;
+    use crate::program::Program;
+    use crate::vm::opcodes::OlaOpcode;
+
+    fn program(words: &[u64]) -> Program {
+        Program {
+            instructions: words.iter().map(|w| format!("0x{:0>16x}", w)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_code_after_jmp_unreachable() {
+        let imm = 1 << IMM_FLAG_FIELD_BIT_POSITION;
+        // jmp 4; mov r0 3; end
+        let words = [
+            imm | OlaOpcode::JMP.binary_bit_mask(),
+            4,
+            imm | OlaOpcode::MOV.binary_bit_mask(),
+            3,
+            OlaOpcode::END.binary_bit_mask(),
+        ];
+        assert_eq!(analyze_reachability(&program(&words)).unwrap(), vec![2]);
+
+        // cjmp r0 4; mov r0 3; end, both branches are reachable.
+        let mut words = words;
+        words[0] = imm | OlaOpcode::CJMP.binary_bit_mask();
+        assert!(analyze_reachability(&program(&words)).unwrap().is_empty());
+    }
+}
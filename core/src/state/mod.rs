@@ -23,6 +23,8 @@ pub struct NodeState<H> {
     pub hasher: H,
     pub storage_queries: Vec<StorageQuery>,
     pub return_data: Vec<GoldilocksField>,
+    /// Whether the last tx reverted, its storage writes are discarded.
+    pub reverted: bool,
 }
 
 impl<H> NodeState<H>
@@ -37,6 +39,7 @@ where
             hasher,
             storage_queries: vec![],
             return_data: vec![],
+            reverted: false,
         }
     }
 
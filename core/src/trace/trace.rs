@@ -1,9 +1,12 @@
+use crate::program::instruction::U32_FLAG_FIELD_BIT_POSITION;
 use crate::program::REGISTER_NUM;
 use crate::types::{account::Address, merkle_tree::TreeValue};
 use crate::utils::split_limbs_from_field;
 use crate::utils::split_u16_limbs_from_field;
+use crate::vm::opcodes::OlaOpcode;
 use plonky2::field::goldilocks_field::GoldilocksField;
 use plonky2::field::types::Field;
+use plonky2::field::types::Field64;
 use plonky2::field::types::PrimeField64;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -126,6 +129,60 @@ pub struct Step {
     pub storage_access_idx: GoldilocksField,
 }
 
+impl Step {
+    pub fn pc(&self) -> u64 {
+        self.pc
+    }
+
+    pub fn clk(&self) -> u32 {
+        self.clk
+    }
+
+    /// Value of register `index` before the step's instruction runs.
+    pub fn register(&self, index: usize) -> u64 {
+        self.regs[index].to_canonical_u64()
+    }
+
+    /// One-hot opcode bit mask of the step's instruction, as in
+    /// `OlaOpcode::binary_bit_mask`.
+    pub fn opcode(&self) -> u64 {
+        self.opcode.to_canonical_u64()
+    }
+
+    /// Whether the step's instruction has the u32 flag, i.e. is a `subu`.
+    pub fn u32_mode(&self) -> bool {
+        self.instruction.to_canonical_u64() >> U32_FLAG_FIELD_BIT_POSITION & 1 == 1
+    }
+
+    /// For an `add` step, whether the integer sum of its canonical operands
+    /// wrapped around the field order; for a `sub` step, whether op1 is
+    /// above op0, i.e. the field subtraction wrapped, or for a `subu` the
+    /// u32 one borrowed. `None` for other steps.
+    ///
+    /// Only the `subu` borrow is proven: it is the aux0 column the cpu table
+    /// constrains against its range checked u32 dst. The `add` and `sub`
+    /// carries are derived from the witness and the cpu table has no carry
+    /// column for them, on purpose: `op0 + op1 == dst + carry * 2^64` can't
+    /// hold with a carry, `2^64` being `2^32 - 1` in the field, and with `p`
+    /// in place of `2^64` it holds for any carry. Binding it would take a
+    /// limb decomposition of op0, op1 and dst proving them canonical and
+    /// comparing them, which a wrapping or u256 opcode should bring along
+    /// with its own range checked limbs instead.
+    pub fn carry(&self) -> Option<bool> {
+        let op0 = self.register_selector.op0.to_canonical_u64();
+        let op1 = self.register_selector.op1.to_canonical_u64();
+        if self.opcode() == OlaOpcode::ADD.binary_bit_mask() {
+            Some(op0 as u128 + op1 as u128 >= GoldilocksField::ORDER as u128)
+        } else if self.opcode() == OlaOpcode::SUB.binary_bit_mask() && self.u32_mode() {
+            Some(self.register_selector.aux0.is_one())
+        } else if self.opcode() == OlaOpcode::SUB.binary_bit_mask() {
+            Some(op0 < op1)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RangeCheckRow {
     //pub tag: u32,
@@ -166,6 +223,41 @@ pub struct BitwiseCombinedRow {
     pub res_3: GoldilocksField,
 }
 
+/// Typed view of a `BitwiseCombinedRow`. Limbs are little endian bytes, so
+/// `op0 == op0_limbs[0] + op0_limbs[1] << 8 + ...`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitwiseRow {
+    pub opcode: u64,
+    pub op0: u32,
+    pub op1: u32,
+    pub res: u32,
+    pub op0_limbs: [u8; 4],
+    pub op1_limbs: [u8; 4],
+    pub res_limbs: [u8; 4],
+}
+
+impl BitwiseRow {
+    /// Recombine little endian byte limbs into the u32 they decompose.
+    pub fn from_limbs(limbs: [u8; 4]) -> u32 {
+        u32::from_le_bytes(limbs)
+    }
+}
+
+impl From<&BitwiseCombinedRow> for BitwiseRow {
+    fn from(row: &BitwiseCombinedRow) -> Self {
+        let limbs = |limbs: [GoldilocksField; 4]| limbs.map(|l| l.to_canonical_u64() as u8);
+        Self {
+            opcode: row.opcode,
+            op0: row.op0.to_canonical_u64() as u32,
+            op1: row.op1.to_canonical_u64() as u32,
+            res: row.res.to_canonical_u64() as u32,
+            op0_limbs: limbs([row.op0_0, row.op0_1, row.op0_2, row.op0_3]),
+            op1_limbs: limbs([row.op1_0, row.op1_1, row.op1_2, row.op1_3]),
+            res_limbs: limbs([row.res_0, row.res_1, row.res_2, row.res_3]),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CmpRow {
     pub op0: GoldilocksField,
@@ -292,6 +384,8 @@ pub struct StorageHashRow {
     pub pre_hash: [GoldilocksField; 4],
     pub hash: [GoldilocksField; 4],
     pub sibling: [GoldilocksField; 4],
+    /// 1 on the leaf row of a write undoing one of a reverted tx.
+    pub is_undo: GoldilocksField,
 }
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
@@ -365,6 +459,10 @@ impl Trace {
         });
     }
 
+    pub fn bitwise_rows(&self) -> impl Iterator<Item = BitwiseRow> + '_ {
+        self.builtin_bitwise_combined.iter().map(BitwiseRow::from)
+    }
+
     pub fn insert_bitwise_combined(
         &mut self,
         opcode: u64,
@@ -1,3 +1,4 @@
+use crate::program::ParseInstrError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -25,6 +26,9 @@ pub enum ProcessorError {
     #[error("Memory visit invalid, bound addr: {0}")]
     MemVistInv(u64),
 
+    #[error("Stack addresses {0} and {1} are too far apart for the memory trace")]
+    MemoryGapTooWide(u64, u64),
+
     #[error("Tape visit invalid, bound addr: {0}")]
     TapeVistInv(u64),
 
@@ -64,6 +68,15 @@ pub enum ProcessorError {
     #[error("Wrong reg index: {0}")]
     RegIndexError(usize),
 
+    #[error("Register field selects several registers: {0:#b}")]
+    RegFieldError(u64),
+
+    #[error("Opcode field sets several opcodes: {0:#x}")]
+    MalformedOpcode(u64),
+
+    #[error("u32 flag set on another instruction than sub: {0:#x}")]
+    U32FlagError(u64),
+
     #[error("Create Regex error: {0}")]
     RegexNewError(String),
 
@@ -72,4 +85,16 @@ pub enum ProcessorError {
 
     #[error("Array indexing error: {0}")]
     ArrayIndexError(String),
+
+    #[error("Branch on secret dependent value: {0}")]
+    SecretDependentBranch(String),
+
+    #[error("Parse instruction failed: {0}")]
+    ParseInstrError(#[from] ParseInstrError),
+
+    #[error("Undefined behavior: {0}")]
+    UndefinedBehavior(String),
+
+    #[error("Program has no instruction")]
+    EmptyProgram,
 }
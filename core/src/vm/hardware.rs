@@ -132,6 +132,8 @@ impl Display for OlaRegister {
 pub enum OlaSpecialRegister {
     PC,
     PSP,
+    /// Clock of the executed instruction, read by `mov rX clk`.
+    CLK,
 }
 
 impl Display for OlaSpecialRegister {
@@ -139,6 +141,7 @@ impl Display for OlaSpecialRegister {
         let token = match self {
             OlaSpecialRegister::PC => "pc",
             OlaSpecialRegister::PSP => "psp",
+            OlaSpecialRegister::CLK => "clk",
         };
         write!(f, "{}", token)
     }
@@ -151,6 +154,7 @@ impl FromStr for OlaSpecialRegister {
         match s {
             "pc" => Ok(OlaSpecialRegister::PC),
             "psp" => Ok(OlaSpecialRegister::PSP),
+            "clk" => Ok(OlaSpecialRegister::CLK),
             _ => Err(format!("invalid special reg identifier: {}", s)),
         }
     }
@@ -1,13 +1,29 @@
+use crate::crypto::hash::Hasher;
+use crate::crypto::ZkHasher;
+use crate::types::merkle_tree::{tree_key_default, TreeValue};
 use crate::vm::error::ProcessorError;
 
 use plonky2::field::goldilocks_field::GoldilocksField;
-use plonky2::field::types::Field64;
+use plonky2::field::types::{Field, Field64, PrimeField64};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 pub const MEM_SPAN_SIZE: u64 = u32::MAX as u64;
 pub const PSP_START_ADDR: u64 = GoldilocksField::ORDER - MEM_SPAN_SIZE;
 pub const HP_START_ADDR: u64 = GoldilocksField::ORDER - 2 * MEM_SPAN_SIZE;
+/// Frame pointer (r9) a transaction starts with, the stack grows up from it.
+pub const FP_START_ADDR: u64 = 0;
+/// Base of the program data section. It is the start of the write-once
+/// prophet region, prophet outputs are placed after the data.
+pub const DATA_START_ADDR: u64 = PSP_START_ADDR;
+/// Memory op of the data section writes, above the bit of every opcode.
+pub const MEM_OP_DATA: u64 = 1 << 32;
+/// Memory op of the writes of the initial stack memory, see
+/// `Process::with_initial_memory`.
+pub const MEM_OP_INIT: u64 = 1 << 33;
+/// Symbol the assembler resolves to `DATA_START_ADDR`, e.g. in
+/// `lea r0 data_start`.
+pub const DATA_START_LABEL: &str = "data_start";
 
 #[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub struct MemoryCell {
@@ -44,30 +60,31 @@ impl MemoryTree {
     ) -> Result<GoldilocksField, ProcessorError> {
         // look up the previous value in the appropriate address trace and add (clk,
         // prev_value) to it; if this is the first time we access this address,
-        // return MemVistInv error because memory must be inited first.
+        // memory is zero initialized, so the read returns zero and starts the
+        // address trace.
         // Return the last value in the address trace.
-        let read_mem_res = self.trace.get_mut(&addr);
-        if let Some(mem_data) = read_mem_res {
-            let last_value = mem_data
-                .last()
-                .ok_or(ProcessorError::EmptyHashTraceError)?
-                .value;
-            let new_value = MemoryCell {
-                env_idx,
-                is_rw,
-                clk,
-                op,
-                is_write,
-                filter_looked_for_main,
-                region_prophet,
-                region_heap,
-                value: last_value,
-            };
-            mem_data.push(new_value);
-            Ok(last_value)
-        } else {
-            Err(ProcessorError::MemVistInv(addr))
-        }
+        let last_value = match self.trace.get(&addr) {
+            Some(mem_data) => {
+                mem_data
+                    .last()
+                    .ok_or(ProcessorError::EmptyHashTraceError)?
+                    .value
+            }
+            None => GoldilocksField::ZERO,
+        };
+        let new_value = MemoryCell {
+            env_idx,
+            is_rw,
+            clk,
+            op,
+            is_write,
+            filter_looked_for_main,
+            region_prophet,
+            region_heap,
+            value: last_value,
+        };
+        self.trace.entry(addr).or_default().push(new_value);
+        Ok(last_value)
     }
 
     pub fn write(
@@ -101,6 +118,44 @@ impl MemoryTree {
             .and_modify(|addr_trace| addr_trace.push(new_cell))
             .or_insert_with(|| vec![new_cell]);
     }
+
+    /// Last word of every stack address, the memory a run leaves behind as
+    /// `memory_root` commits to it.
+    pub fn stack_values(&self) -> BTreeMap<u64, u64> {
+        self.trace
+            .range(..HP_START_ADDR)
+            .filter_map(|(addr, cells)| Some((*addr, cells.last()?.value.to_canonical_u64())))
+            .collect()
+    }
+}
+
+/// Merkle root of the stack words `values`, by address. Memory is zero
+/// initialized, so zero words are left out and empty memory has the zero
+/// root. A leaf hashes an address with its word, the leaves are in address
+/// order and padded with zero hashes to a power of two.
+pub fn memory_root(values: &BTreeMap<u64, u64>) -> TreeValue {
+    let hasher = ZkHasher::default();
+    let mut layer = values
+        .iter()
+        .filter(|(_, value)| **value != 0)
+        .map(|(addr, value)| {
+            hasher.hash_bytes(&vec![
+                GoldilocksField::from_canonical_u64(*addr),
+                GoldilocksField::from_canonical_u64(*value),
+            ])
+        })
+        .collect::<Vec<_>>();
+    if layer.is_empty() {
+        return tree_key_default();
+    }
+    layer.resize(layer.len().next_power_of_two(), tree_key_default());
+    while layer.len() > 1 {
+        layer = layer
+            .chunks(2)
+            .map(|pair| hasher.hash_bytes(&[pair[0], pair[1]].concat()))
+            .collect();
+    }
+    layer[0]
 }
 
 #[macro_export]
@@ -3,6 +3,12 @@ use std::fmt::{Display, Formatter};
 
 use std::str::FromStr;
 
+/// Mnemonic of `sub` in u32 mode: `subu rD rA rB` computes
+/// `rA - rB + borrow * 2^32` on u32 operands, the borrow set when `rB > rA`,
+/// and range checks rD. It is `sub` with the u32 flag of the instruction set,
+/// plain `sub` is the field subtraction.
+pub const SUB_U32_TOKEN: &str = "subu";
+
 #[derive(Debug, Copy, Sequence, Clone, Eq, PartialEq)]
 pub enum OlaOpcode {
     ADD,
@@ -31,6 +37,12 @@ pub enum OlaOpcode {
     TSTORE,
     SCCALL,
     SIGCHECK,
+    SUB,
+    SWAP,
+    ISQRT,
+    CAS,
+    LOADP,
+    REQUIRE,
 }
 
 impl Display for OlaOpcode {
@@ -42,34 +54,49 @@ impl Display for OlaOpcode {
 }
 
 impl OlaOpcode {
+    /// Every opcode of the instruction set.
+    pub fn all() -> impl Iterator<Item = OlaOpcode> {
+        all::<OlaOpcode>()
+    }
+
     pub fn token(&self) -> String {
+        self.as_str().to_string()
+    }
+
+    pub fn as_str(&self) -> &'static str {
         match self {
-            OlaOpcode::ADD => "add".to_string(),
-            OlaOpcode::MUL => "mul".to_string(),
-            OlaOpcode::EQ => "eq".to_string(),
-            OlaOpcode::ASSERT => "assert".to_string(),
-            OlaOpcode::MOV => "mov".to_string(),
-            OlaOpcode::JMP => "jmp".to_string(),
-            OlaOpcode::CJMP => "cjmp".to_string(),
-            OlaOpcode::CALL => "call".to_string(),
-            OlaOpcode::RET => "ret".to_string(),
-            OlaOpcode::MLOAD => "mload".to_string(),
-            OlaOpcode::MSTORE => "mstore".to_string(),
-            OlaOpcode::END => "end".to_string(),
-            OlaOpcode::RC => "range".to_string(),
-            OlaOpcode::AND => "and".to_string(),
-            OlaOpcode::OR => "or".to_string(),
-            OlaOpcode::XOR => "xor".to_string(),
-            OlaOpcode::NOT => "not".to_string(),
-            OlaOpcode::NEQ => "neq".to_string(),
-            OlaOpcode::GTE => "gte".to_string(),
-            OlaOpcode::POSEIDON => "poseidon".to_string(),
-            OlaOpcode::SLOAD => "sload".to_string(),
-            OlaOpcode::SSTORE => "sstore".to_string(),
-            OlaOpcode::TLOAD => "tload".to_string(),
-            OlaOpcode::TSTORE => "tstore".to_string(),
-            OlaOpcode::SCCALL => "sccall".to_string(),
-            OlaOpcode::SIGCHECK => "sigcheck".to_string(),
+            OlaOpcode::ADD => "add",
+            OlaOpcode::MUL => "mul",
+            OlaOpcode::EQ => "eq",
+            OlaOpcode::ASSERT => "assert",
+            OlaOpcode::MOV => "mov",
+            OlaOpcode::JMP => "jmp",
+            OlaOpcode::CJMP => "cjmp",
+            OlaOpcode::CALL => "call",
+            OlaOpcode::RET => "ret",
+            OlaOpcode::MLOAD => "mload",
+            OlaOpcode::MSTORE => "mstore",
+            OlaOpcode::END => "end",
+            OlaOpcode::RC => "range",
+            OlaOpcode::AND => "and",
+            OlaOpcode::OR => "or",
+            OlaOpcode::XOR => "xor",
+            OlaOpcode::NOT => "not",
+            OlaOpcode::NEQ => "neq",
+            OlaOpcode::GTE => "gte",
+            OlaOpcode::POSEIDON => "poseidon",
+            OlaOpcode::SLOAD => "sload",
+            OlaOpcode::SSTORE => "sstore",
+            OlaOpcode::TLOAD => "tload",
+            OlaOpcode::TSTORE => "tstore",
+            OlaOpcode::SCCALL => "sccall",
+            OlaOpcode::SIGCHECK => "sigcheck",
+            OlaOpcode::SUB => "sub",
+            OlaOpcode::SWAP => "swap",
+            OlaOpcode::ISQRT => "isqrt",
+            OlaOpcode::CAS => "cas",
+            OlaOpcode::LOADP => "loadp",
+            OlaOpcode::REQUIRE => "require",
         }
     }
 
@@ -101,6 +128,12 @@ impl OlaOpcode {
             OlaOpcode::TSTORE => 8,
             OlaOpcode::SCCALL => 7,
             OlaOpcode::SIGCHECK => 6,
+            OlaOpcode::SUB => 5,
+            OlaOpcode::SWAP => 4,
+            OlaOpcode::ISQRT => 3,
+            OlaOpcode::CAS => 2,
+            OlaOpcode::LOADP => 1,
+            OlaOpcode::REQUIRE => 0,
         }
     }
 
@@ -113,7 +146,7 @@ impl FromStr for OlaOpcode {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        for op in all::<OlaOpcode>().collect::<Vec<_>>() {
+        for op in OlaOpcode::all() {
             if s == op.token() {
                 return Ok(op);
             }
@@ -30,12 +30,12 @@ pub enum OlaOperand {
 impl OlaOperand {
     pub fn get_asm_token(&self) -> String {
         match self {
-            OlaOperand::ImmediateOperand { value } => value.clone().hex,
+            OlaOperand::ImmediateOperand { value } => value.to_signed_decimal(),
             OlaOperand::RegisterOperand { register } => {
                 format!("{}", register)
             }
             OlaOperand::RegisterWithOffset { register, offset } => {
-                format!("[{},{}]", register, offset.hex)
+                format!("[{},{}]", register, offset.to_signed_decimal())
             }
             OlaOperand::SpecialReg { special_reg } => {
                 format!("{}", special_reg)
@@ -146,6 +146,19 @@ impl ImmediateValue {
         let without_prefix = self.hex.trim_start_matches("0x");
         return u64::from_str_radix(without_prefix, 16);
     }
+
+    /// Decimal form of the value as `from_str` reads it back. Values up to
+    /// `u32::MAX` below the field order are written as negative numbers,
+    /// e.g. `-2` for `0xfffffffeffffffff`.
+    pub fn to_signed_decimal(&self) -> String {
+        let value = self.to_u64().unwrap_or(0);
+        let negated = ImmediateValue::ORDER.wrapping_sub(value);
+        if negated <= u32::MAX as u64 {
+            format!("-{}", negated)
+        } else {
+            format!("{}", value)
+        }
+    }
 }
 
 impl Display for ImmediateValue {
@@ -219,6 +232,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_immediate_signed_decimal() {
+        for value in ["-2", "0", "7", "-4294967295", "-4294967296"] {
+            let immediate = ImmediateValue::from_str(value).unwrap();
+            let rendered = immediate.to_signed_decimal();
+            assert_eq!(ImmediateValue::from_str(&rendered).unwrap(), immediate);
+        }
+        let minus_two = ImmediateValue::from_str("-2").unwrap();
+        assert_eq!(minus_two.hex, "0xfffffffeffffffff");
+        assert_eq!(minus_two.to_signed_decimal(), "-2");
+        let far = ImmediateValue::from_str("-4294967296").unwrap();
+        assert_eq!(far.to_signed_decimal(), "18446744065119617025");
+    }
+
     #[test]
     fn test_operand_parse() {
         let oper_reg = OlaOperand::from_str("r6").unwrap();
@@ -2,6 +2,10 @@ use crate::trace::trace::Step;
 pub use crate::types::account::Address;
 pub use plonky2::field::goldilocks_field::GoldilocksField;
 
+/// Name prophet code reads the call depth of the process by, see
+/// `Process::call_depth`.
+pub const CALL_DEPTH: &str = "call_depth";
+
 #[derive(Debug)]
 pub enum SCCallType {
     Call(Address),
@@ -11,5 +15,9 @@ pub enum SCCallType {
 #[derive(Debug)]
 pub enum VMState {
     ExeEnd(Option<Step>),
+    /// Halted by a `require` on zero.
+    Reverted(Option<Step>),
     SCCall(SCCallType),
+    /// Stopped by the hook of `execute_with_hook` before the step at this pc.
+    Interrupted(u64),
 }
@@ -1,5 +1,6 @@
 use core::program::instruction::{Opcode, *};
 use core::vm::error::ProcessorError;
+use core::vm::opcodes::SUB_U32_TOKEN;
 use log::debug;
 
 pub(crate) const REG_NOT_USED: u8 = 0xff;
@@ -26,6 +27,17 @@ fn get_index(data: u64) -> u8 {
     REG_NOT_USED
 }
 
+/// Index of the register selected by a one-hot register field. The field
+/// has one bit per register, so an index past `REGISTER_NUM` can't be
+/// encoded, but a field with several bits set is rejected: the cpu
+/// constrains the selectors of an operand to sum to at most one.
+fn get_reg_index(field: u64) -> Result<u8, ProcessorError> {
+    if field.count_ones() > 1 {
+        return Err(ProcessorError::RegFieldError(field));
+    }
+    Ok(get_index(field))
+}
+
 pub fn decode_raw_instruction(
     raw_inst_str: &str,
     imm_str: &str,
@@ -33,32 +45,48 @@ pub fn decode_raw_instruction(
     let mut step = NO_IMM_INSTRUCTION_LEN;
     debug!("raw_inst:{}", raw_inst_str);
     let raw_inst = parse_hex_str(raw_inst_str.trim_start_matches("0x"))?;
-    let opcode_index = get_index(raw_inst & OPCODE_FIELD_BITS_MASK);
+    // The cpu constrains the opcode selectors to sum to one, a word setting
+    // several of them would otherwise run as its highest opcode.
+    let opcode_bits = raw_inst & OPCODE_FIELD_BITS_MASK;
+    if opcode_bits.count_ones() > 1 {
+        return Err(ProcessorError::MalformedOpcode(opcode_bits));
+    }
+    let opcode_index = get_index(opcode_bits);
 
     if let Ok(op_code) = Opcode::try_from(opcode_index) {
         debug!("op_code:{:?}", op_code);
         let imm_flag = raw_inst >> IMM_FLAG_FIELD_BIT_POSITION & IMM_FLAG_FIELD_BITS_MASK;
         debug!("imm_flag:{}", imm_flag);
-        let reg0 = get_index(raw_inst >> REG0_FIELD_BIT_POSITION & REG_FIELD_BITS_MASK);
+        let u32_flag = raw_inst >> U32_FLAG_FIELD_BIT_POSITION & U32_FLAG_FIELD_BITS_MASK;
+        if u32_flag == 1 && !matches!(op_code, Opcode::SUB) {
+            return Err(ProcessorError::U32FlagError(raw_inst));
+        }
+        let reg0 = get_reg_index(raw_inst >> REG0_FIELD_BIT_POSITION & REG_FIELD_BITS_MASK)?;
         debug!("reg0:{}", reg0);
-        let reg2 = get_index(raw_inst >> REG1_FIELD_BIT_POSITION & REG_FIELD_BITS_MASK);
+        let reg2 = get_reg_index(raw_inst >> REG1_FIELD_BIT_POSITION & REG_FIELD_BITS_MASK)?;
         debug!("op1:{}", reg2);
-        let reg1 = get_index(raw_inst >> REG2_FIELD_BIT_POSITION & REG_FIELD_BITS_MASK);
+        let reg1 = get_reg_index(raw_inst >> REG2_FIELD_BIT_POSITION & REG_FIELD_BITS_MASK)?;
         debug!("op0:{}", reg1);
 
         let mut instruction = "".to_string();
         match op_code {
             Opcode::ADD
             | Opcode::MUL
+            | Opcode::SUB
             | Opcode::AND
             | Opcode::OR
             | Opcode::XOR
             | Opcode::NEQ
             | Opcode::GTE
             | Opcode::EQ
+            | Opcode::CAS
             | Opcode::TLOAD
             | Opcode::POSEIDON => {
-                instruction += &op_code.to_string();
+                if u32_flag == 1 {
+                    instruction += SUB_U32_TOKEN;
+                } else {
+                    instruction += &op_code.to_string();
+                }
                 instruction += " ";
                 let reg0_name = format!("r{}", reg0);
                 instruction += &reg0_name;
@@ -75,7 +103,12 @@ pub fn decode_raw_instruction(
                     instruction += &reg2_name;
                 }
             }
-            Opcode::CJMP | Opcode::TSTORE | Opcode::SCCALL | Opcode::SLOAD | Opcode::SSTORE => {
+            Opcode::CJMP
+            | Opcode::TSTORE
+            | Opcode::SCCALL
+            | Opcode::SLOAD
+            | Opcode::SSTORE
+            | Opcode::SWAP => {
                 instruction += &op_code.to_string();
                 instruction += " ";
                 let reg1_name = format!("r{}", reg1);
@@ -90,7 +123,7 @@ pub fn decode_raw_instruction(
                     instruction += &reg2_name;
                 }
             }
-            Opcode::MOV | Opcode::NOT | Opcode::SIGCHECK => {
+            Opcode::MOV | Opcode::NOT | Opcode::ISQRT | Opcode::SIGCHECK => {
                 instruction += &op_code.to_string();
                 instruction += " ";
                 let reg0_name = format!("r{}", reg0);
@@ -100,11 +133,26 @@ pub fn decode_raw_instruction(
                     let imm = parse_hex_str(imm_str.trim_start_matches("0x"))?;
                     instruction += &imm.to_string();
                     step = IMM_INSTRUCTION_LEN;
+                } else if matches!(op_code, Opcode::MOV) && reg1 != REG_NOT_USED {
+                    // A mov with an op0 register reads the clock.
+                    instruction += "clk";
                 } else {
                     let reg2_name = format!("r{}", reg2);
                     instruction += &reg2_name;
                 }
             }
+            Opcode::LOADP => {
+                instruction += &op_code.to_string();
+                instruction += " ";
+                let reg0_name = format!("r{}", reg0);
+                instruction += &reg0_name;
+                instruction += " ";
+                let reg2_name = format!("r{}", reg2);
+                instruction += &reg2_name;
+                instruction += " ";
+                let reg1_name = format!("r{}", reg1);
+                instruction += &reg1_name;
+            }
             Opcode::MSTORE => {
                 instruction += &op_code.to_string();
                 instruction += " ";
@@ -189,7 +237,7 @@ pub fn decode_raw_instruction(
                 }
                 step = IMM_INSTRUCTION_LEN;
             }
-            Opcode::ASSERT | Opcode::JMP | Opcode::CALL | Opcode::RC => {
+            Opcode::ASSERT | Opcode::JMP | Opcode::CALL | Opcode::RC | Opcode::REQUIRE => {
                 instruction += &op_code.to_string();
                 instruction += " ";
                 if imm_flag == 1 {
@@ -225,3 +273,69 @@ fn decode_raw_instruction_test() {
     let inst_str = decode_raw_instruction(&inst_str, imm);
     println!("inst_str: {:?}", inst_str);
 }
+
+#[test]
+fn decode_arithmetic_imm_test() {
+    for (opcode, name) in [
+        (Opcode::ADD, "add"),
+        (Opcode::MUL, "mul"),
+        (Opcode::SUB, "sub"),
+    ] {
+        let inst: u64 = 1 << IMM_FLAG_FIELD_BIT_POSITION
+            | 0b1 << REG2_FIELD_BIT_POSITION
+            | 0b10 << REG0_FIELD_BIT_POSITION
+            | 1 << opcode as u8;
+        let inst_str = format!("0x{:x}", inst);
+        let (asm, step) = decode_raw_instruction(&inst_str, "0x5").unwrap();
+        assert_eq!(asm, format!("{} r1 r0 5", name));
+        assert_eq!(step, IMM_INSTRUCTION_LEN);
+    }
+}
+
+#[test]
+fn decode_sub_u32_test() {
+    let inst: u64 = 1 << U32_FLAG_FIELD_BIT_POSITION
+        | 0b100 << REG2_FIELD_BIT_POSITION
+        | 0b1 << REG1_FIELD_BIT_POSITION
+        | 0b10 << REG0_FIELD_BIT_POSITION
+        | 1 << Opcode::SUB as u8;
+    let (asm, _) = decode_raw_instruction(&format!("0x{:x}", inst), "0x0").unwrap();
+    assert_eq!(asm, "subu r1 r2 r0");
+
+    // Only sub has a u32 mode.
+    let inst = inst & !(1 << Opcode::SUB as u8) | 1 << Opcode::ADD as u8;
+    assert!(matches!(
+        decode_raw_instruction(&format!("0x{:x}", inst), "0x0"),
+        Err(ProcessorError::U32FlagError(_))
+    ));
+}
+
+#[test]
+fn decode_multiple_reg_bits_test() {
+    // dst field selecting both r1 and r7, e.g. a corrupted word meant to
+    // address a register past r9.
+    let inst: u64 = 0b1 << REG2_FIELD_BIT_POSITION
+        | 0b1 << REG1_FIELD_BIT_POSITION
+        | 0b10000010 << REG0_FIELD_BIT_POSITION
+        | 1 << Opcode::ADD as u8;
+    let inst_str = format!("0x{:x}", inst);
+    assert!(matches!(
+        decode_raw_instruction(&inst_str, "0x0"),
+        Err(ProcessorError::RegFieldError(0b10000010))
+    ));
+}
+
+#[test]
+fn decode_multiple_opcode_bits_test() {
+    let inst: u64 = 0b1 << REG2_FIELD_BIT_POSITION
+        | 0b1 << REG1_FIELD_BIT_POSITION
+        | 0b10 << REG0_FIELD_BIT_POSITION
+        | 1 << Opcode::ADD as u8
+        | 1 << Opcode::MUL as u8;
+    let inst_str = format!("0x{:x}", inst);
+    assert!(matches!(
+        decode_raw_instruction(&inst_str, "0x0"),
+        Err(ProcessorError::MalformedOpcode(bits))
+            if bits == 1 << Opcode::ADD as u8 | 1 << Opcode::MUL as u8
+    ));
+}
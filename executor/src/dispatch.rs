@@ -0,0 +1,238 @@
+//! The handler executing each instruction, looked up by its mnemonic. Adding
+//! an instruction is adding its handler to `HANDLERS`.
+
+use crate::{Process, TxScopeCacheManager};
+use core::merkle_tree::tree::AccountTree;
+use core::program::instruction::Opcode;
+use core::program::{Program, REGISTER_NUM};
+use core::trace::trace::Step;
+use core::types::account::Address;
+use core::vm::error::ProcessorError;
+use core::vm::vm_state::VMState;
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::Field;
+
+/// The instruction being executed, with what its handler may need besides
+/// the process. The `*_status` fields are the state before it executes.
+pub(crate) struct DecodedInstr<'a> {
+    pub program: &'a mut Program,
+    pub account_tree: &'a mut AccountTree,
+    pub tx_cache_manager: &'a mut TxScopeCacheManager,
+    /// Rows recorded after the instruction's own, e.g. by a storage access.
+    pub aux_steps: &'a mut Vec<Step>,
+    /// Mnemonic and operands.
+    pub ops: &'a [&'a str],
+    /// Words the instruction takes, 2 with an immediate.
+    pub step: u64,
+    pub pc_status: u64,
+    pub ctx_regs_status: &'a Address,
+    pub registers_status: &'a [GoldilocksField; REGISTER_NUM],
+    pub ctx_code_regs_status: &'a Address,
+}
+
+/// What the execution loop does once an instruction executed.
+pub(crate) enum StepOutcome {
+    /// Go on with the next instruction.
+    Next,
+    /// Stop on `end`, or on a `require` of zero if `reverted`.
+    Halt {
+        end_step: Option<Step>,
+        reverted: bool,
+    },
+    /// Return this state from `execute`, e.g. to run a called contract.
+    Exit(VMState),
+}
+
+pub(crate) type Handler =
+    fn(&mut Process, &mut DecodedInstr) -> Result<StepOutcome, ProcessorError>;
+
+fn next(result: Result<(), ProcessorError>) -> Result<StepOutcome, ProcessorError> {
+    result.map(|_| StepOutcome::Next)
+}
+
+const HANDLERS: &[(&str, Handler)] = &[
+    ("mov", |process, instr| {
+        next(process.execute_inst_mov_not(instr.ops, instr.step))
+    }),
+    ("not", |process, instr| {
+        next(process.execute_inst_mov_not(instr.ops, instr.step))
+    }),
+    ("eq", |process, instr| {
+        next(process.execute_inst_eq_neq(instr.ops, instr.step))
+    }),
+    ("neq", |process, instr| {
+        next(process.execute_inst_eq_neq(instr.ops, instr.step))
+    }),
+    ("assert", |process, instr| {
+        next(process.execute_inst_assert(instr.ops, instr.step))
+    }),
+    ("cjmp", |process, instr| {
+        next(process.execute_inst_cjmp(instr.ops, instr.step))
+    }),
+    ("jmp", |process, instr| {
+        next(process.execute_inst_jmp(instr.ops))
+    }),
+    ("add", |process, instr| {
+        next(process.execute_inst_arithmetic(instr.ops, instr.step))
+    }),
+    ("mul", |process, instr| {
+        next(process.execute_inst_arithmetic(instr.ops, instr.step))
+    }),
+    ("sub", |process, instr| {
+        next(process.execute_inst_arithmetic(instr.ops, instr.step))
+    }),
+    ("subu", |process, instr| {
+        next(process.execute_inst_sub_u32(instr.program, instr.ops, instr.step))
+    }),
+    ("swap", |process, instr| {
+        next(process.execute_inst_swap(instr.ops, instr.step))
+    }),
+    ("call", |process, instr| {
+        next(process.execute_inst_call(instr.ops, instr.step))
+    }),
+    ("ret", |process, instr| {
+        next(process.execute_inst_ret(instr.ops))
+    }),
+    ("mstore", |process, instr| {
+        next(process.execute_inst_mstore(instr.ops, instr.step))
+    }),
+    ("mload", |process, instr| {
+        next(process.execute_inst_mload(instr.ops, instr.step))
+    }),
+    ("range", |process, instr| {
+        next(process.execute_inst_range(instr.program, instr.ops, instr.step))
+    }),
+    ("and", |process, instr| {
+        next(process.execute_inst_bitwise(instr.program, instr.ops, instr.step))
+    }),
+    ("or", |process, instr| {
+        next(process.execute_inst_bitwise(instr.program, instr.ops, instr.step))
+    }),
+    ("xor", |process, instr| {
+        next(process.execute_inst_bitwise(instr.program, instr.ops, instr.step))
+    }),
+    ("gte", |process, instr| {
+        next(process.execute_inst_gte(instr.program, instr.ops, instr.step))
+    }),
+    ("isqrt", |process, instr| {
+        next(process.execute_inst_isqrt(instr.program, instr.ops, instr.step))
+    }),
+    ("cas", |process, instr| {
+        next(process.execute_inst_cas(instr.program, instr.ops, instr.step))
+    }),
+    ("loadp", |process, instr| {
+        next(process.execute_inst_loadp(instr.ops, instr.step))
+    }),
+    ("require", |process, instr| {
+        if !process.execute_inst_require(instr.ops, instr.step)? {
+            return Ok(StepOutcome::Next);
+        }
+        // Halt like `end`, the row keeps the require opcode.
+        let end_step = process.execute_inst_end(
+            instr.program,
+            instr.pc_status,
+            instr.ctx_regs_status,
+            instr.registers_status,
+            instr.ctx_code_regs_status,
+        )?;
+        Ok(StepOutcome::Halt {
+            end_step,
+            reverted: true,
+        })
+    }),
+    ("end", |process, instr| {
+        process.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::END as u8);
+        let end_step = process.execute_inst_end(
+            instr.program,
+            instr.pc_status,
+            instr.ctx_regs_status,
+            instr.registers_status,
+            instr.ctx_code_regs_status,
+        )?;
+        Ok(StepOutcome::Halt {
+            end_step,
+            reverted: false,
+        })
+    }),
+    ("sstore", |process, instr| {
+        next(process.execute_inst_sstore(
+            instr.program,
+            instr.tx_cache_manager,
+            instr.account_tree,
+            instr.aux_steps,
+            instr.ops,
+            instr.step,
+            instr.ctx_regs_status,
+            instr.registers_status,
+            instr.ctx_code_regs_status,
+        ))
+    }),
+    ("sload", |process, instr| {
+        next(process.execute_inst_sload(
+            instr.program,
+            instr.tx_cache_manager,
+            instr.account_tree,
+            instr.aux_steps,
+            instr.ops,
+            instr.step,
+            instr.ctx_regs_status,
+            instr.registers_status,
+            instr.ctx_code_regs_status,
+        ))
+    }),
+    ("poseidon", |process, instr| {
+        next(process.execute_inst_poseidon(instr.program, instr.ops, instr.step))
+    }),
+    ("tload", |process, instr| {
+        next(process.execute_inst_tload(
+            instr.program,
+            instr.aux_steps,
+            instr.ops,
+            instr.step,
+            instr.ctx_regs_status,
+            instr.registers_status,
+            instr.ctx_code_regs_status,
+        ))
+    }),
+    ("tstore", |process, instr| {
+        next(process.execute_inst_tstore(
+            instr.aux_steps,
+            instr.ops,
+            instr.step,
+            instr.ctx_regs_status,
+            instr.registers_status,
+            instr.ctx_code_regs_status,
+        ))
+    }),
+    ("sccall", |process, instr| {
+        let state = process.execute_inst_sccall(
+            instr.program,
+            instr.ops,
+            instr.step,
+            instr.pc_status,
+            instr.ctx_regs_status,
+            instr.registers_status,
+            instr.ctx_code_regs_status,
+        )?;
+        Ok(StepOutcome::Exit(state))
+    }),
+    ("sigcheck", |process, instr| {
+        next(process.execute_inst_sigcheck(
+            instr.program,
+            instr.aux_steps,
+            instr.ops,
+            instr.step,
+            instr.ctx_regs_status,
+            instr.registers_status,
+            instr.ctx_code_regs_status,
+        ))
+    }),
+];
+
+/// Handler of the instruction `mnemonic`, in lower case.
+pub(crate) fn handler(mnemonic: &str) -> Option<Handler> {
+    HANDLERS
+        .iter()
+        .find(|(name, _)| *name == mnemonic)
+        .map(|(_, handler)| *handler)
+}
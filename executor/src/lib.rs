@@ -1,18 +1,22 @@
 #![feature(const_trait_impl)]
 
 use crate::decode::{decode_raw_instruction, REG_NOT_USED};
+use crate::dispatch::{DecodedInstr, StepOutcome};
 use crate::storage::StorageTree;
 use core::vm::error::ProcessorError;
-use core::vm::memory::{MemoryTree, HP_START_ADDR, PSP_START_ADDR};
+use core::vm::memory::{
+    MemoryTree, DATA_START_ADDR, FP_START_ADDR, HP_START_ADDR, MEM_OP_DATA, MEM_OP_INIT,
+    PSP_START_ADDR,
+};
 
 use core::merkle_tree::log::{StorageLog, StorageQuery};
 use core::merkle_tree::log::{StorageLogKind, WitnessStorageLog};
 use core::merkle_tree::tree::AccountTree;
 
 use core::program::instruction::IMM_INSTRUCTION_LEN;
-use core::program::instruction::{ImmediateOrRegName, Opcode};
+use core::program::instruction::{ImmediateOrRegName, Opcode, OPCODE_FIELD_BITS_MASK};
 use core::program::{Program, REGISTER_NUM};
-use core::trace::trace::{ComparisonOperation, RegisterSelector};
+use core::trace::trace::{ComparisonOperation, RegisterSelector, Trace};
 use core::trace::trace::{FilterLockForMain, MemoryOperation, MemoryType};
 use core::types::account::AccountTreeId;
 
@@ -21,8 +25,8 @@ use core::crypto::poseidon_trace::{
     calculate_poseidon_and_generate_intermediate_trace, POSEIDON_INPUT_VALUE_LEN,
     POSEIDON_OUTPUT_VALUE_LEN,
 };
-use core::program::binary_program::OlaProphet;
 use core::program::binary_program::OlaProphetInput;
+use core::program::binary_program::{HostAssert, OlaProphet};
 use core::types::account::Address;
 use core::types::merkle_tree::tree_key_to_leaf_index;
 use core::types::merkle_tree::{tree_key_default, TreeKey, TreeValue};
@@ -37,7 +41,9 @@ use plonky2::field::goldilocks_field::GoldilocksField;
 use plonky2::field::types::Field64;
 use plonky2::field::types::{Field, PrimeField64};
 use regex::Regex;
-use std::collections::{BTreeMap, HashMap};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ops::ControlFlow;
 
 use crate::ecdsa::ecdsa_verify;
 use crate::load_tx::append_caller_callee_addr;
@@ -47,13 +53,16 @@ use core::memory_zone_process;
 use core::trace::trace::Step;
 use core::vm::vm_state::SCCallType;
 use core::vm::vm_state::VMState;
-use core::vm::vm_state::VMState::ExeEnd;
+use core::vm::vm_state::VMState::{ExeEnd, Reverted};
+use core::vm::vm_state::CALL_DEPTH;
 use std::time::Instant;
 
 mod decode;
+mod dispatch;
 
 mod ecdsa;
 pub mod load_tx;
+pub mod run;
 pub mod storage;
 mod tape;
 #[cfg(test)]
@@ -216,6 +225,20 @@ impl TxScopeCacheManager {
         self.storage_cache.insert(key, value);
     }
 }
+/// How often an opcode was executed, how many of those executions carried an
+/// immediate and how many extra cpu rows (e.g. the per-word rows of tload)
+/// they generated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OpcodeCount {
+    pub count: usize,
+    pub imm: usize,
+    pub ext_rows: usize,
+}
+
+/// Number of bits of the opcode field, one per opcode. The size of
+/// `Process::opcode_histogram`.
+pub const OPCODE_BIT_NUM: usize = OPCODE_FIELD_BITS_MASK.count_ones() as usize;
+
 #[derive(Debug, Clone)]
 pub struct Process {
     pub block_timestamp: u64,
@@ -243,6 +266,95 @@ pub struct Process {
     pub storage_access_idx: GoldilocksField,
     pub storage_queries: Vec<StorageQuery>,
     pub return_data: Vec<GoldilocksField>,
+    /// Executions of each opcode, indexed by `OlaOpcode::binary_bit_shift`.
+    pub opcode_histogram: [OpcodeCount; OPCODE_BIT_NUM],
+    /// Registers holding values derived from a secret. Tag the secret inputs
+    /// before `execute`, which then fails on any branch or comparison
+    /// depending on them.
+    pub tainted_registers: [bool; REGISTER_NUM],
+    /// Memory addresses holding values derived from a secret, tracked like
+    /// `tainted_registers`. While anything is tainted, each step scans the
+    /// memory trace for its accesses.
+    pub tainted_memory: BTreeSet<u64>,
+    /// Reject undefined behavior instead of executing it, see
+    /// `execute_strict`.
+    pub strict: bool,
+    /// Rows `execute` reserves in the trace before running, see
+    /// `with_capacity`.
+    pub trace_capacity: TraceCapacity,
+    /// Calls not returned from yet, 0 in the entry function. Prophets read it
+    /// as `CALL_DEPTH`, it is not part of the trace.
+    pub call_depth: u64,
+    /// `assert_eq` checks that didn't hold, in execution order. They don't
+    /// stop the run and are not part of the trace.
+    pub host_assert_failures: Vec<HostAssertFailure>,
+    /// Debug mode: `execute_with_hook` tells its hook when the next step is
+    /// a `brk`. Otherwise `brk` is the nop it is encoded
+    /// as, the trace is the same either way.
+    pub debug: bool,
+    /// Stack words the run starts from, see `with_initial_memory`.
+    pub initial_memory: BTreeMap<u64, u64>,
+}
+
+/// Rows to reserve in each vector of a trace, so a run filling it up to them
+/// doesn't grow the vectors one push at a time.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TraceCapacity {
+    pub exec: usize,
+    pub memory: usize,
+    pub builtin_rangecheck: usize,
+    pub builtin_bitwise_combined: usize,
+    pub builtin_cmp: usize,
+    pub builtin_poseidon: usize,
+    pub builtin_poseidon_chunk: usize,
+    pub builtin_storage_hash: usize,
+    pub tape: usize,
+    pub sc_call: usize,
+}
+
+impl TraceCapacity {
+    /// Reserve exactly the rows in the vectors of `trace`, on top of those
+    /// they hold.
+    pub fn reserve(&self, trace: &mut Trace) {
+        trace.exec.reserve_exact(self.exec);
+        trace.memory.reserve_exact(self.memory);
+        trace
+            .builtin_rangecheck
+            .reserve_exact(self.builtin_rangecheck);
+        trace
+            .builtin_bitwise_combined
+            .reserve_exact(self.builtin_bitwise_combined);
+        trace.builtin_cmp.reserve_exact(self.builtin_cmp);
+        trace.builtin_poseidon.reserve_exact(self.builtin_poseidon);
+        trace
+            .builtin_poseidon_chunk
+            .reserve_exact(self.builtin_poseidon_chunk);
+        trace
+            .builtin_storage_hash
+            .reserve_exact(self.builtin_storage_hash);
+        trace.tape.reserve_exact(self.tape);
+        trace.sc_call.reserve_exact(self.sc_call);
+    }
+}
+
+/// An `assert_eq` of the asm not holding when the executor reached it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostAssertFailure {
+    /// Pc of the instruction following the assert.
+    pub pc: u64,
+    pub register: usize,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+impl std::fmt::Display for HostAssertFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "host assert failed at pc {}: r{} is {}, expected {}",
+            self.pc, self.register, self.actual, self.expected
+        )
+    }
 }
 
 impl Process {
@@ -254,7 +366,11 @@ impl Process {
             clk: 0,
             addr_storage: Address::default(),
             addr_code: Address::default(),
-            registers: [Default::default(); REGISTER_NUM],
+            registers: {
+                let mut registers = [GoldilocksField::ZERO; REGISTER_NUM];
+                registers[FP_REG_INDEX] = GoldilocksField(FP_START_ADDR);
+                registers
+            },
             register_selector: Default::default(),
             pc: 0,
             instruction: Default::default(),
@@ -279,6 +395,54 @@ impl Process {
             storage_access_idx: GoldilocksField::ZERO,
             storage_queries: Vec::new(),
             return_data: Vec::new(),
+            opcode_histogram: [OpcodeCount::default(); OPCODE_BIT_NUM],
+            tainted_registers: [false; REGISTER_NUM],
+            tainted_memory: BTreeSet::new(),
+            strict: false,
+            trace_capacity: TraceCapacity::default(),
+            call_depth: 0,
+            host_assert_failures: Vec::new(),
+            debug: false,
+            initial_memory: BTreeMap::new(),
+        }
+    }
+
+    /// A process reserving `capacity` rows in the trace of the program it
+    /// executes, instead of growing it step by step. The rows of a previous
+    /// run of the same program are given by
+    /// `PredictTraceSizes::predict_capacity` in the circuits crate.
+    pub fn with_capacity(capacity: TraceCapacity) -> Self {
+        Self {
+            trace_capacity: capacity,
+            ..Self::new()
+        }
+    }
+
+    /// A process starting from `registers` instead of zero registers, the
+    /// last one being fp. Keep `FP_START_ADDR` there for a program using the
+    /// stack. A proof of the run makes them public, see `initial_registers`
+    /// in the circuits crate's `PublicValues`.
+    pub fn with_initial_registers(registers: [u64; REGISTER_NUM]) -> Self {
+        Self {
+            registers: registers.map(GoldilocksField::from_canonical_u64),
+            ..Self::new()
+        }
+    }
+
+    /// A process starting from the stack words `memory`, by address, instead
+    /// of zero initialized memory, e.g. the state a previous run left. The
+    /// words are written before the first instruction with the op
+    /// `MEM_OP_INIT`, which the cpu doesn't look up. A proof of the run makes
+    /// the memory it starts from and ends with public, see `initial_memory`
+    /// in the circuits crate's `PublicValues`.
+    pub fn with_initial_memory(memory: BTreeMap<u64, u64>) -> Self {
+        assert!(
+            memory.keys().all(|addr| *addr < HP_START_ADDR),
+            "initial memory out of the stack"
+        );
+        Self {
+            initial_memory: memory,
+            ..Self::new()
         }
     }
 
@@ -319,6 +483,97 @@ impl Process {
         }
     }
 
+    fn any_tainted(&self, ops: &[&str]) -> bool {
+        ops.iter()
+            .any(|op| match op.strip_prefix('r').map(str::parse::<usize>) {
+                Some(Ok(index)) if index < REGISTER_NUM => self.tainted_registers[index],
+                _ => false,
+            })
+    }
+
+    fn is_tracking_taint(&self) -> bool {
+        self.tainted_registers.contains(&true) || !self.tainted_memory.is_empty()
+    }
+
+    /// Reject control flow and comparisons depending on a tainted value,
+    /// before the instruction executes.
+    fn check_taint(&self, opcode: &str, ops: &[&str]) -> Result<(), ProcessorError> {
+        match opcode {
+            "cjmp" | "jmp" | "call" | "eq" | "neq" | "gte" | "cas" | "require" => {
+                if self.any_tainted(&ops[1..]) {
+                    return Err(ProcessorError::SecretDependentBranch(ops.join(" ")));
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Addresses the instruction that just executed read and wrote, the
+    /// accesses of its own clk and, for the cas write, the next one. Those
+    /// seeding memory before the run are not looked up by the cpu and left
+    /// out.
+    fn step_memory_accesses(&self) -> (Vec<u64>, Vec<u64>) {
+        let mut reads = Vec::new();
+        let mut writes = Vec::new();
+        for (addr, cells) in &self.memory.trace {
+            let mut step_cells = cells.iter().rev().take_while(|cell| {
+                cell.clk >= self.clk
+                    && cell.env_idx == self.env_idx
+                    && cell.filter_looked_for_main == GoldilocksField::ONE
+            });
+            let mut accessed = false;
+            let mut written = false;
+            for cell in &mut step_cells {
+                accessed = true;
+                written |= cell.is_write == GoldilocksField::ONE;
+            }
+            match (accessed, written) {
+                (_, true) => writes.push(*addr),
+                (true, false) => reads.push(*addr),
+                _ => {}
+            }
+        }
+        (reads, writes)
+    }
+
+    /// Propagate taint once the instruction executed: a register or memory
+    /// word it writes is tainted if an operand register or a word it read
+    /// is. `tainted_registers` still holds the taint before the instruction.
+    fn track_taint(&mut self, opcode: &str, ops: &[&str]) {
+        let (reads, writes) = self.step_memory_accesses();
+        let read_tainted = reads.iter().any(|addr| self.tainted_memory.contains(addr));
+        // Memory writes depend on every operand, the address ones included.
+        let written_tainted = read_tainted || self.any_tainted(&ops[1..]);
+        for addr in writes {
+            if written_tainted {
+                self.tainted_memory.insert(addr);
+            } else {
+                self.tainted_memory.remove(&addr);
+            }
+        }
+        match opcode {
+            "add" | "mul" | "sub" | "subu" | "and" | "or" | "xor" | "mov" | "not" | "isqrt"
+            | "mload" | "sigcheck" => {
+                let dst_index = self.get_reg_index(ops[1]);
+                self.tainted_registers[dst_index] = read_tainted || self.any_tainted(&ops[2..]);
+            }
+            "loadp" => {
+                let tainted = read_tainted || self.any_tainted(&ops[3..]);
+                let dst_index = self.get_reg_index(ops[1]);
+                let op1_index = self.get_reg_index(ops[2]);
+                self.tainted_registers[dst_index] = tainted;
+                self.tainted_registers[op1_index] = tainted;
+            }
+            "swap" => {
+                let op0_index = self.get_reg_index(ops[1]);
+                let op1_index = self.get_reg_index(ops[2]);
+                self.tainted_registers.swap(op0_index, op1_index);
+            }
+            _ => {}
+        }
+    }
+
     pub fn read_prophet_input(
         &mut self,
         input: &OlaProphetInput,
@@ -406,6 +661,7 @@ impl Process {
         }
 
         prophet.ctx.push((HEAP_PTR.to_string(), self.hp.0));
+        prophet.ctx.push((CALL_DEPTH.to_string(), self.call_depth));
         let out = interpreter
             .run(prophet, values, &self.memory)
             .map_err(|err| ProcessorError::InterpreterRunError(err))?;
@@ -444,6 +700,45 @@ impl Process {
         Ok(())
     }
 
+    /// Write the data section at `DATA_START_ADDR` in the write-once region,
+    /// so it is read by `mload` and `mstore` to it fails. The writes have the
+    /// op `MEM_OP_DATA` and are looked up in the code of the program.
+    fn load_data(&mut self, data: &[u64]) {
+        for (index, value) in data.iter().enumerate() {
+            self.memory.write(
+                DATA_START_ADDR + index as u64,
+                0, //write， clk is 0
+                GoldilocksField::from_canonical_u64(MEM_OP_DATA),
+                GoldilocksField::from_canonical_u64(MemoryType::WriteOnce as u64),
+                GoldilocksField::from_canonical_u64(MemoryOperation::Write as u64),
+                GoldilocksField::from_canonical_u64(FilterLockForMain::False as u64),
+                GoldilocksField::from_canonical_u64(1_u64),
+                GoldilocksField::from_canonical_u64(0_u64),
+                GoldilocksField::from_canonical_u64(*value),
+                self.env_idx,
+            );
+        }
+        self.psp = GoldilocksField(DATA_START_ADDR + data.len() as u64);
+        self.psp_start = self.psp;
+    }
+
+    fn load_initial_memory(&mut self) {
+        for (addr, value) in self.initial_memory.clone() {
+            self.memory.write(
+                addr,
+                0, //write， clk is 0
+                GoldilocksField::from_canonical_u64(MEM_OP_INIT),
+                GoldilocksField::from_canonical_u64(MemoryType::ReadWrite as u64),
+                GoldilocksField::from_canonical_u64(MemoryOperation::Write as u64),
+                GoldilocksField::from_canonical_u64(FilterLockForMain::False as u64),
+                GoldilocksField::from_canonical_u64(0_u64),
+                GoldilocksField::from_canonical_u64(0_u64),
+                GoldilocksField::from_canonical_u64(value),
+                self.env_idx,
+            );
+        }
+    }
+
     fn print_vm_state(&mut self, instruction: &str) {
         println!(
             "↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓ tp:{}, clk: {}, pc: {}, instruction: {} ↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓↓",
@@ -583,6 +878,17 @@ impl Process {
             format!("{} params len is 2", opcode.as_str())
         );
         let dst_index = self.get_reg_index(ops[1]);
+        if ops[2] == "clk" {
+            // The clk operand is marked by selecting dst as op0, op1 is unused.
+            self.register_selector.op0 = self.registers[dst_index];
+            self.register_selector.op0_reg_sel[dst_index] = GoldilocksField::ONE;
+            self.registers[dst_index] = GoldilocksField::from_canonical_u64(self.clk as u64);
+            self.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::MOV as u8);
+            self.register_selector.dst = self.registers[dst_index];
+            self.register_selector.dst_reg_sel[dst_index] = GoldilocksField::ONE;
+            self.pc += step;
+            return Ok(());
+        }
         let value = self.get_index_value(ops[2])?;
         self.register_selector.op1 = value.0;
         if let ImmediateOrRegName::RegName(op1_index) = value.1 {
@@ -711,6 +1017,25 @@ impl Process {
         Ok(())
     }
 
+    /// Continue when the operand is nonzero, keeping its inverse in aux0 for
+    /// the circuit. Returns whether the program reverts instead.
+    fn execute_inst_require(&mut self, ops: &[&str], step: u64) -> Result<bool, ProcessorError> {
+        assert_eq!(ops.len(), 2, "require params len is 2");
+        let value = self.get_index_value(ops[1])?;
+
+        self.register_selector.op1 = value.0;
+        if let ImmediateOrRegName::RegName(op1_index) = value.1 {
+            self.register_selector.op1_reg_sel[op1_index] = GoldilocksField::from_canonical_u64(1);
+        }
+        self.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::REQUIRE as u8);
+        if value.0.is_zero() {
+            return Ok(true);
+        }
+        self.register_selector.aux0 = value.0.inverse();
+        self.pc += step;
+        Ok(false)
+    }
+
     fn execute_inst_cjmp(&mut self, ops: &[&str], step: u64) -> Result<(), ProcessorError> {
         let opcode = ops
             .first()
@@ -742,6 +1067,39 @@ impl Process {
         Ok(())
     }
 
+    fn execute_inst_swap(&mut self, ops: &[&str], step: u64) -> Result<(), ProcessorError> {
+        let opcode = ops
+            .first()
+            .ok_or(ProcessorError::ArrayIndexError(String::from(
+                "Empty instructions",
+            )))?
+            .to_lowercase();
+        assert_eq!(
+            ops.len(),
+            3,
+            "{}",
+            format!("{} params len is 2", opcode.as_str())
+        );
+        let op0_index = self.get_reg_index(ops[1]);
+        let op1_index = self.get_reg_index(ops[2]);
+        if op0_index >= REGISTER_NUM {
+            return Err(ProcessorError::RegIndexError(op0_index));
+        }
+        if op1_index >= REGISTER_NUM {
+            return Err(ProcessorError::RegIndexError(op1_index));
+        }
+
+        self.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::SWAP as u8);
+        self.register_selector.op0 = self.registers[op0_index];
+        self.register_selector.op1 = self.registers[op1_index];
+        self.register_selector.op0_reg_sel[op0_index] = GoldilocksField::from_canonical_u64(1);
+        self.register_selector.op1_reg_sel[op1_index] = GoldilocksField::from_canonical_u64(1);
+        self.registers.swap(op0_index, op1_index);
+
+        self.pc += step;
+        Ok(())
+    }
+
     fn execute_inst_jmp(&mut self, ops: &[&str]) -> Result<(), ProcessorError> {
         let opcode = ops
             .first()
@@ -803,6 +1161,10 @@ impl Process {
                 );
                 self.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::MUL as u8);
             }
+            "sub" => {
+                self.registers[dst_index] = self.registers[op0_index] - op1_value.0;
+                self.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::SUB as u8);
+            }
             _ => return Err(ProcessorError::ParseOpcodeError),
         };
 
@@ -845,6 +1207,7 @@ impl Process {
         let fp_addr = self.registers[FP_REG_INDEX].0 - 2;
         memory_op!(self, fp_addr, self.register_selector.aux1, Opcode::CALL);
         self.pc = call_addr.0 .0;
+        self.call_depth += 1;
         Ok(())
     }
 
@@ -862,6 +1225,7 @@ impl Process {
         memory_op!(self, fp_addr, self.registers[FP_REG_INDEX], Opcode::RET);
         self.register_selector.dst = GoldilocksField::from_canonical_u64(self.pc);
         self.register_selector.aux1 = self.registers[FP_REG_INDEX];
+        self.call_depth = self.call_depth.saturating_sub(1);
         Ok(())
     }
 
@@ -1183,6 +1547,223 @@ impl Process {
         Ok(())
     }
 
+    /// `subu`, the u32 subtraction: `dst = op0 - op1 + borrow * 2^32`, the
+    /// borrow in aux0 set when `op1 > op0`. dst is range checked, which makes
+    /// the borrow the only one giving a u32.
+    fn execute_inst_sub_u32(
+        &mut self,
+        program: &mut Program,
+        ops: &[&str],
+        step: u64,
+    ) -> Result<(), ProcessorError> {
+        assert_eq!(ops.len(), 4, "subu params len is 3");
+        let dst_index = self.get_reg_index(ops[1]);
+        let op0_index = self.get_reg_index(ops[2]);
+        let op1_value = self.get_index_value(ops[3])?;
+        let op0 = self.registers[op0_index].to_canonical_u64();
+        let op1 = op1_value.0.to_canonical_u64();
+        if op0 > u32::MAX as u64 || op1 > u32::MAX as u64 {
+            return Err(ProcessorError::U32RangeCheckFail);
+        }
+
+        self.register_selector.op0 = self.registers[op0_index];
+        self.register_selector.op1 = op1_value.0;
+        self.register_selector.op0_reg_sel[op0_index] = GoldilocksField::from_canonical_u64(1);
+        if let ImmediateOrRegName::RegName(op1_index) = op1_value.1 {
+            self.register_selector.op1_reg_sel[op1_index] = GoldilocksField::from_canonical_u64(1);
+        }
+
+        let (dst, borrow) = (op0 as u32).overflowing_sub(op1 as u32);
+        self.registers[dst_index] = GoldilocksField::from_canonical_u64(dst as u64);
+        self.register_selector.aux0 = GoldilocksField::from_bool(borrow);
+        self.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::SUB as u8);
+        self.register_selector.dst = self.registers[dst_index];
+        self.register_selector.dst_reg_sel[dst_index] = GoldilocksField::from_canonical_u64(1);
+
+        if !program.pre_exe_flag {
+            program.trace.insert_rangecheck(
+                self.registers[dst_index],
+                (
+                    GoldilocksField::ZERO,
+                    GoldilocksField::ONE,
+                    GoldilocksField::ZERO,
+                    GoldilocksField::ZERO,
+                    GoldilocksField::ZERO,
+                ),
+            );
+        }
+        self.pc += step;
+        Ok(())
+    }
+
+    fn execute_inst_isqrt(
+        &mut self,
+        program: &mut Program,
+        ops: &[&str],
+        step: u64,
+    ) -> Result<(), ProcessorError> {
+        assert_eq!(ops.len(), 3, "isqrt params len is 2");
+        let dst_index = self.get_reg_index(ops[1]);
+        let value = self.get_index_value(ops[2])?;
+        self.register_selector.op1 = value.0;
+        if let ImmediateOrRegName::RegName(op1_index) = value.1 {
+            self.register_selector.op1_reg_sel[op1_index] = GoldilocksField::from_canonical_u64(1);
+        }
+
+        let a = value.0.to_canonical_u64();
+        // Start from the float estimate and correct it, f64 loses precision
+        // above 2^53.
+        let mut root = (a as f64).sqrt() as u64;
+        while root.checked_mul(root).map_or(true, |square| square > a) {
+            root -= 1;
+        }
+        while (root + 1)
+            .checked_mul(root + 1)
+            .map_or(false, |square| square <= a)
+        {
+            root += 1;
+        }
+
+        self.registers[dst_index] = GoldilocksField::from_canonical_u64(root);
+        self.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::ISQRT as u8);
+        self.register_selector.dst = self.registers[dst_index];
+        self.register_selector.dst_reg_sel[dst_index] = GoldilocksField::from_canonical_u64(1);
+        // a = root^2 + e with e <= 2 * root, split as e = 2q + s and q =
+        // q_lo + q_hi * 2^31; op0 = s, aux0 = q_lo, aux1 = q_hi.
+        let e = a - root * root;
+        let (q, s) = (e >> 1, e & 1);
+        let (q_lo, q_hi) = (q & ((1 << 31) - 1), q >> 31);
+        self.register_selector.op0 = GoldilocksField::from_canonical_u64(s);
+        self.register_selector.aux0 = GoldilocksField::from_canonical_u64(q_lo);
+        self.register_selector.aux1 = GoldilocksField::from_canonical_u64(q_hi);
+
+        if !program.pre_exe_flag {
+            for checked in [2 * q_lo, root + q_hi, root - q - s] {
+                program.trace.insert_rangecheck(
+                    GoldilocksField::from_canonical_u64(checked),
+                    (
+                        GoldilocksField::ZERO,
+                        GoldilocksField::ONE,
+                        GoldilocksField::ZERO,
+                        GoldilocksField::ZERO,
+                        GoldilocksField::ZERO,
+                    ),
+                );
+            }
+        }
+        self.pc += step;
+        Ok(())
+    }
+
+    fn execute_inst_cas(
+        &mut self,
+        program: &mut Program,
+        ops: &[&str],
+        step: u64,
+    ) -> Result<(), ProcessorError> {
+        assert_eq!(ops.len(), 4, "cas params len is 3");
+        let dst_index = self.get_reg_index(ops[1]);
+        let op0_index = self.get_reg_index(ops[2]);
+        let value = self.get_index_value(ops[3])?;
+
+        self.register_selector.op0 = self.registers[op0_index];
+        self.register_selector.op1 = value.0;
+        self.register_selector.op0_reg_sel[op0_index] = GoldilocksField::from_canonical_u64(1);
+        if let ImmediateOrRegName::RegName(op1_index) = value.1 {
+            self.register_selector.op1_reg_sel[op1_index] = GoldilocksField::from_canonical_u64(1);
+        }
+
+        // The read is an mload at clk, the conditional write an mstore at
+        // clk + 1 so that the memory table orders it after the read. The
+        // main loop skips that clk.
+        let addr = self.register_selector.op0.to_canonical_u64();
+        let loaded;
+        memory_op!(self, addr, loaded, Opcode::MLOAD);
+        let expected = self.registers[dst_index];
+        let success = loaded == expected;
+        if success {
+            self.clk += 1;
+            memory_op!(
+                self,
+                addr,
+                value.0,
+                Opcode::MSTORE,
+                return Err(ProcessorError::MemVistInv(addr))
+            );
+            self.clk -= 1;
+        }
+
+        // Equality is proven by two comparisons, loaded >= expected and
+        // expected >= loaded, so both values must be within u32 of each other.
+        if !program.pre_exe_flag {
+            for (op0, op1) in [(loaded, expected), (expected, loaded)] {
+                let gte = op0.to_canonical_u64() >= op1.to_canonical_u64();
+                let abs_diff = if gte { op0 - op1 } else { op1 - op0 };
+                if abs_diff.to_canonical_u64() > u32::MAX as u64 {
+                    return Err(ProcessorError::U32RangeCheckFail);
+                }
+                program.trace.insert_rangecheck(
+                    abs_diff,
+                    (
+                        GoldilocksField::ZERO,
+                        GoldilocksField::ZERO,
+                        GoldilocksField::ONE,
+                        GoldilocksField::ZERO,
+                        GoldilocksField::ZERO,
+                    ),
+                );
+                program.trace.insert_cmp(
+                    op0,
+                    op1,
+                    GoldilocksField::from_canonical_u64(gte as u64),
+                    abs_diff,
+                    GoldilocksField::ONE,
+                );
+            }
+        }
+
+        self.register_selector.aux0 = expected;
+        self.register_selector.aux1 = loaded;
+        self.registers[dst_index] = GoldilocksField::from_canonical_u64(success as u64);
+        self.register_selector.dst = self.registers[dst_index];
+        self.register_selector.dst_reg_sel[dst_index] = GoldilocksField::from_canonical_u64(1);
+        self.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::CAS as u8);
+
+        self.pc += step;
+        Ok(())
+    }
+
+    fn execute_inst_loadp(&mut self, ops: &[&str], step: u64) -> Result<(), ProcessorError> {
+        assert_eq!(ops.len(), 4, "loadp params len is 3");
+        let dst_index = self.get_reg_index(ops[1]);
+        let op1_index = self.get_reg_index(ops[2]);
+        let op0_index = self.get_reg_index(ops[3]);
+        if dst_index == op1_index {
+            return Err(ProcessorError::MloadError(format!(
+                "loadp destinations should differ: {}",
+                ops.join(" ")
+            )));
+        }
+
+        self.register_selector.op0 = self.registers[op0_index];
+        self.register_selector.op1 = self.registers[op1_index];
+        self.register_selector.op0_reg_sel[op0_index] = GoldilocksField::from_canonical_u64(1);
+        self.register_selector.op1_reg_sel[op1_index] = GoldilocksField::from_canonical_u64(1);
+
+        let addr_lo = self.register_selector.op0.to_canonical_u64();
+        let addr_hi = (self.register_selector.op0 + GoldilocksField::ONE).to_canonical_u64();
+        memory_op!(self, addr_lo, self.registers[dst_index], Opcode::MLOAD);
+        memory_op!(self, addr_hi, self.registers[op1_index], Opcode::MLOAD);
+
+        self.register_selector.dst = self.registers[dst_index];
+        self.register_selector.dst_reg_sel[dst_index] = GoldilocksField::from_canonical_u64(1);
+        self.register_selector.aux1 = self.registers[op1_index];
+        self.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::LOADP as u8);
+
+        self.pc += step;
+        Ok(())
+    }
+
     fn execute_inst_end(
         &mut self,
         program: &mut Program,
@@ -1191,8 +1772,6 @@ impl Process {
         registers_status: &[GoldilocksField; REGISTER_NUM],
         ctx_code_regs_status: &Address,
     ) -> Result<Option<Step>, ProcessorError> {
-        self.opcode = GoldilocksField::from_canonical_u64(1 << Opcode::END as u8);
-
         let mut len = GoldilocksField::ZERO;
         if self.tp.to_canonical_u64() > 0 {
             len = self
@@ -1354,7 +1933,7 @@ impl Process {
         if !program.pre_exe_flag {
             self.storage_log.push(WitnessStorageLog {
                 storage_log: StorageLog::new_write(kind, tree_key, store_value),
-                previous_value: tree_key_default(),
+                previous_value: pre_value,
             });
 
             program.trace.builtin_poseidon.push(hash_row);
@@ -2071,12 +2650,111 @@ impl Process {
         Ok(())
     }
 
+    /// Check the `assert_eq` lines preceding the instruction at `pc`, keeping
+    /// the ones not holding in `host_assert_failures`.
+    fn check_host_asserts(&mut self, pc: u64, asserts: &[HostAssert]) {
+        for assert in asserts {
+            let actual = self.registers[assert.register].to_canonical_u64();
+            if actual != assert.expected {
+                let failure = HostAssertFailure {
+                    pc,
+                    register: assert.register,
+                    expected: assert.expected,
+                    actual,
+                };
+                debug!("{}", failure);
+                self.host_assert_failures.push(failure);
+            }
+        }
+    }
+
+    /// Check the undefined behaviors detectable before running `ops`, see
+    /// `execute_strict`.
+    fn check_strict(&self, opcode: &str, ops: &[&str]) -> Result<(), ProcessorError> {
+        let opcode_bits = self.instruction.to_canonical_u64() & OPCODE_FIELD_BITS_MASK;
+        if opcode_bits.count_ones() != 1 {
+            return Err(ProcessorError::UndefinedBehavior(format!(
+                "unallocated opcode bits {:#x}: {}",
+                opcode_bits,
+                ops.join(" ")
+            )));
+        }
+        if let "add" | "mul" | "sub" = opcode {
+            // Operands are read as signed words, so `add r9 r9 -3` stays legal.
+            let signed = |value: GoldilocksField| {
+                let value = value.to_canonical_u64();
+                if value > GoldilocksField::ORDER / 2 {
+                    value as i128 - GoldilocksField::ORDER as i128
+                } else {
+                    value as i128
+                }
+            };
+            let op0 = signed(self.registers[self.get_reg_index(ops[2])]);
+            let op1 = signed(self.get_index_value(ops[3])?.0);
+            let res = match opcode {
+                "add" => op0 + op1,
+                "mul" => op0 * op1,
+                _ => op0 - op1,
+            };
+            if res.unsigned_abs() > (GoldilocksField::ORDER / 2) as u128 {
+                return Err(ProcessorError::UndefinedBehavior(format!(
+                    "word overflow: {}",
+                    ops.join(" ")
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as `execute`, but fails with `ProcessorError::UndefinedBehavior`
+    /// on programs whose result is only defined by the current
+    /// implementation:
+    /// - `mload`, `loadp` or `cas` reading an address never written, which
+    ///   `execute` reads as zero.
+    /// - `add`, `mul` or `sub` whose result, with the operands read as signed
+    ///   words, is out of `-(p-1)/2..=(p-1)/2` and wraps around the field.
+    /// - an instruction word with no or several opcode bits set, which decoding
+    ///   already rejects unless the program comes decoded.
+    pub fn execute_strict(
+        &mut self,
+        program: &mut Program,
+        account_tree: &mut AccountTree,
+        tx_cache_manager: &mut TxScopeCacheManager,
+    ) -> Result<VMState, ProcessorError> {
+        self.strict = true;
+        let res = self.execute(program, account_tree, tx_cache_manager);
+        self.strict = false;
+        res
+    }
+
     pub fn execute(
         &mut self,
         program: &mut Program,
         account_tree: &mut AccountTree,
         tx_cache_manager: &mut TxScopeCacheManager,
     ) -> Result<VMState, ProcessorError> {
+        self.execute_with_hook(program, account_tree, tx_cache_manager, |_, _| {
+            ControlFlow::Continue(())
+        })
+    }
+
+    /// Same as `execute`, but calls `hook` before each instruction with the
+    /// step it is about to record: clk, pc, registers and instruction are
+    /// those of the row, the opcode and register selectors are only set once
+    /// it executes and are left zero. Returning `ControlFlow::Break` stops
+    /// before that instruction with `VMState::Interrupted(pc)`, the trace
+    /// holding the rows executed so far. The hook's flag is set before a
+    /// `brk` in debug mode.
+    pub fn execute_with_hook(
+        &mut self,
+        program: &mut Program,
+        account_tree: &mut AccountTree,
+        tx_cache_manager: &mut TxScopeCacheManager,
+        mut hook: impl FnMut(&Step, bool) -> ControlFlow<()>,
+    ) -> Result<VMState, ProcessorError> {
+        if program.instructions.is_empty() {
+            return Err(ProcessorError::EmptyProgram);
+        }
         let instrs_len = program.instructions.len() as u64;
         // program.trace.raw_binary_instructions.clear();
         let start = Instant::now();
@@ -2098,6 +2776,8 @@ impl Process {
                 GoldilocksField(HP_START_ADDR + 1),
                 self.env_idx,
             );
+            self.load_data(&program.data);
+            self.load_initial_memory();
         }
         let decode_time = start.elapsed();
         debug!("decode_time: {}", decode_time.as_secs());
@@ -2112,19 +2792,11 @@ impl Process {
         // todo : why need clear?
         //self.storage_log.clear();
         let mut end_step = None;
-        let mut prog_hash_rows = calculate_arbitrary_poseidon_and_generate_intermediate_trace(
-            program
-                .instructions
-                .iter()
-                .map(|insts_str| {
-                    let inst = u64::from_str_radix(insts_str.trim_start_matches("0x"), 16)
-                        .map_err(|_| ProcessorError::ParseIntError)?;
-                    Ok(GoldilocksField::from_canonical_u64(inst))
-                })
-                .collect::<Result<Vec<_>, ProcessorError>>()?
-                .as_slice(),
-        )
-        .1;
+        let mut reverted = false;
+        let mut interrupted = None;
+        self.trace_capacity.reserve(&mut program.trace);
+        let mut prog_hash_rows =
+            calculate_arbitrary_poseidon_and_generate_intermediate_trace(&program.code_words()?).1;
         for row in &mut prog_hash_rows {
             row.filter_looked_normal = true;
         }
@@ -2147,6 +2819,14 @@ impl Process {
                 .ok_or(ProcessorError::PcVistInv(self.pc))?
                 .clone();
 
+            if let Some(asserts) = program
+                .host_asserts
+                .as_ref()
+                .and_then(|asserts| asserts.get(&(pc_status as usize)))
+            {
+                self.check_host_asserts(pc_status, asserts);
+            }
+
             // Print vm state for debug only.
             if program.print_flag {
                 self.print_vm_state(&instruction.0);
@@ -2163,95 +2843,92 @@ impl Process {
             let step = instruction.2;
             self.instruction = instruction.3;
             self.immediate_data = instruction.4;
+            let next_step = Step {
+                env_idx: self.env_idx,
+                call_sc_cnt: self.call_sc_cnt,
+                clk: self.clk,
+                pc: pc_status,
+                tp: tp_status,
+                addr_storage: ctx_regs_status,
+                addr_code: ctx_code_regs_status,
+                instruction: self.instruction,
+                immediate_data: self.immediate_data,
+                opcode: GoldilocksField::ZERO,
+                op1_imm: self.op1_imm,
+                regs: registers_status,
+                register_selector: RegisterSelector::default(),
+                is_ext_line: GoldilocksField::ZERO,
+                ext_cnt: GoldilocksField::ZERO,
+                filter_tape_looking: GoldilocksField::ZERO,
+                storage_access_idx: storage_acc_id_status,
+            };
+            let breakpoint = self.debug
+                && program
+                    .breakpoints
+                    .as_ref()
+                    .map_or(false, |pcs| pcs.contains(&(pc_status as usize)));
+            if hook(&next_step, breakpoint).is_break() {
+                interrupted = Some(pc_status);
+                break;
+            }
             debug!("execute opcode: {:?}", ops);
-            match opcode.as_str() {
-                //todo: not need move to arithmatic library
-                "mov" | "not" => self.execute_inst_mov_not(&ops, step)?,
-                "eq" | "neq" => self.execute_inst_eq_neq(&ops, step)?,
-                "assert" => self.execute_inst_assert(&ops, step)?,
-                "cjmp" => self.execute_inst_cjmp(&ops, step)?,
-                "jmp" => self.execute_inst_jmp(&ops)?,
-                "add" | "mul" | "sub" => self.execute_inst_arithmetic(&ops, step)?,
-                "call" => self.execute_inst_call(&ops, step)?,
-                "ret" => self.execute_inst_ret(&ops)?,
-                "mstore" => self.execute_inst_mstore(&ops, step)?,
-                "mload" => self.execute_inst_mload(&ops, step)?,
-                "range" => self.execute_inst_range(program, &ops, step)?,
-                "and" | "or" | "xor" => self.execute_inst_bitwise(program, &ops, step)?,
-                "gte" => self.execute_inst_gte(program, &ops, step)?,
-                "end" => {
-                    end_step = self.execute_inst_end(
-                        program,
-                        pc_status,
-                        &ctx_regs_status,
-                        &registers_status,
-                        &ctx_code_regs_status,
-                    )?;
+            // The opcode's histogram slot is the index of its bit.
+            let opcode_bit = (self.instruction.to_canonical_u64() & OPCODE_FIELD_BITS_MASK)
+                .trailing_zeros() as usize;
+            let opcode_count = self
+                .opcode_histogram
+                .get_mut(opcode_bit)
+                .ok_or(ProcessorError::ParseOpcodeError)?;
+            opcode_count.count += 1;
+            opcode_count.imm += instruction.1 as usize;
+            let tracking_taint = self.is_tracking_taint();
+            if tracking_taint {
+                self.check_taint(&opcode, &ops)?;
+            }
+            if self.strict {
+                self.check_strict(&opcode, &ops)?;
+            }
+            let mem_addrs = self.memory.trace.len();
+            let handler = dispatch::handler(&opcode).ok_or(ProcessorError::ParseOpcodeError)?;
+            let mut instr = DecodedInstr {
+                program,
+                account_tree,
+                tx_cache_manager,
+                aux_steps: &mut aux_steps,
+                ops: &ops,
+                step,
+                pc_status,
+                ctx_regs_status: &ctx_regs_status,
+                registers_status: &registers_status,
+                ctx_code_regs_status: &ctx_code_regs_status,
+            };
+            match handler(self, &mut instr)? {
+                StepOutcome::Next if tracking_taint => self.track_taint(&opcode, &ops),
+                StepOutcome::Next => {}
+                StepOutcome::Halt {
+                    end_step: halt_step,
+                    reverted: halt_reverted,
+                } => {
+                    end_step = halt_step;
+                    reverted = halt_reverted;
                     break;
                 }
-                "sstore" => self.execute_inst_sstore(
-                    program,
-                    tx_cache_manager,
-                    account_tree,
-                    &mut aux_steps,
-                    &ops,
-                    step,
-                    &ctx_regs_status,
-                    &registers_status,
-                    &ctx_code_regs_status,
-                )?,
-                "sload" => self.execute_inst_sload(
-                    program,
-                    tx_cache_manager,
-                    account_tree,
-                    &mut aux_steps,
-                    &ops,
-                    step,
-                    &ctx_regs_status,
-                    &registers_status,
-                    &ctx_code_regs_status,
-                )?,
-                "poseidon" => self.execute_inst_poseidon(program, &ops, step)?,
-                "tload" => self.execute_inst_tload(
-                    program,
-                    &mut aux_steps,
-                    &ops,
-                    step,
-                    &ctx_regs_status,
-                    &registers_status,
-                    &ctx_code_regs_status,
-                )?,
-                "tstore" => self.execute_inst_tstore(
-                    &mut aux_steps,
-                    &ops,
-                    step,
-                    &ctx_regs_status,
-                    &registers_status,
-                    &ctx_code_regs_status,
-                )?,
-                "sccall" => {
-                    return self.execute_inst_sccall(
-                        program,
-                        &ops,
-                        step,
-                        pc_status,
-                        &ctx_regs_status,
-                        &registers_status,
-                        &ctx_code_regs_status,
-                    )
-                }
-                "sigcheck" => self.execute_inst_sigcheck(
-                    program,
-                    &mut aux_steps,
-                    &ops,
-                    step,
-                    &ctx_regs_status,
-                    &registers_status,
-                    &ctx_code_regs_status,
-                )?,
-                _ => return Err(ProcessorError::ParseOpcodeError),
+                StepOutcome::Exit(state) => return Ok(state),
+            }
+
+            // A read of a new address adds it to the memory trace.
+            if self.strict
+                && matches!(opcode.as_str(), "mload" | "loadp" | "cas")
+                && self.memory.trace.len() > mem_addrs
+            {
+                return Err(ProcessorError::UndefinedBehavior(format!(
+                    "read of uninitialized memory: {}",
+                    ops.join(" ")
+                )));
             }
 
+            self.opcode_histogram[opcode_bit].ext_rows += aux_steps.len();
+
             if program.prophets.get(&pc_status).is_some() {
                 self.prophet(&mut program.prophets[&pc_status].clone())?
             }
@@ -2296,6 +2973,10 @@ impl Process {
             }
 
             self.clk += 1;
+            // cas wrote at the clk after its own.
+            if self.opcode == GoldilocksField::from_canonical_u64(1 << Opcode::CAS as u8) {
+                self.clk += 1;
+            }
             if self.clk % 1000000 == 0 {
                 let decode_time = start.elapsed();
                 debug!("100000_step_time: {}", decode_time.as_millis());
@@ -2305,6 +2986,12 @@ impl Process {
 
         gen_memory_table(self, program)?;
         gen_tape_table(self, program)?;
+        if let Some(pc) = interrupted {
+            return Ok(VMState::Interrupted(pc));
+        }
+        if reverted {
+            return Ok(Reverted(end_step));
+        }
         Ok(ExeEnd(end_step))
     }
 }
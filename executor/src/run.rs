@@ -0,0 +1,177 @@
+use crate::load_tx::init_tape;
+use crate::trace::{gen_storage_hash_table, gen_storage_table};
+use crate::{HostAssertFailure, Process, TxScopeCacheManager};
+use core::crypto::hash::Hasher;
+use core::crypto::ZkHasher;
+use core::merkle_tree::log::{StorageLog, StorageLogKind, StorageQuery, WitnessStorageLog};
+use core::merkle_tree::tree::AccountTree;
+use core::program::binary_program::BinaryProgram;
+use core::program::{Program, REGISTER_NUM};
+use core::types::account::Address;
+use core::types::merkle_tree::{encode_addr, tree_key_default};
+use core::vm::error::ProcessorError;
+use core::vm::transaction::init_tx_context_mock;
+use core::vm::vm_state::VMState;
+use plonky2::field::goldilocks_field::GoldilocksField;
+use plonky2::field::types::{Field, PrimeField64};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Observable outcome of running a program, without its trace.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunResult {
+    pub registers: [u64; REGISTER_NUM],
+    /// Last value of every memory address touched by the run.
+    pub memory: BTreeMap<u64, u64>,
+    /// Data passed to `tstore` by the program.
+    pub return_data: Vec<u64>,
+    /// Storage reads and writes in execution order.
+    pub events: Vec<StorageQuery>,
+    /// Number of executed instructions.
+    pub steps: u64,
+    /// Whether the run halted on a failed `require`.
+    pub reverted: bool,
+    /// `assert_eq` checks that didn't hold.
+    pub host_assert_failures: Vec<HostAssertFailure>,
+}
+
+/// Run `program` in a test account tree with `inputs` as calldata, the same
+/// way the cli does but without printing or generating the storage tables.
+pub fn run_program(
+    program: &BinaryProgram,
+    inputs: Option<Vec<GoldilocksField>>,
+) -> Result<RunResult, ProcessorError> {
+    run(program, inputs, false)
+}
+
+/// Same as `run_program`, with `Process::execute_strict`.
+pub fn run_program_strict(
+    program: &BinaryProgram,
+    inputs: Option<Vec<GoldilocksField>>,
+) -> Result<RunResult, ProcessorError> {
+    run(program, inputs, true)
+}
+
+/// Load `program` for `process` as the callee of a mock transaction with
+/// `inputs` as calldata, the code hash written to `account_tree`. This is
+/// the fixture `run_program` and `execute_program` share.
+pub fn load_program(
+    process: &mut Process,
+    program: &BinaryProgram,
+    inputs: Option<Vec<GoldilocksField>>,
+    account_tree: &mut AccountTree,
+) -> Result<Program, ProcessorError> {
+    let mut vm_program = Program::from_bytecode(&program.bytecode)?;
+    vm_program.prophets = program
+        .prophets
+        .iter()
+        .map(|prophet| (prophet.host as u64, prophet.clone()))
+        .collect::<HashMap<_, _>>();
+    vm_program.data = program.data.clone();
+    vm_program.debug_info = program.debug_info.clone();
+    vm_program.source_lines = program.source_lines.clone();
+    vm_program.host_asserts = program.host_asserts.clone();
+    vm_program.breakpoints = program.breakpoints.clone();
+    let code = vm_program.code_words()?;
+    let code_hash = ZkHasher::default().hash_bytes(&code);
+
+    let callee: Address = [9, 10, 11, 12].map(GoldilocksField::from_canonical_u64);
+    let caller_addr: Address = [17, 18, 19, 20].map(GoldilocksField::from_canonical_u64);
+    let callee_exe_addr: Address = [13, 14, 15, 16].map(GoldilocksField::from_canonical_u64);
+    if let Some(calldata) = inputs {
+        process.tp = GoldilocksField::ZERO;
+        init_tape(
+            process,
+            calldata,
+            caller_addr,
+            callee,
+            callee_exe_addr,
+            &init_tx_context_mock(),
+        );
+    }
+    process.addr_code = callee_exe_addr;
+    process.addr_storage = callee;
+    vm_program
+        .trace
+        .addr_program_hash
+        .insert(encode_addr(&callee_exe_addr), code);
+
+    account_tree.process_block(vec![WitnessStorageLog {
+        storage_log: StorageLog::new_write(
+            StorageLogKind::RepeatedWrite,
+            callee_exe_addr,
+            code_hash,
+        ),
+        previous_value: tree_key_default(),
+    }]);
+    process.program_log.push(WitnessStorageLog {
+        storage_log: StorageLog::new_read_log(callee_exe_addr, code_hash),
+        previous_value: tree_key_default(),
+    });
+    Ok(vm_program)
+}
+
+/// Execute `program` with a `process` set up by the caller, the way the
+/// stark tests and benches do: the storage tables are generated and the
+/// trace has the account tree roots before and after the run, the same ones
+/// if it reverted.
+pub fn execute_program(
+    mut process: Process,
+    program: &BinaryProgram,
+    inputs: Option<Vec<GoldilocksField>>,
+    account_tree: &mut AccountTree,
+) -> Result<(Process, Program), ProcessorError> {
+    let mut vm_program = load_program(&mut process, program, inputs, account_tree)?;
+    let _ = account_tree.save();
+    let start = account_tree.root_hash();
+    let state = process.execute(
+        &mut vm_program,
+        account_tree,
+        &mut TxScopeCacheManager::default(),
+    )?;
+    let reverted = matches!(state, VMState::Reverted(_));
+    let hash_roots = gen_storage_hash_table(&mut process, &mut vm_program, account_tree, reverted)?;
+    gen_storage_table(&mut process, &mut vm_program, hash_roots)?;
+    vm_program.trace.start_end_roots = (start, account_tree.root_hash());
+    Ok((process, vm_program))
+}
+
+fn run(
+    program: &BinaryProgram,
+    inputs: Option<Vec<GoldilocksField>>,
+    strict: bool,
+) -> Result<RunResult, ProcessorError> {
+    let mut process = Process::new();
+    let mut account_tree = AccountTree::new_test();
+    let mut vm_program = load_program(&mut process, program, inputs, &mut account_tree)?;
+
+    let mut tx_cache_manager = TxScopeCacheManager::default();
+    let state = if strict {
+        process.execute_strict(&mut vm_program, &mut account_tree, &mut tx_cache_manager)?
+    } else {
+        process.execute(&mut vm_program, &mut account_tree, &mut tx_cache_manager)?
+    };
+
+    Ok(RunResult {
+        registers: process.registers.map(|r| r.to_canonical_u64()),
+        memory: process
+            .memory
+            .trace
+            .iter()
+            .filter_map(|(addr, cells)| Some((*addr, cells.last()?.value.to_canonical_u64())))
+            .collect(),
+        return_data: process
+            .return_data
+            .iter()
+            .map(|v| v.to_canonical_u64())
+            .collect(),
+        events: process.storage_queries.clone(),
+        steps: process
+            .opcode_histogram
+            .iter()
+            .map(|count| count.count as u64)
+            .sum(),
+        reverted: matches!(state, VMState::Reverted(_)),
+        host_assert_failures: process.host_assert_failures.clone(),
+    })
+}
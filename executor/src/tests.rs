@@ -1,7 +1,15 @@
-use crate::trace::{gen_dump_file, gen_storage_hash_table, gen_storage_table};
-use crate::{Process, TxScopeCacheManager};
+use crate::trace::{
+    check_memory_trace, gen_dump_file, gen_storage_hash_table, gen_storage_table, MemError,
+};
+use crate::{HostAssertFailure, Process, TraceCapacity, TxScopeCacheManager};
 
 use crate::load_tx::init_tape;
+use crate::run::{run_program, run_program_strict, RunResult};
+use assembler::encoder::{encode_asm_from_json_file, encode_asm_from_json_str};
+use assembler::stdlib::{
+    abs_prophet, depth_prophet, link, link_with_prophets, mulhi_prophet, range_bits, sext,
+    unpack_bytes_prophet, ABS, BITREV, DEPTH, MULHI, PACK_BYTES, UNPACK_BYTES,
+};
 use core::crypto::hash::Hasher;
 use core::crypto::ZkHasher;
 use core::merkle_tree::log::StorageLog;
@@ -10,10 +18,14 @@ use core::merkle_tree::tree::AccountTree;
 use core::program::binary_program::BinaryProgram;
 use core::program::instruction::Opcode;
 use core::program::Program;
+use core::trace::trace::{MemoryTraceCell, Trace};
 use core::types::account::Address;
 use core::types::merkle_tree::tree_key_default;
 use core::types::merkle_tree::{decode_addr, encode_addr};
+use core::vm::error::ProcessorError;
+use core::vm::opcodes::OlaOpcode;
 use core::vm::transaction::init_tx_context_mock;
+use core::vm::vm_state::VMState;
 use log::{debug, LevelFilter};
 use num::{BigInt, BigUint, Num};
 use plonky2::field::goldilocks_field::GoldilocksField;
@@ -21,6 +33,7 @@ use plonky2::field::types::Field;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Write};
+use std::ops::ControlFlow;
 
 fn executor_run_test_program(
     bin_file_path: &str,
@@ -51,17 +64,14 @@ fn executor_run_test_program(
     }
 
     let mut program: Program = Program {
-        instructions: Vec::new(),
-        trace: Default::default(),
         debug_info: program.debug_info,
+        source_lines: program.source_lines,
+        host_asserts: program.host_asserts,
+        breakpoints: program.breakpoints,
         prophets: prophets,
-        pre_exe_flag: false,
-        print_flag: false,
+        data: program.data,
+        ..Program::from_bytecode(&program.bytecode).unwrap()
     };
-
-    for inst in instructions {
-        program.instructions.push(inst.to_string());
-    }
     let mut process = Process::new();
     process.addr_storage = Address::default();
 
@@ -134,7 +144,8 @@ fn executor_run_test_program(
     if print_trace {
         println!("vm trace: {:?}", program.trace);
     }
-    let hash_roots = gen_storage_hash_table(&mut process, &mut program, &mut account_tree).unwrap();
+    let hash_roots =
+        gen_storage_hash_table(&mut process, &mut program, &mut account_tree, false).unwrap();
     gen_storage_table(&mut process, &mut program, hash_roots);
     program.trace.start_end_roots = (start, account_tree.root_hash());
 
@@ -569,3 +580,725 @@ fn gen_storage_table_test() {
 
     gen_storage_table(&mut process, &mut program, hash);
 }
+
+#[test]
+fn run_program_fibo_test() {
+    // fibo_iter.json leaves fib(10) in r1 after 9 loop iterations.
+    let program =
+        encode_asm_from_json_file("../assembler/test_data/asm/fibo_iter.json".to_string()).unwrap();
+    let result = run_program(&program, None).unwrap();
+    assert_eq!(result.registers[1], 55);
+    assert!(result.steps > 9 * 7);
+
+    let json = serde_json::to_string(&result).unwrap();
+    assert_eq!(serde_json::from_str::<RunResult>(&json).unwrap(), result);
+}
+
+#[test]
+fn run_program_strict_test() {
+    let program =
+        encode_asm_from_json_file("../assembler/test_data/asm/fibo_iter.json".to_string()).unwrap();
+    assert_eq!(run_program_strict(&program, None).unwrap().registers[1], 55);
+
+    // mload_uninit.json reads two addresses never written and checks they
+    // are zero.
+    let program =
+        encode_asm_from_json_file("../assembler/test_data/asm/mload_uninit.json".to_string())
+            .unwrap();
+    assert_eq!(run_program(&program, None).unwrap().registers[4], 0);
+    assert!(matches!(
+        run_program_strict(&program, None),
+        Err(ProcessorError::UndefinedBehavior(_))
+    ));
+}
+
+#[test]
+fn run_program_strict_byte_test() {
+    // stdlib_byte.json takes bytes 0, 3 and 7 of 0x1122334455667788 without
+    // multiplying by a field inverse, which strict mode reads as an overflow.
+    let program =
+        encode_asm_from_json_file("../assembler/test_data/asm/stdlib_byte.json".to_string())
+            .unwrap();
+    assert_eq!(run_program_strict(&program, None).unwrap().registers[0], 17);
+}
+
+#[test]
+fn run_program_strict_rotate_test() {
+    // stdlib_rotate.json rotates 0x12345678 by 8 both ways, the bits shifted
+    // down are a hint rather than a product with a field inverse.
+    let program =
+        encode_asm_from_json_file("../assembler/test_data/asm/stdlib_rotate.json".to_string())
+            .unwrap();
+    assert_eq!(
+        run_program_strict(&program, None).unwrap().registers[0],
+        305419896
+    );
+}
+
+#[test]
+fn run_program_data_section_test() {
+    // rodata.json adds 5 to the second data word.
+    let program =
+        encode_asm_from_json_file("../assembler/test_data/asm/rodata.json".to_string()).unwrap();
+    assert_eq!(program.data, vec![7, 42]);
+    let result = run_program(&program, None).unwrap();
+    assert_eq!(result.registers[3], 47);
+
+    let program =
+        encode_asm_from_json_file("../assembler/test_data/asm/rodata_store.json".to_string())
+            .unwrap();
+    assert!(matches!(
+        run_program(&program, None),
+        Err(ProcessorError::MemVistInv(_))
+    ));
+}
+
+#[test]
+fn run_program_require_test() {
+    // Both programs require r0 before setting r1 to 5, require.json with
+    // r0 = 0.
+    let program =
+        encode_asm_from_json_file("../assembler/test_data/asm/require.json".to_string()).unwrap();
+    let result = run_program(&program, None).unwrap();
+    assert!(result.reverted);
+    assert_eq!(result.registers[1], 0);
+
+    let program =
+        encode_asm_from_json_file("../assembler/test_data/asm/require_pass.json".to_string())
+            .unwrap();
+    let result = run_program(&program, None).unwrap();
+    assert!(!result.reverted);
+    assert_eq!(result.registers[1], 5);
+}
+
+#[test]
+fn run_program_opcode_fixtures_test() {
+    // (fixture, register, value at the end of the run)
+    let expected = [
+        ("add_imm", 1, 8),
+        ("mul_imm", 1, 15),
+        ("sub", 1, 7),
+        ("sub", 4, 6),
+        ("sub_wrap", 1, GoldilocksField::NEG_ONE.0 - 1),
+        ("sub_u32", 1, u32::MAX as u64 - 1),
+        ("sub_u32", 4, 2),
+        ("swap", 0, 7),
+        ("swap", 1, 3),
+        ("isqrt", 1, 4),
+        ("isqrt_non_square", 1, 4),
+        ("isqrt_non_square", 3, 9),
+        // Roots of 2^62, p - 1 = 2^64 - 2^32, (2^32 - 1)^2 and one below it.
+        ("isqrt_large", 1, 1 << 31),
+        ("isqrt_large", 3, u32::MAX as u64),
+        ("isqrt_large", 5, u32::MAX as u64),
+        ("isqrt_large", 7, u32::MAX as u64 - 1),
+        ("cas", 2, 1),
+        ("cas", 3, 9),
+        ("cas_mismatch", 2, 0),
+        ("cas_mismatch", 3, 7),
+        ("loadp", 1, 11),
+        ("loadp", 2, 22),
+    ];
+    for (name, reg, value) in expected {
+        let program =
+            encode_asm_from_json_file(format!("../assembler/test_data/asm/{}.json", name)).unwrap();
+        let result = run_program(&program, None).unwrap();
+        assert_eq!(result.registers[reg], value, "{} r{}", name, reg);
+    }
+}
+
+#[test]
+fn run_program_sub_operands_test() {
+    // sub is the field subtraction, subu is on u32 operands and -1 is the
+    // field element 2^64 - 2^32.
+    let bundle = serde_json::json!({
+        "program": "main:\n.LBL0_0:\n  mov r1 -1\n  sub r2 r1 1\n  end\n",
+        "prophets": [],
+    });
+    let program = encode_asm_from_json_str(bundle.to_string().as_str()).unwrap();
+    let result = run_program(&program, None).unwrap();
+    assert_eq!(result.registers[2], GoldilocksField::NEG_ONE.0 - 1);
+
+    let bundle = serde_json::json!({
+        "program": "main:\n.LBL0_0:\n  mov r1 -1\n  subu r2 r1 1\n  end\n",
+        "prophets": [],
+    });
+    let program = encode_asm_from_json_str(bundle.to_string().as_str()).unwrap();
+    assert!(matches!(
+        run_program(&program, None),
+        Err(ProcessorError::U32RangeCheckFail)
+    ));
+}
+
+#[test]
+fn run_program_cas_far_values_test() {
+    // cas compares through u32 differences, 7 and -1 are too far apart.
+    let bundle = serde_json::json!({
+        "program": "main:\n.LBL0_0:\n  add r9 r9 2\n  mov r4 7\n  mstore [r9,-1] r4\n  add r1 r9 -1\n  mov r2 -1\n  cas r2 r1 9\n  add r9 r9 -2\n  end\n",
+        "prophets": [],
+    });
+    let program = encode_asm_from_json_str(bundle.to_string().as_str()).unwrap();
+    assert!(matches!(
+        run_program(&program, None),
+        Err(ProcessorError::U32RangeCheckFail)
+    ));
+}
+
+#[test]
+fn run_program_sparse_memory_test() {
+    // mem_sparse.json stores 7 at 16 and 9 at 2^33 + 16, two u32 address
+    // diffs apart.
+    let program =
+        encode_asm_from_json_file("../assembler/test_data/asm/mem_sparse.json".to_string())
+            .unwrap();
+    let result = run_program(&program, None).unwrap();
+    assert_eq!(result.registers[6], 16);
+    assert_eq!(result.memory.get(&16), Some(&7));
+    assert_eq!(result.memory.get(&8589934608), Some(&9));
+}
+
+#[test]
+fn run_program_memory_gap_too_wide_test() {
+    // 16 and 2^50 + 16 are 2^18 u32 address diffs apart.
+    let bundle = serde_json::json!({
+        "program": "main:\n.LBL0_0:\n  mov r1 16\n  mov r2 1125899906842640\n  mov r3 7\n  mstore [r1] r3\n  mstore [r2] r3\n  end\n",
+        "prophets": [],
+    });
+    let program = encode_asm_from_json_str(bundle.to_string().as_str()).unwrap();
+    assert!(matches!(
+        run_program(&program, None),
+        Err(ProcessorError::MemoryGapTooWide(16, 1125899906842640))
+    ));
+}
+
+#[test]
+fn run_program_clk_test() {
+    // clk.json reads the clock two instructions apart.
+    let program =
+        encode_asm_from_json_file("../assembler/test_data/asm/clk.json".to_string()).unwrap();
+    let result = run_program(&program, None).unwrap();
+    assert!(result.registers[3] > result.registers[1]);
+    assert_eq!(result.registers[3] - result.registers[1], 2);
+}
+
+fn memory_row(addr: u64, clk: u64, is_write: bool, value: u64) -> MemoryTraceCell {
+    let zero = GoldilocksField::ZERO;
+    MemoryTraceCell {
+        env_idx: zero,
+        addr: GoldilocksField::from_canonical_u64(addr),
+        clk: GoldilocksField::from_canonical_u64(clk),
+        is_rw: GoldilocksField::ONE,
+        op: zero,
+        is_write: GoldilocksField::from_bool(is_write),
+        diff_addr: zero,
+        diff_addr_inv: zero,
+        diff_clk: zero,
+        diff_addr_cond: zero,
+        filter_looked_for_main: GoldilocksField::ONE,
+        rw_addr_unchanged: zero,
+        region_prophet: zero,
+        region_heap: zero,
+        value: GoldilocksField::from_canonical_u64(value),
+        rc_value: zero,
+    }
+}
+
+#[test]
+fn check_memory_trace_test() {
+    let rows = vec![
+        memory_row(5, 1, true, 7),
+        memory_row(5, 3, false, 7),
+        memory_row(6, 2, true, 9),
+        memory_row(6, 4, false, 9),
+    ];
+    assert_eq!(check_memory_trace(&rows), Ok(()));
+
+    let mut unsorted = rows.clone();
+    unsorted.swap(2, 3);
+    assert_eq!(
+        check_memory_trace(&unsorted),
+        Err(MemError::Unsorted { row: 3 })
+    );
+
+    let mut stale = rows.clone();
+    stale[3].value = GoldilocksField::from_canonical_u64(7);
+    assert_eq!(
+        check_memory_trace(&stale),
+        Err(MemError::StaleRead {
+            row: 3,
+            value: 7,
+            expected: 9
+        })
+    );
+
+    let mut unwritten = rows.clone();
+    unwritten[0].is_write = GoldilocksField::ZERO;
+    assert_eq!(
+        check_memory_trace(&unwritten),
+        Err(MemError::NonZeroFirstRead { row: 0, value: 7 })
+    );
+    unwritten[0].value = GoldilocksField::ZERO;
+    unwritten[1].value = GoldilocksField::ZERO;
+    assert_eq!(check_memory_trace(&unwritten), Ok(()));
+
+    let mut negative_diff = rows;
+    negative_diff[1].rc_value = GoldilocksField::NEG_ONE;
+    assert!(matches!(
+        check_memory_trace(&negative_diff),
+        Err(MemError::DiffOutOfRange { row: 1, .. })
+    ));
+}
+
+#[test]
+fn run_test_programs() {
+    for name in assembler::testprograms::names() {
+        let (program, expected) = assembler::testprograms::load(name).unwrap();
+        let result = run_program(&program, None).unwrap();
+        for &(index, value) in expected.registers {
+            assert_eq!(result.registers[index], value, "r{} of {}", index, name);
+        }
+        for &(addr, value) in expected.memory {
+            assert_eq!(
+                result.memory.get(&addr),
+                Some(&value),
+                "[{}] of {}",
+                addr,
+                name
+            );
+        }
+        assert_eq!(result.return_data, expected.outputs, "outputs of {}", name);
+    }
+}
+
+#[test]
+fn execute_with_hook_test() {
+    // add_mul.json: mov r1 2 at pc 0, mov r2 3 at pc 2, add r3 r1 r2 at pc 4.
+    let binary =
+        encode_asm_from_json_file("../assembler/test_data/asm/add_mul.json".to_string()).unwrap();
+    let mut program = Program::from_bytecode(&binary.bytecode).unwrap();
+    let mut process = Process::new();
+    let mut pcs = vec![];
+    let state = process
+        .execute_with_hook(
+            &mut program,
+            &mut AccountTree::new_test(),
+            &mut TxScopeCacheManager::default(),
+            |step, _| {
+                pcs.push(step.pc);
+                if step.pc == 4 {
+                    assert_eq!(step.regs[1].0, 2);
+                    assert_eq!(step.regs[2].0, 3);
+                    return ControlFlow::Break(());
+                }
+                ControlFlow::Continue(())
+            },
+        )
+        .unwrap();
+
+    assert!(matches!(state, VMState::Interrupted(4)));
+    assert_eq!(pcs, vec![0, 2, 4]);
+    let exec_pcs = program
+        .trace
+        .exec
+        .iter()
+        .map(|step| step.pc)
+        .collect::<Vec<_>>();
+    assert_eq!(exec_pcs, vec![0, 2]);
+    assert_eq!(process.registers[3], GoldilocksField::ZERO);
+}
+
+/// Rows held by the vectors of `trace`, or the rows they can hold with
+/// `capacity`.
+fn trace_rows(trace: &Trace, capacity: bool) -> TraceCapacity {
+    macro_rules! rows {
+        ($rows:expr) => {
+            if capacity {
+                $rows.capacity()
+            } else {
+                $rows.len()
+            }
+        };
+    }
+    TraceCapacity {
+        exec: rows!(trace.exec),
+        memory: rows!(trace.memory),
+        builtin_rangecheck: rows!(trace.builtin_rangecheck),
+        builtin_bitwise_combined: rows!(trace.builtin_bitwise_combined),
+        builtin_cmp: rows!(trace.builtin_cmp),
+        builtin_poseidon: rows!(trace.builtin_poseidon),
+        builtin_poseidon_chunk: rows!(trace.builtin_poseidon_chunk),
+        builtin_storage_hash: rows!(trace.builtin_storage_hash),
+        tape: rows!(trace.tape),
+        sc_call: rows!(trace.sc_call),
+    }
+}
+
+#[test]
+fn with_capacity_test() {
+    let binary =
+        encode_asm_from_json_file("../assembler/test_data/asm/memory.json".to_string()).unwrap();
+    let run = |mut process: Process| {
+        let mut program = Program::from_bytecode(&binary.bytecode).unwrap();
+        process
+            .execute(
+                &mut program,
+                &mut AccountTree::new_test(),
+                &mut TxScopeCacheManager::default(),
+            )
+            .unwrap();
+        (process, program)
+    };
+    let (process, program) = run(Process::new());
+    let rows = trace_rows(&program.trace, false);
+    assert!(rows.exec > 0 && rows.memory > 0 && rows.builtin_poseidon > 0);
+
+    // Reserved the rows of the first run, the second one fills every vector
+    // without growing it.
+    let (reserved, program) = run(Process::with_capacity(rows));
+    assert_eq!(trace_rows(&program.trace, false), rows);
+    assert_eq!(trace_rows(&program.trace, true), rows);
+    assert_eq!(reserved.registers, process.registers);
+}
+
+#[test]
+fn range_bits_test() {
+    let main = |value: u64| {
+        format!(
+            "main:\n.LBL0_0:\n  add r9 r9 4\n  mstore [r9,-2] r9\n  mov r1 {}\n  call range_bits_20\n  add r9 r9 -4\n  end\n",
+            value
+        )
+    };
+    let routine = range_bits(20);
+    let program = link(&main((1 << 20) - 1), &[routine.as_str()]).unwrap();
+    assert!(run_program(&program, None).is_ok());
+    let program = link(&main((1 << 21) - 1), &[routine.as_str()]).unwrap();
+    assert!(matches!(
+        run_program(&program, None),
+        Err(ProcessorError::U32RangeCheckFail)
+    ));
+}
+
+#[test]
+fn abs_test() {
+    // 5, -2^40 and i64::MIN as two's complement words.
+    for value in [5u64, (-(1i64 << 40)) as u64, i64::MIN as u64] {
+        let main = format!(
+            "main:\n.LBL0_0:\n  add r9 r9 4\n  mstore [r9,-2] r9\n  mov r1 {}\n  call abs\n  add r9 r9 -4\n  end\n",
+            value
+        );
+        let program = link_with_prophets(&main, &[ABS], &[abs_prophet()]).unwrap();
+        let result = run_program(&program, None).unwrap();
+        assert_eq!(result.registers[0], (value as i64).wrapping_abs() as u64);
+    }
+}
+
+#[test]
+fn mulhi_u32_only_test() {
+    // 2^32 + 1 is no u32, mulhi refuses it rather than dropping its high word.
+    let main = "main:\n.LBL0_0:\n  add r9 r9 4\n  mstore [r9,-2] r9\n  mov r1 4294967297\n  mov r2 3\n  call mulhi\n  add r9 r9 -4\n  end\n";
+    let program = link_with_prophets(main, &[MULHI], &[mulhi_prophet()]).unwrap();
+    assert!(matches!(
+        run_program(&program, None),
+        Err(ProcessorError::U32RangeCheckFail)
+    ));
+}
+
+#[test]
+fn sext_test() {
+    for (bits, value) in [(8u64, 0x17fu64), (16, 0x8000), (32, 0xffff_ffff)] {
+        let main = format!(
+            "main:\n.LBL0_0:\n  add r9 r9 4\n  mstore [r9,-2] r9\n  mov r1 {}\n  call sext_{}\n  add r9 r9 -4\n  end\n",
+            value, bits
+        );
+        let program = link(&main, &[sext(bits).as_str()]).unwrap();
+        let result = run_program(&program, None).unwrap();
+        let shift = 64 - bits;
+        let expected = ((value << shift) as i64) >> shift;
+        assert_eq!(
+            result.registers[0],
+            GoldilocksField::from_noncanonical_i64(expected).0
+        );
+    }
+    // 0x80 on 8 bits is -128.
+    let program = link(
+        "main:\n.LBL0_0:\n  add r9 r9 4\n  mstore [r9,-2] r9\n  mov r1 128\n  call sext_8\n  add r9 r9 -4\n  end\n",
+        &[sext(8).as_str()],
+    )
+    .unwrap();
+    let result = run_program(&program, None).unwrap();
+    assert_eq!(result.registers[0], GoldilocksField::NEG_ONE.0 - 127);
+}
+
+#[test]
+fn pack_bytes_test() {
+    // Pack 8 bytes stored at [0, 8), then unpack the word to [8, 16).
+    let link_pack_unpack = |bytes: [u64; 8]| {
+        let mut main = "main:\n.LBL0_0:\n  add r9 r9 18\n  mstore [r9,-2] r9\n".to_string();
+        for (i, byte) in bytes.iter().enumerate() {
+            main += &format!("  mov r8 {}\n  mstore [r9,{}] r8\n", byte, i as i64 - 18);
+        }
+        main += "  add r1 r9 -18\n  call pack_bytes\n  mov r8 r0\n  mov r1 r0\n  add r2 r9 -10\n  call unpack_bytes\n  add r9 r9 -18\n  end\n";
+        link_with_prophets(
+            &main,
+            &[PACK_BYTES, UNPACK_BYTES],
+            &[unpack_bytes_prophet()],
+        )
+        .unwrap()
+    };
+    let pack_unpack = |bytes: [u64; 8]| run_program(&link_pack_unpack(bytes), None);
+
+    let word = 0x0123_4567_89ab_cdefu64;
+    let bytes = word.to_le_bytes().map(|byte| byte as u64);
+    let result = pack_unpack(bytes).unwrap();
+    assert_eq!(result.registers[8], word);
+    for (i, byte) in bytes.iter().enumerate() {
+        assert_eq!(result.memory[&(8 + i as u64)], *byte);
+    }
+    // Below 2^63 no shift overflows the word, the bytes are not shifted down
+    // with a field inverse.
+    let result = run_program_strict(&link_pack_unpack(bytes), None).unwrap();
+    assert_eq!(result.registers[8], word);
+    for (i, byte) in bytes.iter().enumerate() {
+        assert_eq!(result.memory[&(8 + i as u64)], *byte);
+    }
+
+    // p - 1 is the largest word, its bytes pack back to it.
+    let max = GoldilocksField::NEG_ONE.0;
+    let bytes = max.to_le_bytes().map(|byte| byte as u64);
+    assert_eq!(pack_unpack(bytes).unwrap().registers[8], max);
+    // Bytes of a value above the field order, or not bytes, are rejected.
+    let bytes = u64::MAX.to_le_bytes().map(|byte| byte as u64);
+    assert!(matches!(
+        pack_unpack(bytes),
+        Err(ProcessorError::AssertFail(..))
+    ));
+    assert!(matches!(
+        pack_unpack([256, 0, 0, 0, 0, 0, 0, 0]),
+        Err(ProcessorError::AssertFail(..))
+    ));
+}
+
+#[test]
+fn bitrev_test() {
+    let bitrev = |a: u64, bits: u64| {
+        let main = format!(
+            "main:\n.LBL0_0:\n  add r9 r9 4\n  mstore [r9,-2] r9\n  mov r1 {}\n  mov r2 {}\n  call bitrev\n  add r9 r9 -4\n  end\n",
+            a, bits
+        );
+        let program = link(&main, &[BITREV]).unwrap();
+        run_program(&program, None).unwrap().registers[0]
+    };
+
+    assert_eq!(bitrev(0b0001, 4), 0b1000);
+    assert_eq!(bitrev(0b0110, 3), 0b011);
+    // Bits above the reversed ones are dropped.
+    assert_eq!(bitrev(0b1_0011, 4), 0b1100);
+    assert_eq!(bitrev(5, 0), 0);
+    assert_eq!(bitrev(1, 32), 1 << 31);
+    assert_eq!(
+        bitrev(0x1234_5678, 32),
+        0x1234_5678u32.reverse_bits() as u64
+    );
+}
+
+#[test]
+fn depth_test() {
+    // rec(n) recurses n times below itself, the innermost call is n + 1 deep.
+    let rec = "rec:\n.LBL1_0:\n  add r9 r9 4\n  mstore [r9,-2] r9\n  eq r2 r1 0\n  cjmp r2 .LBL1_1\n  add r1 r1 -1\n  call rec\n  add r9 r9 -4\n  ret\n.LBL1_1:\n  call depth\n  add r9 r9 -4\n  ret\n";
+    for n in [0u64, 1, 3] {
+        let main = format!(
+            "main:\n.LBL0_0:\n  add r9 r9 4\n  mstore [r9,-2] r9\n  mov r1 {}\n  call rec\n  add r9 r9 -4\n  end\n",
+            n
+        );
+        let program = link_with_prophets(&main, &[rec, DEPTH], &[depth_prophet()]).unwrap();
+        let result = run_program(&program, None).unwrap();
+        assert_eq!(result.registers[0], n + 1);
+    }
+}
+
+#[test]
+fn host_assert_test() {
+    let bundle = serde_json::json!({
+        "program": "main:\n.LBL0_0:\n  mov r1 4\n  assert_eq r1 4\n  assert_eq r1 5\n  add r1 r1 1\n  assert_eq r1 5\n  end\n",
+        "prophets": [],
+    });
+    let program = encode_asm_from_json_str(bundle.to_string().as_str()).unwrap();
+    let plain = serde_json::json!({
+        "program": "main:\n.LBL0_0:\n  mov r1 4\n  add r1 r1 1\n  end\n",
+        "prophets": [],
+    });
+    let plain = encode_asm_from_json_str(plain.to_string().as_str()).unwrap();
+    // The asserts are not encoded.
+    assert_eq!(program.bytecode, plain.bytecode);
+
+    let result = run_program(&program, None).unwrap();
+    assert_eq!(result.registers[1], 5);
+    assert_eq!(
+        result.host_assert_failures,
+        vec![HostAssertFailure {
+            pc: 2,
+            register: 1,
+            expected: 5,
+            actual: 4,
+        }]
+    );
+    assert_eq!(
+        result.host_assert_failures[0].to_string(),
+        "host assert failed at pc 2: r1 is 4, expected 5"
+    );
+    assert!(run_program(&plain, None)
+        .unwrap()
+        .host_assert_failures
+        .is_empty());
+}
+
+fn run_tainted(program: &str, registers: &[usize]) -> Result<Process, ProcessorError> {
+    let bundle = serde_json::json!({ "program": program, "prophets": [] });
+    let binary = encode_asm_from_json_str(bundle.to_string().as_str()).unwrap();
+    let mut program = Program::from_bytecode(&binary.bytecode).unwrap();
+    let mut process = Process::new();
+    for register in registers {
+        process.tainted_registers[*register] = true;
+    }
+    process.execute(
+        &mut program,
+        &mut AccountTree::new_test(),
+        &mut TxScopeCacheManager::default(),
+    )?;
+    Ok(process)
+}
+
+#[test]
+fn secret_branch_test() {
+    // r1 flows into the cjmp flag through r2.
+    let binary =
+        encode_asm_from_json_file("../assembler/test_data/asm/secret_branch.json".to_string())
+            .unwrap();
+    let run = |tainted: usize| {
+        let mut program = Program::from_bytecode(&binary.bytecode).unwrap();
+        let mut process = Process::new();
+        process.tainted_registers[tainted] = true;
+        process.execute(
+            &mut program,
+            &mut AccountTree::new_test(),
+            &mut TxScopeCacheManager::default(),
+        )
+    };
+    assert!(matches!(
+        run(1),
+        Err(ProcessorError::SecretDependentBranch(_))
+    ));
+    assert!(run(3).is_ok());
+}
+
+#[test]
+fn secret_through_memory_test() {
+    let branch_on = |load: &str| {
+        format!(
+            "main:\n.LBL0_0:\n  add r9 r9 8\n  mstore [r9,-8] r1\n{}\n  cjmp r2 .LBL0_1\n  jmp .LBL0_1\n.LBL0_1:\n  end\n",
+            load
+        )
+    };
+    let is_secret_branch = |res: Result<Process, ProcessorError>| {
+        matches!(res, Err(ProcessorError::SecretDependentBranch(_)))
+    };
+
+    // A secret stored and loaded back stays tainted.
+    let laundered = branch_on("  mload r2 [r9,-8]");
+    assert!(is_secret_branch(run_tainted(&laundered, &[1])));
+    assert!(run_tainted(&laundered, &[3]).is_ok());
+
+    // Overwriting the word with a public value clears it.
+    let overwritten = branch_on("  mov r3 7\n  mstore [r9,-8] r3\n  mload r2 [r9,-8]");
+    let process = run_tainted(&overwritten, &[1]).unwrap();
+    assert!(process.tainted_memory.is_empty());
+
+    // The poseidon hash of a secret word is tainted, in memory and once
+    // loaded.
+    let hashed =
+        branch_on("  add r3 r9 -8\n  add r4 r9 -4\n  poseidon r4 r3 1\n  mload r2 [r9,-4]");
+    assert!(is_secret_branch(run_tainted(&hashed, &[1])));
+    let hashed_only = "main:\n.LBL0_0:\n  add r9 r9 8\n  mstore [r9,-8] r1\n  add r3 r9 -8\n  add r4 r9 -4\n  poseidon r4 r3 1\n  end\n";
+    let process = run_tainted(hashed_only, &[1]).unwrap();
+    assert!(process.tainted_memory.contains(&process.registers[4].0));
+}
+
+#[test]
+fn breakpoint_test() {
+    let bundle = serde_json::json!({
+        "program": "main:\n.LBL0_0:\n  mov r1 4\n  brk\n  add r1 r1 1\n  end\n",
+        "prophets": [],
+    });
+    let binary = encode_asm_from_json_str(bundle.to_string().as_str()).unwrap();
+    let plain = serde_json::json!({
+        "program": "main:\n.LBL0_0:\n  mov r1 4\n  add r0 r0 0\n  add r1 r1 1\n  end\n",
+        "prophets": [],
+    });
+    let plain = encode_asm_from_json_str(plain.to_string().as_str()).unwrap();
+    // `brk` is the nop it is encoded as.
+    assert_eq!(binary.bytecode, plain.bytecode);
+    assert_eq!(binary.breakpoints, Some([2].into()));
+    let result = run_program(&binary, None).unwrap();
+    assert_eq!(
+        result.registers,
+        run_program(&plain, None).unwrap().registers
+    );
+    assert_eq!(result.registers[1], 5);
+
+    let breakpoints_hit = |debug: bool| {
+        let mut program = Program::from_bytecode(&binary.bytecode).unwrap();
+        program.breakpoints = binary.breakpoints.clone();
+        let mut process = Process::new();
+        process.debug = debug;
+        let mut hits = vec![];
+        process
+            .execute_with_hook(
+                &mut program,
+                &mut AccountTree::new_test(),
+                &mut TxScopeCacheManager::default(),
+                |step, breakpoint| {
+                    if breakpoint {
+                        hits.push((step.pc, step.regs[1].0));
+                    }
+                    ControlFlow::Continue(())
+                },
+            )
+            .unwrap();
+        assert_eq!(process.registers, result.registers.map(GoldilocksField));
+        hits
+    };
+    assert_eq!(breakpoints_hit(true), vec![(2, 4)]);
+    assert!(breakpoints_hit(false).is_empty());
+}
+
+#[test]
+fn early_end_test() {
+    // The first end reached halts, the instructions after it don't run.
+    let (program, _) = assembler::testprograms::load("early_end").unwrap();
+    let result = run_program(&program, None).unwrap();
+    assert_eq!(result.steps, 5);
+    assert_eq!(result.registers[3], 2);
+}
+
+#[test]
+fn empty_program_test() {
+    let mut program = Program::default();
+    let result = Process::new().execute(
+        &mut program,
+        &mut AccountTree::new_test(),
+        &mut TxScopeCacheManager::default(),
+    );
+    assert!(matches!(result, Err(ProcessorError::EmptyProgram)));
+    assert!(program.trace.exec.is_empty());
+}
+
+#[test]
+fn dispatch_covers_opcodes_test() {
+    for opcode in OlaOpcode::all() {
+        assert!(
+            crate::dispatch::handler(opcode.as_str()).is_some(),
+            "no handler for {}",
+            opcode
+        );
+    }
+}
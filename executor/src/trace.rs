@@ -1,12 +1,16 @@
 use crate::{GoldilocksField, MemRangeType, Process};
-use core::merkle_tree::log::WitnessStorageLog;
+use core::merkle_tree::log::{StorageLog, StorageLogKind, WitnessStorageLog};
 use core::merkle_tree::tree::AccountTree;
+use core::program::instruction::Opcode;
 use core::program::Program;
 use core::trace::dump::{DumpMemoryRow, DumpStep, DumpTapeRow, DumpTrace};
-use core::trace::trace::{MemoryTraceCell, StorageHashRow, TapeRow};
+use core::trace::trace::{
+    FilterLockForMain, MemoryOperation, MemoryTraceCell, MemoryType, StorageHashRow, TapeRow,
+};
 use core::types::merkle_tree::constant::ROOT_TREE_DEPTH;
 use core::types::merkle_tree::{tree_key_to_u256, TreeKeyU256, TREE_VALUE_LEN};
 use core::vm::error::ProcessorError;
+use core::vm::memory::MemoryCell;
 use core::vm::memory::HP_START_ADDR;
 use core::vm::memory::MEM_SPAN_SIZE;
 use log::debug;
@@ -15,8 +19,65 @@ use plonky2::field::types::{Field, Field64, PrimeField64};
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Write;
+use thiserror::Error;
 
 const LEAF_LAYER: usize = 255;
+/// Most rows `push_bridge_rows` adds for one gap, which bridge stack addresses
+/// about 2^40 apart. Wider gaps are refused rather than growing the memory
+/// trace by a row per 2^32 addresses.
+pub const MAX_BRIDGE_ROWS: u64 = 1 << 8;
+
+/// Rows for the stack addresses between `origin_addr` and `addr`, every
+/// `MEM_SPAN_SIZE`, so that each address diff passes the u32 range check. A
+/// bridge row is a first read returning zero, which isn't looked up by the
+/// cpu. Returns the last bridge address, or an error if the gap needs more
+/// than `MAX_BRIDGE_ROWS` rows.
+fn push_bridge_rows(
+    program: &mut Program,
+    cell: &MemoryCell,
+    mut origin_addr: u64,
+    addr: u64,
+) -> Result<u64, ProcessorError> {
+    if (addr - origin_addr).saturating_sub(1) / MEM_SPAN_SIZE > MAX_BRIDGE_ROWS {
+        return Err(ProcessorError::MemoryGapTooWide(origin_addr, addr));
+    }
+    let diff_addr = GoldilocksField::from_canonical_u64(MEM_SPAN_SIZE);
+    while addr - origin_addr > MEM_SPAN_SIZE {
+        origin_addr += MEM_SPAN_SIZE;
+        program.trace.memory.push(MemoryTraceCell {
+            env_idx: cell.env_idx,
+            addr: GoldilocksField::from_canonical_u64(origin_addr),
+            clk: GoldilocksField::from_canonical_u64(cell.clk as u64),
+            is_rw: GoldilocksField::from_canonical_u64(MemoryType::ReadWrite as u64),
+            op: GoldilocksField::from_canonical_u64(1 << Opcode::MLOAD as u64),
+            is_write: GoldilocksField::from_canonical_u64(MemoryOperation::Read as u64),
+            diff_addr,
+            diff_addr_inv: diff_addr.inverse(),
+            diff_clk: GoldilocksField::ZERO,
+            diff_addr_cond: GoldilocksField::ZERO,
+            filter_looked_for_main: GoldilocksField::from_canonical_u64(
+                FilterLockForMain::False as u64,
+            ),
+            rw_addr_unchanged: GoldilocksField::ZERO,
+            region_prophet: GoldilocksField::ZERO,
+            region_heap: GoldilocksField::ZERO,
+            value: GoldilocksField::ZERO,
+            rc_value: diff_addr,
+        });
+        program.trace.insert_rangecheck(
+            diff_addr,
+            (
+                GoldilocksField::ONE,
+                GoldilocksField::ZERO,
+                GoldilocksField::ZERO,
+                GoldilocksField::ZERO,
+                GoldilocksField::ZERO,
+            ),
+        );
+    }
+    Ok(origin_addr)
+}
+
 pub fn gen_memory_table(
     process: &mut Process,
     program: &mut Program,
@@ -91,6 +152,9 @@ pub fn gen_memory_table(
                         canonical_addr, origin_addr, write_once_region_flag, diff_addr_cond, first_heap_row_flag
                     );
 
+                if !write_once_region_flag && cell.region_heap.is_zero() {
+                    origin_addr = push_bridge_rows(program, cell, origin_addr, canonical_addr)?;
+                }
                 diff_addr = GoldilocksField::from_canonical_u64(canonical_addr - origin_addr);
                 let rc_value;
 
@@ -200,6 +264,73 @@ pub fn gen_memory_table(
     Ok(())
 }
 
+/// Invariant of the memory trace not holding at `row`.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MemError {
+    #[error("Memory row {row} is not sorted by (addr, clk)")]
+    Unsorted { row: usize },
+
+    #[error("Memory row {row} reads {value}, the last write stored {expected}")]
+    StaleRead {
+        row: usize,
+        value: u64,
+        expected: u64,
+    },
+
+    #[error("Memory row {row} range checks {value}, out of u32")]
+    DiffOutOfRange { row: usize, value: u64 },
+
+    #[error("Memory row {row} reads {value} from an address never written")]
+    NonZeroFirstRead { row: usize, value: u64 },
+}
+
+/// Check `rows`, as made by `gen_memory_table`, are sorted by (addr, clk),
+/// that every read returns the value of the previous write at its address,
+/// or zero if there is none, and that the range checked diffs fit in u32.
+/// The heap pointer is the exception to reading zero before a write: its
+/// initial write is dropped from the table.
+pub fn check_memory_trace(rows: &[MemoryTraceCell]) -> Result<(), MemError> {
+    let mut last_write = None;
+    for (row, cell) in rows.iter().enumerate() {
+        let addr = cell.addr.to_canonical_u64();
+        let clk = cell.clk.to_canonical_u64();
+        if row > 0 {
+            let prev = &rows[row - 1];
+            let prev_key = (prev.addr.to_canonical_u64(), prev.clk.to_canonical_u64());
+            if (addr, clk) < prev_key {
+                return Err(MemError::Unsorted { row });
+            }
+            if addr != prev_key.0 {
+                last_write = None;
+            }
+        }
+
+        let value = cell.value.to_canonical_u64();
+        if cell.is_write.is_one() {
+            last_write = Some(value);
+        } else if let Some(expected) = last_write {
+            if value != expected {
+                return Err(MemError::StaleRead {
+                    row,
+                    value,
+                    expected,
+                });
+            }
+        } else if value != 0 && addr != HP_START_ADDR {
+            return Err(MemError::NonZeroFirstRead { row, value });
+        }
+
+        let rc_value = cell.rc_value.to_canonical_u64();
+        if rc_value > u32::MAX as u64 {
+            return Err(MemError::DiffOutOfRange {
+                row,
+                value: rc_value,
+            });
+        }
+    }
+    Ok(())
+}
+
 pub fn storage_hash_table_gen(
     storage_logs: Vec<WitnessStorageLog>,
     storage_log_len: usize,
@@ -257,6 +388,7 @@ pub fn storage_hash_table_gen(
                     pre_path: item.1 .4,
                     path: item.1 .1,
                     sibling: item.1 .2,
+                    is_undo: GoldilocksField::ZERO,
                 };
                 if layer % 64 == 0 {
                     acc = GoldilocksField::ZERO;
@@ -277,13 +409,27 @@ pub fn storage_hash_table_gen(
     root_hashes
 }
 
+/// Apply the storage accesses of `process` to `account_tree` and make their
+/// hash rows, returning the root after each access.
+///
+/// If the run `reverted`, its writes are undone right after them, the last
+/// one first, so the trace ends on the root the run started from: the undo
+/// rows are proven like any write and looked up in the writes they undo.
 pub fn gen_storage_hash_table(
     process: &mut Process,
     program: &mut Program,
     account_tree: &mut AccountTree,
+    reverted: bool,
 ) -> Result<Vec<[GoldilocksField; TREE_VALUE_LEN]>, ProcessorError> {
     let storage_log_len = process.storage_log.len();
     let mut trace = std::mem::replace(&mut process.storage_log, Vec::new());
+    let undo = if reverted {
+        undo_storage_writes(&trace)
+    } else {
+        Vec::new()
+    };
+    let undo_range = storage_log_len..storage_log_len + undo.len();
+    trace.extend(undo);
     trace.extend(std::mem::replace(&mut process.program_log, Vec::new()));
     let mut pre_root = account_tree.root_hash();
     let (hash_traces, _) = account_tree.process_block(trace.iter());
@@ -338,6 +484,9 @@ pub fn gen_storage_hash_table(
                     pre_path: item.1 .4,
                     path: item.1 .1,
                     sibling: item.1 .2,
+                    is_undo: GoldilocksField::from_bool(
+                        item.0 == LEAF_LAYER && undo_range.contains(&chunk.0),
+                    ),
                 };
                 if layer % 64 == 0 {
                     acc = GoldilocksField::ZERO;
@@ -353,11 +502,28 @@ pub fn gen_storage_hash_table(
     program.trace.builtin_program_hash = program
         .trace
         .builtin_storage_hash
-        .drain(storage_log_len * ROOT_TREE_DEPTH..)
+        .drain(undo_range.end * ROOT_TREE_DEPTH..)
         .collect();
     Ok(root_hashes)
 }
 
+/// Writes putting back the values the writes of `logs` overwrote, the last
+/// write first.
+fn undo_storage_writes(logs: &[WitnessStorageLog]) -> Vec<WitnessStorageLog> {
+    logs.iter()
+        .rev()
+        .filter(|log| log.storage_log.kind != StorageLogKind::Read)
+        .map(|log| WitnessStorageLog {
+            storage_log: StorageLog::new_write(
+                StorageLogKind::RepeatedWrite,
+                log.storage_log.key,
+                log.previous_value,
+            ),
+            previous_value: log.storage_log.value,
+        })
+        .collect()
+}
+
 pub fn gen_storage_table(
     process: &mut Process,
     program: &mut Program,
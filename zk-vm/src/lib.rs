@@ -181,6 +181,9 @@ impl OlaVM {
                 prophets.insert(item.host as u64, item);
             }
             program.debug_info = bin_program.debug_info;
+            program.source_lines = bin_program.source_lines;
+            program.host_asserts = bin_program.host_asserts;
+            program.breakpoints = bin_program.breakpoints;
             program.prophets = prophets;
 
             for inst in instructions {
@@ -282,6 +285,10 @@ impl OlaVM {
         program.print_flag = debug_flag;
         let mut caller_addr = caller_addr;
         let mut code_exe_addr = code_exe_addr;
+        // A tx reverts when a contract it runs ends on a require of zero: no
+        // caller resumes and the storage trace undoes its writes.
+        let storage_cache = self.tx_cache_manager.storage_cache.clone();
+        let storage_queries_len = self.ola_state.storage_queries.len();
         let res = self.contract_run(&mut process, &mut program, caller_addr, code_exe_addr, true);
         let mut res = res.map_err(|err| {
             self.process_ctx
@@ -289,6 +296,7 @@ impl OlaVM {
             err
         })?;
         loop {
+            let reverted = matches!(res, VMState::Reverted(_));
             match res {
                 VMState::SCCall(ref ret) => {
                     debug!("contract call:{:?}", ret);
@@ -329,7 +337,13 @@ impl OlaVM {
                         true,
                     )?;
                 }
-                VMState::ExeEnd(step) => {
+                VMState::Interrupted(pc) => {
+                    return Err(StateError::VmExecError(format!(
+                        "execution interrupted at pc {}",
+                        pc
+                    )))
+                }
+                VMState::ExeEnd(step) | VMState::Reverted(step) => {
                     debug!("end contract:{:?}", process.addr_code);
                     if self.process_ctx.is_empty() {
                         assert_eq!(env_idx, 0);
@@ -337,6 +351,7 @@ impl OlaVM {
                             &mut process,
                             &mut program,
                             &mut self.account_tree,
+                            reverted,
                         )
                         .map_err(StateError::GenStorageTableError)?;
                         let _ = gen_storage_table(&mut process, &mut program, hash_roots)
@@ -348,6 +363,11 @@ impl OlaVM {
                         self.ola_state
                             .storage_queries
                             .append(&mut process.storage_queries);
+                        if reverted {
+                            self.tx_cache_manager.storage_cache = storage_cache;
+                            self.ola_state.storage_queries.truncate(storage_queries_len);
+                        }
+                        self.ola_state.reverted = reverted;
                         self.ola_state.return_data = process.return_data.clone();
                         println!("Final return data: {:?}", process.return_data);
                         debug!("finish tx");
@@ -374,13 +394,16 @@ impl OlaVM {
                         program = ctx.1;
                         caller_addr = ctx.2;
                         code_exe_addr = ctx.3;
-                        let mut step = step.ok_or(StateError::ExeEndStepEmpty)?;
-                        step.clk = process.clk;
-                        step.env_idx = process.env_idx;
-                        step.addr_storage = process.addr_storage;
-                        step.addr_code = process.addr_code;
-
-                        trace.exec.push(step);
+                        // A reverted callee's rows end on its require, with no
+                        // step of the caller returned to.
+                        if !reverted {
+                            let mut step = step.ok_or(StateError::ExeEndStepEmpty)?;
+                            step.clk = process.clk;
+                            step.env_idx = process.env_idx;
+                            step.addr_storage = process.addr_storage;
+                            step.addr_code = process.addr_code;
+                            trace.exec.push(step);
+                        }
                         let exec = std::mem::replace(&mut trace.exec, Vec::new());
                         program.trace.exec.extend(exec);
                         process.storage_log.extend(witness_log);
@@ -401,7 +424,13 @@ impl OlaVM {
                         process.tp = tp;
                         process.tape = tape_tree;
                         process.return_data = return_data;
-                        res = self.contract_run(&mut process, &mut program, ctx.2, ctx.3, false)?;
+                        if reverted {
+                            // Unwind the caller too, up to the entry contract.
+                            res = VMState::Reverted(None);
+                        } else {
+                            res =
+                                self.contract_run(&mut process, &mut program, ctx.2, ctx.3, false)?;
+                        }
                         debug!("contract end:{:?}", res);
                     }
                 }